@@ -0,0 +1,382 @@
+//! Static analysis of ROMs, without running them.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use super::{
+    emulator::RAM_SIZE,
+    fontset::FONTSET_SIZE,
+    opcodes::{decode, Instruction},
+    Quirks,
+};
+
+/// RAM size of XO-CHIP interpreters.
+const XO_CHIP_RAM_SIZE: usize = 0x10000;
+
+/// Instruction set a ROM needs, each a superset of the previous one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Platform {
+    /// Original Chip-8.
+    #[default]
+    Chip8,
+    /// SUPER-CHIP 1.1.
+    Schip,
+    /// XO-CHIP.
+    XoChip,
+}
+impl Platform {
+    /// Return the quirks preset for the platform. Plain Chip-8 ROMs get the modern defaults, which
+    /// most of them expect.
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Self::Chip8 | Self::XoChip => Quirks::modern(),
+            Self::Schip => Quirks::schip(),
+        }
+    }
+
+    /// Return the platform an extension opcode was introduced by, or `None` for opcodes which are
+    /// not extensions.
+    fn of_extension(op: u16) -> Option<Self> {
+        let nibbles = (op >> 12, (op >> 8) & 0xF, (op >> 4) & 0xF, op & 0xF);
+        match nibbles {
+            (0x0, 0x0, 0xC, n) if n > 0 => Some(Self::Schip),
+            (0x0, 0x0, 0xF, 0xB..=0xF) => Some(Self::Schip),
+            (0xD, _, _, 0x0) => Some(Self::Schip),
+            (0xF, _, 0x3, 0x0) | (0xF, _, 0x7, 0x5) | (0xF, _, 0x8, 0x5) => Some(Self::Schip),
+            (0x0, 0x0, 0xD, _) => Some(Self::XoChip),
+            (0x5, _, _, 0x2) | (0x5, _, _, 0x3) => Some(Self::XoChip),
+            (0xF, 0x0, 0x0, 0x0) | (0xF, 0x0, 0x0, 0x2) => Some(Self::XoChip),
+            (0xF, _, 0x0, 0x1) | (0xF, _, 0x3, 0xA) => Some(Self::XoChip),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Chip8 => write!(f, "CHIP-8"),
+            Self::Schip => write!(f, "SUPER-CHIP"),
+            Self::XoChip => write!(f, "XO-CHIP"),
+        }
+    }
+}
+
+/// Behaviour toggled by a field of [Quirks].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Quirk {
+    /// [Quirks::shift_vy], used by 8XY6 and 8XYE.
+    ShiftVy,
+    /// [Quirks::load_store_increment_i], used by FX55 and FX65.
+    LoadStoreIncrementI,
+    /// [Quirks::jump_vx], used by BNNN.
+    JumpVx,
+    /// [Quirks::vf_reset], used by 8XY1, 8XY2 and 8XY3.
+    VfReset,
+    /// [Quirks::clip_sprites], used by DXYN.
+    ClipSprites,
+}
+impl Quirk {
+    /// Return the quirk affecting an instruction, if any.
+    fn of(instruction: &Instruction) -> Option<Self> {
+        match instruction {
+            Instruction::Shr { .. } | Instruction::Shl { .. } => Some(Self::ShiftVy),
+            Instruction::LdIVx { .. } | Instruction::LdVxI { .. } => {
+                Some(Self::LoadStoreIncrementI)
+            }
+            Instruction::JpV0(_) => Some(Self::JumpVx),
+            Instruction::Or { .. } | Instruction::And { .. } | Instruction::Xor { .. } => {
+                Some(Self::VfReset)
+            }
+            Instruction::Drw { .. } => Some(Self::ClipSprites),
+            _ => None,
+        }
+    }
+}
+
+/// Something likely wrong with a ROM, found without running it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Problem {
+    /// A jump or call at `addr` targets an odd address, so opcodes are read misaligned.
+    OddTarget {
+        /// Address of the jump or call.
+        addr: u16,
+        /// Address jumped to.
+        target: u16,
+    },
+    /// Execution continues from the instruction at `addr` to `target`, outside the ROM.
+    LeavesRom {
+        /// Address of the instruction.
+        addr: u16,
+        /// Address execution continues from.
+        target: u16,
+    },
+    /// The instruction at `addr` accesses RAM through I past the end of RAM, or reads data that
+    /// is neither font, ROM nor written by the ROM.
+    IOutOfRange {
+        /// Address of the instruction.
+        addr: u16,
+        /// Value of I.
+        i: u16,
+    },
+    /// The opcode at `addr` is not part of any supported instruction set.
+    UnknownOpcode {
+        /// Address of the opcode.
+        addr: u16,
+        /// The opcode.
+        opcode: u16,
+    },
+}
+impl Problem {
+    /// Return the address of the instruction with the problem.
+    pub fn addr(&self) -> u16 {
+        match *self {
+            Self::OddTarget { addr, .. }
+            | Self::LeavesRom { addr, .. }
+            | Self::IOutOfRange { addr, .. }
+            | Self::UnknownOpcode { addr, .. } => addr,
+        }
+    }
+}
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::OddTarget { addr, target } => {
+                write!(f, "{:#05X}: jump to odd address {:#05X}", addr, target)
+            }
+            Self::LeavesRom { addr, target } => {
+                write!(
+                    f,
+                    "{:#05X}: execution leaves the ROM at {:#05X}",
+                    addr, target
+                )
+            }
+            Self::IOutOfRange { addr, i } => {
+                write!(f, "{:#05X}: I out of range at {:#05X}", addr, i)
+            }
+            Self::UnknownOpcode { addr, opcode } => {
+                write!(f, "{:#05X}: unknown opcode {:04X}", addr, opcode)
+            }
+        }
+    }
+}
+
+/// Result of [analyze].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Analysis {
+    /// Least capable platform supporting every opcode found.
+    pub platform: Platform,
+    /// Addresses of the opcodes reachable from the load address.
+    pub code: BTreeSet<u16>,
+    /// Addresses of the instructions whose behaviour depends on each quirk.
+    pub quirks: BTreeMap<Quirk, Vec<u16>>,
+    /// Problems found, ordered by address.
+    pub problems: Vec<Problem>,
+}
+
+/// RAM access through a known value of I.
+struct IAccess {
+    addr: u16,
+    i: u16,
+    write: bool,
+    len: usize,
+}
+
+/// Scan the opcodes of a ROM loaded at `load_address`, following jumps, calls and skips from the
+/// load address. BNNN jumps cannot be followed, and I is only tracked while it holds a constant.
+pub fn analyze(rom: &[u8], load_address: u16) -> Analysis {
+    let start = load_address as usize;
+    let end = start + rom.len();
+    let in_rom = |addr: u16| (start..end.saturating_sub(1)).contains(&(addr as usize));
+    let opcode_at = |addr: u16| {
+        let offset = (addr as usize).checked_sub(start)?;
+        Some(u16::from_be_bytes([
+            *rom.get(offset)?,
+            *rom.get(offset + 1)?,
+        ]))
+    };
+
+    let mut analysis = Analysis::default();
+    let mut accesses = Vec::new();
+    let mut pending = Vec::new();
+    if in_rom(load_address) {
+        pending.push((load_address, None));
+    }
+    while let Some((addr, i)) = pending.pop() {
+        if !analysis.code.insert(addr) {
+            continue;
+        }
+        let Some(op) = opcode_at(addr) else {
+            continue;
+        };
+        let instruction = decode(op);
+        let extension = Platform::of_extension(op);
+        if instruction.is_none() && extension.is_none() {
+            analysis
+                .problems
+                .push(Problem::UnknownOpcode { addr, opcode: op });
+            continue;
+        }
+        if let Some(platform) = extension {
+            analysis.platform = analysis.platform.max(platform);
+        }
+        if let Some(quirk) = instruction.as_ref().and_then(Quirk::of) {
+            analysis.quirks.entry(quirk).or_default().push(addr);
+        }
+
+        // RAM accesses through I, and what I holds afterwards.
+        let (x, y) = (((op >> 8) & 0xF) as usize, ((op >> 4) & 0xF) as usize);
+        let access = match (op >> 12, op & 0xF) {
+            (0xD, 0x0) => Some((false, 32)),
+            (0x5, 0x2) | (0x5, 0x3) => Some((op & 0xF == 0x2, x.abs_diff(y) + 1)),
+            _ => instruction
+                .and_then(|instruction| instruction.ram_access(0))
+                .map(|(write, _, len)| (write, len as usize)),
+        };
+        if let (Some(i), Some((write, len))) = (i, access) {
+            accesses.push(IAccess {
+                addr,
+                i,
+                write,
+                len,
+            });
+        }
+        let next = addr.wrapping_add(2);
+        let i = match instruction {
+            Some(Instruction::LdIAddr(nnn)) => Some(nnn),
+            _ if op == 0xF000 => opcode_at(next),
+            Some(
+                Instruction::AddIVx { .. }
+                | Instruction::LdFVx { .. }
+                | Instruction::LdIVx { .. }
+                | Instruction::LdVxI { .. },
+            ) => None,
+            _ if op & 0xF0FF == 0xF030 => None,
+            _ => i,
+        };
+
+        let skip = || match opcode_at(next) {
+            Some(0xF000) => next.wrapping_add(4),
+            _ => next.wrapping_add(2),
+        };
+        let successors = match instruction {
+            Some(Instruction::Ret | Instruction::Exit | Instruction::JpV0(_)) => vec![],
+            Some(Instruction::Jp(target)) => vec![target],
+            Some(Instruction::Call(target)) => vec![target, next],
+            Some(
+                Instruction::SeVxByte { .. }
+                | Instruction::SneVxByte { .. }
+                | Instruction::SeVxVy { .. }
+                | Instruction::SneVxVy { .. }
+                | Instruction::Skp { .. }
+                | Instruction::Sknp { .. },
+            ) => vec![next, skip()],
+            _ if op == 0xF000 => vec![next.wrapping_add(2)],
+            _ => vec![next],
+        };
+        let jumps = matches!(instruction, Some(Instruction::Jp(_) | Instruction::Call(_)));
+        for target in successors {
+            if jumps && !target.is_multiple_of(2) {
+                analysis.problems.push(Problem::OddTarget { addr, target });
+            }
+            if in_rom(target) {
+                pending.push((target, i));
+            } else {
+                analysis.problems.push(Problem::LeavesRom { addr, target });
+            }
+        }
+    }
+
+    // Reads are fine from the font, the ROM, or anywhere the ROM wrote to.
+    let ram_size = match analysis.platform {
+        Platform::XoChip => XO_CHIP_RAM_SIZE,
+        _ => RAM_SIZE,
+    };
+    let mut written = vec![false; ram_size];
+    for access in accesses.iter().filter(|access| access.write) {
+        let range = access.i as usize..(access.i as usize + access.len).min(ram_size);
+        written[range].fill(true);
+    }
+    for access in &accesses {
+        let range = access.i as usize..access.i as usize + access.len;
+        let valid = if range.end > ram_size {
+            false
+        } else {
+            access.write
+                || range.end <= FONTSET_SIZE
+                || (range.start >= start && range.end <= end)
+                || written[range].iter().all(|written| *written)
+        };
+        if !valid {
+            analysis.problems.push(Problem::IOutOfRange {
+                addr: access.addr,
+                i: access.i,
+            });
+        }
+    }
+    analysis.problems.sort_by_key(Problem::addr);
+    analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::emulator::START_ADDRESS;
+
+    use super::*;
+
+    #[test]
+    fn test_analyze() {
+        let rom = [
+            0xA3, 0x00, // 0x200: LD I, 0x300
+            0xF0, 0x33, // 0x202: LD B, V0
+            0xF2, 0x65, // 0x204: LD V2, [I]
+            0x80, 0x16, // 0x206: SHR V0, V1
+            0x00, 0xFF, // 0x208: HIGH
+            0x30, 0x00, // 0x20A: SE V0, 0
+            0x13, 0x01, // 0x20C: JP 0x301
+            0xAF, 0xFF, // 0x20E: LD I, 0xFFF
+            0xFF, 0x65, // 0x210: LD VF, [I]
+            0x01, 0x23, // 0x212: SYS 0x123
+        ];
+        let analysis = analyze(&rom, START_ADDRESS);
+        assert_eq!(analysis.platform, Platform::Schip);
+        assert_eq!(
+            analysis.code,
+            (0x200..=0x212).step_by(2).collect::<BTreeSet<_>>()
+        );
+        assert_eq!(analysis.quirks[&Quirk::ShiftVy], [0x206]);
+        assert_eq!(analysis.quirks[&Quirk::LoadStoreIncrementI], [0x204, 0x210]);
+        assert_eq!(
+            analysis.problems,
+            [
+                Problem::OddTarget {
+                    addr: 0x20C,
+                    target: 0x301
+                },
+                Problem::LeavesRom {
+                    addr: 0x20C,
+                    target: 0x301
+                },
+                Problem::IOutOfRange {
+                    addr: 0x210,
+                    i: 0xFFF
+                },
+                Problem::UnknownOpcode {
+                    addr: 0x212,
+                    opcode: 0x0123
+                },
+            ]
+        );
+        assert_eq!(
+            analysis.problems[2].to_string(),
+            "0x210: I out of range at 0xFFF"
+        );
+
+        // 0x200: SAVE V1 - V2; JP 0x202
+        let analysis = analyze(&[0x51, 0x22, 0x12, 0x02], START_ADDRESS);
+        assert_eq!(analysis.platform, Platform::XoChip);
+        assert_eq!(analysis.platform.quirks(), Quirks::modern());
+    }
+}
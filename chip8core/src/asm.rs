@@ -0,0 +1,597 @@
+//! Assembler for Chip-8 mnemonics.
+//!
+//! The dialect follows Cowgod's Chip-8 technical reference, e.g. `LD V0, 0x2A` or `DRW V0, V1, 5`.
+//! - One statement per line. Mnemonics and registers are case-insensitive.
+//! - `;` starts a comment running to the end of the line.
+//! - `name:` defines a label at the current address. Labels can be used wherever an address or
+//!   byte is expected.
+//! - `db` emits bytes and `dw` emits big-endian words, e.g. `db 0xF0, 0x90, 0b1111_0000`.
+//! - Numbers may be decimal, hex (`0x1F`, `#1F`, `$1F`) or binary (`0b1010`).
+//!
+//! Programs are assembled to be loaded at [START_ADDRESS].
+use std::{collections::BTreeMap, error::Error, fmt};
+
+use super::{
+    emulator::{RAM_SIZE, START_ADDRESS},
+    opcodes::Instruction,
+};
+
+/// An assembled program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    /// ROM bytes.
+    pub bytes: Vec<u8>,
+    /// Addresses of every label defined in the source.
+    pub labels: BTreeMap<String, u16>,
+}
+
+/// Error assembling a program, with the position it occurred at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// Column number, starting at 1.
+    pub column: usize,
+    /// Description of the error.
+    pub message: String,
+}
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+impl Error for AsmError {}
+
+/// Assemble the source into ROM bytes.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    assemble_program(source).map(|program| program.bytes)
+}
+
+/// Assemble the source into ROM bytes, also returning the address of each label.
+pub fn assemble_program(source: &str) -> Result<Program, AsmError> {
+    // I. Parse statements and assign addresses to labels.
+    let mut labels = BTreeMap::new();
+    let mut statements = Vec::new();
+    let mut address = START_ADDRESS as usize;
+
+    for (line_idx, line) in source.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let code = match line.find(';') {
+            Some(idx) => &line[..idx],
+            None => line,
+        };
+        let mut tokens = tokenize(code, line_no);
+
+        // Labels
+        while let Some(first) = tokens.first() {
+            let Some(name) = first.text.strip_suffix(':') else {
+                break;
+            };
+            if !is_identifier(name) || parse_operand_keyword(name).is_some() {
+                return Err(first.error(format!("invalid label name `{}`", name)));
+            }
+            if labels.insert(name.to_string(), address as u16).is_some() {
+                return Err(first.error(format!("label `{}` defined more than once", name)));
+            }
+            tokens.remove(0);
+        }
+
+        if tokens.is_empty() {
+            continue;
+        }
+        let mnemonic = tokens.remove(0);
+        let operands = split_operands(tokens, &mnemonic)?;
+        let size = match mnemonic.text.to_ascii_uppercase().as_str() {
+            "DB" => operands.len(),
+            "DW" => operands.len() * 2,
+            _ => 2,
+        };
+
+        address += size;
+        if address > RAM_SIZE {
+            return Err(mnemonic.error("program does not fit in RAM".to_string()));
+        }
+        statements.push(Statement { mnemonic, operands });
+    }
+
+    // II. Encode statements now that every label is known.
+    let mut bytes = Vec::with_capacity(address - START_ADDRESS as usize);
+    for statement in &statements {
+        statement.encode(&labels, &mut bytes)?;
+    }
+
+    Ok(Program { bytes, labels })
+}
+
+/// A piece of source text and its position.
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+impl Token<'_> {
+    fn error(&self, message: String) -> AsmError {
+        AsmError {
+            line: self.line,
+            column: self.column,
+            message,
+        }
+    }
+}
+
+/// Split a line into whitespace-separated tokens, keeping commas as their own tokens.
+fn tokenize(code: &str, line: usize) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (idx, c) in code.char_indices() {
+        if c.is_whitespace() || c == ',' {
+            if let Some(s) = start.take() {
+                tokens.push(Token {
+                    text: &code[s..idx],
+                    line,
+                    column: s + 1,
+                });
+            }
+            if c == ',' {
+                tokens.push(Token {
+                    text: &code[idx..idx + 1],
+                    line,
+                    column: idx + 1,
+                });
+            }
+        } else if start.is_none() {
+            start = Some(idx);
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(Token {
+            text: &code[s..],
+            line,
+            column: s + 1,
+        });
+    }
+    tokens
+}
+
+/// Turn the tokens following a mnemonic into a list of comma-separated operands.
+fn split_operands<'a>(
+    tokens: Vec<Token<'a>>,
+    mnemonic: &Token<'a>,
+) -> Result<Vec<Token<'a>>, AsmError> {
+    let mut operands = Vec::new();
+    let mut expect_operand = true;
+    for token in tokens {
+        match (token.text == ",", expect_operand) {
+            (false, true) => {
+                operands.push(token);
+                expect_operand = false;
+            }
+            (true, false) => expect_operand = true,
+            (true, true) => return Err(token.error("expected operand".to_string())),
+            (false, false) => return Err(token.error("expected `,`".to_string())),
+        }
+    }
+    if expect_operand && !operands.is_empty() {
+        return Err(mnemonic.error("trailing `,`".to_string()));
+    }
+    Ok(operands)
+}
+
+/// Whether the text is a valid label name.
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse a number literal.
+fn parse_number(text: &str) -> Option<u32> {
+    let text = text.replace('_', "");
+    let lower = text.to_ascii_lowercase();
+    if let Some(hex) = lower
+        .strip_prefix("0x")
+        .or_else(|| lower.strip_prefix('#'))
+        .or_else(|| lower.strip_prefix('$'))
+    {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        u32::from_str_radix(bin, 2).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+/// An instruction operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    /// V register.
+    V(u8),
+    /// I register.
+    I,
+    /// Memory at I.
+    IndirectI,
+    /// Delay timer.
+    Dt,
+    /// Sound timer.
+    St,
+    /// Key press.
+    K,
+    /// Font sprite location.
+    F,
+    /// Binary-coded decimal.
+    B,
+    /// Number or label.
+    Value(u32),
+}
+
+/// Parse a register or other reserved operand name.
+fn parse_operand_keyword(text: &str) -> Option<Operand> {
+    let upper = text.to_ascii_uppercase();
+    let operand = match upper.as_str() {
+        "I" => Operand::I,
+        "[I]" => Operand::IndirectI,
+        "DT" => Operand::Dt,
+        "ST" => Operand::St,
+        "K" => Operand::K,
+        "F" => Operand::F,
+        "B" => Operand::B,
+        _ => {
+            let digit = upper.strip_prefix('V')?;
+            if digit.len() != 1 {
+                return None;
+            }
+            Operand::V(u8::from_str_radix(digit, 16).ok()?)
+        }
+    };
+    Some(operand)
+}
+
+/// A parsed line containing a mnemonic or directive.
+struct Statement<'a> {
+    mnemonic: Token<'a>,
+    operands: Vec<Token<'a>>,
+}
+impl Statement<'_> {
+    /// Resolve the operand at `idx`.
+    fn operand(&self, idx: usize, labels: &BTreeMap<String, u16>) -> Result<Operand, AsmError> {
+        let token = &self.operands[idx];
+        if let Some(operand) = parse_operand_keyword(token.text) {
+            return Ok(operand);
+        }
+        if let Some(value) = parse_number(token.text) {
+            return Ok(Operand::Value(value));
+        }
+        match labels.get(token.text) {
+            Some(addr) => Ok(Operand::Value(*addr as u32)),
+            None if is_identifier(token.text) => {
+                Err(token.error(format!("undefined label `{}`", token.text)))
+            }
+            None => Err(token.error(format!("invalid operand `{}`", token.text))),
+        }
+    }
+
+    /// Resolve the operand at `idx` as a value no larger than `max`.
+    fn value(&self, idx: usize, max: u32, labels: &BTreeMap<String, u16>) -> Result<u32, AsmError> {
+        match self.operand(idx, labels)? {
+            Operand::Value(value) if value <= max => Ok(value),
+            Operand::Value(value) => Err(self.operands[idx]
+                .error(format!("value {:#X} out of range (max {:#X})", value, max))),
+            _ => Err(self.operands[idx].error("expected a number or label".to_string())),
+        }
+    }
+
+    /// Resolve the operand at `idx` as a V register index.
+    fn register(&self, idx: usize, labels: &BTreeMap<String, u16>) -> Result<u8, AsmError> {
+        match self.operand(idx, labels)? {
+            Operand::V(x) => Ok(x),
+            _ => Err(self.operands[idx].error("expected a V register".to_string())),
+        }
+    }
+
+    /// Check that the statement has between `min` and `max` operands.
+    fn expect_operands(&self, min: usize, max: usize) -> Result<(), AsmError> {
+        let count = self.operands.len();
+        if count < min || count > max {
+            let expected = if min == max {
+                format!("{}", min)
+            } else {
+                format!("{} to {}", min, max)
+            };
+            return Err(self.mnemonic.error(format!(
+                "`{}` expects {} operand(s), found {}",
+                self.mnemonic.text, expected, count
+            )));
+        }
+        Ok(())
+    }
+
+    /// Append the encoded statement to `bytes`.
+    fn encode(&self, labels: &BTreeMap<String, u16>, bytes: &mut Vec<u8>) -> Result<(), AsmError> {
+        let mnemonic = self.mnemonic.text.to_ascii_uppercase();
+
+        // Data directives
+        match mnemonic.as_str() {
+            "DB" => {
+                self.expect_operands(1, usize::MAX)?;
+                for idx in 0..self.operands.len() {
+                    bytes.push(self.value(idx, 0xFF, labels)? as u8);
+                }
+                return Ok(());
+            }
+            "DW" => {
+                self.expect_operands(1, usize::MAX)?;
+                for idx in 0..self.operands.len() {
+                    let word = self.value(idx, 0xFFFF, labels)? as u16;
+                    bytes.extend_from_slice(&word.to_be_bytes());
+                }
+                return Ok(());
+            }
+            "SYS" => {
+                self.expect_operands(1, 1)?;
+                let addr = self.value(0, 0xFFF, labels)? as u16;
+                bytes.extend_from_slice(&addr.to_be_bytes());
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let instruction = self.instruction(&mnemonic, labels)?;
+        bytes.extend_from_slice(&instruction.opcode().to_be_bytes());
+        Ok(())
+    }
+
+    /// Parse the statement as an instruction.
+    fn instruction(
+        &self,
+        mnemonic: &str,
+        labels: &BTreeMap<String, u16>,
+    ) -> Result<Instruction, AsmError> {
+        let addr = |idx| self.value(idx, 0xFFF, labels).map(|v| v as u16);
+        let byte = |idx| self.value(idx, 0xFF, labels).map(|v| v as u8);
+        let reg = |idx| self.register(idx, labels);
+        let bad_operands = || {
+            self.mnemonic
+                .error(format!("invalid operands for `{}`", self.mnemonic.text))
+        };
+
+        let instruction = match mnemonic {
+            "NOP" => {
+                self.expect_operands(0, 0)?;
+                Instruction::Nop
+            }
+            "CLS" => {
+                self.expect_operands(0, 0)?;
+                Instruction::Cls
+            }
+            "RET" => {
+                self.expect_operands(0, 0)?;
+                Instruction::Ret
+            }
+            "EXIT" => {
+                self.expect_operands(0, 0)?;
+                Instruction::Exit
+            }
+            "JP" => {
+                self.expect_operands(1, 2)?;
+                if self.operands.len() == 1 {
+                    Instruction::Jp(addr(0)?)
+                } else if self.operand(0, labels)? == Operand::V(0) {
+                    Instruction::JpV0(addr(1)?)
+                } else {
+                    return Err(self.operands[0].error("expected V0".to_string()));
+                }
+            }
+            "CALL" => {
+                self.expect_operands(1, 1)?;
+                Instruction::Call(addr(0)?)
+            }
+            "SE" | "SNE" => {
+                self.expect_operands(2, 2)?;
+                let x = reg(0)?;
+                let equal = mnemonic == "SE";
+                match (self.operand(1, labels)?, equal) {
+                    (Operand::V(y), true) => Instruction::SeVxVy { x, y },
+                    (Operand::V(y), false) => Instruction::SneVxVy { x, y },
+                    (_, true) => Instruction::SeVxByte { x, byte: byte(1)? },
+                    (_, false) => Instruction::SneVxByte { x, byte: byte(1)? },
+                }
+            }
+            "LD" => {
+                self.expect_operands(2, 2)?;
+                match (self.operand(0, labels)?, self.operand(1, labels)?) {
+                    (Operand::V(x), Operand::V(y)) => Instruction::LdVxVy { x, y },
+                    (Operand::V(x), Operand::Dt) => Instruction::LdVxDt { x },
+                    (Operand::V(x), Operand::K) => Instruction::LdVxK { x },
+                    (Operand::V(x), Operand::IndirectI) => Instruction::LdVxI { x },
+                    (Operand::V(x), Operand::Value(_)) => {
+                        Instruction::LdVxByte { x, byte: byte(1)? }
+                    }
+                    (Operand::I, Operand::Value(_)) => Instruction::LdIAddr(addr(1)?),
+                    (Operand::Dt, Operand::V(x)) => Instruction::LdDtVx { x },
+                    (Operand::St, Operand::V(x)) => Instruction::LdStVx { x },
+                    (Operand::F, Operand::V(x)) => Instruction::LdFVx { x },
+                    (Operand::B, Operand::V(x)) => Instruction::LdBVx { x },
+                    (Operand::IndirectI, Operand::V(x)) => Instruction::LdIVx { x },
+                    _ => return Err(bad_operands()),
+                }
+            }
+            "ADD" => {
+                self.expect_operands(2, 2)?;
+                match (self.operand(0, labels)?, self.operand(1, labels)?) {
+                    (Operand::V(x), Operand::V(y)) => Instruction::AddVxVy { x, y },
+                    (Operand::V(x), Operand::Value(_)) => {
+                        Instruction::AddVxByte { x, byte: byte(1)? }
+                    }
+                    (Operand::I, Operand::V(x)) => Instruction::AddIVx { x },
+                    _ => return Err(bad_operands()),
+                }
+            }
+            "OR" | "AND" | "XOR" | "SUB" | "SUBN" => {
+                self.expect_operands(2, 2)?;
+                let (x, y) = (reg(0)?, reg(1)?);
+                match mnemonic {
+                    "OR" => Instruction::Or { x, y },
+                    "AND" => Instruction::And { x, y },
+                    "XOR" => Instruction::Xor { x, y },
+                    "SUB" => Instruction::SubVxVy { x, y },
+                    _ => Instruction::SubnVxVy { x, y },
+                }
+            }
+            "SHR" | "SHL" => {
+                self.expect_operands(1, 2)?;
+                let x = reg(0)?;
+                // Shift Vx in place if Vy is omitted.
+                let y = if self.operands.len() == 2 { reg(1)? } else { x };
+                if mnemonic == "SHR" {
+                    Instruction::Shr { x, y }
+                } else {
+                    Instruction::Shl { x, y }
+                }
+            }
+            "RND" => {
+                self.expect_operands(2, 2)?;
+                Instruction::Rnd {
+                    x: reg(0)?,
+                    byte: byte(1)?,
+                }
+            }
+            "DRW" => {
+                self.expect_operands(3, 3)?;
+                Instruction::Drw {
+                    x: reg(0)?,
+                    y: reg(1)?,
+                    n: self.value(2, 0xF, labels)? as u8,
+                }
+            }
+            "SKP" => {
+                self.expect_operands(1, 1)?;
+                Instruction::Skp { x: reg(0)? }
+            }
+            "SKNP" => {
+                self.expect_operands(1, 1)?;
+                Instruction::Sknp { x: reg(0)? }
+            }
+            _ => {
+                return Err(self
+                    .mnemonic
+                    .error(format!("unknown mnemonic `{}`", self.mnemonic.text)))
+            }
+        };
+
+        Ok(instruction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{opcodes::decode, Emulator};
+
+    use super::*;
+
+    #[test]
+    fn test_assemble() {
+        let program = assemble_program(
+            "
+            ; Draw the digit 7 at (V0, V1)
+            start:
+                LD V0, 10
+                ld v1, 0x0C     ; lowercase is fine
+                LD F, V2
+                ADD V2, #7
+            loop: DRW V0, V1, 5
+                JP loop
+            data:
+                db 0b1111_0000, $90
+                dw start
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            program.bytes,
+            vec![
+                0x60, 0x0A, 0x61, 0x0C, 0xF2, 0x29, 0x72, 0x07, 0xD0, 0x15, 0x12, 0x08, 0xF0, 0x90,
+                0x02, 0x00
+            ]
+        );
+        assert_eq!(program.labels["start"], 0x200);
+        assert_eq!(program.labels["loop"], 0x208);
+        assert_eq!(program.labels["data"], 0x20C);
+    }
+
+    #[test]
+    fn test_round_trip_all_instructions() {
+        for op in 0..=0xFFFF {
+            if let Some(instruction) = decode(op) {
+                let source = instruction.to_string();
+                assert_eq!(
+                    assemble(&source),
+                    Ok(op.to_be_bytes().to_vec()),
+                    "{}",
+                    source
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |source: &str| assemble(source).unwrap_err();
+
+        assert_eq!(
+            err("CLS\n  LD V0, 0x100"),
+            AsmError {
+                line: 2,
+                column: 10,
+                message: "value 0x100 out of range (max 0xFF)".to_string()
+            }
+        );
+        assert_eq!(
+            err("JP nowhere"),
+            AsmError {
+                line: 1,
+                column: 4,
+                message: "undefined label `nowhere`".to_string()
+            }
+        );
+        assert_eq!(err("FOO V1").message, "unknown mnemonic `FOO`");
+        assert_eq!(err("ADD DT, V1").message, "invalid operands for `ADD`");
+        assert_eq!(err("CLS V1").message, "`CLS` expects 0 operand(s), found 1");
+        assert_eq!(err("LD V1 V2").message, "expected `,`");
+        assert_eq!(err("a:\na:").message, "label `a` defined more than once");
+        assert_eq!(err("V1: CLS").message, "invalid label name `V1`");
+    }
+
+    #[test]
+    fn test_run_assembled() {
+        let rom = assemble(
+            "
+                LD V0, 3
+            loop:
+                ADD V1, 2
+                ADD V0, 0xFF    ; V0 -= 1
+                SE V0, 0
+                JP loop
+                LD I, result
+                LD [I], V1
+            done:
+                JP done
+            result:
+                db 0, 0
+            ",
+        )
+        .unwrap();
+
+        let mut emu = Emulator::new();
+        emu.load_rom(&rom).unwrap();
+        for _ in 0..20 {
+            emu.tick().unwrap();
+        }
+        assert_eq!(emu.v(1_usize), 6);
+        assert_eq!(emu.ram[0x210], 0);
+        assert_eq!(emu.ram[0x211], 6);
+    }
+}
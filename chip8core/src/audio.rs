@@ -0,0 +1,118 @@
+//! Platform-independent buzzer synthesis.
+use super::Emulator;
+
+/// Buzzer settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioConfig {
+    /// Pitch in Hz.
+    pub frequency: f32,
+    /// Fraction of each period the wave is high, from 0 to 1.
+    pub duty: f32,
+    /// Amplitude, from 0 to 1.
+    pub volume: f32,
+    /// Output samples per second.
+    pub sample_rate: u32,
+}
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            frequency: 440.0,
+            duty: 0.5,
+            volume: 0.25,
+            sample_rate: 44100,
+        }
+    }
+}
+
+/// Square wave generator for the buzzer. Keeps its phase between calls so consecutive buffers
+/// join up without clicks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SquareWave {
+    config: AudioConfig,
+    phase: f32,
+}
+impl SquareWave {
+    /// Create a generator with the given settings.
+    pub fn new(config: AudioConfig) -> Self {
+        Self { config, phase: 0.0 }
+    }
+
+    /// Return the settings.
+    pub fn config(&self) -> &AudioConfig {
+        &self.config
+    }
+
+    /// Change the settings.
+    pub fn set_config(&mut self, config: AudioConfig) {
+        self.config = config;
+    }
+
+    /// Number of samples covering one 60Hz frame.
+    pub fn samples_per_frame(&self) -> usize {
+        (self.config.sample_rate / 60) as usize
+    }
+
+    /// Fill `out` with the square wave if `playing`, otherwise with silence.
+    pub fn fill(&mut self, playing: bool, out: &mut [f32]) {
+        if !playing {
+            out.fill(0.0);
+            return;
+        }
+
+        let phase_inc = self.config.frequency / self.config.sample_rate.max(1) as f32;
+        for sample in out.iter_mut() {
+            *sample = if self.phase < self.config.duty {
+                self.config.volume
+            } else {
+                -self.config.volume
+            };
+            self.phase = (self.phase + phase_inc) % 1.0;
+        }
+    }
+
+    /// Fill `out` with the buzzer output of the emulator, sounding while its sound timer is
+    /// non-zero.
+    pub fn fill_from(&mut self, emu: &Emulator, out: &mut [f32]) {
+        self.fill(emu.is_sound_playing(), out);
+    }
+}
+impl Default for SquareWave {
+    fn default() -> Self {
+        Self::new(AudioConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_square_wave() {
+        let mut wave = SquareWave::new(AudioConfig {
+            frequency: 1.0,
+            duty: 0.25,
+            volume: 0.5,
+            sample_rate: 8,
+        });
+        let mut buf = [1.0; 10];
+        wave.fill(true, &mut buf);
+        assert_eq!(
+            buf,
+            [0.5, 0.5, -0.5, -0.5, -0.5, -0.5, -0.5, -0.5, 0.5, 0.5]
+        );
+
+        // Phase carries over between buffers.
+        let mut buf = [0.0; 2];
+        wave.fill(true, &mut buf);
+        assert_eq!(buf, [-0.5, -0.5]);
+
+        let mut emu = Emulator::new();
+        wave.fill_from(&emu, &mut buf);
+        assert_eq!(buf, [0.0, 0.0]);
+        emu.sound_timer = 3;
+        wave.fill_from(&emu, &mut buf);
+        assert_eq!(buf, [-0.5, -0.5]);
+    }
+}
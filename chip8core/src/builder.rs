@@ -0,0 +1,207 @@
+//! Construction-time emulator options.
+use std::{error::Error, fmt};
+
+use super::{
+    emulator::{StackOverflowBehavior, RAM_SIZE, STACK_SIZE, START_ADDRESS},
+    fontset::{FONTSET, FONTSET_SIZE},
+    quirks::Quirks,
+    rng::random_seed,
+    Emulator,
+};
+
+/// Largest RAM size addressable by a 16-bit program counter.
+pub const MAX_RAM_SIZE: usize = 0x10000;
+/// Default instructions executed per second.
+pub const DEFAULT_CLOCK_HZ: u32 = 500;
+
+/// Configures an [Emulator] before building it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmulatorBuilder {
+    pub(crate) ram_size: usize,
+    pub(crate) start_address: u16,
+    pub(crate) quirks: Quirks,
+    pub(crate) seed: Option<u64>,
+    pub(crate) stack_depth: usize,
+    pub(crate) stack_overflow: StackOverflowBehavior,
+    pub(crate) fontset: [u8; FONTSET_SIZE],
+    pub(crate) clock_hz: u32,
+}
+impl EmulatorBuilder {
+    /// Start from the default options: 4K RAM, programs at 0x200, modern quirks, a random seed,
+    /// 16 stack levels, the standard font and 500 instructions per second.
+    pub fn new() -> Self {
+        Self {
+            ram_size: RAM_SIZE,
+            start_address: START_ADDRESS,
+            quirks: Quirks::default(),
+            seed: None,
+            stack_depth: STACK_SIZE,
+            stack_overflow: StackOverflowBehavior::default(),
+            fontset: FONTSET,
+            clock_hz: DEFAULT_CLOCK_HZ,
+        }
+    }
+
+    /// Set the RAM size in bytes.
+    pub fn ram_size(mut self, ram_size: usize) -> Self {
+        self.ram_size = ram_size;
+        self
+    }
+
+    /// Set the address programs are loaded at and start executing from.
+    pub fn start_address(mut self, start_address: u16) -> Self {
+        self.start_address = start_address;
+        self
+    }
+
+    /// Set the interpreter quirks.
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Seed the random number generator. Emulators built with the same seed produce the same
+    /// sequence of random numbers.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the number of nested subroutine calls allowed.
+    pub fn stack_depth(mut self, stack_depth: usize) -> Self {
+        self.stack_depth = stack_depth;
+        self
+    }
+
+    /// Set what happens when a subroutine call finds the stack full.
+    pub fn stack_overflow(mut self, behavior: StackOverflowBehavior) -> Self {
+        self.stack_overflow = behavior;
+        self
+    }
+
+    /// Set the hex digit sprites copied to the start of RAM.
+    pub fn fontset(mut self, fontset: [u8; FONTSET_SIZE]) -> Self {
+        self.fontset = fontset;
+        self
+    }
+
+    /// Set the instructions executed per second by [Emulator::run_for].
+    pub fn clock_hz(mut self, clock_hz: u32) -> Self {
+        self.clock_hz = clock_hz;
+        self
+    }
+
+    /// Build the emulator, checking the options are consistent.
+    pub fn build(self) -> Result<Emulator, BuildError> {
+        if self.ram_size > MAX_RAM_SIZE {
+            return Err(BuildError::RamTooLarge(self.ram_size));
+        }
+        if (self.start_address as usize) < FONTSET_SIZE
+            || self.start_address as usize >= self.ram_size
+        {
+            return Err(BuildError::StartAddressOutOfRange(self.start_address));
+        }
+        if self.stack_depth == 0 {
+            return Err(BuildError::EmptyStack);
+        }
+        if self.clock_hz == 0 {
+            return Err(BuildError::ZeroClock);
+        }
+        Ok(Emulator::from_builder(
+            &self,
+            self.seed.unwrap_or_else(random_seed),
+        ))
+    }
+}
+impl Default for EmulatorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Error building an emulator from inconsistent options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// RAM is larger than a 16-bit address can reach.
+    RamTooLarge(usize),
+    /// Start address overlaps the fontset or lies outside RAM.
+    StartAddressOutOfRange(u16),
+    /// Stack depth is zero.
+    EmptyStack,
+    /// Clock speed is zero.
+    ZeroClock,
+}
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RamTooLarge(size) => {
+                write!(
+                    f,
+                    "RAM too large: {} bytes (max {} bytes)",
+                    size, MAX_RAM_SIZE
+                )
+            }
+            Self::StartAddressOutOfRange(addr) => {
+                write!(f, "start address out of range: {:#05X}", addr)
+            }
+            Self::EmptyStack => write!(f, "stack depth must be at least 1"),
+            Self::ZeroClock => write!(f, "clock speed must be at least 1Hz"),
+        }
+    }
+}
+impl Error for BuildError {}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Chip8Error;
+
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let mut font = FONTSET;
+        font[0] = 0xAA;
+        let mut emu = EmulatorBuilder::new()
+            .ram_size(0x1000 + 0x600)
+            .start_address(0x600)
+            .quirks(Quirks::chip8())
+            .seed(7)
+            .stack_depth(2)
+            .fontset(font)
+            .clock_hz(1000)
+            .build()
+            .unwrap();
+        assert_eq!(emu.ram().len(), 0x1600);
+        assert_eq!(emu.ram()[0], 0xAA);
+        assert_eq!(emu.pc(), 0x600);
+        assert_eq!(emu.quirks(), &Quirks::chip8());
+        assert_eq!(emu.rng_seed(), 7);
+        assert_eq!(emu.clock_hz(), 1000);
+
+        // CALL 0x600 recurses until the stack overflows.
+        let info = emu.load_rom(&[0x26, 0x00]).unwrap();
+        assert_eq!(info.load_address, 0x600);
+        emu.tick().unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.tick(), Err(Chip8Error::StackOverflow));
+
+        emu.reset();
+        assert_eq!(emu.ram().len(), 0x1600);
+        assert_eq!(emu.pc(), 0x600);
+
+        assert_eq!(
+            EmulatorBuilder::new().ram_size(0x20000).build().err(),
+            Some(BuildError::RamTooLarge(0x20000))
+        );
+        assert_eq!(
+            EmulatorBuilder::new().start_address(0x1000).build().err(),
+            Some(BuildError::StartAddressOutOfRange(0x1000))
+        );
+        assert_eq!(
+            EmulatorBuilder::new().stack_depth(0).build().err(),
+            Some(BuildError::EmptyStack)
+        );
+    }
+}
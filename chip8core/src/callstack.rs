@@ -0,0 +1,127 @@
+//! Structured view of the call stack for debuggers.
+use std::collections::BTreeMap;
+
+use super::Emulator;
+
+/// One subroutine call on the stack.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StackFrame {
+    /// Address execution returns to when the subroutine returns.
+    pub return_addr: u16,
+    /// Address of the 2NNN instruction that made the call.
+    pub call_site: u16,
+    /// Address of the called subroutine, if the call site still holds a 2NNN instruction.
+    pub entry: Option<u16>,
+    /// Name of the called subroutine, if labels were given. See [Emulator::call_stack_with_labels].
+    pub function: Option<String>,
+}
+
+impl Emulator {
+    /// Return the subroutine calls on the stack, outermost first.
+    pub fn call_stack(&self) -> Vec<StackFrame> {
+        self.stack()
+            .iter()
+            .map(|return_addr| {
+                let call_site = return_addr.wrapping_sub(2);
+                let opcode = (
+                    self.read_byte(call_site as usize).ok(),
+                    self.read_byte(call_site.wrapping_add(1) as usize).ok(),
+                );
+                let entry = match opcode {
+                    (Some(high), Some(low)) if high & 0xF0 == 0x20 => {
+                        Some(u16::from_be_bytes([high & 0x0F, low]))
+                    }
+                    _ => None,
+                };
+                StackFrame {
+                    return_addr: *return_addr,
+                    call_site,
+                    entry,
+                    function: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Return the subroutine calls on the stack, outermost first, naming each subroutine after the
+    /// label at its entry, or the closest label before it plus an offset, e.g. `draw+0x4`.
+    /// Labels come from e.g. [crate::asm::Program::labels].
+    pub fn call_stack_with_labels(&self, labels: &BTreeMap<String, u16>) -> Vec<StackFrame> {
+        let by_addr: BTreeMap<u16, &str> = labels
+            .iter()
+            .map(|(name, addr)| (*addr, name.as_str()))
+            .collect();
+        let mut frames = self.call_stack();
+        for frame in &mut frames {
+            frame.function = frame.entry.and_then(|entry| {
+                let (addr, name) = by_addr.range(..=entry).next_back()?;
+                Some(match entry - addr {
+                    0 => name.to_string(),
+                    offset => format!("{}+{:#X}", name, offset),
+                })
+            });
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{asm::assemble_program, Event};
+
+    use super::*;
+
+    #[test]
+    fn test_call_stack() {
+        let program = assemble_program(
+            "
+                CALL outer
+            done:
+                JP done
+            outer:
+                CALL inner
+                RET
+            inner:
+                LD V0, 1
+                CALL inner2
+                RET
+            inner2:
+                RET
+            ",
+        )
+        .unwrap();
+        let mut emu = Emulator::new();
+        emu.load_rom(&program.bytes).unwrap();
+        emu.run_until(program.labels["inner2"], 10).unwrap();
+
+        let frames = emu.call_stack_with_labels(&program.labels);
+        let names: Vec<_> = frames.iter().map(|f| f.function.as_deref()).collect();
+        assert_eq!(names, [Some("outer"), Some("inner"), Some("inner2")]);
+        assert_eq!(frames[0].call_site, 0x200);
+        assert_eq!(frames[0].return_addr, 0x202);
+        assert_eq!(emu.call_stack()[2].function, None);
+
+        // Without a label at the entry, the closest one before it is used.
+        let mut labels = program.labels.clone();
+        labels.remove("inner2");
+        let frames = emu.call_stack_with_labels(&labels);
+        assert_eq!(frames[2].function.as_deref(), Some("inner+0x6"));
+
+        let events: Vec<_> = emu.drain_events().collect();
+        assert_eq!(
+            events,
+            [
+                Event::StackPushed(0x202),
+                Event::StackPushed(0x206),
+                Event::StackPushed(0x20C)
+            ]
+        );
+        emu.step().unwrap();
+        assert_eq!(
+            emu.drain_events().collect::<Vec<_>>(),
+            [Event::StackPopped(0x20C)]
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Named pokes which hold RAM or registers at a fixed value, applied once per frame.
+use std::{error::Error, fmt, str::FromStr};
+
+use super::Emulator;
+
+/// What a cheat writes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheatTarget {
+    /// RAM address.
+    Ram(u16),
+    /// V register index.
+    Register(u8),
+}
+impl fmt::Display for CheatTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ram(addr) => write!(f, "{:#05X}", addr),
+            Self::Register(x) => write!(f, "v{:X}", x),
+        }
+    }
+}
+
+/// A value written to a target every frame while enabled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cheat {
+    /// Name shown to the user, e.g. "Infinite lives".
+    pub name: String,
+    /// Where the value is written.
+    pub target: CheatTarget,
+    /// Value written.
+    pub value: u8,
+    /// Whether the cheat is applied.
+    pub enabled: bool,
+}
+impl Cheat {
+    /// Create an enabled cheat.
+    pub fn new(name: &str, target: CheatTarget, value: u8) -> Self {
+        Self {
+            name: name.to_string(),
+            target,
+            value,
+            enabled: true,
+        }
+    }
+}
+
+/// Set of cheats, attached to every [Emulator] and applied each time the timers tick. Saved to
+/// and loaded from cheat files with [fmt::Display] and [FromStr], one cheat per line:
+///
+/// ```text
+/// # `+` if enabled, `-` if not, then target, value and name
+/// + 0x3A0 = 0x09 Infinite lives
+/// - v3 = 0x00 Freeze V3
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cheats {
+    cheats: Vec<Cheat>,
+}
+impl Cheats {
+    /// Create an empty set of cheats.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a cheat, replacing any with the same name.
+    pub fn add(&mut self, cheat: Cheat) {
+        match self.cheats.iter_mut().find(|c| c.name == cheat.name) {
+            Some(existing) => *existing = cheat,
+            None => self.cheats.push(cheat),
+        }
+    }
+
+    /// Remove the cheat with the given name, returning it.
+    pub fn remove(&mut self, name: &str) -> Option<Cheat> {
+        let idx = self.cheats.iter().position(|c| c.name == name)?;
+        Some(self.cheats.remove(idx))
+    }
+
+    /// Return the cheat with the given name.
+    pub fn get(&self, name: &str) -> Option<&Cheat> {
+        self.cheats.iter().find(|c| c.name == name)
+    }
+
+    /// Enable or disable the cheat with the given name. Returns `false` if there is none.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.cheats.iter_mut().find(|c| c.name == name) {
+            Some(cheat) => {
+                cheat.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Iterate over the cheats in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Cheat> {
+        self.cheats.iter()
+    }
+
+    /// Number of cheats.
+    pub fn len(&self) -> usize {
+        self.cheats.len()
+    }
+
+    /// Whether there are no cheats.
+    pub fn is_empty(&self) -> bool {
+        self.cheats.is_empty()
+    }
+
+    /// Remove every cheat.
+    pub fn clear(&mut self) {
+        self.cheats.clear();
+    }
+
+    /// Write every enabled cheat's value. Addresses outside RAM are skipped.
+    pub(crate) fn apply(&self, emu: &mut Emulator) {
+        for cheat in self.cheats.iter().filter(|c| c.enabled) {
+            match cheat.target {
+                CheatTarget::Ram(addr) => {
+                    let _ = emu.write_byte(addr as usize, cheat.value);
+                }
+                CheatTarget::Register(x) => emu.set_v(x as usize, cheat.value),
+            }
+        }
+    }
+}
+impl fmt::Display for Cheats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for cheat in &self.cheats {
+            let enabled = if cheat.enabled { '+' } else { '-' };
+            writeln!(
+                f,
+                "{} {} = {:#04X} {}",
+                enabled, cheat.target, cheat.value, cheat.name
+            )?;
+        }
+        Ok(())
+    }
+}
+impl FromStr for Cheats {
+    type Err = CheatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cheats = Self::new();
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| CheatError {
+                line: idx + 1,
+                message: message.to_string(),
+            };
+            let mut parts = line.splitn(5, char::is_whitespace);
+            let enabled = match parts.next() {
+                Some("+") => true,
+                Some("-") => false,
+                _ => return Err(error("expected `+` or `-`")),
+            };
+            let target = parts.next().unwrap_or_default();
+            let target = match target.strip_prefix(['v', 'V']) {
+                Some(x) => u8::from_str_radix(x, 16)
+                    .ok()
+                    .filter(|x| *x < 16)
+                    .map(CheatTarget::Register),
+                None => parse_number(target)
+                    .and_then(|addr| u16::try_from(addr).ok())
+                    .map(CheatTarget::Ram),
+            }
+            .ok_or_else(|| error("expected an address or register"))?;
+            if parts.next() != Some("=") {
+                return Err(error("expected `=`"));
+            }
+            let value = parts
+                .next()
+                .and_then(parse_number)
+                .and_then(|value| u8::try_from(value).ok())
+                .ok_or_else(|| error("expected a byte value"))?;
+            let name = parts.next().unwrap_or_default().trim();
+            if name.is_empty() {
+                return Err(error("expected a name"));
+            }
+            cheats.add(Cheat {
+                name: name.to_string(),
+                target,
+                value,
+                enabled,
+            });
+        }
+        Ok(cheats)
+    }
+}
+
+/// Parse a decimal or `0x` hex number.
+fn parse_number(text: &str) -> Option<u32> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Error parsing a cheat file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheatError {
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// Description of the error.
+    pub message: String,
+}
+impl fmt::Display for CheatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+impl Error for CheatError {}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_cheats() {
+        let mut emu = Emulator::new();
+        // 0x200: ADD V0, 1; LD [I], V0; JP 0x200
+        emu.load_rom(&[0x70, 0x01, 0xF0, 0x55, 0x12, 0x00]).unwrap();
+        emu.set_i(0x300);
+        emu.cheats_mut()
+            .add(Cheat::new("Hold V0", CheatTarget::Register(0), 0x40));
+        emu.cheats_mut()
+            .add(Cheat::new("Poke", CheatTarget::Ram(0x301), 7));
+        emu.run_frame(6).unwrap();
+        assert_eq!(emu.v(0_usize), 0x40);
+        assert_eq!(emu.read_byte(0x301).unwrap(), 7);
+
+        // Disabled cheats are left alone.
+        assert!(emu.cheats_mut().set_enabled("Hold V0", false));
+        emu.run_frame(2).unwrap();
+        assert_eq!(emu.v(0_usize), 0x41);
+
+        let text = emu.cheats().to_string();
+        assert_eq!(text, "- v0 = 0x40 Hold V0\n+ 0x301 = 0x07 Poke\n");
+        assert_eq!(&text.parse::<Cheats>().unwrap(), emu.cheats());
+        assert_eq!(
+            "# lives\n+ 0x3A0 = 300 Lives".parse::<Cheats>(),
+            Err(CheatError {
+                line: 2,
+                message: "expected a byte value".to_string()
+            })
+        );
+    }
+}
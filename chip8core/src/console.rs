@@ -0,0 +1,374 @@
+//! Debugger console: commands typed at a prompt to set breakpoints, step and inspect or poke the
+//! machine, shared by frontends that read them from a terminal.
+use std::{error::Error, fmt, str::FromStr};
+
+use super::{opcodes, opcodes::Instruction, Chip8Error, Emulator};
+
+/// Instructions listed by `disasm` unless told otherwise.
+pub const DISASM_COUNT: u16 = 8;
+/// Bytes shown by `mem` unless told otherwise.
+pub const MEMORY_LENGTH: u16 = 64;
+/// Bytes per line of a memory dump.
+pub const BYTES_PER_LINE: u16 = 16;
+
+/// Name of each command, with its shorthand.
+pub const NAMES: [(&str, &str); 9] = [
+    ("break", "b"),
+    ("delete", "d"),
+    ("step", "s"),
+    ("continue", "c"),
+    ("print", "p"),
+    ("poke", "poke"),
+    ("mem", "m"),
+    ("disasm", "x"),
+    ("help", "h"),
+];
+
+/// Commands understood, shown by `help`.
+pub const HELP: &str = "\
+break [ADDR]      pause, or stop when the program counter reaches ADDR
+delete ADDR       remove the breakpoint at ADDR
+step [N]          run N instructions, 1 by default, pausing first
+continue          resume running
+print [REG]       show V0-VF, I, PC, SP, DT or ST, or every register
+poke ADDR VALUE   write a byte to memory
+mem ADDR [LEN]    show LEN bytes of memory from ADDR
+disasm [ADDR] [N] list N instructions from ADDR, the program counter by default
+Commands but poke can be shortened to b, d, s, c, p, m, x and h. Numbers are decimal, or hex
+with a 0x prefix.";
+
+/// Command typed at the console.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Pause, or set a breakpoint at the address.
+    Break(Option<u16>),
+    /// Remove the breakpoint at the address.
+    Delete(u16),
+    /// Run this many instructions, pausing first.
+    Step(u32),
+    /// Resume running.
+    Continue,
+    /// Show a register, or every register.
+    Print(Option<Register>),
+    /// Write a byte to memory.
+    Poke(u16, u8),
+    /// Show this many bytes of memory from the address.
+    Memory(u16, u16),
+    /// List instructions from the address, or from the program counter.
+    Disasm(Option<u16>, u16),
+    /// Show the commands understood.
+    Help,
+}
+impl FromStr for Command {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let name = words.next().ok_or(CommandError::Empty)?;
+        let args: Vec<&str> = words.collect();
+        let command = match (name, args.as_slice()) {
+            ("break" | "b", []) => Self::Break(None),
+            ("break" | "b", [addr]) => Self::Break(Some(number(addr)?)),
+            ("delete" | "d", [addr]) => Self::Delete(number(addr)?),
+            ("step" | "s", []) => Self::Step(1),
+            ("step" | "s", [count]) => Self::Step(number(count)?),
+            ("continue" | "c", []) => Self::Continue,
+            ("print" | "p", []) => Self::Print(None),
+            ("print" | "p", [register]) => Self::Print(Some(register.parse()?)),
+            ("poke", [addr, value]) => Self::Poke(number(addr)?, number(value)?),
+            ("mem" | "m", [addr]) => Self::Memory(number(addr)?, MEMORY_LENGTH),
+            ("mem" | "m", [addr, len]) => Self::Memory(number(addr)?, number(len)?),
+            ("disasm" | "x", []) => Self::Disasm(None, DISASM_COUNT),
+            ("disasm" | "x", [addr]) => Self::Disasm(Some(number(addr)?), DISASM_COUNT),
+            ("disasm" | "x", [addr, count]) => Self::Disasm(Some(number(addr)?), number(count)?),
+            ("help" | "h", []) => Self::Help,
+            _ if NAMES.iter().any(|names| name == names.0 || name == names.1) => {
+                return Err(CommandError::Arguments(name.to_string()))
+            }
+            _ => return Err(CommandError::Unknown(name.to_string())),
+        };
+        Ok(command)
+    }
+}
+impl Command {
+    /// Run the command on `emu`, pausing or resuming through `paused`. Returns what to show, or
+    /// the error an instruction stepped through ran into.
+    pub fn run(self, emu: &mut Emulator, paused: &mut bool) -> Result<String, Chip8Error> {
+        let text = match self {
+            Self::Break(None) => {
+                *paused = true;
+                format!("Paused at {:#05X}", emu.pc())
+            }
+            Self::Break(Some(addr)) => match emu.debugger_mut().add_breakpoint(addr) {
+                true => format!("Breakpoint at {:#05X}", addr),
+                false => format!("Already a breakpoint at {:#05X}", addr),
+            },
+            Self::Delete(addr) => match emu.debugger_mut().remove_breakpoint(addr) {
+                true => format!("Removed breakpoint at {:#05X}", addr),
+                false => format!("No breakpoint at {:#05X}", addr),
+            },
+            Self::Step(count) => {
+                *paused = true;
+                for _ in 0..count {
+                    // Nothing more runs once ended, or while waiting for a key
+                    if emu.state().has_ended() || emu.step()?.instruction.is_none() {
+                        break;
+                    }
+                }
+                let line = disassemble(emu, emu.pc(), 1);
+                line.first().map(ToString::to_string).unwrap_or_default()
+            }
+            Self::Continue => {
+                // A breakpoint just stopped at is skipped when execution resumes
+                *paused = false;
+                "Running".to_string()
+            }
+            Self::Print(Some(register)) => {
+                let value = register.read(emu);
+                format!("{} = {:#X} ({})", register, value, value)
+            }
+            Self::Print(None) => registers(emu),
+            Self::Poke(addr, value) => match emu.write_byte(addr as usize, value) {
+                Ok(()) => format!("{:#05X} = {:#04X}", addr, value),
+                Err(e) => format!("Unable to poke: {}", e),
+            },
+            Self::Memory(addr, len) => dump(emu, addr, len).join("\n"),
+            Self::Disasm(addr, count) => {
+                let lines = disassemble(emu, addr.unwrap_or(emu.pc()), count);
+                let lines: Vec<_> = lines.iter().map(ToString::to_string).collect();
+                lines.join("\n")
+            }
+            Self::Help => HELP.to_string(),
+        };
+        Ok(text)
+    }
+}
+
+/// Register shown by `print`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    /// V register by index.
+    V(u8),
+    /// I register.
+    I,
+    /// Program counter.
+    Pc,
+    /// Stack pointer.
+    Sp,
+    /// Delay timer.
+    Delay,
+    /// Sound timer.
+    Sound,
+}
+impl Register {
+    /// Value of the register in `emu`.
+    pub fn read(self, emu: &Emulator) -> u16 {
+        match self {
+            Self::V(x) => emu.v(x as usize) as u16,
+            Self::I => emu.i(),
+            Self::Pc => emu.pc(),
+            Self::Sp => emu.stack_pointer(),
+            Self::Delay => emu.delay_timer() as u16,
+            Self::Sound => emu.sound_timer() as u16,
+        }
+    }
+}
+impl FromStr for Register {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let register = match s.to_uppercase().as_str() {
+            "I" => Self::I,
+            "PC" => Self::Pc,
+            "SP" => Self::Sp,
+            "DT" => Self::Delay,
+            "ST" => Self::Sound,
+            name => name
+                .strip_prefix('V')
+                .filter(|x| x.len() == 1)
+                .and_then(|x| u8::from_str_radix(x, 16).ok())
+                .map(Self::V)
+                .ok_or_else(|| CommandError::Register(s.to_string()))?,
+        };
+        Ok(register)
+    }
+}
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V(x) => write!(f, "V{:X}", x),
+            Self::I => write!(f, "I"),
+            Self::Pc => write!(f, "PC"),
+            Self::Sp => write!(f, "SP"),
+            Self::Delay => write!(f, "DT"),
+            Self::Sound => write!(f, "ST"),
+        }
+    }
+}
+
+/// Instruction in a listing, marked `>` at the program counter and `*` at a breakpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListingLine {
+    /// Address of the instruction.
+    pub addr: u16,
+    /// Opcode at the address.
+    pub op: u16,
+    /// Instruction the opcode decodes to, if any.
+    pub instruction: Option<Instruction>,
+    /// Whether the program counter is at the address.
+    pub current: bool,
+    /// Whether there's a breakpoint at the address.
+    pub breakpoint: bool,
+}
+impl fmt::Display for ListingLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = match (self.current, self.breakpoint) {
+            (true, _) => '>',
+            (false, true) => '*',
+            (false, false) => ' ',
+        };
+        write!(f, "{} {:03X}: {:04X}  ", mark, self.addr, self.op)?;
+        match self.instruction {
+            Some(instruction) => write!(f, "{}", instruction),
+            None => write!(f, "DW {:#06X}", self.op),
+        }
+    }
+}
+
+/// List `count` instructions of `emu` from `addr`, stopping at the end of memory.
+pub fn disassemble(emu: &Emulator, addr: u16, count: u16) -> Vec<ListingLine> {
+    (0..count)
+        .map(|n| addr.wrapping_add(n.wrapping_mul(2)))
+        .map_while(|addr| {
+            let high = emu.read_byte(addr as usize).ok()?;
+            let low = emu.read_byte(addr as usize + 1).ok()?;
+            let op = u16::from_be_bytes([high, low]);
+            Some(ListingLine {
+                addr,
+                op,
+                instruction: opcodes::decode(op),
+                current: addr == emu.pc(),
+                breakpoint: emu.debugger().breakpoints().any(|a| a == addr),
+            })
+        })
+        .collect()
+}
+
+/// Hex dump of `len` bytes of `emu`'s memory from `addr`, [BYTES_PER_LINE] to a line, stopping at
+/// the end of memory.
+pub fn dump(emu: &Emulator, addr: u16, len: u16) -> Vec<String> {
+    let end = (addr as usize + len as usize).min(emu.ram().len());
+    let bytes = emu.ram().get(addr as usize..end).unwrap_or_default();
+    bytes
+        .chunks(BYTES_PER_LINE as usize)
+        .enumerate()
+        .map(|(n, line)| {
+            let hex: Vec<_> = line.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let start = addr as usize + n * BYTES_PER_LINE as usize;
+            format!("{:03X}: {}", start, hex.join(" "))
+        })
+        .collect()
+}
+
+/// Every register of `emu` on two lines.
+pub fn registers(emu: &Emulator) -> String {
+    let v: Vec<_> = (0..16)
+        .map(|x| format!("V{:X}={:02X}", x, emu.v(x as usize)))
+        .collect();
+    format!(
+        "{}\nI={:03X} PC={:03X} SP={:X} DT={:02X} ST={:02X}",
+        v.join(" "),
+        emu.i(),
+        emu.pc(),
+        emu.stack_pointer(),
+        emu.delay_timer(),
+        emu.sound_timer()
+    )
+}
+
+/// Parse a decimal number, or a hex one with a `0x` prefix.
+fn number<T: TryFrom<u32>>(s: &str) -> Result<T, CommandError> {
+    let value = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    };
+    value
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| CommandError::Number(s.to_string()))
+}
+
+/// Error parsing a console command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    /// The line is blank.
+    Empty,
+    /// No command has the name.
+    Unknown(String),
+    /// The command was given the wrong number of arguments.
+    Arguments(String),
+    /// An argument isn't a number in range.
+    Number(String),
+    /// An argument doesn't name a register.
+    Register(String),
+}
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no command given"),
+            Self::Unknown(name) => write!(f, "unknown command {}, see help", name),
+            Self::Arguments(name) => write!(f, "wrong arguments for {}, see help", name),
+            Self::Number(arg) => write!(f, "bad number {}", arg),
+            Self::Register(arg) => write!(f, "unknown register {}", arg),
+        }
+    }
+}
+impl Error for CommandError {}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Parse and run `line`, unwrapping emulation errors.
+    fn run(emu: &mut Emulator, paused: &mut bool, line: &str) -> Result<String, CommandError> {
+        Ok(line.parse::<Command>()?.run(emu, paused).unwrap())
+    }
+
+    #[test]
+    fn test_console() {
+        // 0x200: LD V3, 0x2A; JP 0x200
+        let mut emu = Emulator::new();
+        emu.load_rom(&[0x63, 0x2A, 0x12, 0x00]).unwrap();
+        let mut paused = false;
+        let mut run = |line: &str| run(&mut emu, &mut paused, line);
+
+        assert_eq!(run("break 0x202").unwrap(), "Breakpoint at 0x202");
+        assert_eq!(run("step").unwrap(), "> 202: 1200  JP 0x200");
+        assert_eq!(run("print V3").unwrap(), "V3 = 0x2A (42)");
+        assert_eq!(run("poke 0x300 0xFF").unwrap(), "0x300 = 0xFF");
+        assert_eq!(run("mem 0x2FE 4").unwrap(), "2FE: 00 00 FF 00");
+        assert_eq!(
+            run("disasm 0x200 2").unwrap(),
+            "  200: 632A  LD V3, 0x2A\n> 202: 1200  JP 0x200"
+        );
+        assert_eq!(run("d 0x202").unwrap(), "Removed breakpoint at 0x202");
+        assert_eq!(
+            run("poke 0x300 256"),
+            Err(CommandError::Number("256".into()))
+        );
+        assert_eq!(run("print V10"), Err(CommandError::Register("V10".into())));
+        assert_eq!(run("step 1 2"), Err(CommandError::Arguments("step".into())));
+        assert_eq!(run("jump"), Err(CommandError::Unknown("jump".into())));
+        run("continue").unwrap();
+        assert!(!paused);
+
+        // Steps stop early while waiting for a key, or once halted
+        // 0x200: LD V0, K
+        let mut emu = Emulator::new();
+        emu.load_rom(&[0xF0, 0x0A]).unwrap();
+        let step = self::run(&mut emu, &mut paused, "step 4000000000");
+        assert_eq!(step.unwrap(), "> 202: 0000  NOP");
+        assert_eq!(emu.cycles(), 1);
+    }
+}
@@ -0,0 +1,343 @@
+//! Breakpoints and watchpoints.
+use std::{collections::BTreeSet, fmt, ops::RangeInclusive};
+
+use super::{emulator::NUM_REGISTERS, opcodes::Instruction, Emulator};
+
+/// Condition which stops execution when met.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Watchpoint {
+    /// Stop after an instruction reads from the RAM range.
+    Read(RangeInclusive<u16>),
+    /// Stop after an instruction writes to the RAM range.
+    Write(RangeInclusive<u16>),
+    /// Stop after an instruction sets a V register to the value.
+    Register {
+        /// Register index.
+        x: u8,
+        /// Value to watch for.
+        value: u8,
+    },
+}
+
+/// Why execution stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StopReason {
+    /// Program counter reached a breakpoint. The instruction there has not been executed yet.
+    Breakpoint(u16),
+    /// An instruction read from a watched RAM address.
+    RamRead(u16),
+    /// An instruction wrote to a watched RAM address.
+    RamWrite(u16),
+    /// An instruction set a watched V register to the watched value.
+    Register {
+        /// Register index.
+        x: u8,
+        /// New register value.
+        value: u8,
+    },
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Breakpoint(addr) => write!(f, "breakpoint at {:#05X}", addr),
+            Self::RamRead(addr) => write!(f, "read from {:#05X}", addr),
+            Self::RamWrite(addr) => write!(f, "write to {:#05X}", addr),
+            Self::Register { x, value } => write!(f, "V{:X} set to {:#04X}", x, value),
+        }
+    }
+}
+
+/// Result of a single emulator tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TickResult {
+    /// Instruction executed, or `None` if execution stopped at a breakpoint before executing it.
+    pub instruction: Option<Instruction>,
+    /// Why execution stopped, if it did.
+    pub stop: Option<StopReason>,
+}
+impl TickResult {
+    /// Whether execution stopped on a breakpoint or watchpoint.
+    pub fn is_stopped(&self) -> bool {
+        self.stop.is_some()
+    }
+}
+
+/// Default maximum number of instructions executed by a single run.
+pub const DEFAULT_INSTRUCTION_BUDGET: usize = 1_000_000;
+
+/// Condition which ends [Emulator::run_until].
+pub trait RunCondition {
+    /// Whether the condition is met.
+    fn is_met(&mut self, emu: &Emulator) -> bool;
+}
+/// Run until the program counter reaches the address.
+impl RunCondition for u16 {
+    fn is_met(&mut self, emu: &Emulator) -> bool {
+        emu.program_counter == *self
+    }
+}
+/// Run until the closure returns `true`.
+impl<F: FnMut(&Emulator) -> bool> RunCondition for F {
+    fn is_met(&mut self, emu: &Emulator) -> bool {
+        self(emu)
+    }
+}
+
+/// Why a run ended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RunOutcome {
+    /// The run condition was met, or a frame ran to completion.
+    #[default]
+    Reached,
+    /// A breakpoint or watchpoint stopped execution.
+    Stopped(StopReason),
+    /// The emulator halted or exited. See [Emulator::state].
+    Ended,
+    /// The instruction budget ran out first.
+    BudgetExhausted,
+}
+
+/// Result of running multiple instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RunResult {
+    /// Number of instructions executed.
+    pub executed: usize,
+    /// Why the run ended.
+    pub outcome: RunOutcome,
+}
+
+/// Breakpoints and watchpoints checked by [Emulator::tick].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    watchpoints: Vec<Watchpoint>,
+    /// Breakpoint execution last stopped at, which is skipped when resuming.
+    resume_from: Option<u16>,
+}
+impl Debugger {
+    /// Create a debugger without any breakpoints or watchpoints.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a breakpoint. Returns `false` if it already existed.
+    pub fn add_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.insert(addr)
+    }
+
+    /// Remove a breakpoint. Returns `false` if it did not exist.
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    /// Iterate over breakpoint addresses in ascending order.
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Add a watchpoint.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+    }
+
+    /// Remove a watchpoint. Returns `false` if it did not exist.
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        let len = self.watchpoints.len();
+        self.watchpoints.retain(|w| w != watchpoint);
+        self.watchpoints.len() != len
+    }
+
+    /// Return all watchpoints.
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Remove every breakpoint and watchpoint.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Whether there are no breakpoints or watchpoints.
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty() && self.watchpoints.is_empty()
+    }
+
+    /// Check for a breakpoint before executing the instruction at `pc`.
+    pub(crate) fn check_breakpoint(&mut self, pc: u16) -> Option<StopReason> {
+        if self.resume_from.take() == Some(pc) || !self.breakpoints.contains(&pc) {
+            return None;
+        }
+        self.resume_from = Some(pc);
+        Some(StopReason::Breakpoint(pc))
+    }
+
+    /// Skip the breakpoint at `pc` on the next tick.
+    pub(crate) fn resume(&mut self, pc: u16) {
+        self.resume_from = Some(pc);
+    }
+
+    /// Capture what is needed to check watchpoints before executing an instruction.
+    pub(crate) fn before(&self, emu: &Emulator, instruction: &Instruction) -> Pending {
+        let access = instruction.ram_access(emu.i_register);
+        let ram = access.and_then(|(write, start, len)| {
+            let end = start.saturating_add(len - 1);
+            self.watchpoints.iter().find_map(|watchpoint| {
+                let range = match (watchpoint, write) {
+                    (Watchpoint::Read(range), false) | (Watchpoint::Write(range), true) => range,
+                    _ => return None,
+                };
+                let addr = start.max(*range.start());
+                if addr > end || addr > *range.end() {
+                    return None;
+                }
+                Some(if write {
+                    StopReason::RamWrite(addr)
+                } else {
+                    StopReason::RamRead(addr)
+                })
+            })
+        });
+
+        Pending {
+            ram,
+            v_registers: emu.v_registers,
+        }
+    }
+
+    /// Check watchpoints after executing an instruction.
+    pub(crate) fn after(&self, emu: &Emulator, pending: Pending) -> Option<StopReason> {
+        pending.ram.or_else(|| {
+            self.watchpoints
+                .iter()
+                .find_map(|watchpoint| match *watchpoint {
+                    Watchpoint::Register { x, value }
+                        if (x as usize) < NUM_REGISTERS
+                            && pending.v_registers[x as usize] != value
+                            && emu.v(x) == value =>
+                    {
+                        Some(StopReason::Register { x, value })
+                    }
+                    _ => None,
+                })
+        })
+    }
+}
+
+/// State captured before executing an instruction.
+pub(crate) struct Pending {
+    ram: Option<StopReason>,
+    v_registers: [u8; NUM_REGISTERS],
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const ROM: [u8; 12] = [
+        0x60, 0x05, // 0x200: LD V0, 5
+        0xA3, 0x00, // 0x202: LD I, 0x300
+        0xF0, 0x33, // 0x204: LD B, V0
+        0xF1, 0x65, // 0x206: LD V1, [I]
+        0x70, 0x01, // 0x208: ADD V0, 1
+        0x12, 0x08, // 0x20A: JP 0x208
+    ];
+
+    fn run_until_stop(emu: &mut Emulator) -> StopReason {
+        loop {
+            if let Some(stop) = emu.tick().unwrap().stop {
+                return stop;
+            }
+        }
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&ROM).unwrap();
+        emu.debugger_mut().add_breakpoint(0x208);
+
+        assert_eq!(run_until_stop(&mut emu), StopReason::Breakpoint(0x208));
+        assert_eq!(emu.program_counter, 0x208);
+        assert_eq!(emu.v(0_usize), 0);
+
+        // Resuming executes the instruction at the breakpoint, then stops there again.
+        assert_eq!(run_until_stop(&mut emu), StopReason::Breakpoint(0x208));
+        assert_eq!(emu.v(0_usize), 1);
+
+        assert!(emu.debugger_mut().remove_breakpoint(0x208));
+        assert!(emu.debugger().is_empty());
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&ROM).unwrap();
+        emu.debugger_mut()
+            .add_watchpoint(Watchpoint::Write(0x301..=0x310));
+        emu.debugger_mut()
+            .add_watchpoint(Watchpoint::Read(0x2FF..=0x300));
+        emu.debugger_mut()
+            .add_watchpoint(Watchpoint::Register { x: 0, value: 8 });
+
+        let stop = run_until_stop(&mut emu);
+        assert_eq!(stop, StopReason::RamWrite(0x301));
+        assert_eq!(stop.to_string(), "write to 0x301");
+        assert_eq!(emu.program_counter, 0x206);
+        assert_eq!(run_until_stop(&mut emu), StopReason::RamRead(0x300));
+        assert_eq!(emu.v(1_usize), 0);
+        assert_eq!(
+            run_until_stop(&mut emu),
+            StopReason::Register { x: 0, value: 8 }
+        );
+        assert_eq!(emu.program_counter, 0x20A);
+    }
+
+    #[test]
+    fn test_step_and_run_until() {
+        let rom = [
+            0x22, 0x06, // 0x200: CALL 0x206
+            0x70, 0x01, // 0x202: ADD V0, 1
+            0x12, 0x02, // 0x204: JP 0x202
+            0x61, 0x07, // 0x206: LD V1, 7
+            0x00, 0xEE, // 0x208: RET
+        ];
+        let mut emu = Emulator::new();
+        emu.load_rom(&rom).unwrap();
+        emu.debugger_mut().add_breakpoint(0x200);
+
+        // Stepping ignores the breakpoint under the program counter.
+        let result = emu.step_over(100).unwrap();
+        assert_eq!(
+            result,
+            RunResult {
+                executed: 3,
+                outcome: RunOutcome::Reached
+            }
+        );
+        assert_eq!(emu.program_counter, 0x202);
+        assert_eq!(emu.v(1_usize), 7);
+
+        emu.step().unwrap();
+        assert_eq!(emu.v(0_usize), 1);
+
+        let result = emu
+            .run_until(|emu: &Emulator| emu.v(0_usize) == 5, 100)
+            .unwrap();
+        assert_eq!(result.outcome, RunOutcome::Reached);
+        assert_eq!(result.executed, 8);
+
+        let result = emu.run_until(0x206, 50).unwrap();
+        assert_eq!(
+            result,
+            RunResult {
+                executed: 50,
+                outcome: RunOutcome::BudgetExhausted
+            }
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! Decompiler from ROMs to Octo source which compiles back to the same bytes.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use super::{
+    analyze::analyze,
+    emulator::START_ADDRESS,
+    opcodes::{decode, Instruction},
+};
+
+/// Raw bytes per line of data.
+const BYTES_PER_LINE: usize = 8;
+
+/// How an address is referred to, in increasing order of precedence for naming its label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Reference {
+    /// Loaded into I.
+    Data,
+    /// Jumped to.
+    Jump,
+    /// Called as a subroutine.
+    Call,
+}
+
+/// Decompile a ROM loaded at [START_ADDRESS] into Octo source for [crate::octo::compile].
+///
+/// Code reachable from the start is lifted into statements, with labels for jump, call and I
+/// targets, `if ... then` for skips and `loop ... again` for backward jumps nothing else targets.
+/// Everything else is emitted as data, in binary where it is drawn as a sprite.
+pub fn decompile(rom: &[u8]) -> String {
+    let start = START_ADDRESS as usize;
+    let end = start + rom.len();
+    let byte = |addr: usize| rom[addr - start];
+    let code = analyze(rom, START_ADDRESS).code;
+
+    // Split the ROM into instructions and data.
+    let mut instructions = BTreeMap::new();
+    let mut addr = start;
+    while addr < end {
+        if code.contains(&(addr as u16)) && addr + 1 < end && !code.contains(&(addr as u16 + 1)) {
+            instructions.insert(addr, u16::from_be_bytes([byte(addr), byte(addr + 1)]));
+            addr += 2;
+        } else {
+            addr += 1;
+        }
+    }
+
+    let mut labels = BTreeMap::new();
+    let mut references: BTreeMap<usize, usize> = BTreeMap::new();
+    for op in instructions.values() {
+        let (target, reference) = match decode(*op) {
+            Some(Instruction::Call(target)) => (target, Reference::Call),
+            Some(Instruction::Jp(target) | Instruction::JpV0(target)) => (target, Reference::Jump),
+            Some(Instruction::LdIAddr(target)) => (target, Reference::Data),
+            _ => continue,
+        };
+        let target = target as usize;
+        if (start..end).contains(&target) {
+            *references.entry(target).or_default() += 1;
+            let label = labels.entry(target).or_insert(reference);
+            *label = (*label).max(reference);
+        }
+    }
+    // Labels can't go inside an instruction, so such instructions become data.
+    instructions.retain(|addr, _| !labels.contains_key(&(addr + 1)));
+
+    // Data drawn as sprites.
+    let mut sprites = BTreeSet::new();
+    let mut i = None;
+    for op in instructions.values() {
+        match decode(*op) {
+            Some(Instruction::LdIAddr(addr)) => i = Some(addr as usize),
+            Some(Instruction::Drw { n, .. }) => {
+                sprites.extend(i.map_or(0..0, |i| i..i + n as usize))
+            }
+            Some(
+                Instruction::AddIVx { .. }
+                | Instruction::LdFVx { .. }
+                | Instruction::LdIVx { .. }
+                | Instruction::LdVxI { .. },
+            ) => i = None,
+            _ => {}
+        }
+    }
+
+    // Loops: backward jumps over unbroken code to a target nothing else refers to, each nested
+    // inside or apart from the others.
+    let mut candidates: Vec<_> = instructions
+        .iter()
+        .filter_map(|(addr, op)| match decode(*op) {
+            Some(Instruction::Jp(target)) => Some((target as usize, *addr)),
+            _ => None,
+        })
+        .filter(|(target, addr)| {
+            (start..=*addr).contains(target)
+                && references.get(target) == Some(&1)
+                && (*target..=*addr)
+                    .step_by(2)
+                    .all(|addr| instructions.contains_key(&addr))
+        })
+        .collect();
+    candidates.sort_by_key(|(target, addr)| (*target, std::cmp::Reverse(*addr)));
+    let mut loops: BTreeMap<usize, usize> = BTreeMap::new();
+    for (target, addr) in candidates {
+        let fits = loops.iter().all(|(other_target, other_addr)| {
+            addr < *other_target
+                || *other_addr < target
+                || (*other_target <= target && addr <= *other_addr)
+        });
+        if fits {
+            loops.insert(target, addr);
+            labels.remove(&target);
+        }
+    }
+
+    let mut source = Source::default();
+    let mut loop_ends = Vec::new();
+    let mut addr = start;
+    while addr < end {
+        if addr == start || labels.contains_key(&addr) {
+            source.flush();
+            let name = label_name(addr, &labels);
+            source.depth = 0;
+            source.line(&format!(": {}", name));
+            source.depth = 1 + loop_ends.len();
+        }
+        if let Some(loop_end) = loops.get(&addr) {
+            source.flush();
+            source.line("loop");
+            loop_ends.push(*loop_end);
+            source.depth += 1;
+        }
+
+        let Some(op) = instructions.get(&addr) else {
+            // Data runs until the next instruction or label, split where sprites start or stop.
+            let sprite = sprites.contains(&addr);
+            let run_end = (addr + 1..end)
+                .find(|next| {
+                    instructions.contains_key(next)
+                        || labels.contains_key(next)
+                        || sprites.contains(next) != sprite
+                })
+                .unwrap_or(end);
+            source.flush();
+            let bytes = &rom[addr - start..run_end - start];
+            if sprite {
+                for byte in bytes {
+                    source.line(&format!("{:#010b}", byte));
+                }
+            } else {
+                for chunk in bytes.chunks(BYTES_PER_LINE) {
+                    let line: Vec<_> = chunk.iter().map(|byte| format!("{:#04X}", byte)).collect();
+                    source.line(&line.join(" "));
+                }
+            }
+            addr = run_end;
+            continue;
+        };
+
+        let instruction = decode(*op);
+        if loop_ends.last() == Some(&addr) {
+            loop_ends.pop();
+            source.depth -= 1;
+            source.statement("again");
+        } else if let Some(condition) = instruction.and_then(|i| condition(&i)) {
+            source.flush();
+            source.pending = Some(format!("if {} then", condition));
+        } else {
+            let name = |target: u16| {
+                let target = target as usize;
+                match labels.contains_key(&target) || target == start {
+                    true => Some(label_name(target, &labels)),
+                    false => None,
+                }
+            };
+            let text = instruction
+                .and_then(|instruction| statement(&instruction, name))
+                .unwrap_or_else(|| {
+                    let [high, low] = op.to_be_bytes();
+                    format!("{:#04X} {:#04X} # {:04X}", high, low, op)
+                });
+            source.statement(&text);
+        }
+        addr += 2;
+    }
+    source.flush();
+    source.text
+}
+
+/// Name the label at `addr`.
+fn label_name(addr: usize, labels: &BTreeMap<usize, Reference>) -> String {
+    match labels.get(&addr) {
+        _ if addr == START_ADDRESS as usize => "main".to_string(),
+        Some(Reference::Call) => format!("sub_{:X}", addr),
+        Some(Reference::Jump) => format!("label_{:X}", addr),
+        Some(Reference::Data) | None => format!("data_{:X}", addr),
+    }
+}
+
+/// Return the condition under which a skip instruction does not skip, in `if ... then` form.
+fn condition(instruction: &Instruction) -> Option<String> {
+    let condition = match *instruction {
+        Instruction::SeVxByte { x, byte } => format!("v{:X} != {}", x, byte),
+        Instruction::SneVxByte { x, byte } => format!("v{:X} == {}", x, byte),
+        Instruction::SeVxVy { x, y } => format!("v{:X} != v{:X}", x, y),
+        Instruction::SneVxVy { x, y } => format!("v{:X} == v{:X}", x, y),
+        Instruction::Skp { x } => format!("v{:X} -key", x),
+        Instruction::Sknp { x } => format!("v{:X} key", x),
+        _ => return None,
+    };
+    Some(condition)
+}
+
+/// Write an instruction as an Octo statement, naming addresses with `name`. Returns `None` if it
+/// has to be written as raw bytes.
+fn statement(instruction: &Instruction, name: impl Fn(u16) -> Option<String>) -> Option<String> {
+    let address = |addr: u16| name(addr).unwrap_or_else(|| format!("{:#05X}", addr));
+    let statement = match *instruction {
+        Instruction::Cls => "clear".to_string(),
+        Instruction::Ret => "return".to_string(),
+        Instruction::Exit => "exit".to_string(),
+        Instruction::Jp(addr) => format!("jump {}", address(addr)),
+        Instruction::Call(addr) => name(addr)?,
+        Instruction::LdVxByte { x, byte } => format!("v{:X} := {}", x, byte),
+        Instruction::AddVxByte { x, byte } => format!("v{:X} += {}", x, byte),
+        Instruction::LdVxVy { x, y } => format!("v{:X} := v{:X}", x, y),
+        Instruction::Or { x, y } => format!("v{:X} |= v{:X}", x, y),
+        Instruction::And { x, y } => format!("v{:X} &= v{:X}", x, y),
+        Instruction::Xor { x, y } => format!("v{:X} ^= v{:X}", x, y),
+        Instruction::AddVxVy { x, y } => format!("v{:X} += v{:X}", x, y),
+        Instruction::SubVxVy { x, y } => format!("v{:X} -= v{:X}", x, y),
+        Instruction::Shr { x, y } => format!("v{:X} >>= v{:X}", x, y),
+        Instruction::SubnVxVy { x, y } => format!("v{:X} =- v{:X}", x, y),
+        Instruction::Shl { x, y } => format!("v{:X} <<= v{:X}", x, y),
+        Instruction::LdIAddr(addr) => format!("i := {}", address(addr)),
+        Instruction::JpV0(addr) => format!("jump0 {}", address(addr)),
+        Instruction::Rnd { x, byte } => format!("v{:X} := random {}", x, byte),
+        Instruction::Drw { x, y, n } => format!("sprite v{:X} v{:X} {}", x, y, n),
+        Instruction::LdVxDt { x } => format!("v{:X} := delay", x),
+        Instruction::LdVxK { x } => format!("v{:X} := key", x),
+        Instruction::LdDtVx { x } => format!("delay := v{:X}", x),
+        Instruction::LdStVx { x } => format!("buzzer := v{:X}", x),
+        Instruction::AddIVx { x } => format!("i += v{:X}", x),
+        Instruction::LdFVx { x } => format!("i := hex v{:X}", x),
+        Instruction::LdBVx { x } => format!("bcd v{:X}", x),
+        Instruction::LdIVx { x } => format!("save v{:X}", x),
+        Instruction::LdVxI { x } => format!("load v{:X}", x),
+        _ => return None,
+    };
+    Some(statement)
+}
+
+/// Source text being written, with indentation and an `if ... then` awaiting its statement.
+#[derive(Default)]
+struct Source {
+    text: String,
+    depth: usize,
+    pending: Option<String>,
+}
+impl Source {
+    fn line(&mut self, line: &str) {
+        let _ = writeln!(self.text, "{}{}", "    ".repeat(self.depth), line);
+    }
+
+    /// Write a statement, after the pending `if ... then` if any.
+    fn statement(&mut self, statement: &str) {
+        match self.pending.take() {
+            Some(condition) => self.line(&format!("{} {}", condition, statement)),
+            None => self.line(statement),
+        }
+    }
+
+    /// Write the pending `if ... then` on its own line.
+    fn flush(&mut self) {
+        if let Some(condition) = self.pending.take() {
+            self.line(&condition);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::octo::compile;
+
+    use super::*;
+
+    #[test]
+    fn test_decompile() {
+        let rom = compile(
+            "
+            : main
+                clear
+                v0 := 0
+                loop
+                    i := ball
+                    sprite v0 v0 3
+                    v0 += 1
+                    if v0 == 8 then update
+                again
+            : update
+                v1 := random 0x0F
+                if v1 key then v2 := 3
+                return
+            : ball
+                0b01000000
+                0b11100000
+                0b01000000
+            ",
+        )
+        .unwrap();
+        let source = decompile(&rom);
+        assert_eq!(
+            source,
+            "\
+: main
+    clear
+    v0 := 0
+    loop
+        i := data_218
+        sprite v0 v0 3
+        v0 += 1
+        if v0 == 8 then sub_210
+    again
+: sub_210
+    v1 := random 15
+    if v1 key then v2 := 3
+    return
+: data_218
+    0b01000000
+    0b11100000
+    0b01000000
+"
+        );
+        assert_eq!(compile(&source).unwrap(), rom);
+
+        for rom in [
+            &include_bytes!("../../roms/BRIX")[..],
+            include_bytes!("../../roms/INVADERS"),
+            include_bytes!("../../roms/TETRIS"),
+        ] {
+            assert_eq!(compile(&decompile(rom)).unwrap(), rom);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Diagnostics for suspicious memory use, emitted as [crate::Event::Diagnostic].
+use std::{collections::HashSet, fmt, ops::Range};
+
+use super::opcodes::Instruction;
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Unusual but often deliberate.
+    Info,
+    /// Probably a bug.
+    Warning,
+}
+
+/// Kind of suspicious memory use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    /// An opcode was executed from an address the ROM wrote to earlier.
+    SelfModifyingCode,
+    /// An opcode was executed from below the start address, i.e. from interpreter memory.
+    ExecuteBelowStart,
+    /// Data was read from an address that was never loaded or written.
+    UninitializedRead,
+}
+impl DiagnosticKind {
+    /// Return the severity of this kind of diagnostic.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::SelfModifyingCode => Severity::Info,
+            Self::ExecuteBelowStart | Self::UninitializedRead => Severity::Warning,
+        }
+    }
+}
+
+/// A single diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// What happened.
+    pub kind: DiagnosticKind,
+    /// Address of the instruction responsible.
+    pub pc: u16,
+    /// Address the diagnostic is about.
+    pub addr: u16,
+}
+impl Diagnostic {
+    /// Return the severity of the diagnostic.
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity() {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+        };
+        let message = match self.kind {
+            DiagnosticKind::SelfModifyingCode => "executing modified code at",
+            DiagnosticKind::ExecuteBelowStart => "executing below the start address at",
+            DiagnosticKind::UninitializedRead => "reading uninitialized RAM at",
+        };
+        write!(
+            f,
+            "{}: {} {:#05X} (PC {:#05X})",
+            severity, message, self.addr, self.pc
+        )
+    }
+}
+
+/// Watches memory use for suspicious patterns. Attach to an emulator with
+/// [crate::Emulator::set_diagnostics]; each address is reported at most once per kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// Addresses loaded or written.
+    initialized: Vec<bool>,
+    /// Addresses written by instructions.
+    written: Vec<bool>,
+    reported: HashSet<(DiagnosticKind, u16)>,
+    silenced: HashSet<DiagnosticKind>,
+    min_severity: Severity,
+}
+impl Diagnostics {
+    /// Create diagnostics for `ram_size` bytes of RAM, all uninitialized, reporting everything.
+    pub fn new(ram_size: usize) -> Self {
+        Self {
+            initialized: vec![false; ram_size],
+            written: vec![false; ram_size],
+            reported: HashSet::new(),
+            silenced: HashSet::new(),
+            min_severity: Severity::Info,
+        }
+    }
+
+    /// Stop reporting a kind of diagnostic.
+    pub fn silence(&mut self, kind: DiagnosticKind) {
+        self.silenced.insert(kind);
+    }
+
+    /// Resume reporting a kind of diagnostic.
+    pub fn unsilence(&mut self, kind: DiagnosticKind) {
+        self.silenced.remove(&kind);
+    }
+
+    /// Only report diagnostics at least this severe.
+    pub fn set_min_severity(&mut self, severity: Severity) {
+        self.min_severity = severity;
+    }
+
+    /// Return the least severe diagnostic reported.
+    pub fn min_severity(&self) -> Severity {
+        self.min_severity
+    }
+
+    /// Mark RAM as initialized, e.g. after loading data into it.
+    pub fn mark_initialized(&mut self, range: Range<usize>) {
+        let end = range.end.min(self.initialized.len());
+        if let Some(bytes) = self.initialized.get_mut(range.start..end) {
+            bytes.fill(true);
+        }
+    }
+
+    /// Forget everything written and reported, keeping the settings.
+    pub fn clear(&mut self) {
+        self.initialized.fill(false);
+        self.written.fill(false);
+        self.reported.clear();
+    }
+
+    /// Check an instruction at `pc` with I holding `i` before executing it, marking what it
+    /// writes. Returns the new diagnostics.
+    pub(crate) fn check(
+        &mut self,
+        pc: u16,
+        i: u16,
+        start_address: u16,
+        instruction: &Instruction,
+    ) -> Vec<Diagnostic> {
+        let mut found = Vec::new();
+        if pc < start_address {
+            self.report(&mut found, DiagnosticKind::ExecuteBelowStart, pc, pc);
+        }
+        let modified = [pc, pc.wrapping_add(1)]
+            .into_iter()
+            .find(|addr| self.written.get(*addr as usize) == Some(&true));
+        if let Some(addr) = modified {
+            self.report(&mut found, DiagnosticKind::SelfModifyingCode, pc, addr);
+        }
+
+        if let Some((write, start, len)) = instruction.ram_access(i) {
+            let range = start as usize..(start as usize + len as usize).min(self.written.len());
+            for addr in range {
+                if write {
+                    self.written[addr] = true;
+                    self.initialized[addr] = true;
+                } else if !self.initialized[addr] {
+                    self.report(
+                        &mut found,
+                        DiagnosticKind::UninitializedRead,
+                        pc,
+                        addr as u16,
+                    );
+                }
+            }
+        }
+        found
+    }
+
+    fn report(&mut self, found: &mut Vec<Diagnostic>, kind: DiagnosticKind, pc: u16, addr: u16) {
+        if self.silenced.contains(&kind) || kind.severity() < self.min_severity {
+            return;
+        }
+        if self.reported.insert((kind, addr)) {
+            found.push(Diagnostic { kind, pc, addr });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{Emulator, Event};
+
+    use super::*;
+
+    #[test]
+    fn test_diagnostics() {
+        let mut emu = Emulator::new();
+        emu.set_diagnostics(Some(Diagnostics::new(emu.ram().len())));
+        emu.load_rom(&[
+            0x60, 0x12, // 0x200: LD V0, 0x12
+            0x61, 0x0A, // 0x202: LD V1, 0x0A
+            0xA2, 0x0A, // 0x204: LD I, 0x20A
+            0xF1, 0x55, // 0x206: LD [I], V1
+            0xF0, 0x65, // 0x208: LD V0, [I]
+        ])
+        .unwrap();
+        for _ in 0..5 {
+            emu.tick().unwrap();
+        }
+        let diagnostics = |emu: &mut Emulator| -> Vec<_> {
+            emu.drain_events()
+                .filter_map(|event| match event {
+                    Event::Diagnostic(diagnostic) => Some(diagnostic),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(diagnostics(&mut emu), []);
+
+        // 0x20A: JP 0x20A, written by the ROM itself.
+        emu.tick().unwrap();
+        assert_eq!(
+            diagnostics(&mut emu),
+            [Diagnostic {
+                kind: DiagnosticKind::SelfModifyingCode,
+                pc: 0x20A,
+                addr: 0x20A,
+            }]
+        );
+
+        // Reading past the ROM, unless silenced.
+        emu.set_pc(0x208);
+        emu.set_i(0x300);
+        emu.diagnostics_mut()
+            .unwrap()
+            .set_min_severity(Severity::Warning);
+        emu.tick().unwrap();
+        let found = diagnostics(&mut emu);
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].to_string(),
+            "warning: reading uninitialized RAM at 0x300 (PC 0x208)"
+        );
+        emu.diagnostics_mut()
+            .unwrap()
+            .silence(DiagnosticKind::ExecuteBelowStart);
+        emu.set_pc(0x100);
+        emu.tick().unwrap();
+        assert_eq!(diagnostics(&mut emu), []);
+    }
+}
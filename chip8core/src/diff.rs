@@ -0,0 +1,242 @@
+//! Differences between two machine states, for tracking down where emulators diverge.
+use std::{fmt, ops::Range};
+
+use super::{
+    emulator::{DISPLAY_WIDTH, NUM_KEYS},
+    EmulatorState, SaveState,
+};
+
+/// A register, timer or other scalar that differs between two states.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegisterChange {
+    /// Name of the register, e.g. `PC`, `VF` or `stack[0]`.
+    pub name: String,
+    /// Value in the first state.
+    pub old: u32,
+    /// Value in the second state.
+    pub new: u32,
+}
+
+/// A run of consecutive RAM bytes that differ between two states.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RamChange {
+    /// Addresses of the changed bytes.
+    pub range: Range<usize>,
+    /// Bytes in the first state.
+    pub old: Vec<u8>,
+    /// Bytes in the second state.
+    pub new: Vec<u8>,
+}
+
+/// A display pixel whose colour differs between two states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PixelChange {
+    /// Column.
+    pub x: usize,
+    /// Row.
+    pub y: usize,
+    /// Colour index in the first state.
+    pub old: u8,
+    /// Colour index in the second state.
+    pub new: u8,
+}
+
+/// Everything that differs between two [SaveState]s. See [SaveState::diff].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// Changed registers, timers, stack entries and keys.
+    pub registers: Vec<RegisterChange>,
+    /// Changed RAM, merged into runs of consecutive addresses.
+    pub ram: Vec<RamChange>,
+    /// Changed display pixels, row-major.
+    pub pixels: Vec<PixelChange>,
+    /// Execution states, if they differ.
+    pub execution_state: Option<(EmulatorState, EmulatorState)>,
+}
+impl StateDiff {
+    /// Whether the states are identical, ignoring ROM information.
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+            && self.ram.is_empty()
+            && self.pixels.is_empty()
+            && self.execution_state.is_none()
+    }
+}
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        if let Some((old, new)) = &self.execution_state {
+            writeln!(f, "state: {:?} -> {:?}", old, new)?;
+        }
+        for change in &self.registers {
+            writeln!(f, "{}: {:#X} -> {:#X}", change.name, change.old, change.new)?;
+        }
+        for change in &self.ram {
+            writeln!(
+                f,
+                "RAM {:#05X}..{:#05X}: {:02X?} -> {:02X?}",
+                change.range.start, change.range.end, change.old, change.new
+            )?;
+        }
+        if !self.pixels.is_empty() {
+            write!(f, "{} pixel(s) changed:", self.pixels.len())?;
+            for change in &self.pixels {
+                write!(
+                    f,
+                    " ({}, {}) {}->{}",
+                    change.x, change.y, change.old, change.new
+                )?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl SaveState {
+    /// List everything that differs from `other`, treating `self` as the old state.
+    pub fn diff(&self, other: &SaveState) -> StateDiff {
+        let mut diff = StateDiff::default();
+
+        let mut register = |name: String, old: u32, new: u32| {
+            if old != new {
+                diff.registers.push(RegisterChange { name, old, new });
+            }
+        };
+        register(
+            "PC".to_string(),
+            self.program_counter.into(),
+            other.program_counter.into(),
+        );
+        register(
+            "I".to_string(),
+            self.i_register.into(),
+            other.i_register.into(),
+        );
+        for (x, (old, new)) in self.v_registers.iter().zip(&other.v_registers).enumerate() {
+            register(format!("V{:X}", x), (*old).into(), (*new).into());
+        }
+        register(
+            "SP".to_string(),
+            self.stack_pointer.into(),
+            other.stack_pointer.into(),
+        );
+        register(
+            "stack depth".to_string(),
+            self.stack.len() as u32,
+            other.stack.len() as u32,
+        );
+        for (n, (old, new)) in self.stack.iter().zip(&other.stack).enumerate() {
+            register(format!("stack[{}]", n), (*old).into(), (*new).into());
+        }
+        register(
+            "DT".to_string(),
+            self.delay_timer.into(),
+            other.delay_timer.into(),
+        );
+        register(
+            "ST".to_string(),
+            self.sound_timer.into(),
+            other.sound_timer.into(),
+        );
+        for key in 0..NUM_KEYS {
+            register(
+                format!("key {:X}", key),
+                self.keys[key].into(),
+                other.keys[key].into(),
+            );
+        }
+        register(
+            "RAM size".to_string(),
+            self.ram.len() as u32,
+            other.ram.len() as u32,
+        );
+        register(
+            "planes".to_string(),
+            self.display.selected_planes().into(),
+            other.display.selected_planes().into(),
+        );
+
+        // Merge changed bytes into runs
+        let len = self.ram.len().min(other.ram.len());
+        let mut addr = 0;
+        while addr < len {
+            if self.ram[addr] == other.ram[addr] {
+                addr += 1;
+                continue;
+            }
+            let start = addr;
+            while addr < len && self.ram[addr] != other.ram[addr] {
+                addr += 1;
+            }
+            diff.ram.push(RamChange {
+                range: start..addr,
+                old: self.ram[start..addr].to_vec(),
+                new: other.ram[start..addr].to_vec(),
+            });
+        }
+
+        diff.pixels = self
+            .display
+            .colors()
+            .zip(other.display.colors())
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(idx, (old, new))| PixelChange {
+                x: idx % DISPLAY_WIDTH,
+                y: idx / DISPLAY_WIDTH,
+                old,
+                new,
+            })
+            .collect();
+
+        if self.execution_state != other.execution_state {
+            diff.execution_state = Some((self.execution_state, other.execution_state));
+        }
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let mut emu = Emulator::with_seed(0);
+        // 0x200: LD V3, 0x2A; LD I, 0x300; LD [I], V1; LD I, 0x301; DRW V0, V0, 1
+        emu.load_rom(&[0x63, 0x2A, 0xA3, 0x00, 0xF1, 0x55, 0xA3, 0x01, 0xD0, 0x01])
+            .unwrap();
+        emu.set_v(1_usize, 7);
+        let before = emu.save_state();
+        assert!(before.diff(&before).is_empty());
+
+        for _ in 0..5 {
+            emu.tick().unwrap();
+        }
+        let diff = before.diff(&emu.save_state());
+        let names: Vec<_> = diff.registers.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["PC", "I", "V3"]);
+        assert_eq!(
+            diff.ram,
+            vec![RamChange {
+                range: 0x301..0x302,
+                old: vec![0],
+                new: vec![7],
+            }]
+        );
+        assert_eq!(diff.pixels.len(), 3);
+        assert_eq!(
+            diff.to_string(),
+            "PC: 0x200 -> 0x20A\nI: 0x0 -> 0x301\nV3: 0x0 -> 0x2A\n\
+             RAM 0x301..0x302: [00] -> [07]\n\
+             3 pixel(s) changed: (5, 0) 0->1 (6, 0) 0->1 (7, 0) 0->1\n"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Screen made of one or more bit planes.
+use super::emulator::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+/// Number of bit planes. XO-CHIP draws to two planes for four colours.
+pub const NUM_PLANES: usize = 2;
+/// Number of distinct colours the planes can combine into.
+pub const NUM_COLORS: usize = 1 << NUM_PLANES;
+/// Pixels per plane.
+pub const PLANE_SIZE: usize = DISPLAY_WIDTH * DISPLAY_HEIGHT;
+
+/// Screen pixels, stored as bit planes. Each pixel's colour is the combination of its bits across
+/// planes, plane 0 being the least significant. Drawing and clearing only affect the selected
+/// planes, which are just plane 0 by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Display {
+    #[cfg_attr(feature = "serde", serde(with = "serde_planes"))]
+    planes: [[bool; PLANE_SIZE]; NUM_PLANES],
+    selected: u8,
+}
+impl Display {
+    /// Create a blank display with plane 0 selected.
+    pub fn new() -> Self {
+        Self {
+            planes: [[false; PLANE_SIZE]; NUM_PLANES],
+            selected: 1,
+        }
+    }
+
+    /// Return the pixels of plane `n`, if it exists.
+    pub fn plane(&self, n: usize) -> Option<&[bool]> {
+        self.planes.get(n).map(|plane| &plane[..])
+    }
+
+    /// Return the pixels of plane `n` for modification, if it exists.
+    pub fn plane_mut(&mut self, n: usize) -> Option<&mut [bool]> {
+        self.planes.get_mut(n).map(|plane| &mut plane[..])
+    }
+
+    /// Return the bitmask of planes drawn to and cleared.
+    pub fn selected_planes(&self) -> u8 {
+        self.selected
+    }
+
+    /// Set the bitmask of planes drawn to and cleared. Bits beyond [NUM_PLANES] are ignored.
+    pub fn select_planes(&mut self, mask: u8) {
+        self.selected = mask & (NUM_COLORS as u8 - 1);
+    }
+
+    /// Whether plane `n` is selected.
+    fn is_selected(&self, n: usize) -> bool {
+        self.selected & (1 << n) != 0
+    }
+
+    /// Switch off every pixel in the selected planes.
+    pub fn clear(&mut self) {
+        for n in 0..NUM_PLANES {
+            if self.is_selected(n) {
+                self.planes[n] = [false; PLANE_SIZE];
+            }
+        }
+    }
+
+    /// Flip the pixel at `idx` in the selected planes. Returns whether any flipped pixel was on.
+    pub fn flip(&mut self, idx: usize) -> bool {
+        let mut erased = false;
+        for n in 0..NUM_PLANES {
+            if self.is_selected(n) {
+                erased |= self.planes[n][idx];
+                self.planes[n][idx] ^= true;
+            }
+        }
+        erased
+    }
+
+    /// Return the colour index of the pixel at `idx`, combining its bit from every plane.
+    pub fn color(&self, idx: usize) -> u8 {
+        self.planes
+            .iter()
+            .enumerate()
+            .fold(0, |color, (n, plane)| color | ((plane[idx] as u8) << n))
+    }
+
+    /// Iterate over the colour index of every pixel, row-major.
+    pub fn colors(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..PLANE_SIZE).map(|idx| self.color(idx))
+    }
+}
+impl Default for Display {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serialize planes as one byte string per plane, packed 8 pixels to a byte, since serde cannot
+/// handle arrays this large.
+#[cfg(feature = "serde")]
+mod serde_planes {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{NUM_PLANES, PLANE_SIZE};
+    use crate::state::{pack_bits, unpack_bits};
+
+    pub fn serialize<S: Serializer>(
+        planes: &[[bool; PLANE_SIZE]; NUM_PLANES],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let packed: Vec<Vec<u8>> = planes
+            .iter()
+            .map(|plane| plane.chunks(8).map(pack_bits).collect())
+            .collect();
+        packed.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[[bool; PLANE_SIZE]; NUM_PLANES], D::Error> {
+        let packed = Vec::<Vec<u8>>::deserialize(deserializer)?;
+        if packed.len() != NUM_PLANES {
+            return Err(D::Error::invalid_length(
+                packed.len(),
+                &"one entry per plane",
+            ));
+        }
+        let mut planes = [[false; PLANE_SIZE]; NUM_PLANES];
+        for (plane, bytes) in planes.iter_mut().zip(packed) {
+            if bytes.len() != PLANE_SIZE / 8 {
+                return Err(D::Error::invalid_length(bytes.len(), &"one bit per pixel"));
+            }
+            for (chunk, byte) in plane.chunks_mut(8).zip(bytes) {
+                unpack_bits(byte, chunk);
+            }
+        }
+        Ok(planes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_planes() {
+        let mut display = Display::new();
+        assert!(!display.flip(0));
+        display.select_planes(0b10);
+        assert!(!display.flip(0));
+        assert!(!display.flip(1));
+        assert_eq!(display.colors().take(3).collect::<Vec<_>>(), vec![3, 2, 0]);
+
+        display.select_planes(0b11);
+        assert!(display.flip(1));
+        assert_eq!(display.color(1), 1);
+
+        display.select_planes(0xFF);
+        assert_eq!(display.selected_planes(), 0b11);
+        display.select_planes(0b01);
+        display.clear();
+        assert_eq!(display.colors().take(3).collect::<Vec<_>>(), vec![2, 0, 0]);
+        assert!(display.plane(1).unwrap()[0]);
+        assert!(display.plane(NUM_PLANES).is_none());
+    }
+}
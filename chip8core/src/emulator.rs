@@ -1,9 +1,30 @@
 //! Emulator struct defining CPU functionality.
-use std::default::Default;
+use std::{
+    default::Default,
+    time::{Duration, Instant},
+};
 
 use super::{
-    fontset::{FONTSET, FONTSET_SIZE},
-    opcodes::execute_opcode,
+    builder::EmulatorBuilder,
+    cheats::Cheats,
+    debug::{Debugger, RunCondition, RunOutcome, RunResult, StopReason, TickResult},
+    diagnostics::Diagnostics,
+    display::Display,
+    error::Chip8Error,
+    events::{Event, EventQueue},
+    fontset::FONTSET_SIZE,
+    heatmap::Heatmap,
+    host::DEFAULT_CYCLES_PER_FRAME,
+    input::{InputQueue, InputTime, ScheduledKey},
+    opcodes::{self, Instruction},
+    profile::Profiler,
+    quirks::Quirks,
+    render::{self, Palette, Phosphor, PlanePalette},
+    rng::{self, RandomSource},
+    rom::{LoadError, RomInfo},
+    state::{SaveState, StateError},
+    timing::Clock,
+    trace::{TraceEntry, Tracer},
 };
 
 // 64x32 monochrome display.
@@ -12,11 +33,11 @@ pub const DISPLAY_WIDTH: usize = 64;
 /// Display height.
 pub const DISPLAY_HEIGHT: usize = 32;
 
-/// 4K RAM
+/// Default RAM size: 4K.
 pub const RAM_SIZE: usize = 4096;
 /// V registers. 16 8-bit registers; V0-VF.
 pub const NUM_REGISTERS: usize = 16;
-/// Stack
+/// Default stack depth.
 pub const STACK_SIZE: usize = 16;
 /// 16-key hex keypad.
 /// Original Layout:
@@ -26,120 +47,879 @@ pub const STACK_SIZE: usize = 16;
 /// A 0 B F
 pub const NUM_KEYS: usize = 16;
 
-/// First 0x200 bytes reserved. Start at RAM address 0x200.
+/// Default start address. First 0x200 bytes reserved. Start at RAM address 0x200.
 pub const START_ADDRESS: u16 = 0x200;
 
+/// Reason the emulator halted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HaltReason {
+    /// An instruction failed.
+    Error(Chip8Error),
+    /// The program jumped to the address of the jump itself, so will never do anything else.
+    InfiniteLoop(u16),
+}
+
+/// What happens when a subroutine call (2NNN) finds the stack full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StackOverflowBehavior {
+    /// Fail with [Chip8Error::StackOverflow], halting the emulator.
+    #[default]
+    Halt,
+    /// Discard the oldest return address to make room, so deep recursion keeps running.
+    DropOldest,
+}
+
+/// Execution state of the emulator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EmulatorState {
+    /// Executing instructions.
+    #[default]
+    Running,
+    /// Waiting for a key press to store in VX (FX0A).
+    WaitingForKey {
+        /// Register index.
+        x: u8,
+    },
+    /// Stopped until reset.
+    Halted(HaltReason),
+    /// Program exited (00FD).
+    Exited,
+}
+impl EmulatorState {
+    /// Whether the emulator halted or exited, so will never execute another instruction.
+    pub fn has_ended(&self) -> bool {
+        matches!(self, Self::Halted(_) | Self::Exited)
+    }
+}
+
+/// Result of running a single frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FrameOutput {
+    /// Whether the display differs from the start of the frame.
+    pub display_changed: bool,
+    /// Whether sound should be playing, i.e. the sound timer is non-zero.
+    pub sound_active: bool,
+    /// Number of instructions executed.
+    pub executed: usize,
+    /// Why the run ended. [RunOutcome::Reached] if it ran to completion.
+    pub outcome: RunOutcome,
+}
+impl FrameOutput {
+    /// Why execution stopped partway through, if a breakpoint or watchpoint stopped it.
+    pub fn stop(&self) -> Option<StopReason> {
+        match self.outcome {
+            RunOutcome::Stopped(stop) => Some(stop),
+            _ => None,
+        }
+    }
+}
+
+/// Mutable references to every part of the machine state, bypassing the accessors. See
+/// [Emulator::raw_parts].
+pub struct RawParts<'a> {
+    /// Program counter.
+    pub program_counter: &'a mut u16,
+    /// Random-access memory.
+    pub ram: &'a mut [u8],
+    /// Screen pixels.
+    pub display: &'a mut Display,
+    /// V registers.
+    pub v_registers: &'a mut [u8; NUM_REGISTERS],
+    /// I register.
+    pub i_register: &'a mut u16,
+    /// Stack pointer.
+    pub stack_pointer: &'a mut u16,
+    /// Stack.
+    pub stack: &'a mut [u16],
+    /// Keypad.
+    pub keys: &'a mut [bool; NUM_KEYS],
+    /// Delay timer.
+    pub delay_timer: &'a mut u8,
+    /// Sound timer.
+    pub sound_timer: &'a mut u8,
+}
+
 /// Emulator. Defines CPU functionality.
 pub struct Emulator {
     /// Special register. Incremented by programs as they run.
-    pub program_counter: u16,
+    pub(crate) program_counter: u16,
     /// Random-access memory. The entire program is copied into RAM.
-    pub ram: [u8; RAM_SIZE],
-    /// Screen pixels. Monochrome; 1 bit per pixel.
-    pub display: [bool; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+    pub(crate) ram: Vec<u8>,
+    /// Screen pixels. One or more bit planes.
+    pub(crate) display: Display,
     /// V registers. 8 bits.
-    pub v_registers: [u8; NUM_REGISTERS],
+    pub(crate) v_registers: [u8; NUM_REGISTERS],
     /// I register. 16 bits. Used for indexing into RAM for reads/writes.
-    pub i_register: u16,
+    pub(crate) i_register: u16,
     /// Stack pointer to locate the top of the stack.
-    pub stack_pointer: u16,
+    pub(crate) stack_pointer: u16,
     /// Stack.
-    pub stack: [u16; STACK_SIZE],
+    pub(crate) stack: Vec<u16>,
     /// Keypad.
-    pub keys: [bool; NUM_KEYS],
+    pub(crate) keys: [bool; NUM_KEYS],
     /// Delay timer. Decrement every clock cycle, perform action when 0.
-    pub delay_timer: u8,
+    pub(crate) delay_timer: u8,
     /// Sound timer. Decrement every clock cycle, emit noise when 0.
-    pub sound_timer: u8,
+    pub(crate) sound_timer: u8,
+    /// Interpreter behaviour differences.
+    pub(crate) quirks: Quirks,
+    /// What happens when the stack is full.
+    stack_overflow: StackOverflowBehavior,
+    /// Address programs are loaded at and start executing from.
+    start_address: u16,
+    /// Hex digit sprites copied to the start of RAM.
+    fontset: [u8; FONTSET_SIZE],
+    /// Instructions executed per second by [Emulator::run_for].
+    clock_hz: u32,
+    /// Information about the currently-loaded ROM.
+    rom_info: Option<RomInfo>,
+    /// Seed of the random number generator.
+    rng_seed: u64,
+    /// Random number generator used by CXKK.
+    rng: Box<dyn RandomSource + Send>,
+    /// Random bytes drawn since the generator was last seeded.
+    random_draws: u64,
+    /// Breakpoints and watchpoints.
+    debugger: Debugger,
+    /// Pokes applied every frame.
+    cheats: Cheats,
+    /// Execution trace logger.
+    tracer: Option<Tracer>,
+    /// Execution profiler.
+    profiler: Option<Profiler>,
+    /// Memory access counter.
+    heatmap: Option<Heatmap>,
+    /// Suspicious memory use checker.
+    diagnostics: Option<Diagnostics>,
+    /// Anti-flicker fade layer.
+    phosphor: Option<Phosphor>,
+    /// Fractional instructions and timer ticks owed by [Emulator::run_for].
+    clock: Clock,
+    /// Execution state.
+    state: EmulatorState,
+    /// Consecutive jumps-to-self after which to halt, if enabled.
+    loop_detection: Option<u32>,
+    /// Consecutive jumps-to-self executed so far.
+    self_jumps: u32,
+    /// Most ticks any single run call may take, if limited.
+    watchdog: Option<usize>,
+    /// Timer ticks since reset.
+    frames: u64,
+    /// Instructions executed since reset.
+    cycles: u64,
+    /// Key events waiting to be applied.
+    inputs: InputQueue,
+    /// Events not yet drained by the frontend.
+    events: EventQueue,
+    /// Whether the sound timer was non-zero when last checked.
+    sound_active: bool,
 }
 impl Emulator {
-    /// Create new emulator with default values.
+    /// Create new emulator with default values and a randomly-seeded random number generator.
     pub fn new() -> Self {
+        Self::with_seed(rng::random_seed())
+    }
+
+    /// Create new emulator with default values and the given random number generator seed.
+    /// Emulators created with the same seed produce the same sequence of random numbers.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::from_builder(&EmulatorBuilder::new(), seed)
+    }
+
+    /// Start configuring an emulator. See [EmulatorBuilder].
+    pub fn builder() -> EmulatorBuilder {
+        EmulatorBuilder::new()
+    }
+
+    /// Create an emulator from already-validated options.
+    pub(crate) fn from_builder(options: &EmulatorBuilder, seed: u64) -> Self {
         let mut new_emu = Self {
-            program_counter: START_ADDRESS,
-            ram: [0; RAM_SIZE],
-            display: [false; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            program_counter: options.start_address,
+            ram: vec![0; options.ram_size],
+            display: Display::new(),
             v_registers: [0; NUM_REGISTERS],
             i_register: 0,
             stack_pointer: 0,
-            stack: [0; STACK_SIZE],
+            stack: vec![0; options.stack_depth],
             keys: [false; NUM_KEYS],
             delay_timer: 0,
             sound_timer: 0,
+            quirks: options.quirks,
+            stack_overflow: options.stack_overflow,
+            start_address: options.start_address,
+            fontset: options.fontset,
+            clock_hz: options.clock_hz,
+            rom_info: None,
+            rng_seed: seed,
+            random_draws: 0,
+            rng: rng::default_source(seed),
+            debugger: Debugger::new(),
+            cheats: Cheats::new(),
+            tracer: None,
+            profiler: None,
+            heatmap: None,
+            diagnostics: None,
+            phosphor: None,
+            clock: Clock::new(),
+            state: EmulatorState::Running,
+            loop_detection: None,
+            self_jumps: 0,
+            watchdog: None,
+            frames: 0,
+            cycles: 0,
+            inputs: InputQueue::default(),
+            events: EventQueue::default(),
+            sound_active: false,
         };
 
         // Copy fontset into reserved section
-        new_emu.ram[..FONTSET_SIZE].copy_from_slice(&FONTSET);
+        new_emu.ram[..FONTSET_SIZE].copy_from_slice(&new_emu.fontset);
 
         new_emu
     }
 
     /// Reset emulator to default values.
     pub fn reset(&mut self) {
-        self.program_counter = START_ADDRESS;
-        self.ram = [0; RAM_SIZE];
-        self.display = [false; DISPLAY_WIDTH * DISPLAY_HEIGHT];
+        self.soft_reset();
+        self.ram.fill(0);
+        self.rom_info = None;
+        self.ram[..FONTSET_SIZE].copy_from_slice(&self.fontset);
+        if let Some(diagnostics) = self.diagnostics.as_mut() {
+            diagnostics.clear();
+        }
+        self.mark_initialized(0..FONTSET_SIZE);
+    }
+
+    /// Reset the CPU, display, keys and timers, keeping RAM and so the loaded ROM as they are.
+    pub fn soft_reset(&mut self) {
+        self.program_counter = self.start_address;
+        self.display = Display::new();
         self.v_registers = [0; NUM_REGISTERS];
         self.i_register = 0;
         self.stack_pointer = 0;
-        self.stack = [0; STACK_SIZE];
+        self.stack.fill(0);
         self.keys = [false; NUM_KEYS];
         self.delay_timer = 0;
         self.sound_timer = 0;
-        self.ram[..FONTSET_SIZE].copy_from_slice(&FONTSET);
+        self.rng.reseed(self.rng_seed);
+        self.random_draws = 0;
+        if let Some(phosphor) = self.phosphor.as_mut() {
+            phosphor.clear();
+        }
+        self.clock.reset();
+        self.state = EmulatorState::Running;
+        self.self_jumps = 0;
+        self.frames = 0;
+        self.cycles = 0;
+        self.inputs.clear();
+        self.events.clear();
+        self.sound_active = false;
     }
 
-    /// Push to stack.
-    pub fn push(&mut self, val: u16) {
+    /// Re-seed the random number generator.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng_seed = seed;
+        self.rng.reseed(seed);
+        self.random_draws = 0;
+    }
+
+    /// Return the seed the random number generator was last seeded with.
+    pub fn rng_seed(&self) -> u64 {
+        self.rng_seed
+    }
+
+    /// Generate a random byte.
+    pub(crate) fn random_byte(&mut self) -> u8 {
+        self.random_draws += 1;
+        self.rng.next_byte()
+    }
+
+    /// Replace the random number generator used by CXKK, seeding it with the current seed.
+    pub fn set_random_source(&mut self, mut source: Box<dyn RandomSource + Send>) {
+        source.reseed(self.rng_seed);
+        self.rng = source;
+        self.random_draws = 0;
+    }
+
+    /// Push to stack. Fails with [Chip8Error::StackOverflow] when full unless the overflow
+    /// behaviour is [StackOverflowBehavior::DropOldest].
+    pub fn push(&mut self, val: u16) -> Result<(), Chip8Error> {
+        if self.stack_pointer as usize >= self.stack.len() {
+            match self.stack_overflow {
+                StackOverflowBehavior::Halt => return Err(Chip8Error::StackOverflow),
+                StackOverflowBehavior::DropOldest => {
+                    self.stack.rotate_left(1);
+                    self.stack_pointer -= 1;
+                }
+            }
+        }
         self.stack[self.stack_pointer as usize] = val;
         self.stack_pointer += 1;
+        self.push_event(Event::StackPushed(val));
+        Ok(())
     }
 
-    /// Pop from stack.
-    pub fn pop(&mut self) -> u16 {
+    /// Pop from stack. Fails with [Chip8Error::StackUnderflow] when empty.
+    pub fn pop(&mut self) -> Result<u16, Chip8Error> {
+        if self.stack_pointer == 0 {
+            return Err(Chip8Error::StackUnderflow);
+        }
         self.stack_pointer -= 1;
-        self.stack[self.stack_pointer as usize]
+        let val = self.stack[self.stack_pointer as usize];
+        self.push_event(Event::StackPopped(val));
+        Ok(val)
     }
 
     /// Basic CPU loop:
     /// - Fetch value from program at memory address defined by program counter.
     /// - Decode instruction.
     /// - Execute instruction. May modify CPU registers or RAM.
-    pub fn tick(&mut self) {
-        // I. Fetch
-        let op = self.fetch();
-        // II. Decode & III. Execute
-        execute_opcode(self, op);
+    ///
+    /// Stops without executing anything when the program counter is at a breakpoint. The next tick
+    /// resumes past it. Does nothing while waiting for a key or once halted or exited. Errors halt
+    /// the emulator. Applies any queued key events which have come due first.
+    pub fn tick(&mut self) -> Result<TickResult, Chip8Error> {
+        if !self.inputs.is_empty() {
+            for event in self.inputs.take_due(self.frames, self.cycles) {
+                self.set_key(event.key as usize, event.pressed);
+            }
+        }
+
+        match self.state {
+            EmulatorState::Running => {}
+            EmulatorState::WaitingForKey { x } => match self.keys.iter().position(|p| *p) {
+                Some(key) => {
+                    self.set_v(x, key as u8);
+                    self.state = EmulatorState::Running;
+                }
+                None => return Ok(TickResult::default()),
+            },
+            EmulatorState::Halted(_) | EmulatorState::Exited => return Ok(TickResult::default()),
+        }
+
+        let pc = self.program_counter;
+        let result = self.tick_running();
+        match &result {
+            Ok(tick) => {
+                if let Some(instruction) = &tick.instruction {
+                    self.cycles += 1;
+                    self.detect_loop(pc, instruction);
+                }
+                if let Some(stop) = tick.stop {
+                    self.push_event(Event::Breakpoint(stop));
+                }
+            }
+            Err(e) => self.set_state(EmulatorState::Halted(HaltReason::Error(*e))),
+        }
+        self.update_sound();
+        result
+    }
+
+    /// Halt once enough consecutive jumps-to-self (1NNN with NNN = its own address) have executed.
+    fn detect_loop(&mut self, pc: u16, instruction: &Instruction) {
+        let Some(threshold) = self.loop_detection else {
+            return;
+        };
+        if *instruction != Instruction::Jp(pc) {
+            self.self_jumps = 0;
+            return;
+        }
+        self.self_jumps += 1;
+        if self.self_jumps >= threshold {
+            self.set_state(EmulatorState::Halted(HaltReason::InfiniteLoop(pc)));
+        }
+    }
+
+    /// Halt after `threshold` consecutive jumps-to-self, the idiomatic way for a ROM to end.
+    /// `None` disables the detection, which is the default.
+    pub fn set_loop_detection(&mut self, threshold: Option<u32>) {
+        self.loop_detection = threshold.map(|t| t.max(1));
+        self.self_jumps = 0;
+    }
+
+    /// Return the jump-to-self threshold, if loop detection is enabled.
+    pub fn loop_detection(&self) -> Option<u32> {
+        self.loop_detection
     }
 
-    /// Fetch opcode. All Chip-8 opcodes are exactly 2 bytes.
-    fn fetch(&mut self) -> u16 {
+    /// Cap the ticks taken by any single `run_*` call, as a hard guarantee against runaway
+    /// execution. Calls that hit the cap end with [RunOutcome::BudgetExhausted]. Ticks spent
+    /// waiting for a key count towards it. `None` removes the cap, which is the default.
+    pub fn set_watchdog(&mut self, max_ticks: Option<usize>) {
+        self.watchdog = max_ticks;
+    }
+
+    /// Return the watchdog's tick cap, if set.
+    pub fn watchdog(&self) -> Option<usize> {
+        self.watchdog
+    }
+
+    /// Run up to `ticks` ticks, capped by `budget`, stopping early at breakpoints, watchpoints or
+    /// once halted or exited. Returns the instructions executed, the ticks taken and why it
+    /// stopped.
+    fn run_ticks(
+        &mut self,
+        ticks: usize,
+        budget: Option<usize>,
+    ) -> Result<(usize, usize, RunOutcome), Chip8Error> {
+        let limit = budget.map_or(ticks, |max| max.min(ticks));
+        let mut executed = 0;
+        for taken in 0..limit {
+            let tick = self.tick()?;
+            executed += tick.instruction.is_some() as usize;
+            if let Some(stop) = tick.stop {
+                return Ok((executed, taken + 1, RunOutcome::Stopped(stop)));
+            }
+            if self.state.has_ended() {
+                return Ok((executed, taken + 1, RunOutcome::Ended));
+            }
+        }
+        let outcome = if limit < ticks {
+            RunOutcome::BudgetExhausted
+        } else {
+            RunOutcome::Reached
+        };
+        Ok((executed, limit, outcome))
+    }
+
+    /// Tick while in the running state.
+    fn tick_running(&mut self) -> Result<TickResult, Chip8Error> {
+        let tracing = self.tracer.as_ref().is_some_and(|t| t.is_enabled());
+        if self.debugger.is_empty()
+            && !tracing
+            && self.profiler.is_none()
+            && self.heatmap.is_none()
+            && self.diagnostics.is_none()
+        {
+            // I. Fetch
+            let op = self.fetch()?;
+            // II. Decode
+            let instruction = self.decode(op)?;
+            // III. Execute
+            self.execute(instruction)?;
+            return Ok(TickResult {
+                instruction: Some(instruction),
+                stop: None,
+            });
+        }
+
+        if let Some(stop) = self.debugger.check_breakpoint(self.program_counter) {
+            return Ok(TickResult {
+                instruction: None,
+                stop: Some(stop),
+            });
+        }
+        let pc = self.program_counter;
+        let (previous_v_registers, previous_i_register) = (self.v_registers, self.i_register);
+        let op = self.fetch()?;
+        let instruction = self.decode(op)?;
+        let pending = self.debugger.before(self, &instruction);
+        if let Some(heatmap) = self.heatmap.as_mut() {
+            heatmap.record_instruction(pc, self.i_register, &instruction);
+        }
+        let found = self
+            .diagnostics
+            .as_mut()
+            .map(|d| d.check(pc, self.i_register, self.start_address, &instruction))
+            .unwrap_or_default();
+        for diagnostic in found {
+            self.push_event(Event::Diagnostic(diagnostic));
+        }
+        let start = Instant::now();
+        self.execute(instruction)?;
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.record(pc, &instruction, start.elapsed());
+        }
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(TraceEntry {
+                pc,
+                opcode: op,
+                instruction,
+                previous_v_registers,
+                v_registers: self.v_registers,
+                previous_i_register,
+                i_register: self.i_register,
+                delay_timer: self.delay_timer,
+                sound_timer: self.sound_timer,
+            });
+        }
+        Ok(TickResult {
+            instruction: Some(instruction),
+            stop: self.debugger.after(self, pending),
+        })
+    }
+
+    /// Run one frame: execute `cycles` instructions, then tick the timers once.
+    ///
+    /// If a breakpoint or watchpoint stops execution, or the watchdog runs out, returns early
+    /// without ticking the timers.
+    pub fn run_frame(&mut self, cycles: u32) -> Result<FrameOutput, Chip8Error> {
+        let display = self.display;
+        let (executed, _, outcome) = self.run_ticks(cycles as usize, self.watchdog)?;
+        if matches!(outcome, RunOutcome::Reached | RunOutcome::Ended) {
+            self.tick_timers();
+        }
+
+        Ok(FrameOutput {
+            display_changed: display != self.display,
+            sound_active: self.is_sound_playing(),
+            executed,
+            outcome,
+        })
+    }
+
+    /// Run for `delta` of wall-clock time at [Emulator::clock_hz] instructions per second, ticking
+    /// the timers at 60Hz in between. Fractions of an instruction or timer tick are carried over to the next
+    /// call.
+    ///
+    /// If a breakpoint or watchpoint stops execution, or the watchdog runs out, returns early.
+    pub fn run_for(&mut self, delta: Duration) -> Result<FrameOutput, Chip8Error> {
+        let (cycles, timer_ticks) = self.clock.advance(delta, self.clock_hz);
+        let display = self.display;
+        let mut ticks = 0;
+        let mut executed = 0;
+        let mut outcome = RunOutcome::Reached;
+
+        // Spread the instructions evenly between timer ticks.
+        let segments = timer_ticks.max(1);
+        for segment in 1..=segments {
+            let target = (cycles * segment / segments) as usize;
+            let budget = self.watchdog.map(|max| max - ticks);
+            let (segment_executed, taken, segment_outcome) =
+                self.run_ticks(target - ticks, budget)?;
+            executed += segment_executed;
+            ticks += taken;
+            outcome = segment_outcome;
+            if outcome != RunOutcome::Reached {
+                break;
+            }
+            if timer_ticks > 0 {
+                self.tick_timers();
+            }
+        }
+
+        Ok(FrameOutput {
+            display_changed: display != self.display,
+            sound_active: self.is_sound_playing(),
+            executed,
+            outcome,
+        })
+    }
+
+    /// Execute exactly one instruction, even if the program counter is at a breakpoint.
+    pub fn step(&mut self) -> Result<TickResult, Chip8Error> {
+        self.debugger.resume(self.program_counter);
+        self.tick()
+    }
+
+    /// Execute one instruction, running called subroutines (2NNN) to completion as if they were a
+    /// single instruction. Gives up after `budget` instructions.
+    pub fn step_over(&mut self, budget: usize) -> Result<RunResult, Chip8Error> {
+        let pc = self.program_counter as usize;
+        let is_call = self.read_byte(pc)? & 0xF0 == 0x20;
+        if !is_call {
+            let stop = self.step()?.stop;
+            return Ok(RunResult {
+                executed: 1,
+                outcome: stop.map_or(RunOutcome::Reached, RunOutcome::Stopped),
+            });
+        }
+
+        // Recursive calls return to the same address with a deeper stack.
+        let return_addr = (pc as u16).wrapping_add(2);
+        let stack_pointer = self.stack_pointer;
+        self.run_until(
+            |emu: &Emulator| {
+                emu.program_counter == return_addr && emu.stack_pointer == stack_pointer
+            },
+            budget,
+        )
+    }
+
+    /// Execute instructions until the condition is met after an instruction, a breakpoint or
+    /// watchpoint stops execution, or `budget` instructions have been executed. Always executes at
+    /// least one instruction.
+    ///
+    /// The condition is either a program counter address or a closure, e.g.
+    /// `emu.run_until(|emu: &Emulator| emu.v(0_usize) == 3, 1000)`. The watchdog, if set, caps the
+    /// budget.
+    pub fn run_until<C: RunCondition>(
+        &mut self,
+        mut condition: C,
+        budget: usize,
+    ) -> Result<RunResult, Chip8Error> {
+        let budget = self.watchdog.map_or(budget, |max| max.min(budget));
+        let mut executed = 0;
+        while executed < budget {
+            let result = if executed == 0 {
+                self.step()?
+            } else {
+                self.tick()?
+            };
+            match result.stop {
+                Some(stop @ StopReason::Breakpoint(_)) => {
+                    return Ok(RunResult {
+                        executed,
+                        outcome: RunOutcome::Stopped(stop),
+                    })
+                }
+                Some(stop) => {
+                    return Ok(RunResult {
+                        executed: executed + 1,
+                        outcome: RunOutcome::Stopped(stop),
+                    })
+                }
+                None => executed += 1,
+            }
+            if self.state.has_ended() {
+                return Ok(RunResult {
+                    executed,
+                    outcome: RunOutcome::Ended,
+                });
+            }
+            if condition.is_met(self) {
+                return Ok(RunResult {
+                    executed,
+                    outcome: RunOutcome::Reached,
+                });
+            }
+        }
+        Ok(RunResult {
+            executed,
+            outcome: RunOutcome::BudgetExhausted,
+        })
+    }
+
+    /// Load `rom` into a new emulator with a fixed seed and run at most `max_instructions` ticks,
+    /// ticking the timers every [crate::host::DEFAULT_CYCLES_PER_FRAME] ticks. Never panics or
+    /// loops forever, whatever the ROM, so it is suitable as a fuzz target. Emulation errors halt
+    /// the emulator and end the run with [RunOutcome::Ended].
+    ///
+    /// Ticks spent waiting for a key count towards the budget.
+    pub fn run_budgeted(
+        rom: &[u8],
+        max_instructions: usize,
+    ) -> Result<(Emulator, RunResult), LoadError> {
+        let mut emu = Emulator::with_seed(0);
+        emu.load_rom(rom)?;
+        let result = emu.run_budget(max_instructions);
+        Ok((emu, result))
+    }
+
+    /// Tick up to `budget` times, ignoring breakpoints and stopping once halted or exited.
+    pub(crate) fn run_budget(&mut self, budget: usize) -> RunResult {
+        let cycles_per_frame = DEFAULT_CYCLES_PER_FRAME as usize;
+        let mut executed = 0;
+        while executed < budget {
+            let ok = self.tick().is_ok();
+            executed += 1;
+            if !ok || self.state.has_ended() {
+                return RunResult {
+                    executed,
+                    outcome: RunOutcome::Ended,
+                };
+            }
+            if executed % cycles_per_frame == 0 {
+                self.tick_timers();
+            }
+        }
+        RunResult {
+            executed,
+            outcome: RunOutcome::BudgetExhausted,
+        }
+    }
+
+    /// Fetch the opcode at the program counter and advance the program counter past it. All
+    /// Chip-8 opcodes are exactly 2 bytes.
+    pub fn fetch(&mut self) -> Result<u16, Chip8Error> {
+        let pc = self.program_counter as usize;
         // Get the two bytes
-        let higher_byte = self.ram[self.program_counter as usize] as u16;
-        let lower_byte = self.ram[(self.program_counter + 1) as usize] as u16;
+        let higher_byte = self.read_byte(pc)? as u16;
+        let lower_byte = self.read_byte(pc + 1)? as u16;
         // Combine together as Big Endian.
         let op = (higher_byte << 8) | lower_byte;
-        // Increment program counter.
-        self.program_counter += 2;
-        op
+        // Increment program counter, wrapping around from the end of a 64K RAM.
+        self.program_counter = self.program_counter.wrapping_add(2);
+        Ok(op)
+    }
+
+    /// Decode an opcode into its instruction.
+    pub fn decode(&self, op: u16) -> Result<Instruction, Chip8Error> {
+        opcodes::decode(op).ok_or(Chip8Error::InvalidOpcode(op))
+    }
+
+    /// Execute a decoded instruction.
+    pub fn execute(&mut self, instruction: Instruction) -> Result<(), Chip8Error> {
+        opcodes::execute(self, instruction)
     }
 
-    /// Tick timers.
+    /// Tick timers. Emits [Event::SoundStopped] when the sound timer reaches zero. Advances the
+    /// phosphor layer, if attached, by one frame, and applies enabled cheats.
     pub fn tick_timers(&mut self) {
+        if !self.cheats.is_empty() {
+            let cheats = std::mem::take(&mut self.cheats);
+            cheats.apply(self);
+            self.cheats = cheats;
+        }
+
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
 
         if self.sound_timer > 0 {
-            if self.sound_timer == 1 {
-                // TODO make noise
-            }
             self.sound_timer -= 1;
         }
+        self.frames += 1;
+        if let Some(phosphor) = self.phosphor.as_mut() {
+            phosphor.update(self.display.plane(0).unwrap());
+        }
+        self.update_sound();
+    }
+
+    /// Whether the buzzer should be sounding, i.e. the sound timer is non-zero.
+    pub fn is_sound_playing(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    /// Emit an event if the sound timer started or stopped since last checked.
+    fn update_sound(&mut self) {
+        let active = self.is_sound_playing();
+        if active != self.sound_active {
+            self.sound_active = active;
+            self.push_event(if active {
+                Event::SoundStarted
+            } else {
+                Event::SoundStopped
+            });
+        }
+    }
+
+    /// Remove and return every event emitted since the last drain, oldest first.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.events.drain()
+    }
+
+    /// Queue an event for the frontend.
+    pub(crate) fn push_event(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// Return the program counter.
+    pub fn pc(&self) -> u16 {
+        self.program_counter
+    }
+
+    /// Set the program counter.
+    pub fn set_pc(&mut self, addr: u16) {
+        self.program_counter = addr;
+    }
+
+    /// Return the I register.
+    pub fn i(&self) -> u16 {
+        self.i_register
+    }
+
+    /// Set the I register.
+    pub fn set_i(&mut self, value: u16) {
+        self.i_register = value;
+    }
+
+    /// Return every V register, V0 first.
+    pub fn v_registers(&self) -> &[u8; NUM_REGISTERS] {
+        &self.v_registers
+    }
+
+    /// Return the stack pointer.
+    pub fn stack_pointer(&self) -> u16 {
+        self.stack_pointer
+    }
+
+    /// Return the number of nested subroutine calls allowed.
+    pub fn stack_depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Change the number of nested subroutine calls allowed. Shrinking the stack discards the
+    /// newest return addresses that no longer fit.
+    pub fn set_stack_depth(&mut self, depth: usize) {
+        let depth = depth.max(1);
+        self.stack.resize(depth, 0);
+        self.stack_pointer = self.stack_pointer.min(depth as u16);
+    }
+
+    /// Return what happens when the stack is full.
+    pub fn stack_overflow_behavior(&self) -> StackOverflowBehavior {
+        self.stack_overflow
+    }
+
+    /// Set what happens when the stack is full.
+    pub fn set_stack_overflow_behavior(&mut self, behavior: StackOverflowBehavior) {
+        self.stack_overflow = behavior;
+    }
+
+    /// Return the addresses currently on the stack, bottom first.
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.stack_pointer as usize]
+    }
+
+    /// Return the delay timer.
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    /// Set the delay timer.
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer = value;
+    }
+
+    /// Return the sound timer.
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    /// Set the sound timer.
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.sound_timer = value;
+    }
+
+    /// Return the state of every key, 0x0 first.
+    pub fn keys(&self) -> &[bool; NUM_KEYS] {
+        &self.keys
+    }
+
+    /// Whether the given key is pressed. Keys not on the keypad are never pressed.
+    pub fn is_key_pressed(&self, idx: usize) -> bool {
+        self.keys.get(idx).copied().unwrap_or(false)
+    }
+
+    /// Return the whole of RAM.
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// Return mutable references to every part of the machine state. Changes made this way skip
+    /// any checks the accessors perform.
+    pub fn raw_parts(&mut self) -> RawParts<'_> {
+        RawParts {
+            program_counter: &mut self.program_counter,
+            ram: &mut self.ram,
+            display: &mut self.display,
+            v_registers: &mut self.v_registers,
+            i_register: &mut self.i_register,
+            stack_pointer: &mut self.stack_pointer,
+            stack: &mut self.stack,
+            keys: &mut self.keys,
+            delay_timer: &mut self.delay_timer,
+            sound_timer: &mut self.sound_timer,
+        }
     }
 
     /// Convenience function: get the V register value at the given index.
-    pub fn get_v<T: Into<usize>>(&self, index: T) -> u8 {
+    pub fn v<T: Into<usize>>(&self, index: T) -> u8 {
         self.v_registers[index.into()]
     }
 
@@ -152,6 +932,24 @@ impl Emulator {
         self.v_registers[index.into()] = value.into();
     }
 
+    /// Read the byte at the given RAM address.
+    pub fn read_byte(&self, addr: usize) -> Result<u8, Chip8Error> {
+        self.ram
+            .get(addr)
+            .copied()
+            .ok_or(Chip8Error::RamOutOfBounds(addr))
+    }
+
+    /// Write a byte to the given RAM address.
+    pub fn write_byte(&mut self, addr: usize, value: u8) -> Result<(), Chip8Error> {
+        let byte = self
+            .ram
+            .get_mut(addr)
+            .ok_or(Chip8Error::RamOutOfBounds(addr))?;
+        *byte = value;
+        Ok(())
+    }
+
     /// Convenience function: write an instruction starting at the given address.
     pub fn write_instruction<T, U>(&mut self, start_index: T, instruction: U)
     where
@@ -168,21 +966,405 @@ impl Emulator {
         self.ram[start_index_usize + 1] = lower_byte;
     }
 
-    /// Return the Chip-8 display.
+    /// Return the pixels of the first display plane, which is all of the display outside
+    /// multi-colour modes.
     pub fn get_display(&self) -> &[bool] {
+        self.get_plane(0).unwrap()
+    }
+
+    /// Iterate over the pixels of the first display plane as `(x, y, on)`, row-major.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize, bool)> + '_ {
+        self.get_display()
+            .iter()
+            .enumerate()
+            .map(|(i, on)| (i % DISPLAY_WIDTH, i / DISPLAY_WIDTH, *on))
+    }
+
+    /// Return the display, including every plane.
+    pub fn display(&self) -> &Display {
         &self.display
     }
 
-    /// Register a Chip-8 key as pressed or un-pressed.
-    pub fn keypress(&mut self, idx: usize, pressed: bool) {
+    /// Return the pixels of display plane `n`, if it exists.
+    pub fn get_plane(&self, n: usize) -> Option<&[bool]> {
+        self.display.plane(n)
+    }
+
+    /// Render the display into `out` as row-major RGBA bytes, ready to upload to a texture. See
+    /// [render::RGBA_BUFFER_SIZE]. Pixels fade out if a phosphor layer is attached.
+    pub fn render_rgba(&self, out: &mut [u8], palette: &Palette) {
+        match &self.phosphor {
+            Some(phosphor) if phosphor.intensity().len() == self.get_display().len() => {
+                render::render_rgba_faded(phosphor.intensity(), out, palette)
+            }
+            _ => render::render_rgba(self.get_display(), out, palette),
+        }
+    }
+
+    /// Render the display into `out` packed 1 bit per pixel. See [render::PACKED_BUFFER_SIZE].
+    pub fn render_packed(&self, out: &mut [u8]) {
+        render::render_packed(self.get_display(), out);
+    }
+
+    /// Draw the display as text, one line per row. See [render::render_text].
+    pub fn display_to_string(&self) -> String {
+        render::render_text(&self.display)
+    }
+
+    /// Hash the display, stable across platforms for golden-output tests. See
+    /// [render::display_hash].
+    pub fn display_hash(&self) -> u64 {
+        render::display_hash(&self.display)
+    }
+
+    /// Render every display plane merged into `out` as row-major RGBA bytes, colouring each pixel
+    /// by its combination of planes.
+    pub fn render_rgba_planes(&self, out: &mut [u8], palette: &PlanePalette) {
+        render::render_rgba_planes(&self.display, out, palette);
+    }
+
+    /// Register a Chip-8 key as pressed or un-pressed. A press completes a pending FX0A.
+    pub fn keypress(&mut self, idx: usize, pressed: bool) -> Result<(), Chip8Error> {
+        if idx >= NUM_KEYS {
+            return Err(Chip8Error::InvalidKey(idx));
+        }
+        self.set_key(idx, pressed);
+        Ok(())
+    }
+
+    /// Set a valid key's state, completing a pending FX0A on a press.
+    fn set_key(&mut self, idx: usize, pressed: bool) {
         self.keys[idx] = pressed;
+        if let (true, EmulatorState::WaitingForKey { x }) = (pressed, self.state) {
+            self.set_v(x, idx as u8);
+            self.state = EmulatorState::Running;
+        }
+    }
+
+    /// Queue a key press or release to be applied at a set frame or instruction count, making
+    /// input timing independent of when the frontend delivers it.
+    pub fn queue_key(
+        &mut self,
+        at: InputTime,
+        idx: usize,
+        pressed: bool,
+    ) -> Result<(), Chip8Error> {
+        if idx >= NUM_KEYS {
+            return Err(Chip8Error::InvalidKey(idx));
+        }
+        self.inputs.push(ScheduledKey {
+            at,
+            key: idx as u8,
+            pressed,
+        });
+        Ok(())
+    }
+
+    /// Return the queued key events not yet applied, in the order they were queued.
+    pub fn queued_keys(&self) -> &[ScheduledKey] {
+        self.inputs.pending()
+    }
+
+    /// Return the number of timer ticks since reset.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Return the number of instructions executed since reset.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Overwrite the timer tick and instruction counts, e.g. after restoring an earlier state.
+    pub(crate) fn set_counters(&mut self, frames: u64, cycles: u64) {
+        self.frames = frames;
+        self.cycles = cycles;
+    }
+
+    /// Press a Chip-8 key. A press completes a pending FX0A.
+    pub fn press(&mut self, idx: usize) -> Result<(), Chip8Error> {
+        self.keypress(idx, true)
+    }
+
+    /// Release a Chip-8 key.
+    pub fn release(&mut self, idx: usize) -> Result<(), Chip8Error> {
+        self.keypress(idx, false)
+    }
+
+    /// Set every key at once from a bitmask, bit `n` being key `n`. A newly-pressed key completes
+    /// a pending FX0A, the lowest winning if several are.
+    pub fn set_keys(&mut self, mask: u16) {
+        let newly_pressed = mask & !self.keys_bitmask();
+        for (idx, key) in self.keys.iter_mut().enumerate() {
+            *key = mask & (1 << idx) != 0;
+        }
+        if let (true, EmulatorState::WaitingForKey { x }) = (newly_pressed != 0, self.state) {
+            self.set_v(x, newly_pressed.trailing_zeros() as u8);
+            self.state = EmulatorState::Running;
+        }
+    }
+
+    /// Return the state of every key as a bitmask, bit `n` being key `n`.
+    pub fn keys_bitmask(&self) -> u16 {
+        self.keys
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (idx, pressed)| mask | ((*pressed as u16) << idx))
     }
 
-    /// Take a given vector of bytes and copy them to RAM.
-    pub fn load(&mut self, data: &[u8]) {
-        let start = START_ADDRESS as usize;
-        let end = start + data.len();
-        self.ram[start..end].copy_from_slice(data);
+    /// Return the execution state.
+    pub fn state(&self) -> EmulatorState {
+        self.state
+    }
+
+    /// Set the execution state, emitting the matching event.
+    pub(crate) fn set_state(&mut self, state: EmulatorState) {
+        self.state = state;
+        match state {
+            EmulatorState::Running => {}
+            EmulatorState::WaitingForKey { x } => self.push_event(Event::KeyWaitStarted { x }),
+            EmulatorState::Halted(reason) => self.push_event(Event::Halted(reason)),
+            EmulatorState::Exited => self.push_event(Event::RomExited),
+        }
+    }
+
+    /// Copy the given ROM bytes to RAM at the start address.
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<RomInfo, LoadError> {
+        let info = self.load_at(self.start_address, data)?;
+        self.rom_info = Some(info);
+        Ok(info)
+    }
+
+    /// Copy the given bytes to RAM at `addr`, e.g. data files or patches alongside a ROM. Unlike
+    /// [Emulator::load_rom], does not change the currently-loaded ROM information.
+    pub fn load_at(&mut self, addr: u16, data: &[u8]) -> Result<RomInfo, LoadError> {
+        let info = self.check_segment(addr, data)?;
+        self.ram[info.range()].copy_from_slice(data);
+        self.mark_initialized(info.range());
+        Ok(info)
+    }
+
+    /// Copy several `(address, bytes)` segments to RAM. Nothing is written unless every segment
+    /// fits in RAM without overlapping another.
+    pub fn load_segments(&mut self, segments: &[(u16, &[u8])]) -> Result<Vec<RomInfo>, LoadError> {
+        let mut infos = Vec::with_capacity(segments.len());
+        for (addr, data) in segments {
+            let info = self.check_segment(*addr, data)?;
+            let range = info.range();
+            if infos.iter().any(|other: &RomInfo| {
+                let other = other.range();
+                range.start < other.end && other.start < range.end
+            }) {
+                return Err(LoadError::SegmentsOverlap { addr: *addr });
+            }
+            infos.push(info);
+        }
+        for (info, (_, data)) in infos.iter().zip(segments) {
+            self.ram[info.range()].copy_from_slice(data);
+            self.mark_initialized(info.range());
+        }
+        Ok(infos)
+    }
+
+    /// Check the bytes fit in RAM at `addr`.
+    fn check_segment(&self, addr: u16, data: &[u8]) -> Result<RomInfo, LoadError> {
+        let max = self.ram.len().saturating_sub(addr as usize);
+        if data.is_empty() {
+            return Err(LoadError::Empty);
+        }
+        if data.len() > max {
+            return Err(LoadError::RomTooLarge {
+                size: data.len(),
+                max,
+            });
+        }
+        Ok(RomInfo::new(data, addr))
+    }
+
+    /// Capture a snapshot of the full machine state.
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            program_counter: self.program_counter,
+            ram: self.ram.clone(),
+            display: self.display,
+            v_registers: self.v_registers,
+            i_register: self.i_register,
+            stack_pointer: self.stack_pointer,
+            stack: self.stack.clone(),
+            keys: self.keys,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            rom_info: self.rom_info,
+            execution_state: self.state,
+            rng_seed: self.rng_seed,
+            random_draws: self.random_draws,
+        }
+    }
+
+    /// Restore the machine state from a snapshot, refusing one that is inconsistent.
+    pub fn load_state(&mut self, state: &SaveState) -> Result<(), StateError> {
+        state.validate()?;
+        self.restore_state(state);
+        Ok(())
+    }
+
+    /// Restore the machine state from a snapshot known to be valid, e.g. one taken with
+    /// [Emulator::save_state].
+    pub(crate) fn restore_state(&mut self, state: &SaveState) {
+        self.program_counter = state.program_counter;
+        self.ram = state.ram.clone();
+        self.display = state.display;
+        self.v_registers = state.v_registers;
+        self.i_register = state.i_register;
+        self.stack_pointer = state.stack_pointer;
+        self.stack = state.stack.clone();
+        self.keys = state.keys;
+        self.delay_timer = state.delay_timer;
+        self.sound_timer = state.sound_timer;
+        self.rom_info = state.rom_info;
+        self.state = state.execution_state;
+        // Put the generator back to the point it had reached
+        self.rng_seed = state.rng_seed;
+        self.rng.seek(state.rng_seed, state.random_draws);
+        self.random_draws = state.random_draws;
+    }
+
+    /// Return the cheats applied every frame.
+    pub fn cheats(&self) -> &Cheats {
+        &self.cheats
+    }
+
+    /// Return the cheats applied every frame for modification.
+    pub fn cheats_mut(&mut self) -> &mut Cheats {
+        &mut self.cheats
+    }
+
+    /// Return the breakpoints and watchpoints.
+    pub fn debugger(&self) -> &Debugger {
+        &self.debugger
+    }
+
+    /// Return the breakpoints and watchpoints for modification.
+    pub fn debugger_mut(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    /// Attach a tracer, or detach it with `None`. Returns the previous tracer.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) -> Option<Tracer> {
+        std::mem::replace(&mut self.tracer, tracer)
+    }
+
+    /// Return the attached tracer, if any.
+    pub fn tracer(&self) -> Option<&Tracer> {
+        self.tracer.as_ref()
+    }
+
+    /// Return the attached tracer for modification, if any.
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// Attach memory diagnostics, or detach them with `None`. Returns the previous diagnostics.
+    /// The font and the loaded ROM count as initialized.
+    pub fn set_diagnostics(&mut self, diagnostics: Option<Diagnostics>) -> Option<Diagnostics> {
+        let previous = std::mem::replace(&mut self.diagnostics, diagnostics);
+        self.mark_initialized(0..FONTSET_SIZE);
+        if let Some(info) = self.rom_info {
+            self.mark_initialized(info.range());
+        }
+        previous
+    }
+
+    /// Return the attached diagnostics, if any.
+    pub fn diagnostics(&self) -> Option<&Diagnostics> {
+        self.diagnostics.as_ref()
+    }
+
+    /// Return the attached diagnostics for modification, if any.
+    pub fn diagnostics_mut(&mut self) -> Option<&mut Diagnostics> {
+        self.diagnostics.as_mut()
+    }
+
+    /// Tell the attached diagnostics, if any, that RAM was loaded.
+    fn mark_initialized(&mut self, range: std::ops::Range<usize>) {
+        if let Some(diagnostics) = self.diagnostics.as_mut() {
+            diagnostics.mark_initialized(range);
+        }
+    }
+
+    /// Attach a memory heatmap, or detach it with `None`. Returns the previous heatmap.
+    pub fn set_heatmap(&mut self, heatmap: Option<Heatmap>) -> Option<Heatmap> {
+        std::mem::replace(&mut self.heatmap, heatmap)
+    }
+
+    /// Return the attached heatmap, if any.
+    pub fn heatmap(&self) -> Option<&Heatmap> {
+        self.heatmap.as_ref()
+    }
+
+    /// Return the attached heatmap for modification, if any.
+    pub fn heatmap_mut(&mut self) -> Option<&mut Heatmap> {
+        self.heatmap.as_mut()
+    }
+
+    /// Attach a profiler, or detach it with `None`. Returns the previous profiler.
+    pub fn set_profiler(&mut self, profiler: Option<Profiler>) -> Option<Profiler> {
+        std::mem::replace(&mut self.profiler, profiler)
+    }
+
+    /// Return the attached profiler, if any.
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Return the attached profiler for modification, if any.
+    pub fn profiler_mut(&mut self) -> Option<&mut Profiler> {
+        self.profiler.as_mut()
+    }
+
+    /// Attach a phosphor fade layer, or detach it with `None`. Returns the previous layer.
+    pub fn set_phosphor(&mut self, phosphor: Option<Phosphor>) -> Option<Phosphor> {
+        std::mem::replace(&mut self.phosphor, phosphor)
+    }
+
+    /// Return the attached phosphor layer, if any.
+    pub fn phosphor(&self) -> Option<&Phosphor> {
+        self.phosphor.as_ref()
+    }
+
+    /// Return the attached phosphor layer for modification, if any.
+    pub fn phosphor_mut(&mut self) -> Option<&mut Phosphor> {
+        self.phosphor.as_mut()
+    }
+
+    /// Return the interpreter quirks.
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    /// Change the interpreter quirks.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Return the address programs are loaded at and start executing from.
+    pub fn start_address(&self) -> u16 {
+        self.start_address
+    }
+
+    /// Return the instructions executed per second by [Emulator::run_for].
+    pub fn clock_hz(&self) -> u32 {
+        self.clock_hz
+    }
+
+    /// Set the instructions executed per second by [Emulator::run_for].
+    pub fn set_clock_hz(&mut self, clock_hz: u32) {
+        self.clock_hz = clock_hz.max(1);
+    }
+
+    /// Return information about the currently-loaded ROM, if any.
+    pub fn rom_info(&self) -> Option<&RomInfo> {
+        self.rom_info.as_ref()
     }
 }
 impl Default for Emulator {
@@ -190,3 +1372,20 @@ impl Default for Emulator {
         Self::new()
     }
 }
+/// Serialized as its [SaveState]. Configuration such as quirks and attached tools is not included.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Emulator {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.save_state().serialize(serializer)
+    }
+}
+/// Deserialized from a [SaveState] into an emulator with default configuration.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Emulator {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = SaveState::deserialize(deserializer)?;
+        let mut emu = Self::new();
+        emu.load_state(&state).map_err(serde::de::Error::custom)?;
+        Ok(emu)
+    }
+}
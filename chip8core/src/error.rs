@@ -0,0 +1,33 @@
+//! Errors which can occur while running the emulator.
+use std::{error::Error, fmt};
+
+/// Chip-8 emulation error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Chip8Error {
+    /// Attempted to push to a full stack.
+    StackOverflow,
+    /// Attempted to pop from an empty stack.
+    StackUnderflow,
+    /// Attempted to access RAM at an address outside of RAM.
+    RamOutOfBounds(usize),
+    /// Opcode does not correspond to any known instruction.
+    InvalidOpcode(u16),
+    /// Key index is not on the 16-key keypad.
+    InvalidKey(usize),
+    /// Instruction names a V register past VF.
+    InvalidRegister(u8),
+}
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StackOverflow => write!(f, "stack overflow"),
+            Self::StackUnderflow => write!(f, "stack underflow"),
+            Self::RamOutOfBounds(addr) => write!(f, "RAM access out of bounds: {:#06X}", addr),
+            Self::InvalidOpcode(op) => write!(f, "invalid opcode: {:#06X}", op),
+            Self::InvalidKey(key) => write!(f, "invalid key: {}", key),
+            Self::InvalidRegister(x) => write!(f, "invalid register: V{}", x),
+        }
+    }
+}
+impl Error for Chip8Error {}
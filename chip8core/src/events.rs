@@ -0,0 +1,119 @@
+//! Events emitted by the emulator as it runs.
+use std::collections::{vec_deque::Drain, VecDeque};
+
+use super::{debug::StopReason, diagnostics::Diagnostic, emulator::HaltReason};
+
+/// Maximum number of undrained events kept. The oldest events are dropped first.
+pub const EVENT_QUEUE_CAPACITY: usize = 256;
+
+/// Something that happened while the emulator ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The display was cleared or drawn to.
+    DisplayUpdated,
+    /// The sound timer became non-zero.
+    SoundStarted,
+    /// The sound timer reached zero.
+    SoundStopped,
+    /// FX0A started waiting for a key press to store in VX.
+    KeyWaitStarted {
+        /// Register index.
+        x: u8,
+    },
+    /// A breakpoint or watchpoint stopped execution.
+    Breakpoint(StopReason),
+    /// A return address was pushed to the stack by a subroutine call.
+    StackPushed(u16),
+    /// A return address was popped from the stack by a subroutine return.
+    StackPopped(u16),
+    /// Suspicious memory use was detected. See [crate::Emulator::set_diagnostics].
+    Diagnostic(Diagnostic),
+    /// The program exited (00FD).
+    RomExited,
+    /// The emulator halted.
+    Halted(HaltReason),
+}
+
+/// Bounded queue of events waiting to be drained by the frontend.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EventQueue {
+    events: VecDeque<Event>,
+}
+impl EventQueue {
+    /// Add an event. Consecutive display updates are merged into one.
+    pub(crate) fn push(&mut self, event: Event) {
+        if event == Event::DisplayUpdated && self.events.back() == Some(&Event::DisplayUpdated) {
+            return;
+        }
+        if self.events.len() == EVENT_QUEUE_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Remove and return every queued event, oldest first.
+    pub(crate) fn drain(&mut self) -> Drain<'_, Event> {
+        self.events.drain(..)
+    }
+
+    /// Remove every queued event.
+    pub(crate) fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{Chip8Error, Emulator};
+
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&[
+            0x00, 0xE0, // 0x200: CLS
+            0xD0, 0x05, // 0x202: DRW V0, V0, 5
+            0x60, 0x02, // 0x204: LD V0, 2
+            0xF0, 0x18, // 0x206: LD ST, V0
+            0xF1, 0x0A, // 0x208: LD V1, K
+            0x00, 0xFD, // 0x20A: EXIT
+        ])
+        .unwrap();
+        emu.debugger_mut().add_breakpoint(0x204);
+
+        emu.run_frame(3).unwrap();
+        assert_eq!(
+            emu.drain_events().collect::<Vec<_>>(),
+            vec![
+                Event::DisplayUpdated,
+                Event::Breakpoint(StopReason::Breakpoint(0x204))
+            ]
+        );
+
+        emu.debugger_mut().clear();
+        emu.run_frame(3).unwrap();
+        emu.keypress(4, true).unwrap();
+        emu.run_frame(3).unwrap();
+        assert_eq!(
+            emu.drain_events().collect::<Vec<_>>(),
+            vec![
+                Event::SoundStarted,
+                Event::KeyWaitStarted { x: 1 },
+                Event::RomExited,
+                Event::SoundStopped
+            ]
+        );
+        assert_eq!(emu.drain_events().count(), 0);
+
+        emu.reset();
+        emu.load_rom(&[0x00, 0xEE]).unwrap();
+        assert!(emu.tick().is_err());
+        assert_eq!(
+            emu.drain_events().collect::<Vec<_>>(),
+            vec![Event::Halted(HaltReason::Error(Chip8Error::StackUnderflow))]
+        );
+    }
+}
@@ -1,9 +1,13 @@
+//! Built-in hex digit sprites.
+
 // Contains characters 0-9, A-F.
 
 // Each character is 8 rows, 5 cols of pixels.
 // Every Chip-8 sprite is 8 cols, so 1 pixel row = 1 byte.
+/// Size of the fontset in bytes: 16 characters, 5 bytes each.
 pub const FONTSET_SIZE: usize = 80;
 
+/// Sprites for hex digits 0-F, copied to the start of RAM.
 pub const FONTSET: [u8; FONTSET_SIZE] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
     0x20, 0x60, 0x20, 0x20, 0x70, // 1
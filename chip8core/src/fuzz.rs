@@ -0,0 +1,50 @@
+//! Fuzzing inputs, e.g. for `cargo fuzz`. Requires the `arbitrary` feature.
+use super::{debug::RunResult, Emulator, Quirks, ScheduledKey};
+
+/// A ROM and everything else needed to run it reproducibly, generated from fuzzer bytes.
+#[derive(Debug, Clone, PartialEq, Eq, arbitrary::Arbitrary)]
+pub struct FuzzInput {
+    /// ROM bytes, loaded at the default start address.
+    pub rom: Vec<u8>,
+    /// Quirks to run with.
+    pub quirks: Quirks,
+    /// Random seed.
+    pub seed: u64,
+    /// Key events to schedule. Events for keys off the keypad are skipped.
+    pub keys: Vec<ScheduledKey>,
+}
+impl FuzzInput {
+    /// Run the input for at most `max_instructions` ticks. Never panics or loops forever. Returns
+    /// `None` if the ROM cannot be loaded.
+    pub fn run(&self, max_instructions: usize) -> Option<(Emulator, RunResult)> {
+        let mut emu = Emulator::with_seed(self.seed);
+        emu.set_quirks(self.quirks);
+        emu.load_rom(&self.rom).ok()?;
+        for event in &self.keys {
+            let _ = emu.queue_key(event.at, event.key as usize, event.pressed);
+        }
+        let result = emu.run_budget(max_instructions);
+        Some((emu, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use super::*;
+
+    #[test]
+    fn test_fuzz_input() {
+        let bytes: Vec<u8> = (0..4096u32).map(|n| (n * 37 % 251) as u8).collect();
+        let mut data = Unstructured::new(&bytes);
+        while let Ok(input) = FuzzInput::arbitrary(&mut data) {
+            if let Some((_, result)) = input.run(1000) {
+                assert!(result.executed <= 1000);
+            }
+            if data.is_empty() {
+                break;
+            }
+        }
+    }
+}
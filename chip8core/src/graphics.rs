@@ -0,0 +1,144 @@
+//! [embedded-graphics](https://docs.rs/embedded-graphics) support for drawing the display to
+//! microcontroller screens such as the SSD1306 or ST7789.
+//!
+//! [Display] is an [ImageDrawable] with one pixel per Chip-8 pixel, and [Scaled] draws it
+//! enlarged, e.g. 2x to fill a 128x64 screen:
+//!
+//! ```text
+//! Image::new(&Scaled::new(emu.display(), 2), Point::zero()).draw(&mut screen)?;
+//! ```
+//!
+//! A pixel is [BinaryColor::On] if it is set in any plane. Convert colours for colour screens with
+//! [DrawTargetExt::color_converted].
+use embedded_graphics::{
+    draw_target::{DrawTarget, DrawTargetExt},
+    geometry::{Dimensions, OriginDimensions, Size},
+    image::ImageDrawable,
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+};
+
+use super::{
+    display::Display,
+    emulator::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+};
+
+/// Colour of the pixel at `(x, y)`.
+fn pixel(display: &Display, x: usize, y: usize) -> BinaryColor {
+    BinaryColor::from(display.color(y * DISPLAY_WIDTH + x) != 0)
+}
+
+/// Draw only `area` of `image`, at its position within the image.
+fn draw_sub_image<I, D>(image: &I, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+where
+    I: ImageDrawable<Color = BinaryColor>,
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let mut translated = target.translated(-area.top_left);
+    image.draw(&mut translated.clipped(area))
+}
+
+impl OriginDimensions for Display {
+    fn size(&self) -> Size {
+        Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32)
+    }
+}
+impl ImageDrawable for Display {
+    type Color = BinaryColor;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let colors = self.colors().map(|color| BinaryColor::from(color != 0));
+        target.fill_contiguous(&self.bounding_box(), colors)
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        draw_sub_image(self, target, area)
+    }
+}
+
+/// [Display] drawn with every Chip-8 pixel as a `scale` by `scale` square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scaled<'a> {
+    display: &'a Display,
+    scale: u32,
+}
+impl<'a> Scaled<'a> {
+    /// Scale a display by a whole factor. A `scale` of 0 is treated as 1.
+    pub fn new(display: &'a Display, scale: u32) -> Self {
+        Self {
+            display,
+            scale: scale.max(1),
+        }
+    }
+}
+impl OriginDimensions for Scaled<'_> {
+    fn size(&self) -> Size {
+        self.display.size() * self.scale
+    }
+}
+impl ImageDrawable for Scaled<'_> {
+    type Color = BinaryColor;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let scale = self.scale as usize;
+        let colors = (0..DISPLAY_HEIGHT * scale).flat_map(|y| {
+            (0..DISPLAY_WIDTH * scale).map(move |x| pixel(self.display, x / scale, y / scale))
+        });
+        target.fill_contiguous(&self.bounding_box(), colors)
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        draw_sub_image(self, target, area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::{
+        geometry::Point,
+        image::{Image, ImageDrawableExt},
+        mock_display::MockDisplay,
+        Drawable,
+    };
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_draw() {
+        let mut display = Display::new();
+        display.flip(0);
+        display.flip(DISPLAY_WIDTH + 1);
+
+        let mut target = MockDisplay::new();
+        Image::new(&display, Point::zero())
+            .draw(&mut target)
+            .unwrap();
+        assert_eq!(target.get_pixel(Point::new(0, 0)), Some(BinaryColor::On));
+        assert_eq!(target.get_pixel(Point::new(1, 1)), Some(BinaryColor::On));
+        assert_eq!(target.get_pixel(Point::new(1, 0)), Some(BinaryColor::Off));
+        assert_eq!(target.get_pixel(Point::new(63, 31)), Some(BinaryColor::Off));
+        assert_eq!(target.get_pixel(Point::new(0, 32)), None);
+
+        let scaled = Scaled::new(&display, 2);
+        assert_eq!(scaled.size(), Size::new(128, 64));
+        let mut target = MockDisplay::new();
+        let area = Rectangle::new(Point::new(2, 2), Size::new(4, 4));
+        Image::new(&scaled.sub_image(&area), Point::zero())
+            .draw(&mut target)
+            .unwrap();
+        target.assert_pattern(&["##..", "##..", "....", "...."]);
+    }
+}
@@ -0,0 +1,261 @@
+//! Headless harness for running test ROMs and checking their results.
+//!
+//! Community test suites such as Timendus' `chip8-test-suite` (corax+, flags and quirks tests)
+//! report their results on screen or in RAM. Describe a run with [TestRom] and call
+//! [TestRom::run] from a `#[test]` to turn a ROM's verdict into a test failure.
+use std::{error::Error, fmt};
+
+use super::{host::DEFAULT_CYCLES_PER_FRAME, rom::LoadError, Chip8Error, Emulator, Quirks};
+
+/// Frames a test ROM runs for unless set otherwise.
+pub const DEFAULT_TEST_FRAMES: u64 = 600;
+
+/// Something a test ROM should have done by the end of its run.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Check {
+    /// RAM starting at `addr` holds `bytes`.
+    Ram {
+        /// First address to check.
+        addr: u16,
+        /// Expected bytes.
+        bytes: Vec<u8>,
+    },
+    /// Register VX holds `value`.
+    Register {
+        /// Register index.
+        x: u8,
+        /// Expected value.
+        value: u8,
+    },
+    /// The display hashes to the given value. See [crate::render::display_hash].
+    DisplayHash(u64),
+    /// Pixel (`x`, `y`) of the first plane is lit or unlit.
+    Pixel {
+        /// Column.
+        x: usize,
+        /// Row.
+        y: usize,
+        /// Whether the pixel should be lit.
+        on: bool,
+    },
+}
+impl Check {
+    /// Describe how the emulator fails the check, if it does.
+    fn verify(&self, emu: &Emulator) -> Option<String> {
+        match self {
+            Self::Ram { addr, bytes } => {
+                let start = *addr as usize;
+                let actual = emu.ram().get(start..start + bytes.len());
+                (actual != Some(bytes.as_slice())).then(|| {
+                    format!(
+                        "RAM at {:#05X}: expected {:02X?}, got {:02X?}",
+                        addr, bytes, actual
+                    )
+                })
+            }
+            Self::Register { x, value } => {
+                let actual = emu.v_registers().get(*x as usize);
+                (actual != Some(value))
+                    .then(|| format!("V{:X}: expected {:02X}, got {:02X?}", x, value, actual))
+            }
+            Self::DisplayHash(hash) => {
+                let actual = emu.display_hash();
+                (actual != *hash).then(|| {
+                    format!(
+                        "display hash: expected {:#018X}, got {:#018X}\n{}",
+                        hash,
+                        actual,
+                        emu.display_to_string()
+                    )
+                })
+            }
+            Self::Pixel { x, y, on } => {
+                let actual = emu.pixels().find(|(px, py, _)| px == x && py == y);
+                (actual.map(|(_, _, lit)| lit) != Some(*on))
+                    .then(|| format!("pixel ({}, {}): expected lit = {}", x, y, on))
+            }
+        }
+    }
+}
+
+/// A test ROM run: what to load, how long to run it and what to check afterwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRom {
+    name: String,
+    rom: Vec<u8>,
+    quirks: Quirks,
+    frames: u64,
+    cycles_per_frame: u32,
+    pokes: Vec<(u16, u8)>,
+    checks: Vec<Check>,
+}
+impl TestRom {
+    /// Describe a run of the given ROM with modern quirks.
+    pub fn new(name: impl Into<String>, rom: impl Into<Vec<u8>>) -> Self {
+        Self {
+            name: name.into(),
+            rom: rom.into(),
+            quirks: Quirks::default(),
+            frames: DEFAULT_TEST_FRAMES,
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            pokes: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Set the quirks to run with.
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Set the number of frames to run for. The run ends early if the ROM exits or halts.
+    pub fn frames(mut self, frames: u64) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    /// Set the number of instructions executed per frame.
+    pub fn cycles_per_frame(mut self, cycles: u32) -> Self {
+        self.cycles_per_frame = cycles;
+        self
+    }
+
+    /// Write `value` to `addr` after loading the ROM, e.g. to preselect the platform of
+    /// Timendus' quirks test at 0x1FF.
+    pub fn poke(mut self, addr: u16, value: u8) -> Self {
+        self.pokes.push((addr, value));
+        self
+    }
+
+    /// Add a check to make after the run.
+    pub fn check(mut self, check: Check) -> Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// Run the ROM and make every check, returning the emulator for further inspection.
+    pub fn run(&self) -> Result<Emulator, HarnessError> {
+        let mut emu = Emulator::new();
+        emu.set_quirks(self.quirks);
+        emu.load_rom(&self.rom).map_err(HarnessError::Load)?;
+        for (addr, value) in &self.pokes {
+            emu.write_byte(*addr as usize, *value)
+                .map_err(HarnessError::Emulation)?;
+        }
+
+        for _ in 0..self.frames {
+            emu.run_frame(self.cycles_per_frame)
+                .map_err(HarnessError::Emulation)?;
+            if emu.state().has_ended() {
+                break;
+            }
+        }
+
+        let failures: Vec<_> = self
+            .checks
+            .iter()
+            .filter_map(|check| check.verify(&emu))
+            .collect();
+        if failures.is_empty() {
+            Ok(emu)
+        } else {
+            Err(HarnessError::Failed {
+                name: self.name.clone(),
+                failures,
+            })
+        }
+    }
+}
+
+/// Error running a test ROM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HarnessError {
+    /// The ROM could not be loaded.
+    Load(LoadError),
+    /// Emulation stopped with an error.
+    Emulation(Chip8Error),
+    /// The ROM ran, but some checks failed.
+    Failed {
+        /// Name of the test ROM.
+        name: String,
+        /// Description of each failed check.
+        failures: Vec<String>,
+    },
+}
+impl fmt::Display for HarnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load(e) => write!(f, "unable to load ROM: {}", e),
+            Self::Emulation(e) => write!(f, "emulation error: {}", e),
+            Self::Failed { name, failures } => {
+                write!(f, "{} failed {} check(s)", name, failures.len())?;
+                for failure in failures {
+                    write!(f, "\n  {}", failure)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+impl Error for HarnessError {}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::asm::assemble;
+
+    use super::*;
+
+    #[test]
+    fn test_harness() {
+        // Store the carry flag of 0xFF + 1 and of 1 + 1, then draw a 0 in the corner.
+        let rom = assemble(
+            "
+                LD V0, 0xFF
+                LD V1, 1
+                ADD V0, V1
+                LD V2, VF
+                LD V0, 1
+                ADD V0, V1
+                LD V3, VF
+                LD V1, V2
+                LD V0, V3
+                LD I, results
+                LD [I], V1
+                LD V0, 0
+                LD F, V0
+                DRW V0, V0, 5
+            done:
+                JP done
+            results:
+                db 0, 0
+            ",
+        )
+        .unwrap();
+        let test = TestRom::new("flags", rom)
+            .frames(10)
+            .check(Check::Ram {
+                addr: 0x21E,
+                bytes: vec![0, 1],
+            })
+            .check(Check::Register { x: 2, value: 1 })
+            .check(Check::Pixel {
+                x: 0,
+                y: 0,
+                on: true,
+            });
+        assert!(test.run().is_ok());
+
+        let failed = test.check(Check::Pixel {
+            x: 4,
+            y: 0,
+            on: true,
+        });
+        assert_eq!(
+            failed.run().err().map(|e| e.to_string()),
+            Some("flags failed 1 check(s)\n  pixel (4, 0): expected lit = true".to_string())
+        );
+    }
+}
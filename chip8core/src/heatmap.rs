@@ -0,0 +1,171 @@
+//! Per-address memory access statistics.
+use super::opcodes::Instruction;
+
+/// Kind of memory access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// Read as data, e.g. by DXYN or FX65.
+    Read,
+    /// Written, e.g. by FX33 or FX55.
+    Write,
+    /// Fetched as an opcode.
+    Execute,
+}
+
+/// Access counts of a single address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct AccessCounts {
+    /// Times read as data.
+    pub reads: u64,
+    /// Times written.
+    pub writes: u64,
+    /// Times fetched as an opcode.
+    pub executes: u64,
+}
+
+/// Counts reads, writes and executions of every RAM address. Attach to an emulator with
+/// [crate::Emulator::set_heatmap].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Heatmap {
+    reads: Vec<u64>,
+    writes: Vec<u64>,
+    executes: Vec<u64>,
+}
+impl Heatmap {
+    /// Create an empty heatmap covering `ram_size` bytes.
+    pub fn new(ram_size: usize) -> Self {
+        Self {
+            reads: vec![0; ram_size],
+            writes: vec![0; ram_size],
+            executes: vec![0; ram_size],
+        }
+    }
+
+    /// Count accesses to `len` bytes starting at `addr`. Addresses outside RAM are ignored.
+    pub fn record(&mut self, access: Access, addr: usize, len: usize) {
+        let counts = self.counts_mut(access);
+        let end = (addr + len).min(counts.len());
+        for count in counts.iter_mut().take(end).skip(addr) {
+            *count += 1;
+        }
+    }
+
+    /// Count the opcode fetch and RAM accesses of an instruction executed at `pc` with I holding
+    /// `i`.
+    pub(crate) fn record_instruction(&mut self, pc: u16, i: u16, instruction: &Instruction) {
+        self.record(Access::Execute, pc as usize, 2);
+        if let Some((write, start, len)) = instruction.ram_access(i) {
+            let access = if write { Access::Write } else { Access::Read };
+            self.record(access, start as usize, len as usize);
+        }
+    }
+
+    /// Return the counts of one kind of access, indexed by address.
+    pub fn counts(&self, access: Access) -> &[u64] {
+        match access {
+            Access::Read => &self.reads,
+            Access::Write => &self.writes,
+            Access::Execute => &self.executes,
+        }
+    }
+
+    fn counts_mut(&mut self, access: Access) -> &mut Vec<u64> {
+        match access {
+            Access::Read => &mut self.reads,
+            Access::Write => &mut self.writes,
+            Access::Execute => &mut self.executes,
+        }
+    }
+
+    /// Return every count of a single address.
+    pub fn get(&self, addr: usize) -> AccessCounts {
+        AccessCounts {
+            reads: self.reads.get(addr).copied().unwrap_or(0),
+            writes: self.writes.get(addr).copied().unwrap_or(0),
+            executes: self.executes.get(addr).copied().unwrap_or(0),
+        }
+    }
+
+    /// Return the counts of one kind of access scaled from 0 to 1 relative to the busiest
+    /// address, on a log scale so rarely-touched addresses still show up.
+    pub fn intensity(&self, access: Access) -> Vec<f32> {
+        let counts = self.counts(access);
+        let max = counts.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            return vec![0.0; counts.len()];
+        }
+        let scale = (max as f32).ln_1p();
+        counts
+            .iter()
+            .map(|count| (*count as f32).ln_1p() / scale)
+            .collect()
+    }
+
+    /// Return the ranges of consecutive addresses with at least one access of the given kind.
+    pub fn regions(&self, access: Access) -> Vec<std::ops::Range<usize>> {
+        let mut regions = Vec::new();
+        let mut start = None;
+        let counts = self.counts(access);
+        for (addr, count) in counts.iter().enumerate() {
+            match (start, *count > 0) {
+                (None, true) => start = Some(addr),
+                (Some(s), false) => {
+                    regions.push(s..addr);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            regions.push(s..counts.len());
+        }
+        regions
+    }
+
+    /// Reset every count to zero.
+    pub fn clear(&mut self) {
+        self.reads.fill(0);
+        self.writes.fill(0);
+        self.executes.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    #[test]
+    fn test_heatmap() {
+        let mut emu = Emulator::new();
+        // 0x200: LD I, 0x300; LD B, V0; LD V2, [I]; DRW V0, V0, 2; JP 0x208
+        emu.load_rom(&[0xA3, 0x00, 0xF0, 0x33, 0xF2, 0x65, 0xD0, 0x02, 0x12, 0x08])
+            .unwrap();
+        emu.set_heatmap(Some(Heatmap::new(emu.ram().len())));
+        for _ in 0..6 {
+            emu.tick().unwrap();
+        }
+
+        let heatmap = emu.heatmap().unwrap();
+        assert_eq!(
+            heatmap.get(0x208),
+            AccessCounts {
+                reads: 0,
+                writes: 0,
+                executes: 2,
+            }
+        );
+        assert_eq!(heatmap.regions(Access::Write), vec![0x300..0x303]);
+        assert_eq!(heatmap.regions(Access::Read), vec![0x300..0x303]);
+        assert_eq!(heatmap.get(0x300).reads, 2);
+        assert_eq!(heatmap.regions(Access::Execute), vec![0x200..0x20A]);
+
+        let intensity = heatmap.intensity(Access::Execute);
+        assert_eq!(intensity[0x208], 1.0);
+        assert!(intensity[0x200] > 0.0 && intensity[0x200] < 1.0);
+        assert_eq!(intensity[0x20A], 0.0);
+    }
+}
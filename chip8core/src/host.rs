@@ -0,0 +1,204 @@
+//! Frontend-independent main loop.
+use super::{AudioConfig, Chip8Error, Emulator, EmulatorState, SquareWave};
+
+/// Default number of instructions executed per frame.
+pub const DEFAULT_CYCLES_PER_FRAME: u32 = 8;
+
+/// What the frontend wants the runner to do next.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Control {
+    /// Run the next frame.
+    #[default]
+    Run,
+    /// Skip emulation this frame, but still present the display.
+    Pause,
+    /// Stop running.
+    Quit,
+}
+
+/// Somewhere to show the display.
+pub trait DisplaySink {
+    /// Show the current display. Called once per frame.
+    fn present(&mut self, emu: &Emulator);
+}
+
+/// Source of key presses and frontend commands.
+pub trait InputSource {
+    /// Apply any pending input to the emulator. Called at the start of every frame.
+    fn poll(&mut self, emu: &mut Emulator) -> Control;
+}
+
+/// Somewhere to play the buzzer.
+pub trait AudioSink {
+    /// Output samples per second.
+    fn sample_rate(&self) -> u32;
+
+    /// Number of samples queued but not yet played. The runner only queues more audio while fewer
+    /// than two frames' worth are waiting.
+    fn queued(&self) -> usize {
+        0
+    }
+
+    /// Queue mono samples for playback.
+    fn queue(&mut self, samples: &[f32]);
+}
+
+impl DisplaySink for () {
+    fn present(&mut self, _emu: &Emulator) {}
+}
+impl InputSource for () {
+    fn poll(&mut self, _emu: &mut Emulator) -> Control {
+        Control::Run
+    }
+}
+impl AudioSink for () {
+    fn sample_rate(&self) -> u32 {
+        AudioConfig::default().sample_rate
+    }
+
+    fn queue(&mut self, _samples: &[f32]) {}
+}
+
+/// Drives an [Emulator] one frame at a time against a display, input and audio frontend.
+#[derive(Debug)]
+pub struct Runner<D, I, A> {
+    /// Where frames are shown.
+    pub display: D,
+    /// Where input comes from.
+    pub input: I,
+    /// Where the buzzer is played.
+    pub audio: A,
+    cycles_per_frame: u32,
+    wave: SquareWave,
+    samples: Vec<f32>,
+}
+impl<D: DisplaySink, I: InputSource, A: AudioSink> Runner<D, I, A> {
+    /// Create a runner with the default buzzer at the audio sink's sample rate.
+    pub fn new(display: D, input: I, audio: A) -> Self {
+        let wave = SquareWave::new(AudioConfig {
+            sample_rate: audio.sample_rate(),
+            ..AudioConfig::default()
+        });
+        Self {
+            display,
+            input,
+            audio,
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            wave,
+            samples: vec![0.0; wave.samples_per_frame()],
+        }
+    }
+
+    /// Return the number of instructions executed per frame.
+    pub fn cycles_per_frame(&self) -> u32 {
+        self.cycles_per_frame
+    }
+
+    /// Set the number of instructions executed per frame.
+    pub fn set_cycles_per_frame(&mut self, cycles: u32) {
+        self.cycles_per_frame = cycles;
+    }
+
+    /// Return the buzzer settings.
+    pub fn audio_config(&self) -> &AudioConfig {
+        self.wave.config()
+    }
+
+    /// Change the buzzer settings. The sample rate should match the audio sink's.
+    pub fn set_audio_config(&mut self, config: AudioConfig) {
+        self.wave.set_config(config);
+        self.samples.resize(self.wave.samples_per_frame(), 0.0);
+    }
+
+    /// Poll input, run one frame, queue its audio and present the display. Returns what the input
+    /// source asked for.
+    pub fn run_frame(&mut self, emu: &mut Emulator) -> Result<Control, Chip8Error> {
+        let control = self.input.poll(emu);
+        match control {
+            Control::Quit => return Ok(control),
+            Control::Pause => {}
+            Control::Run => {
+                emu.run_frame(self.cycles_per_frame)?;
+                if self.audio.queued() < self.samples.len() * 2 {
+                    self.wave.fill_from(emu, &mut self.samples);
+                    self.audio.queue(&self.samples);
+                }
+            }
+        }
+        self.display.present(emu);
+        Ok(control)
+    }
+
+    /// Run frames until the input source quits or the ROM exits. Frame pacing is left to the
+    /// frontend, e.g. by presenting with vsync.
+    pub fn run(&mut self, emu: &mut Emulator) -> Result<(), Chip8Error> {
+        while self.run_frame(emu)? != Control::Quit {
+            if emu.state() == EmulatorState::Exited {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Frames(usize);
+    impl DisplaySink for Frames {
+        fn present(&mut self, _emu: &Emulator) {
+            self.0 += 1;
+        }
+    }
+
+    struct QuitAfter(usize);
+    impl InputSource for QuitAfter {
+        fn poll(&mut self, emu: &mut Emulator) -> Control {
+            emu.press(5).unwrap();
+            if self.0 == 0 {
+                return Control::Quit;
+            }
+            self.0 -= 1;
+            Control::Run
+        }
+    }
+
+    #[derive(Default)]
+    struct Samples(Vec<f32>);
+    impl AudioSink for Samples {
+        fn sample_rate(&self) -> u32 {
+            600
+        }
+
+        fn queue(&mut self, samples: &[f32]) {
+            self.0.extend_from_slice(samples);
+        }
+    }
+
+    #[test]
+    fn test_runner() {
+        let mut emu = Emulator::new();
+        // 0x200: LD V0, 0xFF; LD ST, V0; JP 0x204
+        emu.load_rom(&[0x60, 0xFF, 0xF0, 0x18, 0x12, 0x04]).unwrap();
+        let mut runner = Runner::new(Frames::default(), QuitAfter(3), Samples::default());
+        runner.run(&mut emu).unwrap();
+
+        assert_eq!(runner.display.0, 3);
+        assert_eq!(emu.frames(), 3);
+        assert!(emu.is_key_pressed(5));
+        assert_eq!(runner.audio.0.len(), 30);
+        assert!(runner.audio.0.iter().all(|sample| *sample != 0.0));
+
+        // Stops once the ROM exits.
+        let mut emu = Emulator::new();
+        // 0x200: EXIT
+        emu.load_rom(&[0x00, 0xFD]).unwrap();
+        let mut runner = Runner::new((), (), ());
+        runner.run(&mut emu).unwrap();
+        assert_eq!(emu.state(), EmulatorState::Exited);
+    }
+}
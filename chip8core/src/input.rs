@@ -0,0 +1,99 @@
+//! Key events scheduled ahead of time.
+
+/// When a scheduled key event applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum InputTime {
+    /// At the start of the given frame, counting timer ticks since reset.
+    Frame(u64),
+    /// Once the given number of instructions have executed since reset.
+    Cycle(u64),
+}
+
+/// A key press or release applied at a set time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ScheduledKey {
+    /// When to apply the event.
+    pub at: InputTime,
+    /// Chip-8 key index.
+    pub key: u8,
+    /// Whether the key is pressed or released.
+    pub pressed: bool,
+}
+
+/// Key events waiting for their time to come, in the order they were queued.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InputQueue {
+    pending: Vec<ScheduledKey>,
+}
+impl InputQueue {
+    /// Add an event.
+    pub(crate) fn push(&mut self, event: ScheduledKey) {
+        self.pending.push(event);
+    }
+
+    /// Whether no events are waiting.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Remove every event.
+    pub(crate) fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Return the events waiting, in the order they were queued.
+    pub(crate) fn pending(&self) -> &[ScheduledKey] {
+        &self.pending
+    }
+
+    /// Remove and return the events due at or before `frame` and `cycle`, in the order they were
+    /// queued.
+    pub(crate) fn take_due(&mut self, frame: u64, cycle: u64) -> Vec<ScheduledKey> {
+        let is_due = |event: &ScheduledKey| match event.at {
+            InputTime::Frame(at) => at <= frame,
+            InputTime::Cycle(at) => at <= cycle,
+        };
+        let (due, pending) = self.pending.drain(..).partition(is_due);
+        self.pending = pending;
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{Chip8Error, Emulator};
+
+    use super::*;
+
+    #[test]
+    fn test_scheduled_keys() {
+        let mut emu = Emulator::new();
+        // 0x200: LD V0, K; JP 0x200
+        emu.load_rom(&[0xF0, 0x0A, 0x12, 0x00]).unwrap();
+        emu.queue_key(InputTime::Frame(2), 7, true).unwrap();
+        emu.queue_key(InputTime::Cycle(3), 7, false).unwrap();
+        assert_eq!(
+            emu.queue_key(InputTime::Cycle(0), 16, true),
+            Err(Chip8Error::InvalidKey(16))
+        );
+        assert_eq!(emu.queued_keys().len(), 2);
+
+        emu.run_frame(4).unwrap();
+        emu.run_frame(4).unwrap();
+        assert_eq!(emu.cycles(), 1);
+        assert_eq!(emu.v(0_usize), 0);
+
+        // The key goes down at the start of frame 2, then up after the 3rd instruction.
+        emu.run_frame(4).unwrap();
+        assert_eq!(emu.frames(), 3);
+        assert_eq!(emu.v(0_usize), 7);
+        assert!(!emu.is_key_pressed(7));
+        assert!(emu.queued_keys().is_empty());
+    }
+}
@@ -1,9 +1,91 @@
 //! Backend for `chip8emu`.
 #![warn(missing_docs)]
 
+pub mod analyze;
+pub mod asm;
+pub mod audio;
+pub mod builder;
+pub mod callstack;
+pub mod cheats;
+pub mod console;
+pub mod debug;
+pub mod decompile;
+pub mod diagnostics;
+pub mod diff;
+pub mod display;
 pub mod emulator;
-mod fontset;
+pub mod error;
+pub mod events;
+pub mod fontset;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+#[cfg(feature = "embedded-graphics")]
+pub mod graphics;
+pub mod harness;
+pub mod heatmap;
+pub mod host;
+pub mod input;
+pub mod multi;
+pub mod netplay;
+pub mod octo;
 pub mod opcodes;
+pub mod profile;
+pub mod quirks;
+pub mod recording;
+pub mod render;
+pub mod rewind;
+pub mod rng;
+pub mod rom;
+#[cfg(feature = "romdb")]
+pub mod romdb;
+#[cfg(feature = "archives")]
+pub mod romfile;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod search;
+pub mod state;
+pub mod testing;
+pub mod timetravel;
+pub mod timing;
+pub mod trace;
+pub mod verify;
 
 // Re-exports
-pub use emulator::Emulator;
+pub use analyze::{Analysis, Platform, Problem, Quirk};
+pub use audio::{AudioConfig, SquareWave};
+pub use builder::{BuildError, EmulatorBuilder};
+pub use callstack::StackFrame;
+pub use cheats::{Cheat, CheatError, CheatTarget, Cheats};
+pub use debug::{Debugger, RunOutcome, RunResult, StopReason, TickResult, Watchpoint};
+pub use diagnostics::{Diagnostic, DiagnosticKind, Diagnostics, Severity};
+pub use diff::{PixelChange, RamChange, RegisterChange, StateDiff};
+pub use display::Display;
+pub use emulator::{
+    Emulator, EmulatorState, FrameOutput, HaltReason, RawParts, StackOverflowBehavior,
+};
+pub use error::Chip8Error;
+pub use events::Event;
+pub use harness::{Check, HarnessError, TestRom};
+pub use heatmap::{Access, AccessCounts, Heatmap};
+pub use host::{AudioSink, Control, DisplaySink, InputSource, Runner};
+pub use input::{InputTime, ScheduledKey};
+pub use multi::{Instance, Instances};
+pub use netplay::{Netplay, NetplayError};
+pub use profile::{ProfileReport, Profiler};
+pub use quirks::Quirks;
+pub use recording::{Player, Recorder, Recording};
+pub use render::{FlashLimiter, Palette, Phosphor, PlanePalette, Rotation};
+pub use rewind::RewindBuffer;
+pub use rng::{RandomSource, XorShift};
+pub use rom::{LoadError, RomInfo};
+#[cfg(feature = "romdb")]
+pub use romdb::RomEntry;
+#[cfg(feature = "archives")]
+pub use romfile::{RomFile, RomFileError};
+#[cfg(feature = "scripting")]
+pub use scripting::{Script, ScriptError};
+pub use search::{Comparison, RamSearch};
+pub use state::SaveState;
+pub use timetravel::TimeTravel;
+pub use trace::Tracer;
+pub use verify::{Divergence, Lockstep, Reference};
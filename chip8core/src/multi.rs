@@ -0,0 +1,231 @@
+//! Several independent emulators run side by side, e.g. to compare quirks or versions of a ROM.
+use super::{Chip8Error, Emulator, EmulatorState, StopReason};
+
+/// One emulator among several, with its own speed.
+pub struct Instance {
+    /// Emulator being run.
+    pub emu: Emulator,
+    /// Name shown for the instance, e.g. the ROM and quirks it runs with.
+    pub label: String,
+    /// Instructions executed per frame.
+    pub cycles_per_frame: u32,
+    /// Error which stopped the instance, if any.
+    pub error: Option<Chip8Error>,
+    /// Breakpoint or watchpoint which cut the last frame short, if any.
+    pub stop: Option<StopReason>,
+}
+impl Instance {
+    /// Wrap an emulator to run at `cycles_per_frame`.
+    pub fn new(emu: Emulator, label: impl Into<String>, cycles_per_frame: u32) -> Self {
+        Self {
+            emu,
+            label: label.into(),
+            cycles_per_frame,
+            error: None,
+            stop: None,
+        }
+    }
+
+    /// Whether the instance still runs, i.e. hasn't exited or hit an error.
+    pub fn is_running(&self) -> bool {
+        self.error.is_none() && self.emu.state() != EmulatorState::Exited
+    }
+}
+
+/// Emulators run in lockstep, one frame each at a time, with one focused to receive input.
+#[derive(Default)]
+pub struct Instances {
+    instances: Vec<Instance>,
+    focus: usize,
+    linked: bool,
+}
+impl Instances {
+    /// Create an empty set of instances.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an instance after the others.
+    pub fn push(&mut self, instance: Instance) {
+        self.instances.push(instance);
+    }
+
+    /// Number of instances.
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Whether there are no instances.
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// Iterate over the instances in order.
+    pub fn iter(&self) -> impl Iterator<Item = &Instance> {
+        self.instances.iter()
+    }
+
+    /// Iterate over the instances in order, mutably.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Instance> {
+        self.instances.iter_mut()
+    }
+
+    /// Return the instance at `index`.
+    pub fn get(&self, index: usize) -> Option<&Instance> {
+        self.instances.get(index)
+    }
+
+    /// Return the instance at `index` mutably.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Instance> {
+        self.instances.get_mut(index)
+    }
+
+    /// Index of the instance receiving input.
+    pub fn focus(&self) -> usize {
+        self.focus
+    }
+
+    /// Return the instance receiving input.
+    pub fn focused(&self) -> Option<&Instance> {
+        self.instances.get(self.focus)
+    }
+
+    /// Give input to the instance at `index`, if there is one.
+    pub fn set_focus(&mut self, index: usize) {
+        if index < self.instances.len() {
+            self.focus = index;
+        }
+    }
+
+    /// Move the focus to the next instance, wrapping around.
+    pub fn focus_next(&mut self) {
+        if !self.instances.is_empty() {
+            self.focus = (self.focus + 1) % self.instances.len();
+        }
+    }
+
+    /// Move the focus to the previous instance, wrapping around.
+    pub fn focus_previous(&mut self) {
+        if !self.instances.is_empty() {
+            self.focus = (self.focus + self.instances.len() - 1) % self.instances.len();
+        }
+    }
+
+    /// Whether input goes to every instance instead of just the focused one.
+    pub fn linked(&self) -> bool {
+        self.linked
+    }
+
+    /// Send input to every instance, or only the focused one.
+    pub fn set_linked(&mut self, linked: bool) {
+        self.linked = linked;
+    }
+
+    /// Set the keys of the focused instance, or every instance if linked, from a bitmask. Other
+    /// instances have their keys released.
+    pub fn set_keys(&mut self, mask: u16) {
+        for (index, instance) in self.instances.iter_mut().enumerate() {
+            let keys = if self.linked || index == self.focus {
+                mask
+            } else {
+                0
+            };
+            instance.emu.set_keys(keys);
+        }
+    }
+
+    /// Run a frame of every instance still running. An error stops only the instance it came
+    /// from, and a breakpoint or watchpoint only cuts its frame short. Returns whether any
+    /// instance is still running.
+    pub fn run_frame(&mut self) -> bool {
+        for instance in self.instances.iter_mut().filter(|i| i.is_running()) {
+            match instance.emu.run_frame(instance.cycles_per_frame) {
+                Ok(output) => instance.stop = output.stop(),
+                Err(e) => instance.error = Some(e),
+            }
+        }
+        self.instances.iter().any(Instance::is_running)
+    }
+
+    /// Columns and rows of the [grid] fitting every instance.
+    pub fn grid(&self) -> (usize, usize) {
+        grid(self.instances.len())
+    }
+
+    /// Column and row of the instance at `index` in the [grid](Self::grid).
+    pub fn tile(&self, index: usize) -> (usize, usize) {
+        let (columns, _) = self.grid();
+        (index % columns, index / columns)
+    }
+}
+impl IntoIterator for Instances {
+    type Item = Instance;
+    type IntoIter = std::vec::IntoIter<Instance>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.instances.into_iter()
+    }
+}
+
+/// Columns and rows of the most square grid fitting `count` tiles, wider than tall.
+pub fn grid(count: usize) -> (usize, usize) {
+    let count = count.max(1);
+    let columns = (1..=count).find(|c| c * c >= count).unwrap_or(count);
+    (columns, count.div_ceil(columns))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_instances() {
+        // 0x200: LD V0, K; JP 0x200
+        let rom = [0xF0, 0x0A, 0x12, 0x00];
+        let mut instances = Instances::new();
+        for label in ["a", "b", "c"] {
+            let mut emu = Emulator::new();
+            emu.load_rom(&rom).unwrap();
+            instances.push(Instance::new(emu, label, 8));
+        }
+        // An invalid opcode stops only its own instance.
+        let mut broken = Emulator::new();
+        broken.load_rom(&[0xFF, 0xFF]).unwrap();
+        instances.push(Instance::new(broken, "broken", 8));
+        assert_eq!(instances.grid(), (2, 2));
+        assert_eq!(instances.tile(3), (1, 1));
+
+        assert!(instances.run_frame());
+        assert!(instances.get(3).unwrap().error.is_some());
+
+        // Keys go to the focused instance only, unless linked.
+        instances.focus_previous();
+        instances.focus_previous();
+        assert_eq!(instances.focused().unwrap().label, "c");
+        instances.set_keys(1 << 7);
+        instances.run_frame();
+        let v0: Vec<_> = instances.iter().map(|i| i.emu.v(0_usize)).collect();
+        assert_eq!(v0, [0, 0, 7, 0]);
+
+        instances.set_keys(0);
+        instances.set_linked(true);
+        instances.set_keys(1 << 9);
+        instances.run_frame();
+        let v0: Vec<_> = instances.iter().map(|i| i.emu.v(0_usize)).collect();
+        assert_eq!(v0, [9, 9, 9, 0]);
+
+        // A breakpoint cuts only its own instance's frame short.
+        let a = instances.get_mut(0).unwrap();
+        a.emu.debugger_mut().add_breakpoint(0x202);
+        instances.set_keys(0);
+        instances.run_frame();
+        let stops: Vec<_> = instances.iter().map(|i| i.stop).collect();
+        assert_eq!(
+            stops,
+            [Some(StopReason::Breakpoint(0x202)), None, None, None]
+        );
+        assert_eq!(instances.get(0).unwrap().emu.pc(), 0x202);
+    }
+}
@@ -0,0 +1,336 @@
+//! Two-player netplay by exchanging every frame's keys over a stream, e.g. a
+//! [TcpStream](std::net::TcpStream) with `set_nodelay(true)`.
+//!
+//! Both peers run the same ROM with the same quirks, speed and RNG seed and apply the same keys on
+//! the same frames, so they stay in step without sending any other state. Keys are delayed by a fixed number of
+//! frames to give them time to arrive, and the keys of both players are combined, since CHIP-8
+//! games share one keypad between players.
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    io::{self, Read, Write},
+};
+
+use super::{state::pack_bits, Emulator, Quirks};
+
+/// Start of every handshake.
+const MAGIC: &[u8; 4] = b"C8NP";
+/// Protocol version, bumped on incompatible changes.
+pub const VERSION: u8 = 2;
+/// Frames of input delay used when hosting, enough for most connections at 60Hz.
+pub const DEFAULT_DELAY: u8 = 3;
+
+/// Session with a peer, started before the first frame is run.
+#[derive(Debug)]
+pub struct Netplay<S> {
+    stream: S,
+    delay: u8,
+    frame: u64,
+    /// Local keys sent but not applied yet, oldest first.
+    pending: VecDeque<u16>,
+}
+impl<S: Read + Write> Netplay<S> {
+    /// Host a session running `cycles_per_frame` instructions a frame, choosing the RNG seed and
+    /// input delay.
+    pub fn host(
+        stream: S,
+        emu: &mut Emulator,
+        cycles_per_frame: u32,
+        delay: u8,
+    ) -> Result<Self, NetplayError> {
+        Self::start(stream, emu, cycles_per_frame, Some((emu.rng_seed(), delay)))
+    }
+
+    /// Join a session hosted by the peer running `cycles_per_frame` instructions a frame, taking
+    /// its RNG seed and input delay.
+    pub fn join(
+        stream: S,
+        emu: &mut Emulator,
+        cycles_per_frame: u32,
+    ) -> Result<Self, NetplayError> {
+        Self::start(stream, emu, cycles_per_frame, None)
+    }
+
+    /// Swap handshakes and check both peers run the same ROM with the same quirks and speed.
+    /// `settings` is the seed and delay, if hosting.
+    fn start(
+        mut stream: S,
+        emu: &mut Emulator,
+        cycles_per_frame: u32,
+        settings: Option<(u64, u8)>,
+    ) -> Result<Self, NetplayError> {
+        let sha1 = emu.rom_info().ok_or(NetplayError::NoRom)?.sha1;
+        let quirks = quirk_bits(emu.quirks());
+        let (seed, delay) = settings.unwrap_or_default();
+        let mut hello = Vec::with_capacity(HELLO_SIZE);
+        hello.extend_from_slice(MAGIC);
+        hello.push(VERSION);
+        hello.push(settings.is_some() as u8);
+        hello.extend_from_slice(&seed.to_be_bytes());
+        hello.push(delay);
+        hello.push(quirks);
+        hello.extend_from_slice(&cycles_per_frame.to_be_bytes());
+        hello.extend_from_slice(&sha1);
+        stream.write_all(&hello)?;
+        stream.flush()?;
+
+        let mut peer = [0; HELLO_SIZE];
+        stream.read_exact(&mut peer)?;
+        if &peer[..4] != MAGIC {
+            return Err(NetplayError::Protocol);
+        }
+        if peer[4] != VERSION {
+            return Err(NetplayError::Version(peer[4]));
+        }
+        let peer_hosting = peer[5] != 0;
+        if peer_hosting == settings.is_some() {
+            return Err(NetplayError::Roles);
+        }
+        if peer[20..] != sha1 {
+            return Err(NetplayError::RomMismatch);
+        }
+        if peer[15] != quirks {
+            return Err(NetplayError::QuirksMismatch);
+        }
+        let peer_cycles = u32::from_be_bytes(peer[16..20].try_into().unwrap());
+        if peer_cycles != cycles_per_frame {
+            return Err(NetplayError::SpeedMismatch(peer_cycles));
+        }
+        let (seed, delay) = match settings {
+            Some(settings) => settings,
+            None => (
+                u64::from_be_bytes(peer[6..14].try_into().unwrap()),
+                peer[14],
+            ),
+        };
+
+        emu.seed_rng(seed);
+        Ok(Self {
+            stream,
+            delay,
+            frame: 0,
+            pending: VecDeque::from(vec![0; delay as usize]),
+        })
+    }
+
+    /// Send this player's keys and set the emulator's keys for the next frame to both players'
+    /// keys from `delay` frames ago, waiting for the peer's if they haven't arrived. Call once
+    /// before every frame. Returns the keys set.
+    pub fn exchange(&mut self, emu: &mut Emulator, local: u16) -> Result<u16, NetplayError> {
+        let target = self.frame + self.delay as u64;
+        let mut message = [0; INPUT_SIZE];
+        message[..8].copy_from_slice(&target.to_be_bytes());
+        message[8..].copy_from_slice(&local.to_be_bytes());
+        self.stream.write_all(&message)?;
+        self.stream.flush()?;
+        self.pending.push_back(local);
+
+        // The peer's keys for the first `delay` frames are implicitly none, like ours.
+        let remote = if self.frame < self.delay as u64 {
+            0
+        } else {
+            self.stream.read_exact(&mut message)?;
+            let frame = u64::from_be_bytes(message[..8].try_into().unwrap());
+            if frame != self.frame {
+                return Err(NetplayError::Desync {
+                    expected: self.frame,
+                    received: frame,
+                });
+            }
+            u16::from_be_bytes([message[8], message[9]])
+        };
+
+        let keys = self.pending.pop_front().unwrap_or_default() | remote;
+        emu.set_keys(keys);
+        self.frame += 1;
+        Ok(keys)
+    }
+
+    /// Frames of delay between pressing a key and it taking effect.
+    pub fn delay(&self) -> u8 {
+        self.delay
+    }
+
+    /// Number of frames exchanged so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Stream to the peer.
+    pub fn stream(&self) -> &S {
+        &self.stream
+    }
+}
+
+/// Length of a handshake: magic, version, whether hosting, seed, delay, quirks, cycles per frame
+/// and ROM hash.
+const HELLO_SIZE: usize = 4 + 1 + 1 + 8 + 1 + 1 + 4 + 20;
+/// Length of an input message: frame number and keys.
+const INPUT_SIZE: usize = 8 + 2;
+
+/// Error starting or running a netplay session.
+#[derive(Debug)]
+pub enum NetplayError {
+    /// Reading from or writing to the peer failed, e.g. because it disconnected.
+    Io(io::Error),
+    /// No ROM is loaded.
+    NoRom,
+    /// The peer isn't speaking the netplay protocol.
+    Protocol,
+    /// The peer uses a different protocol version.
+    Version(u8),
+    /// Both peers are hosting, or both are joining.
+    Roles,
+    /// The peer is running a different ROM.
+    RomMismatch,
+    /// The peer emulates different quirks.
+    QuirksMismatch,
+    /// The peer runs a different number of instructions per frame, given.
+    SpeedMismatch(u32),
+    /// The peer sent keys for the wrong frame.
+    Desync {
+        /// Frame expected.
+        expected: u64,
+        /// Frame received.
+        received: u64,
+    },
+}
+impl fmt::Display for NetplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "connection error: {}", e),
+            Self::NoRom => write!(f, "no ROM loaded"),
+            Self::Protocol => write!(f, "peer is not a chip8emu netplay session"),
+            Self::Version(version) => write!(
+                f,
+                "peer uses netplay version {}, expected {}",
+                version, VERSION
+            ),
+            Self::Roles => write!(f, "one peer must host and the other join"),
+            Self::RomMismatch => write!(f, "peer is running a different ROM"),
+            Self::QuirksMismatch => write!(f, "peer emulates different quirks"),
+            Self::SpeedMismatch(cycles) => write!(
+                f,
+                "peer runs {} instructions per frame, a different speed",
+                cycles
+            ),
+            Self::Desync { expected, received } => write!(
+                f,
+                "expected keys for frame {}, received frame {}",
+                expected, received
+            ),
+        }
+    }
+}
+impl Error for NetplayError {}
+
+/// Quirks packed into a byte, to compare with the peer's.
+fn quirk_bits(quirks: &Quirks) -> u8 {
+    pack_bits(&[
+        quirks.shift_vy,
+        quirks.load_store_increment_i,
+        quirks.jump_vx,
+        quirks.vf_reset,
+        quirks.clip_sprites,
+    ])
+}
+impl From<io::Error> for NetplayError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    // 0x200: RND V1, 0xFF; LD V0, 5; SKP V0; JP 0x200; ADD V3, 1; JP 0x200
+    const ROM: [u8; 12] = [
+        0xC1, 0xFF, 0x60, 0x05, 0xE0, 0x9E, 0x12, 0x00, 0x73, 0x01, 0x12, 0x00,
+    ];
+
+    /// Run 20 frames, pressing key 5 on the frames in `presses`.
+    fn play<S: Read + Write>(
+        netplay: &mut Netplay<S>,
+        emu: &mut Emulator,
+        presses: std::ops::Range<u64>,
+    ) {
+        for frame in 0..20 {
+            let keys = if presses.contains(&frame) { 1 << 5 } else { 0 };
+            netplay.exchange(emu, keys).unwrap();
+            emu.run_frame(8).unwrap();
+        }
+    }
+
+    /// Host `emu` at 8 cycles per frame for `guest` running `cycles`, returning the errors both
+    /// sides start with.
+    fn refuse(
+        listener: &TcpListener,
+        emu: &mut Emulator,
+        mut guest: Emulator,
+        cycles: u32,
+    ) -> (NetplayError, NetplayError) {
+        let addr = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || {
+            Netplay::join(TcpStream::connect(addr).unwrap(), &mut guest, cycles).map(|_| ())
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let host = Netplay::host(stream, emu, 8, DEFAULT_DELAY).map(|_| ());
+        (host.unwrap_err(), guest.join().unwrap().unwrap_err())
+    }
+
+    #[test]
+    fn test_netplay() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || {
+            let mut emu = Emulator::with_seed(2);
+            emu.load_rom(&ROM).unwrap();
+            let stream = TcpStream::connect(addr).unwrap();
+            let mut netplay = Netplay::join(stream, &mut emu, 8).unwrap();
+            play(&mut netplay, &mut emu, 0..0);
+            // Closing the stream with the host's last keys unread could reset the connection
+            // before the host reads ours.
+            (*emu.v_registers(), netplay)
+        });
+
+        let mut emu = Emulator::with_seed(1);
+        emu.load_rom(&ROM).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let mut netplay = Netplay::host(stream, &mut emu, 8, DEFAULT_DELAY).unwrap();
+        play(&mut netplay, &mut emu, 5..10);
+
+        // The host's presses and the random numbers are the same on both sides.
+        let (guest, _) = guest.join().unwrap();
+        assert_eq!(*emu.v_registers(), guest);
+        assert!(emu.v(3_usize) > 0);
+        assert_eq!(netplay.frame(), 20);
+
+        // Different ROMs, quirks or speeds can't play together.
+        let mut other_rom = Emulator::new();
+        other_rom.load_rom(&[0x12, 0x00]).unwrap();
+        let (host, guest) = refuse(&listener, &mut emu, other_rom, 8);
+        assert!(matches!(host, NetplayError::RomMismatch));
+        assert!(matches!(guest, NetplayError::RomMismatch));
+
+        let mut chip8 = Emulator::builder().quirks(Quirks::chip8()).build().unwrap();
+        chip8.load_rom(&ROM).unwrap();
+        let (host, guest) = refuse(&listener, &mut emu, chip8, 8);
+        assert!(matches!(host, NetplayError::QuirksMismatch));
+        assert!(matches!(guest, NetplayError::QuirksMismatch));
+
+        let mut faster = Emulator::new();
+        faster.load_rom(&ROM).unwrap();
+        let (host, guest) = refuse(&listener, &mut emu, faster, 10);
+        assert!(matches!(host, NetplayError::SpeedMismatch(10)));
+        assert!(matches!(guest, NetplayError::SpeedMismatch(8)));
+    }
+}
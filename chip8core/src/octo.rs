@@ -0,0 +1,711 @@
+//! Compiler for Octo (`.o8`) source files.
+//!
+//! Supports the core of the Octo language:
+//! - Labels (`: name`), `:alias`, `:const`, `:byte` and raw bytes for sprite data.
+//! - Register statements such as `v0 := 5`, `v1 += v2`, `v3 := random 0xFF` and `vf <<= v0`.
+//! - `i := label`, `i := hex vx`, `i += vx`, `delay := vx`, `buzzer := vx`, `save`, `load`, `bcd`.
+//! - `clear`, `sprite`, `jump`, `jump0`, `return`/`;`, `exit` and subroutine calls by label name.
+//! - `if ... then`, `if ... begin ... else ... end`, `loop ... while ... again`.
+//!
+//! Conditions compare a register using `==`, `!=`, `key` or `-key`. Execution starts at `: main`.
+use std::{collections::HashMap, error::Error, fmt};
+
+use super::{
+    emulator::{RAM_SIZE, START_ADDRESS},
+    opcodes::Instruction,
+};
+
+/// Maximum size of a compiled program.
+const MAX_ROM_SIZE: usize = RAM_SIZE - START_ADDRESS as usize;
+
+/// Error compiling an Octo program, with the position it occurred at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OctoError {
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// Column number, starting at 1.
+    pub column: usize,
+    /// Description of the error.
+    pub message: String,
+}
+impl fmt::Display for OctoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+impl Error for OctoError {}
+
+/// Compile Octo source into ROM bytes.
+pub fn compile(source: &str) -> Result<Vec<u8>, OctoError> {
+    let mut compiler = Compiler::new(tokenize(source));
+    compiler.compile()?;
+    Ok(compiler.rom)
+}
+
+/// A piece of source text and its position.
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+impl Token<'_> {
+    fn error(&self, message: String) -> OctoError {
+        OctoError {
+            line: self.line,
+            column: self.column,
+            message,
+        }
+    }
+}
+
+/// Split the source into whitespace-separated tokens, skipping `#` comments.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for (line_idx, line) in source.lines().enumerate() {
+        let code = match line.find('#') {
+            Some(idx) => &line[..idx],
+            None => line,
+        };
+        let mut start = None;
+        for (idx, c) in code.char_indices().chain([(code.len(), ' ')]) {
+            if c.is_whitespace() {
+                if let Some(s) = start.take() {
+                    tokens.push(Token {
+                        text: &code[s..idx],
+                        line: line_idx + 1,
+                        column: s + 1,
+                    });
+                }
+            } else if start.is_none() {
+                start = Some(idx);
+            }
+        }
+    }
+    tokens
+}
+
+/// Parse a number literal. Negative numbers are allowed.
+fn parse_number(text: &str) -> Option<i32> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let lower = digits.to_ascii_lowercase();
+    let value = if let Some(hex) = lower.strip_prefix("0x") {
+        i32::from_str_radix(hex, 16).ok()?
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i32::from_str_radix(bin, 2).ok()?
+    } else {
+        lower.parse().ok()?
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// Parse a V register name.
+fn parse_register(text: &str) -> Option<u8> {
+    let digit = text.strip_prefix(['v', 'V'])?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(digit, 16).ok()
+}
+
+/// Whether the text is a valid name for a label, constant or alias.
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && parse_register(text).is_none()
+}
+
+/// An open control flow block.
+enum Block<'a> {
+    /// `loop`, with the jumps emitted by each `while` inside it.
+    Loop {
+        token: Token<'a>,
+        start: u16,
+        breaks: Vec<usize>,
+    },
+    /// `if ... begin`, with the jump to the end of the block.
+    If { token: Token<'a>, jump: usize },
+    /// `else`, with the jump to the end of the block.
+    Else { token: Token<'a>, jump: usize },
+}
+
+/// Single-pass compiler which patches forward references once every label is known.
+struct Compiler<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    rom: Vec<u8>,
+    labels: HashMap<&'a str, u16>,
+    consts: HashMap<&'a str, i32>,
+    aliases: HashMap<&'a str, u8>,
+    /// Instructions whose address refers to a label which was not yet defined.
+    fixups: Vec<(usize, Token<'a>)>,
+    blocks: Vec<Block<'a>>,
+}
+impl<'a> Compiler<'a> {
+    fn new(tokens: Vec<Token<'a>>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            rom: Vec::new(),
+            labels: HashMap::new(),
+            consts: HashMap::new(),
+            aliases: HashMap::new(),
+            fixups: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    fn compile(&mut self) -> Result<(), OctoError> {
+        while self.pos < self.tokens.len() {
+            self.statement()?;
+        }
+
+        if let Some(block) = self.blocks.last() {
+            let (token, message) = match block {
+                Block::Loop { token, .. } => (token, "`loop` without matching `again`"),
+                Block::If { token, .. } | Block::Else { token, .. } => {
+                    (token, "`begin` without matching `end`")
+                }
+            };
+            return Err(token.error(message.to_string()));
+        }
+
+        for (idx, token) in std::mem::take(&mut self.fixups) {
+            match self.labels.get(token.text) {
+                Some(&addr) => self.patch(idx, addr),
+                None => return Err(token.error(format!("undefined label `{}`", token.text))),
+            }
+        }
+        Ok(())
+    }
+
+    /// Address the next emitted byte will be loaded at.
+    fn here(&self) -> u16 {
+        START_ADDRESS + self.rom.len() as u16
+    }
+
+    fn next(&mut self) -> Result<Token<'a>, OctoError> {
+        match self.tokens.get(self.pos) {
+            Some(&token) => {
+                self.pos += 1;
+                Ok(token)
+            }
+            None => {
+                let last = self.tokens[self.pos - 1];
+                Err(last.error("unexpected end of file".to_string()))
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(|token| token.text)
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), OctoError> {
+        let token = self.next()?;
+        if token.text != text {
+            return Err(token.error(format!("expected `{}`, found `{}`", text, token.text)));
+        }
+        Ok(())
+    }
+
+    /// Jump to main at the start of the program, unless the program starts with it.
+    fn jump_to_main(&mut self) {
+        if self.rom.is_empty() && !self.labels.contains_key("main") {
+            let main = Token {
+                text: "main",
+                line: 1,
+                column: 1,
+            };
+            self.rom
+                .extend_from_slice(&Instruction::Jp(0).opcode().to_be_bytes());
+            self.fixups.push((0, main));
+        }
+    }
+
+    fn push_byte(&mut self, byte: u8, token: Token<'a>) -> Result<(), OctoError> {
+        self.jump_to_main();
+        if self.rom.len() >= MAX_ROM_SIZE {
+            return Err(token.error("program does not fit in RAM".to_string()));
+        }
+        self.rom.push(byte);
+        Ok(())
+    }
+
+    /// Emit an instruction, returning its index in the ROM.
+    fn emit(&mut self, instruction: Instruction, token: Token<'a>) -> Result<usize, OctoError> {
+        let idx = self.rom.len();
+        let [hi, lo] = instruction.opcode().to_be_bytes();
+        self.push_byte(hi, token)?;
+        self.push_byte(lo, token)?;
+        Ok(idx)
+    }
+
+    /// Emit an instruction taking an address, which may refer to a label defined later on.
+    fn emit_with_address(
+        &mut self,
+        target: Token<'a>,
+        token: Token<'a>,
+        make: fn(u16) -> Instruction,
+    ) -> Result<(), OctoError> {
+        let known = parse_number(target.text)
+            .or_else(|| self.consts.get(target.text).copied())
+            .map(|value| {
+                u16::try_from(value)
+                    .ok()
+                    .filter(|addr| *addr <= 0xFFF)
+                    .ok_or_else(|| target.error(format!("address {} out of range", value)))
+            })
+            .transpose()?
+            .or_else(|| self.labels.get(target.text).copied());
+
+        match known {
+            Some(addr) => {
+                self.emit(make(addr), token)?;
+            }
+            None if is_identifier(target.text) => {
+                let idx = self.emit(make(0), token)?;
+                self.fixups.push((idx, target));
+            }
+            None => return Err(target.error(format!("invalid address `{}`", target.text))),
+        }
+        Ok(())
+    }
+
+    /// Set the address of the instruction at `idx`.
+    fn patch(&mut self, idx: usize, addr: u16) {
+        let op = u16::from_be_bytes([self.rom[idx], self.rom[idx + 1]]) & 0xF000 | addr;
+        self.rom[idx..idx + 2].copy_from_slice(&op.to_be_bytes());
+    }
+
+    fn register(&mut self) -> Result<u8, OctoError> {
+        let token = self.next()?;
+        parse_register(token.text)
+            .or_else(|| self.aliases.get(token.text).copied())
+            .ok_or_else(|| token.error(format!("expected a register, found `{}`", token.text)))
+    }
+
+    /// Parse a value in `min..=max`.
+    fn value(&mut self, min: i32, max: i32) -> Result<i32, OctoError> {
+        let token = self.next()?;
+        let value = parse_number(token.text)
+            .or_else(|| self.consts.get(token.text).copied())
+            .or_else(|| self.labels.get(token.text).map(|addr| *addr as i32))
+            .ok_or_else(|| token.error(format!("expected a number, found `{}`", token.text)))?;
+        if !(min..=max).contains(&value) {
+            return Err(token.error(format!("value {} out of range", value)));
+        }
+        Ok(value)
+    }
+
+    fn byte(&mut self) -> Result<u8, OctoError> {
+        self.value(-128, 255).map(|value| value as u8)
+    }
+
+    fn name(&mut self) -> Result<Token<'a>, OctoError> {
+        let token = self.next()?;
+        if !is_identifier(token.text) {
+            return Err(token.error(format!("invalid name `{}`", token.text)));
+        }
+        Ok(token)
+    }
+
+    /// Parse a condition, returning instructions which skip when it is true and when it is false.
+    fn condition(&mut self) -> Result<(Instruction, Instruction), OctoError> {
+        let x = self.register()?;
+        let op = self.next()?;
+        let rhs_register = self
+            .peek()
+            .and_then(|text| parse_register(text).or_else(|| self.aliases.get(text).copied()));
+
+        let skips = match op.text {
+            "key" => (Instruction::Skp { x }, Instruction::Sknp { x }),
+            "-key" => (Instruction::Sknp { x }, Instruction::Skp { x }),
+            "==" | "!=" => {
+                let (eq, ne) = match rhs_register {
+                    Some(y) => {
+                        self.pos += 1;
+                        (Instruction::SeVxVy { x, y }, Instruction::SneVxVy { x, y })
+                    }
+                    None => {
+                        let byte = self.byte()?;
+                        (
+                            Instruction::SeVxByte { x, byte },
+                            Instruction::SneVxByte { x, byte },
+                        )
+                    }
+                };
+                if op.text == "==" {
+                    (eq, ne)
+                } else {
+                    (ne, eq)
+                }
+            }
+            _ => return Err(op.error(format!("unsupported comparison `{}`", op.text))),
+        };
+        Ok(skips)
+    }
+
+    fn statement(&mut self) -> Result<(), OctoError> {
+        let token = self.next()?;
+        match token.text {
+            ":" => {
+                let name = self.name()?;
+                if name.text != "main" {
+                    self.jump_to_main();
+                }
+                if self.labels.insert(name.text, self.here()).is_some() {
+                    return Err(name.error(format!("label `{}` defined more than once", name.text)));
+                }
+            }
+            ":alias" => {
+                let name = self.name()?;
+                let x = self.register()?;
+                self.aliases.insert(name.text, x);
+            }
+            ":const" => {
+                let name = self.name()?;
+                let value = self.value(i32::MIN, i32::MAX)?;
+                self.consts.insert(name.text, value);
+            }
+            ":byte" => {
+                let byte = self.byte()?;
+                self.push_byte(byte, token)?;
+            }
+            "clear" => {
+                self.emit(Instruction::Cls, token)?;
+            }
+            "return" | ";" => {
+                self.emit(Instruction::Ret, token)?;
+            }
+            "exit" => {
+                self.emit(Instruction::Exit, token)?;
+            }
+            "jump" => {
+                let target = self.next()?;
+                self.emit_with_address(target, token, Instruction::Jp)?;
+            }
+            "jump0" => {
+                let target = self.next()?;
+                self.emit_with_address(target, token, Instruction::JpV0)?;
+            }
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.value(0, 15)? as u8;
+                self.emit(Instruction::Drw { x, y, n }, token)?;
+            }
+            "save" | "load" | "bcd" => {
+                let x = self.register()?;
+                let instruction = match token.text {
+                    "save" => Instruction::LdIVx { x },
+                    "load" => Instruction::LdVxI { x },
+                    _ => Instruction::LdBVx { x },
+                };
+                self.emit(instruction, token)?;
+            }
+            "delay" | "buzzer" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                let instruction = if token.text == "delay" {
+                    Instruction::LdDtVx { x }
+                } else {
+                    Instruction::LdStVx { x }
+                };
+                self.emit(instruction, token)?;
+            }
+            "i" => {
+                let op = self.next()?;
+                match op.text {
+                    ":=" if self.peek() == Some("hex") => {
+                        self.pos += 1;
+                        let x = self.register()?;
+                        self.emit(Instruction::LdFVx { x }, token)?;
+                    }
+                    ":=" => {
+                        let target = self.next()?;
+                        self.emit_with_address(target, token, Instruction::LdIAddr)?;
+                    }
+                    "+=" => {
+                        let x = self.register()?;
+                        self.emit(Instruction::AddIVx { x }, token)?;
+                    }
+                    _ => return Err(op.error(format!("unsupported operator `{}`", op.text))),
+                }
+            }
+            "if" => {
+                let (skip_if_true, skip_if_false) = self.condition()?;
+                let keyword = self.next()?;
+                match keyword.text {
+                    "then" => {
+                        self.emit(skip_if_false, token)?;
+                    }
+                    "begin" => {
+                        self.emit(skip_if_true, token)?;
+                        let jump = self.emit(Instruction::Jp(0), token)?;
+                        self.blocks.push(Block::If { token, jump });
+                    }
+                    _ => {
+                        return Err(keyword.error(format!(
+                            "expected `then` or `begin`, found `{}`",
+                            keyword.text
+                        )))
+                    }
+                }
+            }
+            "else" => match self.blocks.pop() {
+                Some(Block::If { jump, .. }) => {
+                    let end_jump = self.emit(Instruction::Jp(0), token)?;
+                    self.patch(jump, self.here());
+                    self.blocks.push(Block::Else {
+                        token,
+                        jump: end_jump,
+                    });
+                }
+                _ => return Err(token.error("`else` without matching `begin`".to_string())),
+            },
+            "end" => match self.blocks.pop() {
+                Some(Block::If { jump, .. } | Block::Else { jump, .. }) => {
+                    self.patch(jump, self.here());
+                }
+                _ => return Err(token.error("`end` without matching `begin`".to_string())),
+            },
+            "loop" => {
+                self.blocks.push(Block::Loop {
+                    token,
+                    start: self.here(),
+                    breaks: Vec::new(),
+                });
+            }
+            "while" => {
+                let (skip_if_true, _) = self.condition()?;
+                self.emit(skip_if_true, token)?;
+                let jump = self.emit(Instruction::Jp(0), token)?;
+                let innermost_loop = self.blocks.iter_mut().rev().find_map(|block| match block {
+                    Block::Loop { breaks, .. } => Some(breaks),
+                    _ => None,
+                });
+                match innermost_loop {
+                    Some(breaks) => breaks.push(jump),
+                    None => return Err(token.error("`while` outside of a loop".to_string())),
+                }
+            }
+            "again" => match self.blocks.pop() {
+                Some(Block::Loop { start, breaks, .. }) => {
+                    self.emit(Instruction::Jp(start), token)?;
+                    for jump in breaks {
+                        self.patch(jump, self.here());
+                    }
+                }
+                _ => return Err(token.error("`again` without matching `loop`".to_string())),
+            },
+            text if text.starts_with(':') => {
+                return Err(token.error(format!("unsupported directive `{}`", text)));
+            }
+            text => {
+                if let Some(value) = parse_number(text) {
+                    if !(-128..=255).contains(&value) {
+                        return Err(token.error(format!("value {} out of range", value)));
+                    }
+                    self.push_byte(value as u8, token)?;
+                } else if parse_register(text).is_some() || self.aliases.contains_key(text) {
+                    self.pos -= 1;
+                    self.register_statement()?;
+                } else if is_identifier(text) {
+                    self.emit_with_address(token, token, Instruction::Call)?;
+                } else {
+                    return Err(token.error(format!("unexpected `{}`", text)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a statement assigning to a V register.
+    fn register_statement(&mut self) -> Result<(), OctoError> {
+        let token = self.tokens[self.pos];
+        let x = self.register()?;
+        let op = self.next()?;
+        let rhs_register = self
+            .peek()
+            .and_then(|text| parse_register(text).or_else(|| self.aliases.get(text).copied()));
+
+        if rhs_register.is_some() {
+            self.pos += 1;
+        }
+
+        let instruction = match (op.text, rhs_register) {
+            (":=", Some(y)) => Instruction::LdVxVy { x, y },
+            (":=", None) => match self.peek() {
+                Some("delay") => {
+                    self.pos += 1;
+                    Instruction::LdVxDt { x }
+                }
+                Some("key") => {
+                    self.pos += 1;
+                    Instruction::LdVxK { x }
+                }
+                Some("random") => {
+                    self.pos += 1;
+                    Instruction::Rnd {
+                        x,
+                        byte: self.byte()?,
+                    }
+                }
+                _ => Instruction::LdVxByte {
+                    x,
+                    byte: self.byte()?,
+                },
+            },
+            ("+=", Some(y)) => Instruction::AddVxVy { x, y },
+            ("+=", None) => Instruction::AddVxByte {
+                x,
+                byte: self.byte()?,
+            },
+            ("-=", Some(y)) => Instruction::SubVxVy { x, y },
+            ("-=", None) => Instruction::AddVxByte {
+                x,
+                byte: self.byte()?.wrapping_neg(),
+            },
+            ("=-", Some(y)) => Instruction::SubnVxVy { x, y },
+            ("|=", Some(y)) => Instruction::Or { x, y },
+            ("&=", Some(y)) => Instruction::And { x, y },
+            ("^=", Some(y)) => Instruction::Xor { x, y },
+            (">>=", Some(y)) => Instruction::Shr { x, y },
+            ("<<=", Some(y)) => Instruction::Shl { x, y },
+            (_, None) if ["=-", "|=", "&=", "^=", ">>=", "<<="].contains(&op.text) => {
+                let rhs = self.next()?;
+                return Err(rhs.error(format!("expected a register, found `{}`", rhs.text)));
+            }
+            _ => return Err(op.error(format!("unsupported operator `{}`", op.text))),
+        };
+        self.emit(instruction, token)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    #[test]
+    fn test_compile() {
+        let rom = compile(
+            "
+            :alias x v0
+            :const SPEED 2
+            : main
+                clear
+                x := 10
+                v1 := random 0xFF
+                x += SPEED
+                v1 -= 1
+                v2 <<= v2
+                i := digit
+                sprite x v1 5
+                if v1 == 3 then v2 := key
+                draw
+                loop again
+            : draw
+                i := hex v2
+                ;
+            : digit
+                0xF0 0x10 0x20 0x40 0x40
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            rom,
+            vec![
+                0x00, 0xE0, // clear
+                0x60, 0x0A, // x := 10
+                0xC1, 0xFF, // v1 := random 0xFF
+                0x70, 0x02, // x += SPEED
+                0x71, 0xFF, // v1 -= 1
+                0x82, 0x2E, // v2 <<= v2
+                0xA2, 0x1C, // i := digit
+                0xD0, 0x15, // sprite x v1 5
+                0x41, 0x03, // if v1 == 3 then
+                0xF2, 0x0A, // v2 := key
+                0x22, 0x18, // draw
+                0x12, 0x16, // loop again
+                0xF2, 0x29, // i := hex v2
+                0x00, 0xEE, // ;
+                0xF0, 0x10, 0x20, 0x40, 0x40, // digit
+            ]
+        );
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let rom = compile(
+            "
+            : count
+                v0 := 0
+                loop
+                    v0 += 1
+                    if v0 == 7 begin
+                        v1 := 1
+                    else
+                        v1 := 2
+                    end
+                    while v0 != 10
+                again
+                i := result
+                save v1
+                loop again
+            : main
+                count
+            : result
+                0 0
+            ",
+        )
+        .unwrap();
+
+        let mut emu = Emulator::new();
+        emu.load_rom(&rom).unwrap();
+        for _ in 0..200 {
+            emu.tick().unwrap();
+        }
+        let result = 0x200 + rom.len() - 2;
+        assert_eq!(emu.ram[result..result + 2], [10, 2]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |source: &str| compile(source).unwrap_err();
+
+        assert_eq!(
+            err(": main\n  jump nowhere"),
+            OctoError {
+                line: 2,
+                column: 8,
+                message: "undefined label `nowhere`".to_string()
+            }
+        );
+        assert_eq!(err(": main v0 := 256").message, "value 256 out of range");
+        assert_eq!(
+            err(": main loop v0 += 1").message,
+            "`loop` without matching `again`"
+        );
+        assert_eq!(err(": main end").message, "`end` without matching `begin`");
+        assert_eq!(
+            err(": main v0 |= 1").message,
+            "expected a register, found `1`"
+        );
+        assert_eq!(
+            err(": main :macro").message,
+            "unsupported directive `:macro`"
+        );
+        assert_eq!(err("clear").message, "undefined label `main`");
+    }
+}
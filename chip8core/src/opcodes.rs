@@ -1,108 +1,537 @@
 //! All the Chip-8 opcodes and their corresponding instruction implementations.
-use rand::random;
+use std::fmt;
 
 use super::{
-    emulator::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
-    Emulator,
+    emulator::{EmulatorState, DISPLAY_HEIGHT, DISPLAY_WIDTH, NUM_REGISTERS},
+    events::Event,
+    Chip8Error, Emulator,
 };
 
-/// Match the opcode to the corresponding instruction, then execute the instruction.
-pub fn execute_opcode(emu: &mut Emulator, op: u16) {
+/// A decoded Chip-8 instruction. Register operands are V register indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    /// 0x0000 - Nop
+    Nop,
+    /// 0x00E0 - Clear display
+    Cls,
+    /// 0x00EE - Return from subroutine
+    Ret,
+    /// 0x00FD - Exit the interpreter (SUPER-CHIP)
+    Exit,
+    /// 0x1NNN - Jump to location NNN
+    Jp(u16),
+    /// 0x2NNN - Call subroutine at location NNN
+    Call(u16),
+    /// 0x3XKK - Skip next instruction iff VX == KK.
+    SeVxByte {
+        /// Register index.
+        x: u8,
+        /// Value to compare against.
+        byte: u8,
+    },
+    /// 0x4XKK - Skip next instruction iff VX != KK.
+    SneVxByte {
+        /// Register index.
+        x: u8,
+        /// Value to compare against.
+        byte: u8,
+    },
+    /// 0x5XY0 - Skip next instrution iff VX == VY.
+    SeVxVy {
+        /// First register index.
+        x: u8,
+        /// Second register index.
+        y: u8,
+    },
+    /// 0x6XKK - Set VX = KK.
+    LdVxByte {
+        /// Register index.
+        x: u8,
+        /// Value to load.
+        byte: u8,
+    },
+    /// 0x7XKK - Set VX = VX + KK.
+    AddVxByte {
+        /// Register index.
+        x: u8,
+        /// Value to add.
+        byte: u8,
+    },
+    /// 0x8XY0 - Set VX = VY.
+    LdVxVy {
+        /// Destination register index.
+        x: u8,
+        /// Source register index.
+        y: u8,
+    },
+    /// 0x8XY1 - Set VX = OR VX, VY.
+    Or {
+        /// Destination register index.
+        x: u8,
+        /// Source register index.
+        y: u8,
+    },
+    /// 0x8XY2 - Set VX = AND VX, VY.
+    And {
+        /// Destination register index.
+        x: u8,
+        /// Source register index.
+        y: u8,
+    },
+    /// 0x8XY3 - Set VX = XOR VX, VY.
+    Xor {
+        /// Destination register index.
+        x: u8,
+        /// Source register index.
+        y: u8,
+    },
+    /// 0x8XY4 - Set VX = VX + VY.
+    AddVxVy {
+        /// Destination register index.
+        x: u8,
+        /// Source register index.
+        y: u8,
+    },
+    /// 0x8XY5 - Set VX = VX - VY.
+    SubVxVy {
+        /// Destination register index.
+        x: u8,
+        /// Source register index.
+        y: u8,
+    },
+    /// 0x8XY6 - Set VX = VX SHR 1.
+    Shr {
+        /// Destination register index.
+        x: u8,
+        /// Source register index. Ignored.
+        y: u8,
+    },
+    /// 0x8XY7 - Set VX = VY - VX.
+    SubnVxVy {
+        /// Destination register index.
+        x: u8,
+        /// Source register index.
+        y: u8,
+    },
+    /// 0x8XYE - Set VX = VX SHL 1.
+    Shl {
+        /// Destination register index.
+        x: u8,
+        /// Source register index. Ignored.
+        y: u8,
+    },
+    /// 0x9XY0 - Skip next instruction iff VX != VY.
+    SneVxVy {
+        /// First register index.
+        x: u8,
+        /// Second register index.
+        y: u8,
+    },
+    /// 0xANNN - Set I = NNN.
+    LdIAddr(u16),
+    /// 0xBNNN - Jump to location NNN + V0.
+    JpV0(u16),
+    /// 0xCXKK - Set VX = random byte AND KK.
+    Rnd {
+        /// Register index.
+        x: u8,
+        /// Mask applied to the random byte.
+        byte: u8,
+    },
+    /// 0xDXYN - Display N-byte sprite @ [VX, VY] with VF = collision.
+    Drw {
+        /// Register index holding the x-coordinate.
+        x: u8,
+        /// Register index holding the y-coordinate.
+        y: u8,
+        /// Number of sprite rows.
+        n: u8,
+    },
+    /// 0xEX9E - Skip next instruction iff key with value of VX is pressed.
+    Skp {
+        /// Register index.
+        x: u8,
+    },
+    /// 0xEXA1 - Skip next instruction iff key with value of VX is not pressed.
+    Sknp {
+        /// Register index.
+        x: u8,
+    },
+    /// 0xFX07 - Set VX = delay timer value.
+    LdVxDt {
+        /// Register index.
+        x: u8,
+    },
+    /// 0xFX0A - Wait for key press, then store pressed key value in VX.
+    LdVxK {
+        /// Register index.
+        x: u8,
+    },
+    /// 0xFX15 - Set delay timer = VX.
+    LdDtVx {
+        /// Register index.
+        x: u8,
+    },
+    /// 0xFX18 - Set sound timer = VX.
+    LdStVx {
+        /// Register index.
+        x: u8,
+    },
+    /// 0xFX1E - Set I += VX.
+    AddIVx {
+        /// Register index.
+        x: u8,
+    },
+    /// 0xFX29 - Set I = location of sprite for digit VX.
+    LdFVx {
+        /// Register index.
+        x: u8,
+    },
+    /// 0xFX33 - Store BCD representation of VX at I.
+    LdBVx {
+        /// Register index.
+        x: u8,
+    },
+    /// 0xFX55 - Store registers V0..=VX at I.
+    LdIVx {
+        /// Last register index.
+        x: u8,
+    },
+    /// 0xFX65 - Read registers V0..=VX from I.
+    LdVxI {
+        /// Last register index.
+        x: u8,
+    },
+}
+
+/// Decode an opcode into its corresponding instruction.
+///
+/// Returns `None` if the opcode does not correspond to any known instruction.
+/// 0NNN - SYS addr is purposefully unimplemented. Typically ignored by modern interpreters as it
+/// was only used on the old computers upon which Chip-8 was originally implemented.
+pub fn decode(op: u16) -> Option<Instruction> {
     let digit1 = (op & 0xF000) >> 12;
     let digit2 = (op & 0x0F00) >> 8;
     let digit3 = (op & 0x00F0) >> 4;
     let digit4 = op & 0x000F;
 
-    match (digit1, digit2, digit3, digit4) {
-        // 0x0000 - Nop
-        (0x0, 0x0, 0x0, 0x0) => nop(),
-        // 0x00E0 - Clear display
-        (0x0, 0x0, 0xE, 0x0) => cls(emu),
-        // 0x00EE - Return from subroutine
-        (0x0, 0x0, 0xE, 0xE) => ret(emu),
-        // 0x1NNN - Jump to location NNN
-        (0x1, _, _, _) => jp(emu, op & 0x0FFF),
-        // 0x2NNN - Call subroutine at location NNN
-        (0x2, _, _, _) => call(emu, op & 0x0FFF),
-        // 0x3XKK - Skip next instruction iff VX == KK.
-        (0x3, x, _, _) => se_vx_byte(emu, x, (op & 0x00FF) as u8),
-        // 0x4XKK - Skip next instruction iff VX != KK.
-        (0x4, x, _, _) => sne_vx_byte(emu, x, (op & 0x00FF) as u8),
-        // 0x5XY0 - Skip next instrution iff VX == VY.
-        (0x5, x, y, 0) => se_vx_vy(emu, x, y),
-        // 0x6XKK - Set VX = KK.
-        (0x6, x, _, _) => ld_vx_byte(emu, x, (op & 0x00FF) as u8),
-        // 0x7XKK - Set VX = VX + KK.
-        (0x7, x, _, _) => add_vx_byte(emu, x, (op & 0x00FF) as u8),
-        // 0x8XY0 - Set VX = VY.
-        (0x8, x, y, 0x0) => ld_vx_vy(emu, x, y),
-        // 0x8XY1 - Set VX = OR VX, VY.
-        (0x8, x, y, 0x1) => or(emu, x, y),
-        // 0x8XY2 - Set VX = AND VX, VY.
-        (0x8, x, y, 0x2) => and(emu, x, y),
-        // 0x8XY3 - Set VX = XOR VX, VY.
-        (0x8, x, y, 0x3) => xor(emu, x, y),
-        // 0x8XY4 - Set VX = VX + VY.
-        (0x8, x, y, 0x4) => add_vx_vy(emu, x, y),
-        // 0x8XY5 - Set VX = VX - VY.
-        (0x8, x, y, 0x5) => sub_vx_vy(emu, x, y),
-        // 0x8XY6 - Set VX = VX SHR 1.
-        (0x8, x, _, 0x6) => shr(emu, x),
-        // 0x8XY7 - Set VX = VY - VX.
-        (0x8, x, y, 0x7) => subn_vx_vy(emu, x, y),
-        // 0x8XYE - Set VX = VX SHL 1.
-        (0x8, x, _, 0xE) => shl(emu, x),
-        // 0x9XY0 - Skip next instruction iff VX != VY.
-        (0x9, x, y, 0x0) => sne_vx_vy(emu, x, y),
-        // 0xANNN - Set I = NNN.
-        (0xA, _, _, _) => ld_i_addr(emu, op & 0x0FFF),
-        // 0xBNNN - Jump to location NNN + V0.
-        (0xB, _, _, _) => jp_v0(emu, op & 0x0FFF),
-        // 0xCXKK - Set VX = random byte AND KK.
-        (0xC, x, _, _) => rnd(emu, x, (op & 0x00FF) as u8),
-        // 0xDXYN - Display N-byte sprite @ [VX, VY] with VF = collision.
-        (0xD, x, y, n) => drw(emu, x, y, n),
-        // 0xEX9E - Skip next instruction iff key with value of VX is pressed.
-        (0xE, x, 0x9, 0xE) => skp(emu, x),
-        // 0xEXA1 - Skip next instruction iff key with value of VX is not pressed.
-        (0xE, x, 0xA, 0x1) => sknp(emu, x),
-        // 0xFX07 - Set VX = delay timer value.
-        (0xF, x, 0x0, 0x7) => ld_vx_dt(emu, x),
-        // 0xFX0A - Wait for key press, then store pressed key value in VX.
-        (0xF, x, 0x0, 0xA) => ld_vx_k(emu, x),
-        // 0xFX15 - Set delay timer = VX.
-        (0xF, x, 0x1, 0x5) => ld_dt_vx(emu, x),
-        // 0xFX18 - Set sound timer = VX.
-        (0xF, x, 0x1, 0x8) => ld_st_vx(emu, x),
-        // 0xFX1E - Set I += VX.
-        (0xF, x, 0x1, 0xE) => add_i_vx(emu, x),
-        // 0xFX29 - Set I = location of sprite for digit VX.
-        (0xF, x, 0x2, 0x9) => ld_f_vx(emu, x),
-        // 0xFX33 - Store BCD representation of VX at I.
-        (0xF, x, 0x3, 0x3) => ld_b_vx(emu, x),
-        // 0xFX55 - Store registers V0..=VX at I.
-        (0xF, x, 0x5, 0x5) => ld_i_vx(emu, x),
-        // 0xFX65 - Read registers V0..=VX from I.
-        (0xF, x, 0x6, 0x5) => ld_vx_i(emu, x),
-        // Unimplemented.
-        // 0NNN - SYS addr is purposefully unimplemented. Typically ignored by modern interpreters
-        // as it was only used on the old computers upon which Chip-8 was originally implemented.
-        (_, _, _, _) => unimplemented!("Unimplemented opcode: {}", op),
+    let x = digit2 as u8;
+    let y = digit3 as u8;
+    let byte = (op & 0x00FF) as u8;
+    let addr = op & 0x0FFF;
+
+    let instruction = match (digit1, digit2, digit3, digit4) {
+        (0x0, 0x0, 0x0, 0x0) => Instruction::Nop,
+        (0x0, 0x0, 0xE, 0x0) => Instruction::Cls,
+        (0x0, 0x0, 0xE, 0xE) => Instruction::Ret,
+        (0x0, 0x0, 0xF, 0xD) => Instruction::Exit,
+        (0x1, _, _, _) => Instruction::Jp(addr),
+        (0x2, _, _, _) => Instruction::Call(addr),
+        (0x3, _, _, _) => Instruction::SeVxByte { x, byte },
+        (0x4, _, _, _) => Instruction::SneVxByte { x, byte },
+        (0x5, _, _, 0x0) => Instruction::SeVxVy { x, y },
+        (0x6, _, _, _) => Instruction::LdVxByte { x, byte },
+        (0x7, _, _, _) => Instruction::AddVxByte { x, byte },
+        (0x8, _, _, 0x0) => Instruction::LdVxVy { x, y },
+        (0x8, _, _, 0x1) => Instruction::Or { x, y },
+        (0x8, _, _, 0x2) => Instruction::And { x, y },
+        (0x8, _, _, 0x3) => Instruction::Xor { x, y },
+        (0x8, _, _, 0x4) => Instruction::AddVxVy { x, y },
+        (0x8, _, _, 0x5) => Instruction::SubVxVy { x, y },
+        (0x8, _, _, 0x6) => Instruction::Shr { x, y },
+        (0x8, _, _, 0x7) => Instruction::SubnVxVy { x, y },
+        (0x8, _, _, 0xE) => Instruction::Shl { x, y },
+        (0x9, _, _, 0x0) => Instruction::SneVxVy { x, y },
+        (0xA, _, _, _) => Instruction::LdIAddr(addr),
+        (0xB, _, _, _) => Instruction::JpV0(addr),
+        (0xC, _, _, _) => Instruction::Rnd { x, byte },
+        (0xD, _, _, n) => Instruction::Drw { x, y, n: n as u8 },
+        (0xE, _, 0x9, 0xE) => Instruction::Skp { x },
+        (0xE, _, 0xA, 0x1) => Instruction::Sknp { x },
+        (0xF, _, 0x0, 0x7) => Instruction::LdVxDt { x },
+        (0xF, _, 0x0, 0xA) => Instruction::LdVxK { x },
+        (0xF, _, 0x1, 0x5) => Instruction::LdDtVx { x },
+        (0xF, _, 0x1, 0x8) => Instruction::LdStVx { x },
+        (0xF, _, 0x1, 0xE) => Instruction::AddIVx { x },
+        (0xF, _, 0x2, 0x9) => Instruction::LdFVx { x },
+        (0xF, _, 0x3, 0x3) => Instruction::LdBVx { x },
+        (0xF, _, 0x5, 0x5) => Instruction::LdIVx { x },
+        (0xF, _, 0x6, 0x5) => Instruction::LdVxI { x },
+        (_, _, _, _) => return None,
+    };
+
+    Some(instruction)
+}
+
+impl Instruction {
+    /// Return the RAM the instruction reads or writes when I holds `i`, as
+    /// `(is_write, first address, number of bytes)`.
+    pub(crate) fn ram_access(&self, i: u16) -> Option<(bool, u16, u16)> {
+        match *self {
+            Self::Drw { n, .. } if n > 0 => Some((false, i, n as u16)),
+            Self::LdVxI { x } => Some((false, i, x as u16 + 1)),
+            Self::LdIVx { x } => Some((true, i, x as u16 + 1)),
+            Self::LdBVx { .. } => Some((true, i, 3)),
+            _ => None,
+        }
+    }
+
+    /// Return the V registers the instruction names, X then Y.
+    pub(crate) fn registers(&self) -> [Option<u8>; 2] {
+        match *self {
+            Self::SeVxVy { x, y }
+            | Self::LdVxVy { x, y }
+            | Self::Or { x, y }
+            | Self::And { x, y }
+            | Self::Xor { x, y }
+            | Self::AddVxVy { x, y }
+            | Self::SubVxVy { x, y }
+            | Self::Shr { x, y }
+            | Self::SubnVxVy { x, y }
+            | Self::Shl { x, y }
+            | Self::SneVxVy { x, y }
+            | Self::Drw { x, y, .. } => [Some(x), Some(y)],
+            Self::SeVxByte { x, .. }
+            | Self::SneVxByte { x, .. }
+            | Self::LdVxByte { x, .. }
+            | Self::AddVxByte { x, .. }
+            | Self::Rnd { x, .. }
+            | Self::Skp { x }
+            | Self::Sknp { x }
+            | Self::LdVxDt { x }
+            | Self::LdVxK { x }
+            | Self::LdDtVx { x }
+            | Self::LdStVx { x }
+            | Self::AddIVx { x }
+            | Self::LdFVx { x }
+            | Self::LdBVx { x }
+            | Self::LdIVx { x }
+            | Self::LdVxI { x } => [Some(x), None],
+            Self::Nop
+            | Self::Cls
+            | Self::Ret
+            | Self::Exit
+            | Self::Jp(_)
+            | Self::Call(_)
+            | Self::LdIAddr(_)
+            | Self::JpV0(_) => [None, None],
+        }
+    }
+
+    /// Return the opcode pattern of the instruction, e.g. `6XKK`.
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            Self::Nop => "0000",
+            Self::Cls => "00E0",
+            Self::Ret => "00EE",
+            Self::Exit => "00FD",
+            Self::Jp(_) => "1NNN",
+            Self::Call(_) => "2NNN",
+            Self::SeVxByte { .. } => "3XKK",
+            Self::SneVxByte { .. } => "4XKK",
+            Self::SeVxVy { .. } => "5XY0",
+            Self::LdVxByte { .. } => "6XKK",
+            Self::AddVxByte { .. } => "7XKK",
+            Self::LdVxVy { .. } => "8XY0",
+            Self::Or { .. } => "8XY1",
+            Self::And { .. } => "8XY2",
+            Self::Xor { .. } => "8XY3",
+            Self::AddVxVy { .. } => "8XY4",
+            Self::SubVxVy { .. } => "8XY5",
+            Self::Shr { .. } => "8XY6",
+            Self::SubnVxVy { .. } => "8XY7",
+            Self::Shl { .. } => "8XYE",
+            Self::SneVxVy { .. } => "9XY0",
+            Self::LdIAddr(_) => "ANNN",
+            Self::JpV0(_) => "BNNN",
+            Self::Rnd { .. } => "CXKK",
+            Self::Drw { .. } => "DXYN",
+            Self::Skp { .. } => "EX9E",
+            Self::Sknp { .. } => "EXA1",
+            Self::LdVxDt { .. } => "FX07",
+            Self::LdVxK { .. } => "FX0A",
+            Self::LdDtVx { .. } => "FX15",
+            Self::LdStVx { .. } => "FX18",
+            Self::AddIVx { .. } => "FX1E",
+            Self::LdFVx { .. } => "FX29",
+            Self::LdBVx { .. } => "FX33",
+            Self::LdIVx { .. } => "FX55",
+            Self::LdVxI { .. } => "FX65",
+        }
+    }
+
+    /// Encode the instruction back into its opcode.
+    pub fn opcode(&self) -> u16 {
+        let xy = |prefix: u16, x: u8, y: u8, suffix: u16| {
+            (prefix << 12) | ((x as u16 & 0xF) << 8) | ((y as u16 & 0xF) << 4) | suffix
+        };
+        let xkk =
+            |prefix: u16, x: u8, byte: u8| (prefix << 12) | ((x as u16 & 0xF) << 8) | byte as u16;
+        let fx = |x: u8, suffix: u16| 0xF000 | ((x as u16 & 0xF) << 8) | suffix;
+
+        match *self {
+            Self::Nop => 0x0000,
+            Self::Cls => 0x00E0,
+            Self::Ret => 0x00EE,
+            Self::Exit => 0x00FD,
+            Self::Jp(addr) => 0x1000 | (addr & 0x0FFF),
+            Self::Call(addr) => 0x2000 | (addr & 0x0FFF),
+            Self::SeVxByte { x, byte } => xkk(0x3, x, byte),
+            Self::SneVxByte { x, byte } => xkk(0x4, x, byte),
+            Self::SeVxVy { x, y } => xy(0x5, x, y, 0x0),
+            Self::LdVxByte { x, byte } => xkk(0x6, x, byte),
+            Self::AddVxByte { x, byte } => xkk(0x7, x, byte),
+            Self::LdVxVy { x, y } => xy(0x8, x, y, 0x0),
+            Self::Or { x, y } => xy(0x8, x, y, 0x1),
+            Self::And { x, y } => xy(0x8, x, y, 0x2),
+            Self::Xor { x, y } => xy(0x8, x, y, 0x3),
+            Self::AddVxVy { x, y } => xy(0x8, x, y, 0x4),
+            Self::SubVxVy { x, y } => xy(0x8, x, y, 0x5),
+            Self::Shr { x, y } => xy(0x8, x, y, 0x6),
+            Self::SubnVxVy { x, y } => xy(0x8, x, y, 0x7),
+            Self::Shl { x, y } => xy(0x8, x, y, 0xE),
+            Self::SneVxVy { x, y } => xy(0x9, x, y, 0x0),
+            Self::LdIAddr(addr) => 0xA000 | (addr & 0x0FFF),
+            Self::JpV0(addr) => 0xB000 | (addr & 0x0FFF),
+            Self::Rnd { x, byte } => xkk(0xC, x, byte),
+            Self::Drw { x, y, n } => xy(0xD, x, y, n as u16 & 0xF),
+            Self::Skp { x } => xkk(0xE, x, 0x9E),
+            Self::Sknp { x } => xkk(0xE, x, 0xA1),
+            Self::LdVxDt { x } => fx(x, 0x07),
+            Self::LdVxK { x } => fx(x, 0x0A),
+            Self::LdDtVx { x } => fx(x, 0x15),
+            Self::LdStVx { x } => fx(x, 0x18),
+            Self::AddIVx { x } => fx(x, 0x1E),
+            Self::LdFVx { x } => fx(x, 0x29),
+            Self::LdBVx { x } => fx(x, 0x33),
+            Self::LdIVx { x } => fx(x, 0x55),
+            Self::LdVxI { x } => fx(x, 0x65),
+        }
     }
 }
+/// Cowgod-style assembly mnemonic, e.g. `LD V1, 0x2A`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Nop => write!(f, "NOP"),
+            Self::Cls => write!(f, "CLS"),
+            Self::Ret => write!(f, "RET"),
+            Self::Exit => write!(f, "EXIT"),
+            Self::Jp(addr) => write!(f, "JP 0x{:03X}", addr),
+            Self::Call(addr) => write!(f, "CALL 0x{:03X}", addr),
+            Self::SeVxByte { x, byte } => write!(f, "SE V{:X}, 0x{:02X}", x, byte),
+            Self::SneVxByte { x, byte } => write!(f, "SNE V{:X}, 0x{:02X}", x, byte),
+            Self::SeVxVy { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            Self::LdVxByte { x, byte } => write!(f, "LD V{:X}, 0x{:02X}", x, byte),
+            Self::AddVxByte { x, byte } => write!(f, "ADD V{:X}, 0x{:02X}", x, byte),
+            Self::LdVxVy { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Self::Or { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            Self::And { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Self::Xor { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Self::AddVxVy { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Self::SubVxVy { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Self::Shr { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Self::SubnVxVy { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Self::Shl { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Self::SneVxVy { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Self::LdIAddr(addr) => write!(f, "LD I, 0x{:03X}", addr),
+            Self::JpV0(addr) => write!(f, "JP V0, 0x{:03X}", addr),
+            Self::Rnd { x, byte } => write!(f, "RND V{:X}, 0x{:02X}", x, byte),
+            Self::Drw { x, y, n } => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Self::Skp { x } => write!(f, "SKP V{:X}", x),
+            Self::Sknp { x } => write!(f, "SKNP V{:X}", x),
+            Self::LdVxDt { x } => write!(f, "LD V{:X}, DT", x),
+            Self::LdVxK { x } => write!(f, "LD V{:X}, K", x),
+            Self::LdDtVx { x } => write!(f, "LD DT, V{:X}", x),
+            Self::LdStVx { x } => write!(f, "LD ST, V{:X}", x),
+            Self::AddIVx { x } => write!(f, "ADD I, V{:X}", x),
+            Self::LdFVx { x } => write!(f, "LD F, V{:X}", x),
+            Self::LdBVx { x } => write!(f, "LD B, V{:X}", x),
+            Self::LdIVx { x } => write!(f, "LD [I], V{:X}", x),
+            Self::LdVxI { x } => write!(f, "LD V{:X}, [I]", x),
+        }
+    }
+}
+
+/// Execute a decoded instruction. Fails with [Chip8Error::InvalidRegister] if it was built by
+/// hand naming a register past VF.
+pub fn execute(emu: &mut Emulator, instruction: Instruction) -> Result<(), Chip8Error> {
+    if let Some(x) = instruction
+        .registers()
+        .into_iter()
+        .flatten()
+        .find(|x| *x as usize >= NUM_REGISTERS)
+    {
+        return Err(Chip8Error::InvalidRegister(x));
+    }
+
+    match instruction {
+        Instruction::Nop => nop(),
+        Instruction::Cls => cls(emu),
+        Instruction::Ret => ret(emu)?,
+        Instruction::Exit => exit(emu),
+        Instruction::Jp(addr) => jp(emu, addr),
+        Instruction::Call(addr) => call(emu, addr)?,
+        Instruction::SeVxByte { x, byte } => se_vx_byte(emu, x, byte),
+        Instruction::SneVxByte { x, byte } => sne_vx_byte(emu, x, byte),
+        Instruction::SeVxVy { x, y } => se_vx_vy(emu, x, y),
+        Instruction::LdVxByte { x, byte } => ld_vx_byte(emu, x, byte),
+        Instruction::AddVxByte { x, byte } => add_vx_byte(emu, x, byte),
+        Instruction::LdVxVy { x, y } => ld_vx_vy(emu, x, y),
+        Instruction::Or { x, y } => or(emu, x, y),
+        Instruction::And { x, y } => and(emu, x, y),
+        Instruction::Xor { x, y } => xor(emu, x, y),
+        Instruction::AddVxVy { x, y } => add_vx_vy(emu, x, y),
+        Instruction::SubVxVy { x, y } => sub_vx_vy(emu, x, y),
+        Instruction::Shr { x, y } => shr(emu, x, y),
+        Instruction::SubnVxVy { x, y } => subn_vx_vy(emu, x, y),
+        Instruction::Shl { x, y } => shl(emu, x, y),
+        Instruction::SneVxVy { x, y } => sne_vx_vy(emu, x, y),
+        Instruction::LdIAddr(addr) => ld_i_addr(emu, addr),
+        Instruction::JpV0(addr) => jp_v0(emu, addr),
+        Instruction::Rnd { x, byte } => rnd(emu, x, byte),
+        Instruction::Drw { x, y, n } => drw(emu, x, y, n)?,
+        Instruction::Skp { x } => skp(emu, x),
+        Instruction::Sknp { x } => sknp(emu, x),
+        Instruction::LdVxDt { x } => ld_vx_dt(emu, x),
+        Instruction::LdVxK { x } => ld_vx_k(emu, x),
+        Instruction::LdDtVx { x } => ld_dt_vx(emu, x),
+        Instruction::LdStVx { x } => ld_st_vx(emu, x),
+        Instruction::AddIVx { x } => add_i_vx(emu, x),
+        Instruction::LdFVx { x } => ld_f_vx(emu, x),
+        Instruction::LdBVx { x } => ld_b_vx(emu, x)?,
+        Instruction::LdIVx { x } => ld_i_vx(emu, x)?,
+        Instruction::LdVxI { x } => ld_vx_i(emu, x)?,
+    }
+
+    Ok(())
+}
+
+/// Match the opcode to the corresponding instruction, then execute the instruction.
+pub fn execute_opcode(emu: &mut Emulator, op: u16) -> Result<(), Chip8Error> {
+    let instruction = decode(op).ok_or(Chip8Error::InvalidOpcode(op))?;
+    execute(emu, instruction)
+}
 
 /// Do nothing.
 fn nop() {}
 
 /// Clear the display.
 fn cls(emu: &mut Emulator) {
-    emu.display = [false; DISPLAY_HEIGHT * DISPLAY_WIDTH];
+    emu.display.clear();
+    emu.push_event(Event::DisplayUpdated);
 }
 
 /// Return from a subroutine.
-fn ret(emu: &mut Emulator) {
-    let return_addr = emu.pop();
+fn ret(emu: &mut Emulator) -> Result<(), Chip8Error> {
+    let return_addr = emu.pop()?;
     emu.program_counter = return_addr;
+    Ok(())
+}
+
+/// Exit the interpreter.
+fn exit(emu: &mut Emulator) {
+    emu.set_state(EmulatorState::Exited);
 }
 
 /// Jump to location `addr`.
@@ -111,82 +540,102 @@ fn jp(emu: &mut Emulator, addr: u16) {
 }
 
 /// Call subroutine at `addr`.
-fn call(emu: &mut Emulator, addr: u16) {
-    emu.push(emu.program_counter);
+fn call(emu: &mut Emulator, addr: u16) -> Result<(), Chip8Error> {
+    emu.push(emu.program_counter)?;
     emu.program_counter = addr;
+    Ok(())
 }
 
 /// Skip next instruction iff Vx == `byte`.
-fn se_vx_byte(emu: &mut Emulator, x: u16, byte: u8) {
-    if emu.get_v(x) == byte {
-        emu.program_counter += 2;
+fn se_vx_byte(emu: &mut Emulator, x: u8, byte: u8) {
+    if emu.v(x) == byte {
+        emu.program_counter = emu.program_counter.wrapping_add(2);
     }
 }
 
 /// Skip next instruction iff Vx == `byte`.
-fn sne_vx_byte(emu: &mut Emulator, x: u16, byte: u8) {
-    if emu.get_v(x) != byte {
-        emu.program_counter += 2;
+fn sne_vx_byte(emu: &mut Emulator, x: u8, byte: u8) {
+    if emu.v(x) != byte {
+        emu.program_counter = emu.program_counter.wrapping_add(2);
     }
 }
 
 /// Skip next instruction iff Vx == Vy.
-fn se_vx_vy(emu: &mut Emulator, x: u16, y: u16) {
-    if emu.get_v(x) == emu.get_v(y) {
-        emu.program_counter += 2;
+fn se_vx_vy(emu: &mut Emulator, x: u8, y: u8) {
+    if emu.v(x) == emu.v(y) {
+        emu.program_counter = emu.program_counter.wrapping_add(2);
     }
 }
 
 /// Set Vx = `byte`.
-fn ld_vx_byte(emu: &mut Emulator, x: u16, byte: u8) {
+fn ld_vx_byte(emu: &mut Emulator, x: u8, byte: u8) {
     emu.set_v(x, byte);
 }
 
 /// Set Vx = Vx + `byte`.
-fn add_vx_byte(emu: &mut Emulator, x: u16, byte: u8) {
-    emu.set_v(x, emu.get_v(x).wrapping_add(byte));
+fn add_vx_byte(emu: &mut Emulator, x: u8, byte: u8) {
+    emu.set_v(x, emu.v(x).wrapping_add(byte));
 }
 
 /// Set Vx = Vy.
-fn ld_vx_vy(emu: &mut Emulator, x: u16, y: u16) {
-    emu.set_v(x, emu.get_v(y));
+fn ld_vx_vy(emu: &mut Emulator, x: u8, y: u8) {
+    emu.set_v(x, emu.v(y));
 }
 
 /// Set Vx = bitwise Vx OR Vy.
-fn or(emu: &mut Emulator, x: u16, y: u16) {
-    emu.set_v(x, emu.get_v(x) | emu.get_v(y));
+fn or(emu: &mut Emulator, x: u8, y: u8) {
+    emu.set_v(x, emu.v(x) | emu.v(y));
+    vf_reset(emu);
 }
 
 /// Set Vx = bitwise Vx AND Vy.
-fn and(emu: &mut Emulator, x: u16, y: u16) {
-    emu.set_v(x, emu.get_v(x) & emu.get_v(y));
+fn and(emu: &mut Emulator, x: u8, y: u8) {
+    emu.set_v(x, emu.v(x) & emu.v(y));
+    vf_reset(emu);
 }
 
 /// Set Vx = bitwise Vx XOR Vy.
-fn xor(emu: &mut Emulator, x: u16, y: u16) {
-    emu.set_v(x, emu.get_v(x) ^ emu.get_v(y));
+fn xor(emu: &mut Emulator, x: u8, y: u8) {
+    emu.set_v(x, emu.v(x) ^ emu.v(y));
+    vf_reset(emu);
+}
+
+/// Reset VF after a logical operation, if the quirk is enabled.
+fn vf_reset(emu: &mut Emulator) {
+    if emu.quirks.vf_reset {
+        emu.set_v(0xF_usize, 0);
+    }
+}
+
+/// Return the register shifted by 8XY6 and 8XYE: Vy if the quirk is enabled, else Vx.
+fn shift_source(emu: &Emulator, x: u8, y: u8) -> u8 {
+    if emu.quirks.shift_vy {
+        emu.v(y)
+    } else {
+        emu.v(x)
+    }
 }
 
 /// Set Vx = Vx + Vy; set VF = carry.
 /// (VF = 1 if result > 255; else 0)
-fn add_vx_vy(emu: &mut Emulator, x: u16, y: u16) {
-    let (new_vx, carry) = emu.get_v(x).overflowing_add(emu.get_v(y));
+fn add_vx_vy(emu: &mut Emulator, x: u8, y: u8) {
+    let (new_vx, carry) = emu.v(x).overflowing_add(emu.v(y));
     emu.set_v(x, new_vx);
     emu.set_v(0xF_usize, if carry { 1 } else { 0 });
 }
 
 /// Set Vx = Vx - Vy, set VF = NOT borrow.
 /// (VF = 1 if Vx > Vy; else 0)
-fn sub_vx_vy(emu: &mut Emulator, x: u16, y: u16) {
-    let (new_vx, borrow) = emu.get_v(x).overflowing_sub(emu.get_v(y));
+fn sub_vx_vy(emu: &mut Emulator, x: u8, y: u8) {
+    let (new_vx, borrow) = emu.v(x).overflowing_sub(emu.v(y));
     emu.set_v(x, new_vx);
     emu.set_v(0xF_usize, if borrow { 0 } else { 1 });
 }
 
 /// Set Vx = Vx SHR 1.
 /// (VF = least significant bit of Vx)
-fn shr(emu: &mut Emulator, x: u16) {
-    let vx = emu.get_v(x);
+fn shr(emu: &mut Emulator, x: u8, y: u8) {
+    let vx = shift_source(emu, x, y);
     let lsb = vx & 0x0001;
     emu.set_v(x, vx >> 1);
     emu.set_v(0xF_usize, lsb);
@@ -194,25 +643,25 @@ fn shr(emu: &mut Emulator, x: u16) {
 
 /// Set Vx = Vy - Vx, set VF = NOT borrow.
 /// (VF = 1 if Vy > Vx; else 0)
-fn subn_vx_vy(emu: &mut Emulator, x: u16, y: u16) {
-    let (new_vx, borrow) = emu.get_v(y).overflowing_sub(emu.get_v(x));
+fn subn_vx_vy(emu: &mut Emulator, x: u8, y: u8) {
+    let (new_vx, borrow) = emu.v(y).overflowing_sub(emu.v(x));
     emu.set_v(x, new_vx);
     emu.set_v(0xF_usize, if borrow { 0 } else { 1 });
 }
 
 /// Set Vx = Vx SHL 1.
 /// (VF = most significant bit of Vx)
-fn shl(emu: &mut Emulator, x: u16) {
-    let vx = emu.get_v(x);
+fn shl(emu: &mut Emulator, x: u8, y: u8) {
+    let vx = shift_source(emu, x, y);
     let msb = (vx >> 7) & 0x0001;
     emu.set_v(x, vx << 1);
     emu.set_v(0xF_usize, msb);
 }
 
 /// Skip next instruction iff Vx != Vy.
-fn sne_vx_vy(emu: &mut Emulator, x: u16, y: u16) {
-    if emu.get_v(x) != emu.get_v(y) {
-        emu.program_counter += 2;
+fn sne_vx_vy(emu: &mut Emulator, x: u8, y: u8) {
+    if emu.v(x) != emu.v(y) {
+        emu.program_counter = emu.program_counter.wrapping_add(2);
     }
 }
 
@@ -221,46 +670,57 @@ fn ld_i_addr(emu: &mut Emulator, addr: u16) {
     emu.i_register = addr;
 }
 
-/// Jump to location `addr` + V0.
+/// Jump to location `addr` + V0, or `addr` + Vx where x is the highest nibble of `addr` if the
+/// quirk is enabled.
 fn jp_v0(emu: &mut Emulator, addr: u16) {
-    emu.program_counter = addr + (emu.get_v(0_usize) as u16);
+    let x = if emu.quirks.jump_vx {
+        (addr >> 8) as u8
+    } else {
+        0
+    };
+    emu.program_counter = addr + (emu.v(x) as u16);
 }
 
 /// Set Vx = random byte AND `byte`.
-fn rnd(emu: &mut Emulator, x: u16, byte: u8) {
-    emu.set_v(x, random::<u8>() & byte);
+fn rnd(emu: &mut Emulator, x: u8, byte: u8) {
+    let random_byte = emu.random_byte();
+    emu.set_v(x, random_byte & byte);
 }
 
 /// Display `num_rows`-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.
 /// (VF = 1 if XOR rendering of sprite causes pixels to be erased; else 0)
-fn drw(emu: &mut Emulator, x: u16, y: u16, num_rows: u16) {
+fn drw(emu: &mut Emulator, x: u8, y: u8, num_rows: u8) -> Result<(), Chip8Error> {
+    emu.push_event(Event::DisplayUpdated);
     // Keep track of whether any pixels were flipped.
     let mut pixels_flipped = false;
 
     // Starting coordinates
-    let starting_col = emu.get_v(x);
-    let starting_row = emu.get_v(y);
+    let starting_col = emu.v(x);
+    let starting_row = emu.v(y);
 
     // Iterate over sprite rows
     for row_offset in 0..num_rows {
         // Get pixel data for this row
-        let row_pixels = emu.ram[(emu.i_register + row_offset) as usize];
+        let row_pixels = emu.read_byte(emu.i_register as usize + row_offset as usize)?;
 
         // Iterate over sprite cols
         for col_offset in 0..8 {
             // For each sprite pixel's location, if the pixel is already on, flip it.
             if (row_pixels & (0b1000_0000 >> col_offset)) != 0 {
-                // Wrap sprite around screen.
-                // TODO make sprite wrapping togglable.
-                let x = (starting_col + col_offset) as usize % DISPLAY_WIDTH;
-                let y = ((starting_row as u16) + row_offset) as usize % DISPLAY_HEIGHT;
+                // Wrap sprite around screen, or clip it at the edges.
+                let x = starting_col as usize % DISPLAY_WIDTH + col_offset;
+                let y = starting_row as usize % DISPLAY_HEIGHT + row_offset as usize;
+                if emu.quirks.clip_sprites && (x >= DISPLAY_WIDTH || y >= DISPLAY_HEIGHT) {
+                    continue;
+                }
+                let x = x % DISPLAY_WIDTH;
+                let y = y % DISPLAY_HEIGHT;
 
                 // Get pixel index for screen array.
                 let idx = x + (DISPLAY_WIDTH * y);
 
-                // Check if about to flip pixel, then set the pixel
-                pixels_flipped |= emu.display[idx];
-                emu.display[idx] ^= true;
+                // Flip the pixel, noting if it was on
+                pixels_flipped |= emu.display.flip(idx);
             }
         }
     }
@@ -271,100 +731,104 @@ fn drw(emu: &mut Emulator, x: u16, y: u16, num_rows: u16) {
     } else {
         emu.set_v(0xF_usize, 0);
     }
+
+    Ok(())
 }
 
 /// Skip next instruction if key with value of Vx is pressed.
-fn skp(emu: &mut Emulator, x: u16) {
-    if emu.keys[emu.get_v(x) as usize] {
-        emu.program_counter += 2;
+fn skp(emu: &mut Emulator, x: u8) {
+    // Only the lowest nibble of Vx identifies the key.
+    if emu.keys[(emu.v(x) & 0xF) as usize] {
+        emu.program_counter = emu.program_counter.wrapping_add(2);
     }
 }
 
 /// Skip next instruction if key with value of Vx is not pressed.
-fn sknp(emu: &mut Emulator, x: u16) {
-    if !emu.keys[emu.get_v(x) as usize] {
-        emu.program_counter += 2;
+fn sknp(emu: &mut Emulator, x: u8) {
+    // Only the lowest nibble of Vx identifies the key.
+    if !emu.keys[(emu.v(x) & 0xF) as usize] {
+        emu.program_counter = emu.program_counter.wrapping_add(2);
     }
 }
 
 /// Set Vx = current value of delay timer.
-fn ld_vx_dt(emu: &mut Emulator, x: u16) {
+fn ld_vx_dt(emu: &mut Emulator, x: u8) {
     emu.set_v(x, emu.delay_timer);
 }
 
 /// Wait for key press (stopping all execution), then store value of presssed key in Vx.
-fn ld_vx_k(emu: &mut Emulator, x: u16) {
-    let mut is_key_pressed = false;
-
-    for i in 0..emu.keys.len() {
-        if emu.keys[i] {
-            emu.set_v(x, i as u8);
-            is_key_pressed = true;
-            break;
-        }
-    }
-
-    if !is_key_pressed {
-        // Redo opcode
-        emu.program_counter -= 2;
+fn ld_vx_k(emu: &mut Emulator, x: u8) {
+    match emu.keys.iter().position(|pressed| *pressed) {
+        Some(key) => emu.set_v(x, key as u8),
+        None => emu.set_state(EmulatorState::WaitingForKey { x }),
     }
 }
 
 /// Set delay timer = Vx.
-fn ld_dt_vx(emu: &mut Emulator, x: u16) {
-    emu.delay_timer = emu.get_v(x);
+fn ld_dt_vx(emu: &mut Emulator, x: u8) {
+    emu.delay_timer = emu.v(x);
 }
 
 /// Set sound timer = Vx.
-fn ld_st_vx(emu: &mut Emulator, x: u16) {
-    emu.sound_timer = emu.get_v(x);
+fn ld_st_vx(emu: &mut Emulator, x: u8) {
+    emu.sound_timer = emu.v(x);
 }
 
 /// Set I register = I register + Vx.
-fn add_i_vx(emu: &mut Emulator, x: u16) {
-    emu.i_register = emu.i_register.wrapping_add(emu.get_v(x).into());
+fn add_i_vx(emu: &mut Emulator, x: u8) {
+    emu.i_register = emu.i_register.wrapping_add(emu.v(x).into());
 }
 
 /// Set I = location of sprite for digit Vx.
-fn ld_f_vx(emu: &mut Emulator, x: u16) {
+fn ld_f_vx(emu: &mut Emulator, x: u8) {
     // Font is stored at the start of memory, so no memory location offset needed.
     // All sprites are 5 bytes.
-    emu.i_register = (emu.get_v(x) as u16) * 5;
+    emu.i_register = (emu.v(x) as u16) * 5;
 }
 
 /// Store binary-coded decimal representation of Vx in memory locations I, I+1, I+2.
-fn ld_b_vx(emu: &mut Emulator, x: u16) {
+fn ld_b_vx(emu: &mut Emulator, x: u8) -> Result<(), Chip8Error> {
     // TODO use a better BCD algorithm
-    let vx = emu.get_v(x) as f32;
+    let vx = emu.v(x) as f32;
 
     let hundreds = (vx / 100.0).floor() as u8;
     let tens = ((vx / 10.0) % 10.0).floor() as u8;
     let ones = (vx % 10.0) as u8;
 
-    emu.ram[emu.i_register as usize] = hundreds;
-    emu.ram[(emu.i_register + 1) as usize] = tens;
-    emu.ram[(emu.i_register + 2) as usize] = ones;
+    let i = emu.i_register as usize;
+    emu.write_byte(i, hundreds)?;
+    emu.write_byte(i + 1, tens)?;
+    emu.write_byte(i + 2, ones)
 }
 
 /// Store registers V0-`x` in memory starting at location I.
-fn ld_i_vx(emu: &mut Emulator, x: u16) {
+fn ld_i_vx(emu: &mut Emulator, x: u8) -> Result<(), Chip8Error> {
     for i in 0..=x {
-        emu.ram[(emu.i_register + i) as usize] = emu.get_v(i);
+        emu.write_byte(emu.i_register as usize + i as usize, emu.v(i))?;
+    }
+    if emu.quirks.load_store_increment_i {
+        emu.i_register = emu.i_register.wrapping_add(x as u16 + 1);
     }
+    Ok(())
 }
 
 /// Read registers V0-`x` from memory starting at location I.
-fn ld_vx_i(emu: &mut Emulator, x: u16) {
+fn ld_vx_i(emu: &mut Emulator, x: u8) -> Result<(), Chip8Error> {
     for i in 0..=x {
-        emu.set_v(i, emu.ram[(emu.i_register + i) as usize]);
+        emu.set_v(i, emu.read_byte(emu.i_register as usize + i as usize)?);
     }
+    if emu.quirks.load_store_increment_i {
+        emu.i_register = emu.i_register.wrapping_add(x as u16 + 1);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
+    use rand::random;
 
-    use crate::emulator;
+    use crate::{builder::MAX_RAM_SIZE, emulator, EmulatorBuilder, LoadError, RunOutcome};
 
     use super::*;
 
@@ -373,19 +837,19 @@ mod tests {
         let mut emu = Emulator::default();
 
         // Set random pixel values
-        for i in 0..emu.display.len() {
-            emu.display[i] = random();
+        for pixel in emu.display.plane_mut(0).unwrap() {
+            *pixel = random();
         }
 
         // Ensure at least one pixel is on
-        emu.display[0] = true;
-        assert!(emu.display[0]);
+        emu.display.plane_mut(0).unwrap()[0] = true;
+        assert!(emu.get_display()[0]);
 
         // Clear screen
-        execute_opcode(&mut emu, 0x00E0);
+        execute_opcode(&mut emu, 0x00E0).unwrap();
 
         // Ensure all pixels are off
-        for pixel in emu.display {
+        for pixel in emu.get_display() {
             assert!(!pixel);
         }
     }
@@ -396,11 +860,11 @@ mod tests {
         assert_eq!(emu.program_counter, emulator::START_ADDRESS);
 
         // Jump to location 0x5FE
-        execute_opcode(&mut emu, 0x15FE);
+        execute_opcode(&mut emu, 0x15FE).unwrap();
         assert_eq!(emu.program_counter, 0x5FE);
 
         // Jump to location 0x89
-        execute_opcode(&mut emu, 0x1089);
+        execute_opcode(&mut emu, 0x1089).unwrap();
         assert_eq!(emu.program_counter, 0x89);
     }
 
@@ -417,12 +881,12 @@ mod tests {
         emu.write_instruction(0x0E06_usize, 0x00EE_u16);
 
         // Tick: should jump to location 0xE06
-        emu.tick();
+        emu.tick().unwrap();
         assert_eq!(emu.stack_pointer, 1);
         assert_eq!(emu.program_counter, 0xE06);
 
         // Tick: should return from subroutine
-        emu.tick();
+        emu.tick().unwrap();
         assert_eq!(emu.stack_pointer, 0);
         assert_eq!(emu.program_counter, emulator::START_ADDRESS + 0x2);
     }
@@ -481,41 +945,41 @@ mod tests {
         emu.write_instruction(emu.program_counter + 34, 0x0000_u16);
 
         // Execute and test
-        assert_eq!(emu.get_v(0xD_usize), 0);
-        assert_eq!(emu.get_v(0xE_usize), 0);
-        emu.tick();
-        emu.tick();
-        assert_eq!(emu.get_v(0xD_usize), 0);
-        assert_eq!(emu.get_v(0xE_usize), 1);
-
-        emu.tick();
-        emu.tick();
-        assert_eq!(emu.get_v(0xD_usize), 2);
-        assert_eq!(emu.get_v(0xE_usize), 1);
-
-        emu.tick();
-        emu.tick();
-        assert_eq!(emu.get_v(0xD_usize), 3);
-        assert_eq!(emu.get_v(0xE_usize), 1);
-
-        emu.tick();
-
-        emu.tick();
-
-        emu.tick();
-        emu.tick();
-        assert_eq!(emu.get_v(0xD_usize), 6);
-        assert_eq!(emu.get_v(0xE_usize), 1);
-
-        emu.tick();
-        emu.tick();
-        assert_eq!(emu.get_v(0xD_usize), 6);
-        assert_eq!(emu.get_v(0xE_usize), 7);
-
-        emu.tick();
-        emu.tick();
-        assert_eq!(emu.get_v(0xD_usize), 6);
-        assert_eq!(emu.get_v(0xE_usize), 7);
+        assert_eq!(emu.v(0xD_usize), 0);
+        assert_eq!(emu.v(0xE_usize), 0);
+        emu.tick().unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.v(0xD_usize), 0);
+        assert_eq!(emu.v(0xE_usize), 1);
+
+        emu.tick().unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.v(0xD_usize), 2);
+        assert_eq!(emu.v(0xE_usize), 1);
+
+        emu.tick().unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.v(0xD_usize), 3);
+        assert_eq!(emu.v(0xE_usize), 1);
+
+        emu.tick().unwrap();
+
+        emu.tick().unwrap();
+
+        emu.tick().unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.v(0xD_usize), 6);
+        assert_eq!(emu.v(0xE_usize), 1);
+
+        emu.tick().unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.v(0xD_usize), 6);
+        assert_eq!(emu.v(0xE_usize), 7);
+
+        emu.tick().unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.v(0xD_usize), 6);
+        assert_eq!(emu.v(0xE_usize), 7);
 
         assert_eq!(emu.program_counter, emulator::START_ADDRESS + 36);
     }
@@ -525,15 +989,15 @@ mod tests {
         let mut emu = Emulator::new();
 
         emu.set_v(0_usize, 6);
-        execute_opcode(&mut emu, 0x7001);
-        assert_eq!(emu.get_v(0_usize), 7);
-        execute_opcode(&mut emu, 0x7008);
-        assert_eq!(emu.get_v(0_usize), 0xF);
-        execute_opcode(&mut emu, 0x70F0);
-        assert_eq!(emu.get_v(0_usize), 0xFF);
+        execute_opcode(&mut emu, 0x7001).unwrap();
+        assert_eq!(emu.v(0_usize), 7);
+        execute_opcode(&mut emu, 0x7008).unwrap();
+        assert_eq!(emu.v(0_usize), 0xF);
+        execute_opcode(&mut emu, 0x70F0).unwrap();
+        assert_eq!(emu.v(0_usize), 0xFF);
         // Test wrapping
-        execute_opcode(&mut emu, 0x7005);
-        assert_eq!(emu.get_v(0_usize), 4);
+        execute_opcode(&mut emu, 0x7005).unwrap();
+        assert_eq!(emu.v(0_usize), 4);
     }
 
     #[test]
@@ -543,9 +1007,9 @@ mod tests {
         emu.set_v(0_usize, 0xF);
         emu.set_v(1_usize, 0x7E);
 
-        execute_opcode(&mut emu, 0x8010);
-        assert_eq!(emu.get_v(0_usize), 0x7E);
-        assert_eq!(emu.get_v(1_usize), 0x7E);
+        execute_opcode(&mut emu, 0x8010).unwrap();
+        assert_eq!(emu.v(0_usize), 0x7E);
+        assert_eq!(emu.v(1_usize), 0x7E);
     }
 
     #[test]
@@ -555,9 +1019,9 @@ mod tests {
         emu.set_v(0_usize, 0b_0101_1110);
         emu.set_v(1_usize, 0b_0010_0010);
 
-        execute_opcode(&mut emu, 0x8011);
-        assert_eq!(emu.get_v(0_usize), 0b_0111_1110);
-        assert_eq!(emu.get_v(1_usize), 0b_0010_0010);
+        execute_opcode(&mut emu, 0x8011).unwrap();
+        assert_eq!(emu.v(0_usize), 0b_0111_1110);
+        assert_eq!(emu.v(1_usize), 0b_0010_0010);
     }
 
     #[test]
@@ -567,9 +1031,9 @@ mod tests {
         emu.set_v(0_usize, 0b_0101_1110);
         emu.set_v(1_usize, 0b_0110_0011);
 
-        execute_opcode(&mut emu, 0x8012);
-        assert_eq!(emu.get_v(0_usize), 0b_0100_0010);
-        assert_eq!(emu.get_v(1_usize), 0b_0110_0011);
+        execute_opcode(&mut emu, 0x8012).unwrap();
+        assert_eq!(emu.v(0_usize), 0b_0100_0010);
+        assert_eq!(emu.v(1_usize), 0b_0110_0011);
     }
 
     #[test]
@@ -579,9 +1043,9 @@ mod tests {
         emu.set_v(0_usize, 0b_0101_1110);
         emu.set_v(1_usize, 0b_0110_0011);
 
-        execute_opcode(&mut emu, 0x8013);
-        assert_eq!(emu.get_v(0_usize), 0b_0011_1101);
-        assert_eq!(emu.get_v(1_usize), 0b_0110_0011);
+        execute_opcode(&mut emu, 0x8013).unwrap();
+        assert_eq!(emu.v(0_usize), 0b_0011_1101);
+        assert_eq!(emu.v(1_usize), 0b_0110_0011);
     }
 
     #[test]
@@ -591,19 +1055,19 @@ mod tests {
         emu.set_v(0_usize, 0xB7);
         emu.set_v(1_usize, 0x1F);
 
-        execute_opcode(&mut emu, 0x8104);
-        assert_eq!(emu.get_v(0_usize), 0xB7);
-        assert_eq!(emu.get_v(1_usize), 0xD6);
-        assert_eq!(emu.get_v(0xF_usize), 0x0);
+        execute_opcode(&mut emu, 0x8104).unwrap();
+        assert_eq!(emu.v(0_usize), 0xB7);
+        assert_eq!(emu.v(1_usize), 0xD6);
+        assert_eq!(emu.v(0xF_usize), 0x0);
 
         // Test overflow
         emu.set_v(2_usize, 0xF0);
         emu.set_v(3_usize, 0x9D);
 
-        execute_opcode(&mut emu, 0x8234);
-        assert_eq!(emu.get_v(2_usize), 0x8D);
-        assert_eq!(emu.get_v(3_usize), 0x9D);
-        assert_eq!(emu.get_v(0xF_usize), 0x1);
+        execute_opcode(&mut emu, 0x8234).unwrap();
+        assert_eq!(emu.v(2_usize), 0x8D);
+        assert_eq!(emu.v(3_usize), 0x9D);
+        assert_eq!(emu.v(0xF_usize), 0x1);
     }
 
     #[test]
@@ -613,19 +1077,19 @@ mod tests {
         emu.set_v(0_usize, 0xB7);
         emu.set_v(1_usize, 0x1F);
 
-        execute_opcode(&mut emu, 0x8015);
-        assert_eq!(emu.get_v(0_usize), 0x98);
-        assert_eq!(emu.get_v(1_usize), 0x1F);
-        assert_eq!(emu.get_v(0xF_usize), 0x1);
+        execute_opcode(&mut emu, 0x8015).unwrap();
+        assert_eq!(emu.v(0_usize), 0x98);
+        assert_eq!(emu.v(1_usize), 0x1F);
+        assert_eq!(emu.v(0xF_usize), 0x1);
 
         // Test borrow
         emu.set_v(2_usize, 0xA0);
         emu.set_v(3_usize, 0xB5);
 
-        execute_opcode(&mut emu, 0x8235);
-        assert_eq!(emu.get_v(2_usize), 0xEB);
-        assert_eq!(emu.get_v(3_usize), 0xB5);
-        assert_eq!(emu.get_v(0xF_usize), 0x0);
+        execute_opcode(&mut emu, 0x8235).unwrap();
+        assert_eq!(emu.v(2_usize), 0xEB);
+        assert_eq!(emu.v(3_usize), 0xB5);
+        assert_eq!(emu.v(0xF_usize), 0x0);
     }
 
     #[test]
@@ -638,24 +1102,24 @@ mod tests {
         emu.set_v(3_usize, 0b_0101_0101);
 
         // shr no remainder
-        execute_opcode(&mut emu, 0x80A6);
-        assert_eq!(emu.get_v(0x0_usize), 0b_0101_0101);
-        assert_eq!(emu.get_v(0xF_usize), 0x0);
+        execute_opcode(&mut emu, 0x80A6).unwrap();
+        assert_eq!(emu.v(0x0_usize), 0b_0101_0101);
+        assert_eq!(emu.v(0xF_usize), 0x0);
 
         // shr remainder
-        execute_opcode(&mut emu, 0x81B6);
-        assert_eq!(emu.get_v(0x1_usize), 0b_0010_1010);
-        assert_eq!(emu.get_v(0xF_usize), 0x1);
+        execute_opcode(&mut emu, 0x81B6).unwrap();
+        assert_eq!(emu.v(0x1_usize), 0b_0010_1010);
+        assert_eq!(emu.v(0xF_usize), 0x1);
 
         // shl not too big
-        execute_opcode(&mut emu, 0x830E);
-        assert_eq!(emu.get_v(0x3_usize), 0b_1010_1010);
-        assert_eq!(emu.get_v(0xF_usize), 0x0);
+        execute_opcode(&mut emu, 0x830E).unwrap();
+        assert_eq!(emu.v(0x3_usize), 0b_1010_1010);
+        assert_eq!(emu.v(0xF_usize), 0x0);
 
         // shl too big
-        execute_opcode(&mut emu, 0x82FE);
-        assert_eq!(emu.get_v(0x2_usize), 0b_0101_0100);
-        assert_eq!(emu.get_v(0xF_usize), 0x1);
+        execute_opcode(&mut emu, 0x82FE).unwrap();
+        assert_eq!(emu.v(0x2_usize), 0b_0101_0100);
+        assert_eq!(emu.v(0xF_usize), 0x1);
     }
 
     #[test]
@@ -665,28 +1129,28 @@ mod tests {
         emu.set_v(0_usize, 0xB7);
         emu.set_v(1_usize, 0x1F);
 
-        execute_opcode(&mut emu, 0x8107);
-        assert_eq!(emu.get_v(0_usize), 0xB7);
-        assert_eq!(emu.get_v(1_usize), 0x98);
-        assert_eq!(emu.get_v(0xF_usize), 0x1);
+        execute_opcode(&mut emu, 0x8107).unwrap();
+        assert_eq!(emu.v(0_usize), 0xB7);
+        assert_eq!(emu.v(1_usize), 0x98);
+        assert_eq!(emu.v(0xF_usize), 0x1);
 
         // Test borrow
         emu.set_v(2_usize, 0xA0);
         emu.set_v(3_usize, 0xB5);
 
-        execute_opcode(&mut emu, 0x8327);
-        assert_eq!(emu.get_v(2_usize), 0xA0);
-        assert_eq!(emu.get_v(3_usize), 0xEB);
-        assert_eq!(emu.get_v(0xF_usize), 0x0);
+        execute_opcode(&mut emu, 0x8327).unwrap();
+        assert_eq!(emu.v(2_usize), 0xA0);
+        assert_eq!(emu.v(3_usize), 0xEB);
+        assert_eq!(emu.v(0xF_usize), 0x0);
     }
 
     #[test]
     fn test_ld_i_addr() {
         let mut emu = Emulator::new();
         emu.i_register = 0x000;
-        execute_opcode(&mut emu, 0xA123);
+        execute_opcode(&mut emu, 0xA123).unwrap();
         assert_eq!(emu.i_register, 0x123);
-        execute_opcode(&mut emu, 0xAFFF);
+        execute_opcode(&mut emu, 0xAFFF).unwrap();
         assert_eq!(emu.i_register, 0xFFF);
     }
 
@@ -694,9 +1158,445 @@ mod tests {
     fn test_jp_v0() {
         let mut emu = Emulator::new();
         emu.set_v(0_usize, 0x12);
-        execute_opcode(&mut emu, 0xBF00);
+        execute_opcode(&mut emu, 0xBF00).unwrap();
         assert_eq!(emu.program_counter, 0xF12);
     }
 
+    #[test]
+    fn test_stack_errors() {
+        let mut emu = Emulator::new();
+
+        // Return with nothing on the stack
+        assert_eq!(
+            execute_opcode(&mut emu, 0x00EE),
+            Err(Chip8Error::StackUnderflow)
+        );
+
+        // Fill the stack, then overflow it
+        for _ in 0..emulator::STACK_SIZE {
+            execute_opcode(&mut emu, 0x2300).unwrap();
+        }
+        assert_eq!(
+            execute_opcode(&mut emu, 0x2300),
+            Err(Chip8Error::StackOverflow)
+        );
+
+        // Dropping the oldest return address keeps recursion running
+        let mut emu = Emulator::builder()
+            .stack_depth(2)
+            .stack_overflow(emulator::StackOverflowBehavior::DropOldest)
+            .build()
+            .unwrap();
+        for addr in [0x300, 0x400, 0x500] {
+            call(&mut emu, addr).unwrap();
+        }
+        assert_eq!(emu.stack(), &[0x300, 0x400]);
+
+        emu.set_stack_depth(1);
+        assert_eq!(emu.stack(), &[0x300]);
+    }
+
+    #[test]
+    fn test_invalid_opcode() {
+        let mut emu = Emulator::new();
+        assert_eq!(
+            execute_opcode(&mut emu, 0x5121),
+            Err(Chip8Error::InvalidOpcode(0x5121))
+        );
+    }
+
+    #[test]
+    fn test_load_rom() {
+        let mut emu = Emulator::new();
+        let max = emulator::RAM_SIZE - emulator::START_ADDRESS as usize;
+        assert_eq!(emu.load_rom(&[]), Err(LoadError::Empty));
+        assert_eq!(
+            emu.load_rom(&vec![0; max + 1]),
+            Err(LoadError::RomTooLarge { size: max + 1, max })
+        );
+        assert!(emu.rom_info().is_none());
+
+        let info = emu.load_rom(&[0x12, 0x34, 0x56]).unwrap();
+        assert_eq!(info.range(), 0x200..0x203);
+        assert_eq!(emu.rom_info(), Some(&info));
+        assert_eq!(&emu.ram[0x200..0x203], &[0x12, 0x34, 0x56]);
+
+        emu.load_rom(&vec![0; max]).unwrap();
+        emu.reset();
+        assert!(emu.rom_info().is_none());
+    }
+
+    #[test]
+    fn test_soft_reset() {
+        let mut emu = Emulator::new();
+        // 0x200: LD V0, 5; LD DT, V0; JP 0x204
+        let rom = [0x60, 0x05, 0xF0, 0x15, 0x12, 0x04];
+        let info = emu.load_rom(&rom).unwrap();
+        emu.run_frame(3).unwrap();
+        assert_eq!(emu.v(0_usize), 5);
+
+        // Registers and timers are cleared, but the ROM stays loaded.
+        emu.soft_reset();
+        assert_eq!(emu.program_counter, 0x200);
+        assert_eq!(emu.v(0_usize), 0);
+        assert_eq!(emu.delay_timer, 0);
+        assert_eq!(&emu.ram[0x200..0x206], &rom);
+        assert_eq!(emu.rom_info(), Some(&info));
+        emu.run_frame(3).unwrap();
+        assert_eq!(emu.v(0_usize), 5);
+    }
+
+    #[test]
+    fn test_ram_out_of_bounds() {
+        let mut emu = Emulator::new();
+        emu.i_register = 0xFFFF;
+        emu.set_v(0_usize, 123);
+
+        // Draw sprite from I
+        assert_eq!(
+            execute_opcode(&mut emu, 0xD001),
+            Err(Chip8Error::RamOutOfBounds(0xFFFF))
+        );
+        // Store BCD at I
+        assert_eq!(
+            execute_opcode(&mut emu, 0xF033),
+            Err(Chip8Error::RamOutOfBounds(0xFFFF))
+        );
+        // Store V0..=V1 at I; the write past I should also be caught
+        emu.i_register = (emulator::RAM_SIZE - 1) as u16;
+        assert_eq!(
+            execute_opcode(&mut emu, 0xF155),
+            Err(Chip8Error::RamOutOfBounds(emulator::RAM_SIZE))
+        );
+        // Read V0..=V1 from I
+        assert_eq!(
+            execute_opcode(&mut emu, 0xF165),
+            Err(Chip8Error::RamOutOfBounds(emulator::RAM_SIZE))
+        );
+
+        // Fetch past the end of RAM
+        emu.program_counter = (emulator::RAM_SIZE - 1) as u16;
+        assert_eq!(
+            emu.tick(),
+            Err(Chip8Error::RamOutOfBounds(emulator::RAM_SIZE))
+        );
+
+        // The program counter wraps around at the end of a 64K RAM
+        let mut emu = EmulatorBuilder::new()
+            .ram_size(MAX_RAM_SIZE)
+            .start_address(0xFFFC)
+            .build()
+            .unwrap();
+        // 0xFFFC: SE V0, 0x00; 0xFFFE: LD V0, 0x01
+        emu.load_rom(&[0x30, 0x00, 0x60, 0x01]).unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.pc(), 0x0000);
+        emu.set_pc(0xFFFE);
+        emu.tick().unwrap();
+        assert_eq!(emu.v(0_usize), 0x01);
+        assert_eq!(emu.pc(), 0x0000);
+    }
+
+    #[test]
+    fn test_random_rom_never_panics() {
+        for i in 0..50 {
+            // Half with the largest RAM, where the program counter can reach the end of its range
+            let ram_size = match i % 2 {
+                0 => emulator::RAM_SIZE,
+                _ => MAX_RAM_SIZE,
+            };
+            let mut emu = EmulatorBuilder::new().ram_size(ram_size).build().unwrap();
+            let rom: Vec<u8> = (0..ram_size - emulator::START_ADDRESS as usize)
+                .map(|_| random())
+                .collect();
+            emu.load_rom(&rom).unwrap();
+            for key in 0..emulator::NUM_KEYS {
+                emu.keypress(key, random()).unwrap();
+            }
+
+            // Errors are fine; panics are not.
+            for _ in 0..1000 {
+                if emu.tick().is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_budgeted() {
+        // 0x200: JP 0x200
+        let (emu, result) = Emulator::run_budgeted(&[0x12, 0x00], 100).unwrap();
+        assert_eq!(result.executed, 100);
+        assert_eq!(result.outcome, RunOutcome::BudgetExhausted);
+        assert_eq!(emu.frames(), 12);
+
+        // Waiting for a key that never comes still uses up the budget.
+        let (_, result) = Emulator::run_budgeted(&[0xF0, 0x0A], 50).unwrap();
+        assert_eq!(result.outcome, RunOutcome::BudgetExhausted);
+
+        let (emu, result) = Emulator::run_budgeted(&[0x51, 0x21], 50).unwrap();
+        assert_eq!(result.executed, 1);
+        assert_eq!(result.outcome, RunOutcome::Ended);
+        assert!(emu.state().has_ended());
+        assert_eq!(
+            Emulator::run_budgeted(&[], 50).err(),
+            Some(LoadError::Empty)
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(0x0000), Some(Instruction::Nop));
+        assert_eq!(decode(0x00E0), Some(Instruction::Cls));
+        assert_eq!(decode(0x00FD), Some(Instruction::Exit));
+        assert_eq!(decode(0x1ABC), Some(Instruction::Jp(0xABC)));
+        assert_eq!(
+            decode(0x3A42),
+            Some(Instruction::SeVxByte { x: 0xA, byte: 0x42 })
+        );
+        assert_eq!(
+            decode(0x8AB5),
+            Some(Instruction::SubVxVy { x: 0xA, y: 0xB })
+        );
+        assert_eq!(
+            decode(0xD12F),
+            Some(Instruction::Drw {
+                x: 0x1,
+                y: 0x2,
+                n: 0xF
+            })
+        );
+        assert_eq!(decode(0xF565), Some(Instruction::LdVxI { x: 0x5 }));
+
+        // Invalid opcodes
+        assert_eq!(decode(0x0123), None);
+        assert_eq!(decode(0x5121), None);
+        assert_eq!(decode(0x800F), None);
+        assert_eq!(decode(0xE000), None);
+        assert_eq!(decode(0xFFFF), None);
+    }
+
+    #[test]
+    fn test_execute() {
+        let mut emu = Emulator::new();
+        execute(&mut emu, Instruction::LdVxByte { x: 3, byte: 0x21 }).unwrap();
+        execute(&mut emu, Instruction::AddVxByte { x: 3, byte: 0x21 }).unwrap();
+        assert_eq!(emu.v(3_usize), 0x42);
+
+        // Instructions built by hand can name registers past VF
+        assert_eq!(
+            emu.execute(Instruction::LdVxVy { x: 1, y: 16 }),
+            Err(Chip8Error::InvalidRegister(16))
+        );
+        assert_eq!(
+            emu.execute(Instruction::LdVxI { x: 200 }),
+            Err(Chip8Error::InvalidRegister(200))
+        );
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&[0x63, 0x21, 0x73, 0x21, 0xFF, 0xFF]).unwrap();
+
+        let op = emu.fetch().unwrap();
+        assert_eq!(op, 0x6321);
+        assert_eq!(emu.program_counter, 0x202);
+        let instruction = emu.decode(op).unwrap();
+        emu.execute(instruction).unwrap();
+        assert_eq!(emu.v(3_usize), 0x21);
+
+        let result = emu.tick().unwrap();
+        assert_eq!(
+            result.instruction,
+            Some(Instruction::AddVxByte { x: 3, byte: 0x21 })
+        );
+        assert_eq!(emu.v(3_usize), 0x42);
+        assert_eq!(emu.tick(), Err(Chip8Error::InvalidOpcode(0xFFFF)));
+    }
+
+    #[test]
+    fn test_run_frame() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&[
+            0x60, 0x05, // LD V0, 5
+            0xF0, 0x18, // LD ST, V0
+            0xD1, 0x15, // DRW V1, V1, 5
+            0x12, 0x06, // JP 0x206
+        ])
+        .unwrap();
+
+        let output = emu.run_frame(2).unwrap();
+        assert!(!output.display_changed);
+        assert!(output.sound_active);
+        assert_eq!(emu.sound_timer, 4);
+
+        let output = emu.run_frame(2).unwrap();
+        assert!(output.display_changed);
+        assert_eq!(emu.program_counter, 0x206);
+        assert!(!emu.run_frame(2).unwrap().display_changed);
+
+        // The watchdog cuts frames short without ticking the timers.
+        emu.set_watchdog(Some(3));
+        let output = emu.run_frame(10).unwrap();
+        assert_eq!(output.executed, 3);
+        assert_eq!(output.outcome, RunOutcome::BudgetExhausted);
+        assert_eq!(emu.sound_timer, 2);
+        let result = emu.run_until(0x300, 100).unwrap();
+        assert_eq!(result.executed, 3);
+        assert_eq!(result.outcome, RunOutcome::BudgetExhausted);
+    }
+
+    #[test]
+    fn test_execution_state() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&[
+            0xF3, 0x0A, // LD V3, K
+            0x00, 0xFD, // EXIT
+        ])
+        .unwrap();
+
+        emu.tick().unwrap();
+        assert_eq!(emu.state(), EmulatorState::WaitingForKey { x: 3 });
+        assert_eq!(emu.program_counter, 0x202);
+        assert_eq!(emu.tick().unwrap().instruction, None);
+
+        emu.keypress(0xB, true).unwrap();
+        assert_eq!(emu.state(), EmulatorState::Running);
+        assert_eq!(emu.v(3_usize), 0xB);
+
+        emu.tick().unwrap();
+        assert_eq!(emu.state(), EmulatorState::Exited);
+        assert_eq!(emu.tick().unwrap().instruction, None);
+        assert_eq!(emu.program_counter, 0x204);
+
+        emu.reset();
+        emu.load_rom(&[0x00, 0xEE]).unwrap();
+        assert_eq!(emu.tick(), Err(Chip8Error::StackUnderflow));
+        assert_eq!(
+            emu.state(),
+            EmulatorState::Halted(emulator::HaltReason::Error(Chip8Error::StackUnderflow))
+        );
+    }
+
+    #[test]
+    fn test_loop_detection() {
+        let rom = [
+            0x70, 0x01, // 0x200: ADD V0, 1
+            0x30, 0x03, // 0x202: SE V0, 3
+            0x12, 0x00, // 0x204: JP 0x200
+            0x12, 0x06, // 0x206: JP 0x206
+        ];
+        let mut emu = Emulator::new();
+        emu.load_rom(&rom).unwrap();
+        emu.set_loop_detection(Some(3));
+
+        let result = emu.run_until(|_: &Emulator| false, 100).unwrap();
+        assert_eq!(result.outcome, crate::RunOutcome::Ended);
+        assert_eq!(result.executed, 11);
+        assert_eq!(
+            emu.state(),
+            EmulatorState::Halted(emulator::HaltReason::InfiniteLoop(0x206))
+        );
+
+        // Disabled by default
+        let mut emu = Emulator::new();
+        emu.load_rom(&rom).unwrap();
+        assert_eq!(
+            emu.run_until(|_: &Emulator| false, 100).unwrap().executed,
+            100
+        );
+        assert_eq!(emu.state(), EmulatorState::Running);
+    }
+
+    #[test]
+    fn test_drw_wraps_large_coordinates() {
+        let mut emu = Emulator::new();
+        emu.set_v(0_usize, 0xFF);
+        emu.set_v(1_usize, 0xFF);
+        // Font sprite for 0 has its top row filled
+        emu.i_register = 0;
+        execute_opcode(&mut emu, 0xD011).unwrap();
+        // 0xFF % 64 = 63, 0xFF % 32 = 31; the row wraps around to the left edge
+        let row = 31 * emulator::DISPLAY_WIDTH;
+        assert!(emu.get_display()[row + 63]);
+        assert!(emu.get_display()[row]);
+        assert!(emu.get_display()[row + 2]);
+        assert!(!emu.get_display()[row + 3]);
+    }
+
+    #[test]
+    fn test_rnd_deterministic() {
+        let mut emu_a = Emulator::with_seed(0xC8);
+        let mut emu_b = Emulator::with_seed(0xC8);
+
+        let mut sequence = Vec::new();
+        for _ in 0..32 {
+            execute_opcode(&mut emu_a, 0xC0FF).unwrap();
+            execute_opcode(&mut emu_b, 0xC0FF).unwrap();
+            assert_eq!(emu_a.v(0_usize), emu_b.v(0_usize));
+            sequence.push(emu_a.v(0_usize));
+        }
+
+        // Mask is applied
+        execute_opcode(&mut emu_a, 0xC10F).unwrap();
+        assert_eq!(emu_a.v(1_usize) & 0xF0, 0);
+
+        // Resetting replays the same sequence
+        emu_b.reset();
+        for expected in sequence {
+            execute_opcode(&mut emu_b, 0xC0FF).unwrap();
+            assert_eq!(emu_b.v(0_usize), expected);
+        }
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&[0x22, 0x04, 0x00, 0x00, 0xD0, 0x01]).unwrap();
+        emu.set_i(0x204);
+        emu.set_delay_timer(7);
+        emu.tick().unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.pc(), 0x206);
+        assert_eq!(emu.i(), 0x204);
+        assert_eq!(emu.stack(), &[0x202]);
+        assert_eq!(emu.delay_timer(), 7);
+        assert_eq!(emu.read_byte(0x204), Ok(0xD0));
+        assert_eq!(
+            emu.pixels().filter(|(_, _, on)| *on).collect::<Vec<_>>(),
+            vec![(0, 0, true), (1, 0, true), (3, 0, true)]
+        );
+
+        let parts = emu.raw_parts();
+        *parts.program_counter = 0x300;
+        parts.v_registers[3] = 9;
+        assert_eq!(emu.pc(), 0x300);
+        assert_eq!(emu.v_registers()[3], 9);
+    }
+
+    #[test]
+    fn test_keypad() {
+        let mut emu = Emulator::new();
+        assert_eq!(emu.press(16), Err(Chip8Error::InvalidKey(16)));
+        emu.press(0x3).unwrap();
+        emu.press(0xF).unwrap();
+        assert_eq!(emu.keys_bitmask(), 0x8008);
+        emu.release(0x3).unwrap();
+        assert_eq!(emu.keys_bitmask(), 0x8000);
+
+        // LD V2, K completes with the lowest newly-pressed key
+        emu.load_rom(&[0xF2, 0x0A]).unwrap();
+        emu.set_keys(0);
+        emu.tick().unwrap();
+        assert_eq!(emu.state(), EmulatorState::WaitingForKey { x: 2 });
+        emu.set_keys(0b0110_0000);
+        assert_eq!(emu.state(), EmulatorState::Running);
+        assert_eq!(emu.v(2_usize), 5);
+        assert!(emu.is_key_pressed(6));
+        assert!(!emu.is_key_pressed(99));
+    }
+
     // TODO more tests
 }
@@ -0,0 +1,194 @@
+//! Per-instruction and per-address execution profiling.
+use std::{collections::HashMap, fmt, time::Duration};
+
+use super::opcodes::Instruction;
+
+/// Execution count and cumulative host time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ProfileStats {
+    /// Number of executions.
+    pub count: u64,
+    /// Cumulative host time spent executing.
+    pub time: Duration,
+}
+impl ProfileStats {
+    fn add(&mut self, time: Duration) {
+        self.count += 1;
+        self.time += time;
+    }
+}
+
+/// Counts executions and host time per opcode pattern and per address. Attach to an emulator
+/// with [crate::Emulator::set_profiler].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profiler {
+    by_pattern: HashMap<&'static str, ProfileStats>,
+    by_address: HashMap<u16, ProfileStats>,
+}
+impl Profiler {
+    /// Create an empty profiler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an instruction executed at `pc` which took `time` to execute.
+    pub fn record(&mut self, pc: u16, instruction: &Instruction, time: Duration) {
+        self.by_pattern
+            .entry(instruction.pattern())
+            .or_default()
+            .add(time);
+        self.by_address.entry(pc).or_default().add(time);
+    }
+
+    /// Discard everything recorded so far.
+    pub fn clear(&mut self) {
+        self.by_pattern.clear();
+        self.by_address.clear();
+    }
+
+    /// Summarise everything recorded so far, hottest first.
+    pub fn report(&self) -> ProfileReport {
+        let mut by_pattern: Vec<_> = self
+            .by_pattern
+            .iter()
+            .map(|(pattern, stats)| (*pattern, *stats))
+            .collect();
+        by_pattern.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(b.0)));
+
+        let mut by_address: Vec<_> = self
+            .by_address
+            .iter()
+            .map(|(addr, stats)| (*addr, *stats))
+            .collect();
+        by_address.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(&b.0)));
+
+        ProfileReport {
+            total: by_pattern.iter().map(|(_, stats)| stats.count).sum(),
+            by_pattern,
+            by_address,
+        }
+    }
+}
+
+/// Snapshot of a [Profiler]'s results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileReport {
+    /// Total instructions executed.
+    pub total: u64,
+    /// Stats per opcode pattern (e.g. `DXYN`), most executed first.
+    pub by_pattern: Vec<(&'static str, ProfileStats)>,
+    /// Stats per ROM address, most executed first.
+    pub by_address: Vec<(u16, ProfileStats)>,
+}
+impl ProfileReport {
+    /// Number of addresses listed when printing the report.
+    pub const TOP_ADDRESSES: usize = 20;
+
+    /// Encode the report as a JSON object.
+    pub fn to_json(&self) -> String {
+        let stats = |key: String, stats: &ProfileStats| {
+            format!(
+                "{{{},\"count\":{},\"time_ns\":{}}}",
+                key,
+                stats.count,
+                stats.time.as_nanos()
+            )
+        };
+        let by_pattern: Vec<_> = self
+            .by_pattern
+            .iter()
+            .map(|(pattern, s)| stats(format!("\"pattern\":\"{}\"", pattern), s))
+            .collect();
+        let by_address: Vec<_> = self
+            .by_address
+            .iter()
+            .map(|(addr, s)| stats(format!("\"address\":{}", addr), s))
+            .collect();
+        format!(
+            "{{\"total\":{},\"by_pattern\":[{}],\"by_address\":[{}]}}",
+            self.total,
+            by_pattern.join(","),
+            by_address.join(",")
+        )
+    }
+
+    fn percent(&self, count: u64) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} instructions executed", self.total)?;
+        writeln!(f, "pattern       count      %    time (us)")?;
+        for (pattern, stats) in &self.by_pattern {
+            writeln!(
+                f,
+                "{:<7} {:>11} {:>6.2} {:>12}",
+                pattern,
+                stats.count,
+                self.percent(stats.count),
+                stats.time.as_micros()
+            )?;
+        }
+        writeln!(f, "address       count      %    time (us)")?;
+        for (addr, stats) in self.by_address.iter().take(Self::TOP_ADDRESSES) {
+            writeln!(
+                f,
+                "0x{:03X}   {:>11} {:>6.2} {:>12}",
+                addr,
+                stats.count,
+                self.percent(stats.count),
+                stats.time.as_micros()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        let rom = [
+            0x60, 0x00, // 0x200: LD V0, 0
+            0x70, 0x01, // 0x202: ADD V0, 1
+            0x30, 0x0A, // 0x204: SE V0, 10
+            0x12, 0x02, // 0x206: JP 0x202
+            0x12, 0x08, // 0x208: JP 0x208
+        ];
+        let mut emu = Emulator::new();
+        emu.load_rom(&rom).unwrap();
+        emu.set_profiler(Some(Profiler::new()));
+        for _ in 0..35 {
+            emu.tick().unwrap();
+        }
+
+        let report = emu.profiler().unwrap().report();
+        assert_eq!(report.total, 35);
+        let counts: Vec<_> = report
+            .by_pattern
+            .iter()
+            .map(|(pattern, stats)| (*pattern, stats.count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![("1NNN", 14), ("3XKK", 10), ("7XKK", 10), ("6XKK", 1)]
+        );
+        assert_eq!(report.by_address[0].0, 0x202);
+        assert_eq!(report.by_address[0].1.count, 10);
+
+        let json = report.to_json();
+        assert!(json.starts_with(r#"{"total":35,"by_pattern":[{"pattern":"1NNN","count":14,"#));
+        assert!(report.to_string().contains("1NNN             14  40.00"));
+    }
+}
@@ -0,0 +1,91 @@
+//! Behaviour differences between Chip-8 interpreters.
+
+/// Toggles for instructions whose behaviour differs between interpreters. The default matches
+/// modern interpreters, which most ROMs written today expect.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Quirks {
+    /// 8XY6 and 8XYE shift VY into VX instead of shifting VX in place.
+    pub shift_vy: bool,
+    /// FX55 and FX65 leave I pointing past the last register stored or loaded.
+    pub load_store_increment_i: bool,
+    /// BNNN jumps to NNN + VX, where X is the highest nibble of NNN, instead of NNN + V0.
+    pub jump_vx: bool,
+    /// 8XY1, 8XY2 and 8XY3 reset VF to 0.
+    pub vf_reset: bool,
+    /// Sprites are clipped at the screen edges instead of wrapping around.
+    pub clip_sprites: bool,
+}
+impl Quirks {
+    /// Modern interpreter behaviour. Same as [Quirks::default].
+    pub const fn modern() -> Self {
+        Self {
+            shift_vy: false,
+            load_store_increment_i: false,
+            jump_vx: false,
+            vf_reset: false,
+            clip_sprites: false,
+        }
+    }
+
+    /// Original COSMAC VIP interpreter behaviour.
+    pub const fn chip8() -> Self {
+        Self {
+            shift_vy: true,
+            load_store_increment_i: true,
+            jump_vx: false,
+            vf_reset: true,
+            clip_sprites: true,
+        }
+    }
+
+    /// SUPER-CHIP 1.1 behaviour.
+    pub const fn schip() -> Self {
+        Self {
+            shift_vy: false,
+            load_store_increment_i: false,
+            jump_vx: true,
+            vf_reset: false,
+            clip_sprites: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{emulator::DISPLAY_WIDTH, opcodes::execute_opcode, Emulator};
+
+    use super::*;
+
+    #[test]
+    fn test_quirks() {
+        let mut modern = Emulator::new();
+        let mut chip8 = Emulator::builder().quirks(Quirks::chip8()).build().unwrap();
+        for emu in [&mut modern, &mut chip8] {
+            emu.set_v(0_usize, 0b0110);
+            emu.set_v(1_usize, 0b0011);
+            emu.set_v(0xF_usize, 9);
+            execute_opcode(emu, 0x8011).unwrap();
+            execute_opcode(emu, 0x8016).unwrap();
+            emu.set_i(0x300);
+            execute_opcode(emu, 0xF155).unwrap();
+        }
+        // Modern: V0 = 0b0111 >> 1, VF = 1 from the shift. CHIP-8: V0 = V1 >> 1.
+        assert_eq!((modern.v(0_usize), modern.i()), (0b0011, 0x300));
+        assert_eq!((chip8.v(0_usize), chip8.i()), (0b0001, 0x302));
+
+        let mut schip = Emulator::builder().quirks(Quirks::schip()).build().unwrap();
+        schip.set_v(2_usize, 4);
+        execute_opcode(&mut schip, 0xB220).unwrap();
+        assert_eq!(schip.pc(), 0x224);
+
+        // Sprites drawn off the right edge are clipped rather than wrapped.
+        schip.set_v(0_usize, DISPLAY_WIDTH as u8 - 2);
+        schip.set_i(0);
+        execute_opcode(&mut schip, 0xD001).unwrap();
+        assert_eq!(schip.pixels().filter(|(_, _, on)| *on).count(), 2);
+    }
+}
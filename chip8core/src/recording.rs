@@ -0,0 +1,325 @@
+//! Input recording and deterministic replay.
+use std::{error::Error, fmt};
+
+use super::{emulator::NUM_KEYS, Chip8Error, Emulator, LoadError};
+
+/// Magic bytes at the start of every `.c8rec` file.
+pub const RECORDING_MAGIC: [u8; 4] = *b"C8RC";
+/// Version of the `.c8rec` format.
+pub const RECORDING_VERSION: u16 = 1;
+
+/// A single keypad state change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// Frame the event happened on.
+    pub frame: u64,
+    /// Chip-8 key index.
+    pub key: u8,
+    /// Whether the key was pressed or released.
+    pub pressed: bool,
+}
+
+/// Recorded inputs of a play session, replayable against the same ROM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    /// SHA-1 hash of the ROM the recording was made with.
+    pub rom_sha1: [u8; 20],
+    /// Random number generator seed.
+    pub seed: u64,
+    /// Instructions executed per frame.
+    pub ticks_per_frame: u32,
+    /// Total number of frames recorded.
+    pub frames: u64,
+    /// Key events, in order.
+    pub events: Vec<KeyEvent>,
+}
+impl Recording {
+    /// Encode the recording in the `.c8rec` format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(50 + self.events.len() * 10);
+        bytes.extend_from_slice(&RECORDING_MAGIC);
+        bytes.extend_from_slice(&RECORDING_VERSION.to_be_bytes());
+        bytes.extend_from_slice(&self.rom_sha1);
+        bytes.extend_from_slice(&self.seed.to_be_bytes());
+        bytes.extend_from_slice(&self.ticks_per_frame.to_be_bytes());
+        bytes.extend_from_slice(&self.frames.to_be_bytes());
+        bytes.extend_from_slice(&(self.events.len() as u32).to_be_bytes());
+        for event in &self.events {
+            bytes.extend_from_slice(&event.frame.to_be_bytes());
+            bytes.push(event.key);
+            bytes.push(event.pressed as u8);
+        }
+        bytes
+    }
+
+    /// Decode a recording in the `.c8rec` format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RecordingError> {
+        let mut pos = 0;
+        let mut take = |len: usize| -> Result<&[u8], RecordingError> {
+            let slice = bytes
+                .get(pos..pos + len)
+                .ok_or(RecordingError::UnexpectedEof)?;
+            pos += len;
+            Ok(slice)
+        };
+
+        if take(4)? != RECORDING_MAGIC {
+            return Err(RecordingError::BadMagic);
+        }
+        let version = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        if version > RECORDING_VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+        let rom_sha1: [u8; 20] = take(20)?.try_into().unwrap();
+        let seed = u64::from_be_bytes(take(8)?.try_into().unwrap());
+        let ticks_per_frame = u32::from_be_bytes(take(4)?.try_into().unwrap());
+        let frames = u64::from_be_bytes(take(8)?.try_into().unwrap());
+        let num_events = u32::from_be_bytes(take(4)?.try_into().unwrap());
+
+        let mut events = Vec::new();
+        for _ in 0..num_events {
+            let frame = u64::from_be_bytes(take(8)?.try_into().unwrap());
+            let key = take(1)?[0];
+            if key as usize >= NUM_KEYS {
+                return Err(RecordingError::InvalidKey(key));
+            }
+            let pressed = take(1)?[0] != 0;
+            events.push(KeyEvent {
+                frame,
+                key,
+                pressed,
+            });
+        }
+
+        Ok(Self {
+            rom_sha1,
+            seed,
+            ticks_per_frame,
+            frames,
+            events,
+        })
+    }
+}
+
+/// Records keypad input while passing it through to an emulator.
+///
+/// Start recording immediately after loading the ROM so the replay starts from the same state.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    recording: Recording,
+}
+impl Recorder {
+    /// Start recording. Re-seeds the emulator's random number generator so the replay can
+    /// reproduce it.
+    pub fn start(emu: &mut Emulator, ticks_per_frame: u32) -> Self {
+        let seed = emu.rng_seed();
+        emu.seed_rng(seed);
+        Self {
+            recording: Recording {
+                rom_sha1: emu.rom_info().map(|info| info.sha1).unwrap_or_default(),
+                seed,
+                ticks_per_frame,
+                frames: 0,
+                events: Vec::new(),
+            },
+        }
+    }
+
+    /// Register a Chip-8 key as pressed or un-pressed, recording the event.
+    pub fn keypress(
+        &mut self,
+        emu: &mut Emulator,
+        key: usize,
+        pressed: bool,
+    ) -> Result<(), Chip8Error> {
+        emu.keypress(key, pressed)?;
+        self.recording.events.push(KeyEvent {
+            frame: self.recording.frames,
+            key: key as u8,
+            pressed,
+        });
+        Ok(())
+    }
+
+    /// Mark the end of the current frame.
+    pub fn end_frame(&mut self) {
+        self.recording.frames += 1;
+    }
+
+    /// Stop recording and return the recording.
+    pub fn finish(self) -> Recording {
+        self.recording
+    }
+}
+
+/// Replays a recording against an emulator.
+#[derive(Debug, Clone)]
+pub struct Player {
+    recording: Recording,
+    frame: u64,
+    next_event: usize,
+}
+impl Player {
+    /// Prepare the emulator to replay the recording. The emulator must have the recording's ROM
+    /// freshly loaded.
+    pub fn start(emu: &mut Emulator, recording: Recording) -> Result<Self, RecordingError> {
+        let rom_sha1 = emu.rom_info().map(|info| info.sha1).unwrap_or_default();
+        if rom_sha1 != recording.rom_sha1 {
+            return Err(RecordingError::RomMismatch);
+        }
+        emu.seed_rng(recording.seed);
+        Ok(Self {
+            recording,
+            frame: 0,
+            next_event: 0,
+        })
+    }
+
+    /// Whether every recorded frame has been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames
+    }
+
+    /// Current frame number.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Replay one frame: apply this frame's key events, run the recorded number of instructions
+    /// and tick the timers. Returns `false` once the recording has finished.
+    pub fn run_frame(&mut self, emu: &mut Emulator) -> Result<bool, Chip8Error> {
+        if self.is_finished() {
+            return Ok(false);
+        }
+
+        while let Some(event) = self.recording.events.get(self.next_event) {
+            if event.frame > self.frame {
+                break;
+            }
+            emu.keypress(event.key as usize, event.pressed)?;
+            self.next_event += 1;
+        }
+        emu.run_frame(self.recording.ticks_per_frame)?;
+        self.frame += 1;
+
+        Ok(true)
+    }
+}
+
+/// Replay a recording headlessly against a ROM, returning the emulator in its final state.
+pub fn replay(rom: &[u8], recording: Recording) -> Result<Emulator, RecordingError> {
+    let mut emu = Emulator::with_seed(recording.seed);
+    emu.load_rom(rom)?;
+    let mut player = Player::start(&mut emu, recording)?;
+    while player.run_frame(&mut emu)? {}
+    Ok(emu)
+}
+
+/// Error recording or replaying input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingError {
+    /// Data ended before the recording was fully read.
+    UnexpectedEof,
+    /// Data is not a recording.
+    BadMagic,
+    /// Recording was written with a newer, unsupported format version.
+    UnsupportedVersion(u16),
+    /// Recording contains an invalid key index.
+    InvalidKey(u8),
+    /// Recording was made with a different ROM than the one loaded.
+    RomMismatch,
+    /// ROM could not be loaded.
+    Load(LoadError),
+    /// Emulation failed during the replay.
+    Emulation(Chip8Error),
+}
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "unexpected end of recording"),
+            Self::BadMagic => write!(f, "not a recording file"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported recording version: {}", v),
+            Self::InvalidKey(key) => write!(f, "invalid key in recording: {}", key),
+            Self::RomMismatch => write!(f, "recording was made with a different ROM"),
+            Self::Load(e) => write!(f, "unable to load ROM: {}", e),
+            Self::Emulation(e) => write!(f, "emulation error during replay: {}", e),
+        }
+    }
+}
+impl Error for RecordingError {}
+impl From<LoadError> for RecordingError {
+    fn from(e: LoadError) -> Self {
+        Self::Load(e)
+    }
+}
+impl From<Chip8Error> for RecordingError {
+    fn from(e: Chip8Error) -> Self {
+        Self::Emulation(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    // Wait for a key, store a random byte at 0x300 + key, repeat.
+    const ROM: [u8; 12] = [
+        0xF0, 0x0A, // LD V0, K
+        0xC1, 0xFF, // RND V1, 0xFF
+        0xA3, 0x00, // LD I, 0x300
+        0xF0, 0x1E, // ADD I, V0
+        0xF1, 0x55, // LD [I], V1 (stores V0, V1)
+        0x12, 0x00, // JP 0x200
+    ];
+
+    fn play(emu: &mut Emulator, recorder: &mut Recorder) {
+        for frame in 0..60 {
+            if frame % 10 == 0 {
+                recorder.keypress(emu, (frame / 10) as usize, true).unwrap();
+            }
+            if frame % 10 == 5 {
+                recorder
+                    .keypress(emu, (frame / 10) as usize, false)
+                    .unwrap();
+            }
+            emu.run_frame(8).unwrap();
+            recorder.end_frame();
+        }
+    }
+
+    #[test]
+    fn test_record_replay() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&ROM).unwrap();
+        let mut recorder = Recorder::start(&mut emu, 8);
+        play(&mut emu, &mut recorder);
+        let recording = recorder.finish();
+        assert_eq!(recording.frames, 60);
+        assert_eq!(recording.events.len(), 12);
+
+        let bytes = recording.to_bytes();
+        let decoded = Recording::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, recording);
+
+        let replayed = replay(&ROM, decoded).unwrap();
+        assert_eq!(replayed.save_state(), emu.save_state());
+    }
+
+    #[test]
+    fn test_replay_wrong_rom() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&ROM).unwrap();
+        let recording = Recorder::start(&mut emu, 8).finish();
+
+        assert_eq!(
+            replay(&[0x12, 0x00], recording).err(),
+            Some(RecordingError::RomMismatch)
+        );
+        assert_eq!(
+            Recording::from_bytes(b"C8RC").err(),
+            Some(RecordingError::UnexpectedEof)
+        );
+    }
+}
@@ -0,0 +1,585 @@
+//! Framebuffer rendering for frontends.
+use std::collections::VecDeque;
+
+use super::{
+    display::{Display, NUM_COLORS},
+    emulator::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+};
+
+/// Length in bytes of an RGBA framebuffer holding the whole display.
+pub const RGBA_BUFFER_SIZE: usize = DISPLAY_WIDTH * DISPLAY_HEIGHT * 4;
+/// Length in bytes of a packed 1-bit-per-pixel framebuffer holding the whole display.
+pub const PACKED_BUFFER_SIZE: usize = DISPLAY_WIDTH * DISPLAY_HEIGHT / 8;
+/// Names of the palettes [PlanePalette::preset] knows, in the order frontends cycle through them.
+pub const PALETTE_PRESETS: [&str; 7] = [
+    "green",
+    "amber",
+    "paper",
+    "gameboy",
+    "octo",
+    "contrast",
+    "contrast-light",
+];
+/// Fraction of the display that must change brightness between frames to count as a flash.
+pub const FLASH_AREA: f32 = 0.25;
+/// Flashes allowed per second before [FlashLimiter] slows them down, the limit commonly given
+/// as safe for people with photosensitive epilepsy.
+pub const MAX_FLASHES_PER_SEC: usize = 3;
+/// Characters used by [render_text] for each colour index, from unlit to both planes lit.
+pub const TEXT_CHARS: [char; NUM_COLORS] = [' ', '█', '▒', '▓'];
+
+/// RGBA colours used to render the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Palette {
+    /// Colour of unset pixels.
+    pub background: [u8; 4],
+    /// Colour of set pixels.
+    pub foreground: [u8; 4],
+}
+impl Palette {
+    /// Create a palette from background and foreground colours.
+    pub fn new(background: [u8; 4], foreground: [u8; 4]) -> Self {
+        Self {
+            background,
+            foreground,
+        }
+    }
+}
+impl Default for Palette {
+    /// White on black.
+    fn default() -> Self {
+        Self::new([0x00, 0x00, 0x00, 0xFF], [0xFF, 0xFF, 0xFF, 0xFF])
+    }
+}
+
+/// RGBA colours used to render every combination of display planes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlanePalette {
+    /// Colour per colour index. Index 0 is the background, 1 is plane 0 only, 2 is plane 1 only
+    /// and 3 is both planes.
+    pub colors: [[u8; 4]; NUM_COLORS],
+}
+impl PlanePalette {
+    /// Create a palette from a colour per colour index.
+    pub fn new(colors: [[u8; 4]; NUM_COLORS]) -> Self {
+        Self { colors }
+    }
+
+    /// Return the palette named in [PALETTE_PRESETS]: green or amber phosphor, ink on paper, the
+    /// original Game Boy's greens, Octo's defaults, or high contrast on black or white.
+    pub fn preset(name: &str) -> Option<Self> {
+        let colors = match name {
+            "green" => [
+                [0x00, 0x00, 0x00, 0xFF],
+                [0x00, 0xFF, 0x00, 0xFF],
+                [0x00, 0x88, 0x00, 0xFF],
+                [0x88, 0xFF, 0x88, 0xFF],
+            ],
+            "amber" => [
+                [0x00, 0x00, 0x00, 0xFF],
+                [0xFF, 0xB0, 0x00, 0xFF],
+                [0x99, 0x6A, 0x00, 0xFF],
+                [0xFF, 0xD8, 0x70, 0xFF],
+            ],
+            "paper" => [
+                [0xF5, 0xF2, 0xE8, 0xFF],
+                [0x20, 0x20, 0x20, 0xFF],
+                [0x80, 0x80, 0x80, 0xFF],
+                [0x50, 0x50, 0x50, 0xFF],
+            ],
+            "gameboy" => [
+                [0x9B, 0xBC, 0x0F, 0xFF],
+                [0x0F, 0x38, 0x0F, 0xFF],
+                [0x8B, 0xAC, 0x0F, 0xFF],
+                [0x30, 0x62, 0x30, 0xFF],
+            ],
+            "octo" => return Some(Self::default()),
+            "contrast" => [
+                [0x00, 0x00, 0x00, 0xFF],
+                [0xFF, 0xFF, 0xFF, 0xFF],
+                [0xFF, 0xFF, 0x00, 0xFF],
+                [0x00, 0xFF, 0xFF, 0xFF],
+            ],
+            "contrast-light" => [
+                [0xFF, 0xFF, 0xFF, 0xFF],
+                [0x00, 0x00, 0x00, 0xFF],
+                [0x00, 0x00, 0xC0, 0xFF],
+                [0xA0, 0x00, 0x00, 0xFF],
+            ],
+            _ => return None,
+        };
+        Some(Self::new(colors))
+    }
+}
+impl Default for PlanePalette {
+    /// Octo's default colours.
+    fn default() -> Self {
+        Self::new([
+            [0x99, 0x66, 0x00, 0xFF],
+            [0xFF, 0xCC, 0x00, 0xFF],
+            [0xFF, 0x66, 0x00, 0xFF],
+            [0x66, 0x22, 0x00, 0xFF],
+        ])
+    }
+}
+impl From<Palette> for PlanePalette {
+    /// Use the foreground colour wherever any plane is set.
+    fn from(palette: Palette) -> Self {
+        let mut colors = [palette.foreground; NUM_COLORS];
+        colors[0] = palette.background;
+        Self::new(colors)
+    }
+}
+
+/// Per-pixel brightness which fades out over frames instead of switching off instantly, hiding
+/// the flicker caused by XOR drawing. Attach to an emulator with [crate::Emulator::set_phosphor].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phosphor {
+    intensity: Vec<f32>,
+    decay: f32,
+}
+impl Phosphor {
+    /// Create a layer keeping `decay` of each unlit pixel's brightness every frame, from 0 (no
+    /// fade) to 1 (never fades).
+    pub fn new(decay: f32) -> Self {
+        Self {
+            intensity: Vec::new(),
+            decay: decay.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Return the fraction of brightness kept each frame.
+    pub fn decay(&self) -> f32 {
+        self.decay
+    }
+
+    /// Set the fraction of brightness kept each frame.
+    pub fn set_decay(&mut self, decay: f32) {
+        self.decay = decay.clamp(0.0, 1.0);
+    }
+
+    /// Return the brightness of each pixel, from 0 to 1.
+    pub fn intensity(&self) -> &[f32] {
+        &self.intensity
+    }
+
+    /// Advance one frame: lit pixels become fully bright and unlit pixels fade.
+    pub fn update(&mut self, display: &[bool]) {
+        self.intensity.resize(display.len(), 0.0);
+        for (intensity, pixel) in self.intensity.iter_mut().zip(display) {
+            *intensity = if *pixel { 1.0 } else { *intensity * self.decay };
+        }
+    }
+
+    /// Switch every pixel off immediately.
+    pub fn clear(&mut self) {
+        self.intensity.fill(0.0);
+    }
+}
+impl Default for Phosphor {
+    fn default() -> Self {
+        Self::new(0.6)
+    }
+}
+
+/// Guard against strobing: once whole-display brightness changes come faster than
+/// [MAX_FLASHES_PER_SEC], colours fade towards each new frame like a slow [Phosphor] instead of
+/// switching, until the display settles for a second.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlashLimiter {
+    /// Last RGBA frame given.
+    previous: Vec<u8>,
+    /// Channels of the RGBA frame last shown.
+    shown: Vec<f32>,
+    /// Frames on which recent flashes started, oldest first.
+    flashes: VecDeque<u64>,
+    frame: u64,
+    frame_rate: u32,
+}
+impl FlashLimiter {
+    /// Create a limiter for frames shown `frame_rate` times per second.
+    pub fn new(frame_rate: u32) -> Self {
+        Self {
+            previous: Vec::new(),
+            shown: Vec::new(),
+            flashes: VecDeque::new(),
+            frame: 0,
+            frame_rate: frame_rate.max(1),
+        }
+    }
+
+    /// Whether brightness changes are being slowed down.
+    pub fn is_limiting(&self) -> bool {
+        self.flashes.len() > MAX_FLASHES_PER_SEC
+    }
+
+    /// Replace an RGBA frame in `rgba` with the one to show instead. Call once per frame.
+    pub fn apply(&mut self, rgba: &mut [u8]) {
+        if self.previous.len() != rgba.len() {
+            self.previous = rgba.to_vec();
+            self.shown = rgba.iter().map(|&channel| channel as f32).collect();
+            self.frame += 1;
+            return;
+        }
+
+        let changed = rgba
+            .chunks_exact(4)
+            .zip(self.previous.chunks_exact(4))
+            .filter(|(new, old)| luma(new).abs_diff(luma(old)) > 127)
+            .count();
+        self.previous.copy_from_slice(rgba);
+        if changed as f32 > FLASH_AREA * (rgba.len() / 4) as f32 {
+            self.flashes.push_back(self.frame);
+        }
+        while self
+            .flashes
+            .front()
+            .is_some_and(|&start| start + self.frame_rate as u64 <= self.frame)
+        {
+            self.flashes.pop_front();
+        }
+
+        // A full swing takes half of a flash's share of a second each way
+        let max_step = if self.is_limiting() {
+            255.0 * 2.0 * MAX_FLASHES_PER_SEC as f32 / self.frame_rate as f32
+        } else {
+            255.0
+        };
+        for (shown, channel) in self.shown.iter_mut().zip(rgba.iter_mut()) {
+            *shown += (*channel as f32 - *shown).clamp(-max_step, max_step);
+            *channel = shown.round() as u8;
+        }
+        self.frame += 1;
+    }
+}
+impl Default for FlashLimiter {
+    /// Limiter for 60 frames per second.
+    fn default() -> Self {
+        Self::new(60)
+    }
+}
+
+/// Perceived brightness of an RGBA colour, from 0 to 255.
+fn luma(rgba: &[u8]) -> u8 {
+    ((299 * rgba[0] as u32 + 587 * rgba[1] as u32 + 114 * rgba[2] as u32) / 1000) as u8
+}
+
+/// Clockwise quarter turns the display can be drawn rotated by, for games designed for a screen
+/// on its side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rotation {
+    /// Upright.
+    #[default]
+    None,
+    /// A quarter turn clockwise.
+    Cw90,
+    /// Upside down.
+    Half,
+    /// A quarter turn anticlockwise.
+    Cw270,
+}
+impl Rotation {
+    /// Return the rotation by `degrees` clockwise, if a multiple of 90.
+    pub fn from_degrees(degrees: u32) -> Option<Self> {
+        match degrees % 360 {
+            0 => Some(Self::None),
+            90 => Some(Self::Cw90),
+            180 => Some(Self::Half),
+            270 => Some(Self::Cw270),
+            _ => None,
+        }
+    }
+
+    /// Degrees turned clockwise.
+    pub fn degrees(self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Cw90 => 90,
+            Self::Half => 180,
+            Self::Cw270 => 270,
+        }
+    }
+
+    /// Width and height of a `width` by `height` image once rotated.
+    pub fn size(self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Self::None | Self::Half => (width, height),
+            Self::Cw90 | Self::Cw270 => (height, width),
+        }
+    }
+
+    /// Return the column and row in a `width` by `height` image that ends up at `x` and `y` once
+    /// rotated, e.g. to find the display pixel under the mouse.
+    pub fn unrotate(self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Self::None => (x, y),
+            Self::Cw90 => (y, height - 1 - x),
+            Self::Half => (width - 1 - x, height - 1 - y),
+            Self::Cw270 => (width - 1 - y, x),
+        }
+    }
+}
+
+/// Copy a `width` pixel wide RGBA image from `rgba` into `out`, rotated by `rotation`.
+///
+/// # Panics
+///
+/// Panics if `out` is shorter than `rgba`.
+pub fn rotate_rgba(rgba: &[u8], width: usize, out: &mut [u8], rotation: Rotation) {
+    assert!(
+        out.len() >= rgba.len(),
+        "RGBA buffer too small: {} < {}",
+        out.len(),
+        rgba.len()
+    );
+    let height = rgba.len() / 4 / width.max(1);
+    let (rotated_width, _) = rotation.size(width, height);
+    for (index, pixel) in out[..rgba.len()].chunks_exact_mut(4).enumerate() {
+        let (x, y) = (index % rotated_width, index / rotated_width);
+        let (x, y) = rotation.unrotate(x, y, width, height);
+        let source = (y * width + x) * 4;
+        pixel.copy_from_slice(&rgba[source..source + 4]);
+    }
+}
+
+/// Render display pixels into `out` as row-major RGBA bytes, 4 per pixel.
+///
+/// # Panics
+///
+/// Panics if `out` is shorter than 4 bytes per pixel.
+pub fn render_rgba(display: &[bool], out: &mut [u8], palette: &Palette) {
+    assert!(
+        out.len() >= display.len() * 4,
+        "RGBA buffer too small: {} < {}",
+        out.len(),
+        display.len() * 4
+    );
+    for (pixel, rgba) in display.iter().zip(out.chunks_exact_mut(4)) {
+        rgba.copy_from_slice(if *pixel {
+            &palette.foreground
+        } else {
+            &palette.background
+        });
+    }
+}
+
+/// Render every display plane merged into `out` as row-major RGBA bytes, colouring each pixel by
+/// its combination of planes.
+///
+/// # Panics
+///
+/// Panics if `out` is shorter than [RGBA_BUFFER_SIZE].
+pub fn render_rgba_planes(display: &Display, out: &mut [u8], palette: &PlanePalette) {
+    assert!(
+        out.len() >= RGBA_BUFFER_SIZE,
+        "RGBA buffer too small: {} < {}",
+        out.len(),
+        RGBA_BUFFER_SIZE
+    );
+    for (color, rgba) in display.colors().zip(out.chunks_exact_mut(4)) {
+        rgba.copy_from_slice(&palette.colors[color as usize]);
+    }
+}
+
+/// Render pixel brightnesses from 0 to 1 into `out` as row-major RGBA bytes, blending between
+/// the palette colours.
+///
+/// # Panics
+///
+/// Panics if `out` is shorter than 4 bytes per pixel.
+pub fn render_rgba_faded(intensity: &[f32], out: &mut [u8], palette: &Palette) {
+    assert!(
+        out.len() >= intensity.len() * 4,
+        "RGBA buffer too small: {} < {}",
+        out.len(),
+        intensity.len() * 4
+    );
+    for (intensity, rgba) in intensity.iter().zip(out.chunks_exact_mut(4)) {
+        for ((channel, bg), fg) in rgba
+            .iter_mut()
+            .zip(palette.background)
+            .zip(palette.foreground)
+        {
+            *channel = (bg as f32 + (fg as f32 - bg as f32) * intensity).round() as u8;
+        }
+    }
+}
+
+/// Render display pixels into `out` packed 1 bit per pixel, row-major with the most significant
+/// bit leftmost.
+///
+/// # Panics
+///
+/// Panics if `out` is shorter than 1 bit per pixel.
+pub fn render_packed(display: &[bool], out: &mut [u8]) {
+    let len = display.len().div_ceil(8);
+    assert!(
+        out.len() >= len,
+        "packed buffer too small: {} < {}",
+        out.len(),
+        len
+    );
+    for (pixels, byte) in display.chunks(8).zip(out.iter_mut()) {
+        *byte = pixels
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (bit, pixel)| acc | ((*pixel as u8) << (7 - bit)));
+    }
+}
+
+/// Render the display as text, one line per row, with a character from [TEXT_CHARS] for each
+/// pixel.
+pub fn render_text(display: &Display) -> String {
+    let mut text = String::with_capacity((DISPLAY_WIDTH + 1) * DISPLAY_HEIGHT);
+    for (idx, color) in display.colors().enumerate() {
+        text.push(TEXT_CHARS[color as usize]);
+        if idx % DISPLAY_WIDTH == DISPLAY_WIDTH - 1 {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Hash the colour of every pixel with 64-bit FNV-1a. Unlike [std::hash::Hash], the result is
+/// stable across platforms and Rust versions, so it can be stored as a golden value.
+pub fn display_hash(display: &Display) -> u64 {
+    display.colors().fold(0xCBF2_9CE4_8422_2325, |hash, color| {
+        (hash ^ color as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut emu = Emulator::new();
+        emu.display.plane_mut(0).unwrap()[0] = true;
+        emu.display.plane_mut(0).unwrap()[2] = true;
+        emu.display.plane_mut(0).unwrap()[DISPLAY_WIDTH + 7] = true;
+
+        let palette = Palette::new([1, 2, 3, 4], [5, 6, 7, 8]);
+        let mut rgba = vec![0; RGBA_BUFFER_SIZE];
+        emu.render_rgba(&mut rgba, &palette);
+        assert_eq!(&rgba[..12], &[5, 6, 7, 8, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(&rgba[DISPLAY_WIDTH * 4..][..4], &[1, 2, 3, 4]);
+        assert_eq!(&rgba[(DISPLAY_WIDTH + 7) * 4..][..4], &[5, 6, 7, 8]);
+
+        let mut packed = [0xFF; PACKED_BUFFER_SIZE];
+        emu.render_packed(&mut packed);
+        assert_eq!(packed[0], 0b1010_0000);
+        assert_eq!(packed[DISPLAY_WIDTH / 8], 0b0000_0001);
+        assert_eq!(packed.iter().map(|b| b.count_ones()).sum::<u32>(), 3);
+
+        let text = emu.display_to_string();
+        assert_eq!(text.lines().count(), DISPLAY_HEIGHT);
+        assert_eq!(
+            text.lines()
+                .next()
+                .unwrap()
+                .chars()
+                .take(4)
+                .collect::<String>(),
+            "█ █ "
+        );
+        assert_eq!(text.chars().filter(|c| *c == '█').count(), 3);
+        let hash = emu.display_hash();
+        assert_ne!(hash, Emulator::new().display_hash());
+        emu.display.plane_mut(1).unwrap()[0] = true;
+        assert_ne!(emu.display_hash(), hash);
+        assert!(emu.display_to_string().starts_with("▓ █"));
+    }
+
+    #[test]
+    fn test_palette_presets() {
+        for name in PALETTE_PRESETS {
+            let palette = PlanePalette::preset(name).unwrap();
+            assert_ne!(palette.colors[0], palette.colors[1], "{}", name);
+        }
+        assert_eq!(PlanePalette::preset("octo"), Some(PlanePalette::default()));
+        assert_eq!(PlanePalette::preset("purple"), None);
+    }
+
+    #[test]
+    fn test_rotate_rgba() {
+        // 3x2 image with one byte per pixel repeated four times: 0 1 2 / 3 4 5
+        let rgba: Vec<u8> = (0..6).flat_map(|pixel| [pixel; 4]).collect();
+        let rotated = |rotation| {
+            let mut out = vec![0; rgba.len()];
+            rotate_rgba(&rgba, 3, &mut out, rotation);
+            out.chunks_exact(4)
+                .map(|pixel| pixel[0])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rotated(Rotation::None), [0, 1, 2, 3, 4, 5]);
+        assert_eq!(rotated(Rotation::Cw90), [3, 0, 4, 1, 5, 2]);
+        assert_eq!(rotated(Rotation::Half), [5, 4, 3, 2, 1, 0]);
+        assert_eq!(rotated(Rotation::Cw270), [2, 5, 1, 4, 0, 3]);
+        assert_eq!(Rotation::Cw90.size(3, 2), (2, 3));
+        assert_eq!(Rotation::from_degrees(450), Some(Rotation::Cw90));
+        assert_eq!(Rotation::from_degrees(45), None);
+    }
+
+    #[test]
+    fn test_flash_limiter() {
+        let black = [0, 0, 0, 255].repeat(4);
+        let white = [255; 16];
+        let mut limiter = FlashLimiter::new(60);
+        // A few flashes pass untouched.
+        for frame in 0..=MAX_FLASHES_PER_SEC {
+            let mut rgba = if frame % 2 == 0 {
+                black.clone()
+            } else {
+                white.to_vec()
+            };
+            let expected = rgba.clone();
+            limiter.apply(&mut rgba);
+            assert_eq!(rgba, expected);
+        }
+        assert!(!limiter.is_limiting());
+
+        // Strobing beyond that fades instead.
+        let mut rgba = black.clone();
+        limiter.apply(&mut rgba);
+        assert!(limiter.is_limiting());
+        assert_eq!(&rgba[..4], &[230, 230, 230, 255]);
+
+        // Once the display settles, frames pass untouched again.
+        for _ in 0..60 {
+            rgba = black.clone();
+            limiter.apply(&mut rgba);
+        }
+        assert!(!limiter.is_limiting());
+        let mut rgba = white.to_vec();
+        limiter.apply(&mut rgba);
+        assert_eq!(rgba, white);
+    }
+
+    #[test]
+    fn test_phosphor() {
+        let palette = Palette::new([0, 0, 0, 255], [200, 100, 0, 255]);
+        let mut emu = Emulator::new();
+        emu.set_phosphor(Some(Phosphor::new(0.5)));
+        emu.display.plane_mut(0).unwrap()[0] = true;
+        emu.tick_timers();
+        emu.display.plane_mut(0).unwrap()[0] = false;
+        emu.tick_timers();
+
+        let mut rgba = vec![0; RGBA_BUFFER_SIZE];
+        emu.render_rgba(&mut rgba, &palette);
+        assert_eq!(&rgba[..8], &[100, 50, 0, 255, 0, 0, 0, 255]);
+
+        emu.tick_timers();
+        assert_eq!(emu.phosphor().unwrap().intensity()[0], 0.25);
+
+        emu.set_phosphor(None);
+        emu.render_rgba(&mut rgba, &palette);
+        assert_eq!(&rgba[..4], &[0, 0, 0, 255]);
+    }
+}
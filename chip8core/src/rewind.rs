@@ -0,0 +1,126 @@
+//! Rewinding via a bounded ring buffer of save states.
+use std::collections::VecDeque;
+
+use super::{state::SaveState, Emulator};
+
+/// Ring buffer of periodic emulator snapshots which can be stepped backwards through.
+#[derive(Debug, Clone)]
+pub struct RewindBuffer {
+    states: VecDeque<SaveState>,
+    capacity: usize,
+    interval: u32,
+    frames_since_snapshot: u32,
+}
+impl RewindBuffer {
+    /// Create a rewind buffer holding up to `capacity` snapshots, taken every `interval` frames.
+    pub fn new(capacity: usize, interval: u32) -> Self {
+        Self {
+            states: VecDeque::with_capacity(capacity),
+            capacity,
+            interval: interval.max(1),
+            frames_since_snapshot: 0,
+        }
+    }
+
+    /// Record a frame. Call once per frame; a snapshot is taken every `interval` frames, dropping
+    /// the oldest snapshot when the buffer is full.
+    pub fn record(&mut self, emu: &Emulator) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.frames_since_snapshot += 1;
+        if self.frames_since_snapshot < self.interval {
+            return;
+        }
+        self.frames_since_snapshot = 0;
+
+        if self.states.len() == self.capacity {
+            self.states.pop_front();
+        }
+        self.states.push_back(emu.save_state());
+    }
+
+    /// Restore the most recent snapshot and remove it from the buffer. Returns `false` if there
+    /// was nothing left to rewind to.
+    pub fn step_back(&mut self, emu: &mut Emulator) -> bool {
+        match self.states.pop_back() {
+            Some(state) => {
+                emu.restore_state(&state);
+                self.frames_since_snapshot = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of snapshots currently held.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Whether there are no snapshots to rewind to.
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Discard all snapshots.
+    pub fn clear(&mut self) {
+        self.states.clear();
+        self.frames_since_snapshot = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_rewind() {
+        let mut emu = Emulator::new();
+        // V0 += 1; jump back
+        emu.load_rom(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        let mut rewind = RewindBuffer::new(3, 2);
+
+        // One instruction pair per "frame"
+        for _ in 0..10 {
+            emu.tick().unwrap();
+            emu.tick().unwrap();
+            rewind.record(&emu);
+        }
+        assert_eq!(emu.v(0_usize), 10);
+        // Snapshots at frames 6, 8, 10; older ones dropped
+        assert_eq!(rewind.len(), 3);
+
+        assert!(rewind.step_back(&mut emu));
+        assert_eq!(emu.v(0_usize), 10);
+        assert!(rewind.step_back(&mut emu));
+        assert_eq!(emu.v(0_usize), 8);
+        assert!(rewind.step_back(&mut emu));
+        assert_eq!(emu.v(0_usize), 6);
+        assert!(!rewind.step_back(&mut emu));
+        assert_eq!(emu.v(0_usize), 6);
+        assert!(rewind.is_empty());
+
+        // Replaying after a rewind draws the same random numbers again
+        let mut emu = Emulator::new();
+        // V0 = random; jump back
+        emu.load_rom(&[0xC0, 0xFF, 0x12, 0x00]).unwrap();
+        let mut rewind = RewindBuffer::new(1, 1);
+        rewind.record(&emu);
+        let draw = |emu: &mut Emulator| -> Vec<u8> {
+            (0..8)
+                .map(|_| {
+                    emu.tick().unwrap();
+                    emu.tick().unwrap();
+                    emu.v(0_usize)
+                })
+                .collect()
+        };
+        let drawn = draw(&mut emu);
+        assert!(rewind.step_back(&mut emu));
+        assert_eq!(draw(&mut emu), drawn);
+    }
+}
@@ -0,0 +1,192 @@
+//! Random number sources for CXKK.
+
+/// Source of the random bytes used by CXKK. Attach to an emulator with
+/// [crate::Emulator::set_random_source].
+pub trait RandomSource {
+    /// Return the next random byte.
+    fn next_byte(&mut self) -> u8;
+
+    /// Restart the sequence from `seed`. The same seed must always produce the same sequence.
+    fn reseed(&mut self, seed: u64);
+
+    /// Move to the point `draws` bytes into the sequence from `seed`. Draws the bytes one by one
+    /// unless overridden by a source which can jump ahead, as the built-in sources do.
+    fn seek(&mut self, seed: u64, draws: u64) {
+        self.reseed(seed);
+        for _ in 0..draws {
+            self.next_byte();
+        }
+    }
+}
+
+/// Small, fast xorshift64* generator. Used when the `rand` feature is disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XorShift {
+    state: u64,
+}
+impl XorShift {
+    /// Create a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.reseed(seed);
+        rng
+    }
+
+    /// Return the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = Self::step(self.state);
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Advance a state by one number.
+    fn step(mut state: u64) -> u64 {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state
+    }
+
+    /// Advance the state by `steps` numbers at once. A step is linear over GF(2), so its matrix,
+    /// stored as where each bit of the state goes, is raised to the power of `steps`.
+    fn jump(&mut self, mut steps: u64) {
+        let apply = |matrix: &[u64; 64], state: u64| {
+            (0..64)
+                .filter(|bit| state >> bit & 1 == 1)
+                .fold(0, |result, bit| result ^ matrix[bit])
+        };
+        let mut matrix: [u64; 64] = std::array::from_fn(|bit| Self::step(1 << bit));
+        while steps > 0 {
+            if steps & 1 == 1 {
+                self.state = apply(&matrix, self.state);
+            }
+            matrix = std::array::from_fn(|bit| apply(&matrix, matrix[bit]));
+            steps >>= 1;
+        }
+    }
+}
+impl RandomSource for XorShift {
+    fn next_byte(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        // Scramble the seed with splitmix64 so similar seeds diverge and zero is never the state.
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        self.state = if z == 0 { 0x9E37_79B9_7F4A_7C15 } else { z };
+    }
+
+    fn seek(&mut self, seed: u64, draws: u64) {
+        self.reseed(seed);
+        self.jump(draws);
+    }
+}
+
+/// Generator backed by the `rand` crate's standard RNG, ChaCha12. The default source.
+#[cfg(feature = "rand")]
+#[derive(Debug, Clone)]
+pub struct StdRandom(rand_chacha::ChaCha12Rng);
+#[cfg(feature = "rand")]
+impl StdRandom {
+    /// Create a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self(rand::SeedableRng::seed_from_u64(seed))
+    }
+}
+#[cfg(feature = "rand")]
+impl RandomSource for StdRandom {
+    fn next_byte(&mut self) -> u8 {
+        rand::Rng::gen(&mut self.0)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    fn seek(&mut self, seed: u64, draws: u64) {
+        // Each byte is drawn from a 32-bit word of the stream
+        self.reseed(seed);
+        self.0.set_word_pos(draws.into());
+    }
+}
+
+/// Create the default random source.
+#[cfg(feature = "rand")]
+pub(crate) fn default_source(seed: u64) -> Box<dyn RandomSource + Send> {
+    Box::new(StdRandom::new(seed))
+}
+
+/// Create the default random source.
+#[cfg(not(feature = "rand"))]
+pub(crate) fn default_source(seed: u64) -> Box<dyn RandomSource + Send> {
+    Box::new(XorShift::new(seed))
+}
+
+/// Pick an unpredictable seed.
+#[cfg(feature = "rand")]
+pub(crate) fn random_seed() -> u64 {
+    rand::random()
+}
+
+/// Pick an unpredictable seed from the standard library's per-process hash keys.
+#[cfg(not(feature = "rand"))]
+pub(crate) fn random_seed() -> u64 {
+    use std::{
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+    };
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    #[test]
+    fn test_xorshift() {
+        let mut a = XorShift::new(0);
+        let mut b = XorShift::new(0);
+        let bytes: Vec<_> = (0..8).map(|_| a.next_byte()).collect();
+        assert_eq!(bytes, (0..8).map(|_| b.next_byte()).collect::<Vec<_>>());
+        assert!(bytes.iter().any(|byte| *byte != bytes[0]));
+
+        // CXKK draws from the attached source, reseeded with the emulator's seed.
+        let mut emu = Emulator::with_seed(5);
+        emu.set_random_source(Box::new(XorShift::new(0)));
+        emu.load_rom(&[0xC0, 0xFF]).unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.v(0_usize), XorShift::new(5).next_byte());
+
+        // Seeking lands where drawing one by one does, without drawing every byte
+        let mut drawn = XorShift::new(3);
+        let mut sought = XorShift::new(0);
+        for draws in [1, 2, 63, 1000] {
+            drawn.reseed(3);
+            for _ in 0..draws {
+                drawn.next_byte();
+            }
+            sought.seek(3, draws);
+            assert_eq!(sought, drawn);
+        }
+        sought.seek(3, u64::MAX);
+        #[cfg(feature = "rand")]
+        {
+            let mut drawn = StdRandom::new(3);
+            let bytes: Vec<_> = (0..100).map(|_| drawn.next_byte()).collect();
+            let mut sought = StdRandom::new(0);
+            sought.seek(3, 37);
+            assert_eq!(sought.next_byte(), bytes[37]);
+            sought.seek(3, u64::MAX);
+            // Same stream as the `rand` crate's standard RNG
+            let mut std: rand::rngs::StdRng = rand::SeedableRng::seed_from_u64(3);
+            let std_bytes: Vec<u8> = (0..100).map(|_| rand::Rng::gen(&mut std)).collect();
+            assert_eq!(std_bytes, bytes);
+        }
+    }
+}
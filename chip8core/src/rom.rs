@@ -0,0 +1,116 @@
+//! ROM loading and metadata.
+use std::{error::Error, fmt, ops::Range};
+
+use sha1::{Digest, Sha1};
+
+/// Information about a ROM loaded into RAM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RomInfo {
+    /// Size of the ROM in bytes.
+    pub size: usize,
+    /// RAM address the ROM was loaded at.
+    pub load_address: u16,
+    /// SHA-1 hash of the ROM bytes.
+    pub sha1: [u8; 20],
+}
+impl RomInfo {
+    /// Gather information about the given ROM bytes loaded at `load_address`.
+    pub fn new(data: &[u8], load_address: u16) -> Self {
+        Self {
+            size: data.len(),
+            load_address,
+            sha1: Sha1::digest(data).into(),
+        }
+    }
+
+    /// Range of RAM addresses occupied by the ROM.
+    pub fn range(&self) -> Range<usize> {
+        let start = self.load_address as usize;
+        start..(start + self.size)
+    }
+
+    /// SHA-1 hash of the ROM as a lowercase hex string.
+    pub fn sha1_hex(&self) -> String {
+        self.sha1
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+/// Error loading a ROM into RAM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    /// ROM contains no bytes.
+    Empty,
+    /// ROM is too large to fit in the available RAM.
+    RomTooLarge {
+        /// Size of the ROM in bytes.
+        size: usize,
+        /// Maximum ROM size in bytes.
+        max: usize,
+    },
+    /// Two segments would be loaded over each other.
+    SegmentsOverlap {
+        /// Load address of the later segment.
+        addr: u16,
+    },
+}
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "ROM is empty"),
+            Self::RomTooLarge { size, max } => {
+                write!(f, "ROM too large: {} bytes (max {} bytes)", size, max)
+            }
+            Self::SegmentsOverlap { addr } => {
+                write!(f, "segment at {:#05X} overlaps another segment", addr)
+            }
+        }
+    }
+}
+impl Error for LoadError {}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    #[test]
+    fn test_rom_info() {
+        let info = RomInfo::new(b"abc", 0x200);
+        assert_eq!(info.size, 3);
+        assert_eq!(info.range(), 0x200..0x203);
+        assert_eq!(info.sha1_hex(), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+
+    #[test]
+    fn test_load_segments() {
+        let mut emu = Emulator::new();
+        let info = emu.load_at(0xE00, &[1, 2, 3]).unwrap();
+        assert_eq!(info.range(), 0xE00..0xE03);
+        assert_eq!(emu.rom_info(), None);
+        assert_eq!(&emu.ram()[0xE00..0xE03], &[1, 2, 3]);
+        assert_eq!(
+            emu.load_at(0xFFF, &[1, 2]),
+            Err(LoadError::RomTooLarge { size: 2, max: 1 })
+        );
+
+        let segments: [(u16, &[u8]); 2] = [(0x200, &[0x12, 0x00]), (0x300, &[0xAA; 4])];
+        let infos = emu.load_segments(&segments).unwrap();
+        assert_eq!(infos.len(), 2);
+        assert_eq!(emu.read_byte(0x303), Ok(0xAA));
+
+        // Nothing is written if any segment is invalid.
+        let overlapping: [(u16, &[u8]); 2] = [(0x400, &[1; 4]), (0x402, &[2; 4])];
+        assert_eq!(
+            emu.load_segments(&overlapping),
+            Err(LoadError::SegmentsOverlap { addr: 0x402 })
+        );
+        assert_eq!(emu.read_byte(0x400), Ok(0));
+    }
+}
@@ -0,0 +1,175 @@
+//! Database of known ROMs, identified by SHA-1 hash.
+use super::{host::DEFAULT_CYCLES_PER_FRAME, Quirks, RomInfo};
+
+/// Known ROM and the settings it runs best with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RomEntry {
+    /// SHA-1 hash of the ROM as a lowercase hex string.
+    pub sha1: &'static str,
+    /// Title of the game or program.
+    pub title: &'static str,
+    /// Author, if known.
+    pub author: Option<&'static str>,
+    /// Recommended instructions per 60Hz frame.
+    pub cycles_per_frame: u32,
+    /// Recommended quirks.
+    pub quirks: Quirks,
+}
+impl RomEntry {
+    /// Entry with the default clock speed and modern quirks.
+    const fn new(sha1: &'static str, title: &'static str, author: Option<&'static str>) -> Self {
+        Self {
+            sha1,
+            title,
+            author,
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            quirks: Quirks::modern(),
+        }
+    }
+}
+
+/// Every known ROM. Covers the public domain games in the repository's `roms` directory.
+pub static ROMS: &[RomEntry] = &[
+    RomEntry::new(
+        "ea9af3c09b0d9e265fcd92bcc5d51a2939fdf27a",
+        "15 Puzzle",
+        Some("Roger Ivie"),
+    ),
+    RomEntry::new(
+        "d40abc54374e4343639f993e897e00904ddf85d9",
+        "Blinky",
+        Some("Hans Christian Egeberg"),
+    ),
+    RomEntry::new(
+        "6f6509f38220e057a7e32ebb22dd353c1078e3e7",
+        "Blitz",
+        Some("David Winter"),
+    ),
+    RomEntry::new(
+        "f13766c14aeb02ad8d4d103cb5eadd282d20cddc",
+        "Brix",
+        Some("Andreas Gustafsson"),
+    ),
+    RomEntry::new(
+        "2d10c07b532f4fa7c07a07324ba26ca39fe484fd",
+        "Connect 4",
+        Some("David Winter"),
+    ),
+    RomEntry::new(
+        "5260f8931e0e9f41e555b382a14a88368e3ed886",
+        "Guess",
+        Some("David Winter"),
+    ),
+    RomEntry::new(
+        "050f07a54371da79f924dd0227b89d07b4f2aed0",
+        "Hidden",
+        Some("David Winter"),
+    ),
+    RomEntry::new(
+        "f100197f0f2f05b4f3c8c31ab9c2c3930d3e9571",
+        "Space Invaders",
+        Some("David Winter"),
+    ),
+    RomEntry::new(
+        "d6fa9dc9005dc0496f39ba52fef56f9fd0a5a158",
+        "Kaleidoscope",
+        Some("Joseph Weisbecker"),
+    ),
+    RomEntry::new(
+        "b9272ae1acdaaa79ab649f6b48b72088ca2b1d74",
+        "Maze",
+        Some("David Winter"),
+    ),
+    RomEntry::new(
+        "d979858bb9ffd07b48f52f92a8bcac0199f3623e",
+        "Merlin",
+        Some("David Winter"),
+    ),
+    RomEntry::new(
+        "0d0cc129dad3c45ba672f85fec71a668232212cc",
+        "Missile Command",
+        Some("David Winter"),
+    ),
+    RomEntry::new(
+        "b232ef880bd6060fb45fa6effed7edf0ae95670e",
+        "Pong",
+        Some("Paul Vervalin"),
+    ),
+    RomEntry::new(
+        "a60611339661e3ab2d8af024ad1da5880a6f8665",
+        "Pong 2",
+        Some("David Winter"),
+    ),
+    RomEntry::new("1293db0ccccbe7dd3fc5a09a2abc5d7b175e18e0", "Puzzle", None),
+    RomEntry::new(
+        "1bdb4ddaa7049266fa3226851f28855a365cfd12",
+        "Syzygy",
+        Some("Roy Trevino"),
+    ),
+    RomEntry::new("18b9d15f4c159e1f0ed58c2d8ec1d89325d3a3b6", "Tank", None),
+    RomEntry::new(
+        "5f518084744bf3cb8733f6e5454dfd1634320563",
+        "Tetris",
+        Some("Fran Dachille"),
+    ),
+    RomEntry::new(
+        "429d455a4bc53167942bf6fd934d72b0f648dce3",
+        "Tic-Tac-Toe",
+        Some("David Winter"),
+    ),
+    RomEntry::new(
+        "bdb92475acfe11bc7814a2f5eade13fcd09b756a",
+        "UFO",
+        Some("Lutz V"),
+    ),
+    RomEntry::new(
+        "da710f631f8e35534d0b9170bcf892a60f49c43d",
+        "Vertical Brix",
+        Some("Paul Robson"),
+    ),
+    RomEntry::new(
+        "ade839585ddeb0e3633177df03c1d91589e629eb",
+        "Vers",
+        Some("JMN"),
+    ),
+    RomEntry::new(
+        "d666688a8fce468a7d88b536bc1ef5f35ba12031",
+        "Wipe Off",
+        Some("Joseph Weisbecker"),
+    ),
+];
+
+/// Look up a ROM by the lowercase hex string of its SHA-1 hash.
+pub fn lookup(sha1: &str) -> Option<&'static RomEntry> {
+    ROMS.iter().find(|entry| entry.sha1 == sha1)
+}
+
+impl RomInfo {
+    /// Look the ROM up in the database of known ROMs.
+    pub fn identify(&self) -> Option<&'static RomEntry> {
+        lookup(&self.sha1_hex())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::emulator::START_ADDRESS;
+
+    use super::*;
+
+    #[test]
+    fn test_identify() {
+        let maze = RomInfo::new(include_bytes!("../../roms/MAZE"), START_ADDRESS);
+        let entry = maze.identify().unwrap();
+        assert_eq!(entry.title, "Maze");
+        assert_eq!(entry.cycles_per_frame, DEFAULT_CYCLES_PER_FRAME);
+        assert_eq!(RomInfo::new(&[0x12, 0x00], START_ADDRESS).identify(), None);
+
+        let mut hashes: Vec<_> = ROMS.iter().map(|entry| entry.sha1).collect();
+        hashes.sort();
+        hashes.dedup();
+        assert_eq!(hashes.len(), ROMS.len());
+    }
+}
@@ -0,0 +1,195 @@
+//! Reading ROM files, including ones packed in `.zip` and `.gz` archives, as ROM packs usually
+//! are.
+use std::{
+    error::Error,
+    fmt, fs,
+    io::{self, Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+/// Extensions of ROMs picked from archives when no entry is named, lowercase.
+pub const ROM_EXTENSIONS: [&str; 5] = ["ch8", "c8", "sc8", "xo8", "o8"];
+/// Extensions of the archives ROMs are unpacked from, lowercase.
+pub const ARCHIVE_EXTENSIONS: [&str; 2] = ["zip", "gz"];
+
+/// ROM read from a file or archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomFile {
+    /// Name of the file, or of the entry in the archive, e.g. to tell Octo source apart by its
+    /// extension.
+    pub name: String,
+    /// Contents of the ROM.
+    pub data: Vec<u8>,
+}
+
+/// Read the ROM at `path`. In a `.zip` archive, this is the entry named `entry` by its path or
+/// file name, or the first entry with one of the [ROM_EXTENSIONS]. Other files are read whole,
+/// unpacking `.gz` files.
+pub fn read(path: &Path, entry: Option<&str>) -> Result<RomFile, RomFileError> {
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    unpack(&name, fs::read(path)?, entry)
+}
+
+/// Split a path written as `archive.zip#entry` into the archive and the entry named. Paths to
+/// files that exist are left whole, in case their name contains `#`.
+pub fn split_entry(path: &Path) -> (PathBuf, Option<String>) {
+    let text = path.to_string_lossy();
+    match text.rsplit_once('#') {
+        Some((archive, entry)) if !path.exists() => (PathBuf::from(archive), Some(entry.into())),
+        _ => (path.to_path_buf(), None),
+    }
+}
+
+/// Unpack the ROM from the contents of a file called `name`, as [read] does.
+pub fn unpack(name: &str, data: Vec<u8>, entry: Option<&str>) -> Result<RomFile, RomFileError> {
+    match extension(name).as_deref() {
+        Some("zip") => unzip(data, entry),
+        Some("gz") => {
+            let mut rom = Vec::new();
+            GzDecoder::new(data.as_slice()).read_to_end(&mut rom)?;
+            Ok(RomFile {
+                name: name[..name.len() - ".gz".len()].to_string(),
+                data: rom,
+            })
+        }
+        _ => Ok(RomFile {
+            name: name.to_string(),
+            data,
+        }),
+    }
+}
+
+/// Pick a ROM out of a `.zip` archive.
+fn unzip(data: Vec<u8>, entry: Option<&str>) -> Result<RomFile, RomFileError> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let wanted = (0..archive.len()).find(|&index| {
+        let Some(name) = archive.name_for_index(index) else {
+            return false;
+        };
+        let file_name = name.rsplit('/').next().unwrap_or(name);
+        match entry {
+            Some(entry) => name == entry || file_name == entry,
+            None => extension(name).is_some_and(|ext| ROM_EXTENSIONS.contains(&ext.as_str())),
+        }
+    });
+    let Some(index) = wanted else {
+        return Err(match entry {
+            Some(entry) => RomFileError::MissingEntry(entry.to_string()),
+            None => RomFileError::NoRom,
+        });
+    };
+    let mut file = archive.by_index(index)?;
+    let mut rom = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut rom)?;
+    Ok(RomFile {
+        name: file.name().to_string(),
+        data: rom,
+    })
+}
+
+/// Lowercase extension of a file name, if it has one.
+fn extension(name: &str) -> Option<String> {
+    Path::new(name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// Error reading a ROM file.
+#[derive(Debug)]
+pub enum RomFileError {
+    /// The file couldn't be read or unpacked.
+    Io(io::Error),
+    /// The file isn't a valid `.zip` archive.
+    Zip(String),
+    /// No entry in the archive has a ROM's extension.
+    NoRom,
+    /// The archive has no entry by the name given.
+    MissingEntry(String),
+}
+impl fmt::Display for RomFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Zip(e) => write!(f, "invalid zip archive: {}", e),
+            Self::NoRom => write!(f, "no ROM in archive"),
+            Self::MissingEntry(entry) => write!(f, "no {} in archive", entry),
+        }
+    }
+}
+impl Error for RomFileError {}
+impl From<io::Error> for RomFileError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<zip::result::ZipError> for RomFileError {
+    fn from(e: zip::result::ZipError) -> Self {
+        match e {
+            zip::result::ZipError::Io(e) => Self::Io(e),
+            e => Self::Zip(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+    use pretty_assertions::assert_eq;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+
+    #[test]
+    fn test_unpack() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in [
+            ("README.txt", &b"readme"[..]),
+            ("games/pong.ch8", &[0x12, 0x00]),
+            ("games/tetris.ch8", &[0x00, 0xE0]),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        let zip = zip.finish().unwrap().into_inner();
+
+        // The first ROM, unless one is named by its path or file name
+        let rom = unpack("pack.ZIP", zip.clone(), None).unwrap();
+        assert_eq!(rom.name, "games/pong.ch8");
+        assert_eq!(rom.data, [0x12, 0x00]);
+        let rom = unpack("pack.zip", zip.clone(), Some("tetris.ch8")).unwrap();
+        assert_eq!(rom.data, [0x00, 0xE0]);
+        assert!(matches!(
+            unpack("pack.zip", zip, Some("brix.ch8")),
+            Err(RomFileError::MissingEntry(_))
+        ));
+
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&[0x60, 0x05]).unwrap();
+        let rom = unpack("game.ch8.gz", gz.finish().unwrap(), None).unwrap();
+        assert_eq!(
+            rom,
+            RomFile {
+                name: "game.ch8".to_string(),
+                data: vec![0x60, 0x05]
+            }
+        );
+        assert_eq!(unpack("game.ch8", vec![1], None).unwrap().data, [1]);
+
+        assert_eq!(
+            split_entry(Path::new("no/such/pack.zip#pong.ch8")),
+            (
+                PathBuf::from("no/such/pack.zip"),
+                Some("pong.ch8".to_string())
+            )
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! [Rhai](https://rhai.rs) scripts called every frame and whenever execution stops.
+//!
+//! Scripts define any of these functions, which act on the machine through `this`:
+//! - `on_frame()`, called before each frame.
+//! - `on_break(pc, at)`, called when a breakpoint or watchpoint stops execution. `at` is the
+//!   breakpoint or watched address, or the watched register index.
+//!
+//! `this` has the properties `pc`, `i`, `delay`, `sound`, `frames` (read-only) and `store`, a map
+//! kept between calls, and the methods `v(x)`, `set_v(x, value)`, `read(addr)`,
+//! `write(addr, value)`, `is_pressed(key)`, `press(key)`, `release(key)`, `break_at(addr)` and
+//! `clear_break(addr)`. Changes are written back to the emulator when the function returns.
+use std::{error::Error, fmt};
+
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST, INT};
+
+use super::{emulator::NUM_REGISTERS, Emulator, StopReason};
+
+/// Result of a function called by a script.
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Copy of the machine state a script works on.
+#[derive(Debug, Clone)]
+struct Machine {
+    v: [u8; NUM_REGISTERS],
+    i: u16,
+    pc: u16,
+    ram: Vec<u8>,
+    keys: u16,
+    delay: u8,
+    sound: u8,
+    frames: u64,
+    store: Map,
+    breakpoints: Vec<(u16, bool)>,
+}
+impl Machine {
+    fn new(emu: &Emulator, store: Map) -> Self {
+        Self {
+            v: *emu.v_registers(),
+            i: emu.i(),
+            pc: emu.pc(),
+            ram: emu.ram().to_vec(),
+            keys: emu.keys_bitmask(),
+            delay: emu.delay_timer(),
+            sound: emu.sound_timer(),
+            frames: emu.frames(),
+            store,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Write the changes back to the emulator, returning the store.
+    fn apply(self, emu: &mut Emulator) -> Map {
+        for (x, value) in self.v.iter().enumerate() {
+            emu.set_v(x, *value);
+        }
+        emu.set_i(self.i);
+        emu.set_pc(self.pc);
+        for (addr, value) in self.ram.iter().enumerate() {
+            if emu.ram()[addr] != *value {
+                let _ = emu.write_byte(addr, *value);
+            }
+        }
+        emu.set_keys(self.keys);
+        emu.set_delay_timer(self.delay);
+        emu.set_sound_timer(self.sound);
+        for (addr, add) in self.breakpoints {
+            if add {
+                emu.debugger_mut().add_breakpoint(addr);
+            } else {
+                emu.debugger_mut().remove_breakpoint(addr);
+            }
+        }
+        self.store
+    }
+
+    fn register(&self, x: INT) -> ScriptResult<usize> {
+        usize::try_from(x)
+            .ok()
+            .filter(|x| *x < NUM_REGISTERS)
+            .ok_or_else(|| format!("register {} out of range", x).into())
+    }
+
+    fn addr(&self, addr: INT) -> ScriptResult<usize> {
+        usize::try_from(addr)
+            .ok()
+            .filter(|addr| *addr < self.ram.len())
+            .ok_or_else(|| format!("address {} out of range", addr).into())
+    }
+
+    fn key(key: INT) -> ScriptResult<u16> {
+        match key {
+            0..=15 => Ok(1 << key),
+            _ => Err(format!("key {} out of range", key).into()),
+        }
+    }
+}
+
+/// Create an engine with the [Machine] API registered.
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .register_type_with_name::<Machine>("Machine")
+        .register_get_set(
+            "pc",
+            |m: &mut Machine| m.pc as INT,
+            |m: &mut Machine, pc: INT| m.pc = pc as u16,
+        )
+        .register_get_set(
+            "i",
+            |m: &mut Machine| m.i as INT,
+            |m: &mut Machine, i: INT| m.i = i as u16,
+        )
+        .register_get_set(
+            "delay",
+            |m: &mut Machine| m.delay as INT,
+            |m: &mut Machine, value: INT| m.delay = value as u8,
+        )
+        .register_get_set(
+            "sound",
+            |m: &mut Machine| m.sound as INT,
+            |m: &mut Machine, value: INT| m.sound = value as u8,
+        )
+        .register_get("frames", |m: &mut Machine| m.frames as INT)
+        .register_get_set(
+            "store",
+            |m: &mut Machine| m.store.clone(),
+            |m: &mut Machine, store: Map| m.store = store,
+        )
+        .register_fn("v", |m: &mut Machine, x: INT| {
+            ScriptResult::Ok(m.v[m.register(x)?] as INT)
+        })
+        .register_fn("set_v", |m: &mut Machine, x: INT, value: INT| {
+            m.v[m.register(x)?] = value as u8;
+            ScriptResult::Ok(())
+        })
+        .register_fn("read", |m: &mut Machine, addr: INT| {
+            ScriptResult::Ok(m.ram[m.addr(addr)?] as INT)
+        })
+        .register_fn("write", |m: &mut Machine, addr: INT, value: INT| {
+            let addr = m.addr(addr)?;
+            m.ram[addr] = value as u8;
+            ScriptResult::Ok(())
+        })
+        .register_fn("is_pressed", |m: &mut Machine, key: INT| {
+            ScriptResult::Ok(m.keys & Machine::key(key)? != 0)
+        })
+        .register_fn("press", |m: &mut Machine, key: INT| {
+            m.keys |= Machine::key(key)?;
+            ScriptResult::Ok(())
+        })
+        .register_fn("release", |m: &mut Machine, key: INT| {
+            m.keys &= !Machine::key(key)?;
+            ScriptResult::Ok(())
+        })
+        .register_fn("break_at", |m: &mut Machine, addr: INT| {
+            let addr = m.addr(addr)? as u16;
+            m.breakpoints.push((addr, true));
+            ScriptResult::Ok(())
+        })
+        .register_fn("clear_break", |m: &mut Machine, addr: INT| {
+            let addr = m.addr(addr)? as u16;
+            m.breakpoints.push((addr, false));
+            ScriptResult::Ok(())
+        });
+    engine
+}
+
+/// A compiled script and the state it keeps between calls.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    store: Map,
+}
+impl Script {
+    /// Compile a script and run its top-level statements.
+    pub fn new(source: &str) -> Result<Self, ScriptError> {
+        let engine = engine();
+        let ast = engine
+            .compile(source)
+            .map_err(|e| ScriptError::Parse(e.to_string()))?;
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|e| ScriptError::Runtime(e.to_string()))?;
+        Ok(Self {
+            engine,
+            ast,
+            scope,
+            store: Map::new(),
+        })
+    }
+
+    /// Call `on_frame()`, if defined. Call before running each frame.
+    pub fn on_frame(&mut self, emu: &mut Emulator) -> Result<(), ScriptError> {
+        self.call(emu, "on_frame", ())
+    }
+
+    /// Call `on_break(pc, at)`, if defined. Call when a breakpoint or watchpoint stops execution.
+    pub fn on_stop(&mut self, emu: &mut Emulator, stop: StopReason) -> Result<(), ScriptError> {
+        let at = match stop {
+            StopReason::Breakpoint(addr)
+            | StopReason::RamRead(addr)
+            | StopReason::RamWrite(addr) => addr as INT,
+            StopReason::Register { x, .. } => x as INT,
+        };
+        let pc = emu.pc() as INT;
+        self.call(emu, "on_break", (pc, at))
+    }
+
+    /// Call a script function with `this` bound to the machine, if the script defines it.
+    fn call(
+        &mut self,
+        emu: &mut Emulator,
+        name: &str,
+        args: impl rhai::FuncArgs,
+    ) -> Result<(), ScriptError> {
+        if !self.ast.iter_functions().any(|f| f.name == name) {
+            return Ok(());
+        }
+        let store = std::mem::take(&mut self.store);
+        let mut this = Dynamic::from(Machine::new(emu, store));
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut this);
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut self.scope,
+            &self.ast,
+            name,
+            args,
+        );
+        self.store = this.cast::<Machine>().apply(emu);
+        result
+            .map(|_| ())
+            .map_err(|e| ScriptError::Runtime(e.to_string()))
+    }
+}
+
+/// Error compiling or running a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    /// The script has a syntax error.
+    Parse(String),
+    /// The script failed while running.
+    Runtime(String),
+}
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "script syntax error: {}", e),
+            Self::Runtime(e) => write!(f, "script error: {}", e),
+        }
+    }
+}
+impl Error for ScriptError {}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_script() {
+        let mut emu = Emulator::new();
+        // 0x200: ADD V0, 1; LD V1, K; JP 0x200
+        emu.load_rom(&[0x70, 0x01, 0xF1, 0x0A, 0x12, 0x00]).unwrap();
+        let mut script = Script::new(
+            "
+            fn on_frame() {
+                this.store.frames = this.frames;
+                this.write(0x300, this.v(0));
+                if this.frames == 0 { this.break_at(0x204); }
+                if this.frames == 1 { this.press(5); }
+            }
+            fn on_break(pc, at) {
+                this.set_v(2, pc - 0x200);
+                this.set_v(3, at - 0x200);
+                this.release(5);
+            }
+            ",
+        )
+        .unwrap();
+
+        // The first frame waits for a key, which the script presses on the second.
+        for _ in 0..2 {
+            script.on_frame(&mut emu).unwrap();
+            let output = emu.run_frame(4).unwrap();
+            if let Some(stop) = output.stop() {
+                script.on_stop(&mut emu, stop).unwrap();
+            }
+        }
+        assert_eq!(emu.read_byte(0x300).unwrap(), 1);
+        assert_eq!(emu.v(1_usize), 5);
+        assert_eq!((emu.v(2_usize), emu.v(3_usize)), (4, 4));
+        assert!(!emu.is_key_pressed(5));
+        assert_eq!(script.store["frames"].as_int(), Ok(1));
+
+        assert!(matches!(
+            Script::new("fn on_frame( {"),
+            Err(ScriptError::Parse(_))
+        ));
+        let mut script = Script::new("fn on_frame() { this.v(16) }").unwrap();
+        assert!(matches!(
+            script.on_frame(&mut emu),
+            Err(ScriptError::Runtime(_))
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+//! RAM search for finding the addresses of values such as lives or score.
+use super::{Cheat, CheatTarget, Emulator};
+
+/// Test applied to each candidate address when refining a [RamSearch].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparison {
+    /// The value is equal to the given one.
+    Equal(u8),
+    /// The value differs from the last snapshot.
+    Changed,
+    /// The value is the same as in the last snapshot.
+    Unchanged,
+    /// The value is greater than in the last snapshot.
+    Increased,
+    /// The value is less than in the last snapshot.
+    Decreased,
+}
+impl Comparison {
+    fn matches(&self, old: u8, new: u8) -> bool {
+        match *self {
+            Self::Equal(value) => new == value,
+            Self::Changed => new != old,
+            Self::Unchanged => new == old,
+            Self::Increased => new > old,
+            Self::Decreased => new < old,
+        }
+    }
+}
+
+/// Narrows down the RAM addresses holding a value by comparing snapshots taken between frames,
+/// e.g. "increased" after scoring, then "unchanged" while idle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RamSearch {
+    candidates: Vec<u16>,
+    snapshot: Vec<u8>,
+}
+impl RamSearch {
+    /// Start a search with every RAM address as a candidate, taking the first snapshot.
+    pub fn new(emu: &Emulator) -> Self {
+        Self {
+            candidates: (0..emu.ram().len() as u16).collect(),
+            snapshot: emu.ram().to_vec(),
+        }
+    }
+
+    /// Keep only the candidates passing `comparison` against the last snapshot, then take a new
+    /// snapshot. Returns the number of candidates left.
+    pub fn refine(&mut self, emu: &Emulator, comparison: Comparison) -> usize {
+        let ram = emu.ram();
+        let snapshot = &self.snapshot;
+        self.candidates.retain(|addr| {
+            let addr = *addr as usize;
+            match (snapshot.get(addr), ram.get(addr)) {
+                (Some(old), Some(new)) => comparison.matches(*old, *new),
+                _ => false,
+            }
+        });
+        self.snapshot = ram.to_vec();
+        self.candidates.len()
+    }
+
+    /// Return the remaining candidate addresses, in ascending order.
+    pub fn candidates(&self) -> &[u16] {
+        &self.candidates
+    }
+
+    /// Return the value of an address in the last snapshot.
+    pub fn value(&self, addr: u16) -> Option<u8> {
+        self.snapshot.get(addr as usize).copied()
+    }
+
+    /// Number of candidates left.
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Whether no candidates are left.
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Return a cheat holding the only candidate left at `value`, once the search has narrowed
+    /// down to one address.
+    pub fn cheat(&self, name: &str, value: u8) -> Option<Cheat> {
+        match self.candidates[..] {
+            [addr] => Some(Cheat::new(name, CheatTarget::Ram(addr), value)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_ram_search() {
+        let mut emu = Emulator::new();
+        // 0x200: LD I, 0x300; LD V0, [I]; ADD V0, 1; LD [I], V0; LD V1, K; JP 0x208
+        emu.load_rom(&[
+            0xA3, 0x00, 0xF0, 0x65, 0x70, 0x01, 0xF0, 0x55, 0xF1, 0x0A, 0x12, 0x08,
+        ])
+        .unwrap();
+        let mut search = RamSearch::new(&emu);
+        emu.run_frame(8).unwrap();
+
+        // 0x300 is incremented once, then the ROM waits for a key.
+        assert_eq!(search.refine(&emu, Comparison::Increased), 1);
+        assert_eq!(search.candidates(), [0x300]);
+        assert_eq!(search.value(0x300), Some(1));
+        emu.run_frame(8).unwrap();
+        assert_eq!(search.refine(&emu, Comparison::Unchanged), 1);
+
+        let cheat = search.cheat("Count", 9).unwrap();
+        assert_eq!(cheat.target, CheatTarget::Ram(0x300));
+        emu.cheats_mut().add(cheat);
+        emu.run_frame(8).unwrap();
+        assert_eq!(search.refine(&emu, Comparison::Equal(9)), 1);
+        assert_eq!(search.refine(&emu, Comparison::Changed), 0);
+        assert_eq!(search.cheat("Count", 9), None);
+    }
+}
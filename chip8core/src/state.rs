@@ -0,0 +1,626 @@
+//! Snapshots of the full machine state.
+use std::{error::Error, fmt};
+
+use super::{
+    builder::MAX_RAM_SIZE,
+    display::{Display, NUM_PLANES, PLANE_SIZE},
+    emulator::{EmulatorState, HaltReason, NUM_KEYS, NUM_REGISTERS, RAM_SIZE, STACK_SIZE},
+    fontset::FONTSET_SIZE,
+    rom::RomInfo,
+    Chip8Error,
+};
+
+/// Version of the binary encoding produced by [SaveState::encode]. Version 1 states, which lack
+/// the execution state, version 2 states, which only have the first display plane, version 3
+/// states, which assume the default RAM size and stack depth, and version 4 states, which lack
+/// the random number generator's position and decode with seed 0 and nothing drawn, can still be
+/// decoded.
+pub const STATE_VERSION: u8 = 5;
+
+/// Magic bytes at the start of every save file produced by [SaveState::to_bytes].
+pub const SAVE_FILE_MAGIC: [u8; 4] = *b"C8SV";
+/// Version of the save file container produced by [SaveState::to_bytes].
+pub const SAVE_FILE_VERSION: u16 = 1;
+/// Length of the save file header: magic, version, ROM flag, ROM SHA-1.
+const SAVE_FILE_HEADER_LEN: usize = 4 + 2 + 1 + 20;
+
+/// Snapshot of the full machine state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SaveState {
+    /// Program counter.
+    pub program_counter: u16,
+    /// Random-access memory.
+    pub ram: Vec<u8>,
+    /// Screen pixels.
+    pub display: Display,
+    /// V registers.
+    pub v_registers: [u8; NUM_REGISTERS],
+    /// I register.
+    pub i_register: u16,
+    /// Stack pointer.
+    pub stack_pointer: u16,
+    /// Stack.
+    pub stack: Vec<u16>,
+    /// Keypad.
+    pub keys: [bool; NUM_KEYS],
+    /// Delay timer.
+    pub delay_timer: u8,
+    /// Sound timer.
+    pub sound_timer: u8,
+    /// Information about the ROM loaded when the state was saved.
+    pub rom_info: Option<RomInfo>,
+    /// Execution state.
+    pub execution_state: EmulatorState,
+    /// Seed the random number generator was last seeded with.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rng_seed: u64,
+    /// Random bytes drawn since the generator was last seeded.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub random_draws: u64,
+}
+impl SaveState {
+    /// Encode the state as a versioned binary blob.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.ram.len() + NUM_PLANES * PLANE_SIZE / 8 + 128);
+        bytes.push(STATE_VERSION);
+        bytes.extend_from_slice(&self.program_counter.to_be_bytes());
+        bytes.extend_from_slice(&(self.ram.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.ram);
+        // Pack display pixels 8 to a byte
+        for n in 0..NUM_PLANES {
+            for chunk in self.display.plane(n).unwrap().chunks(8) {
+                bytes.push(pack_bits(chunk));
+            }
+        }
+        bytes.push(self.display.selected_planes());
+        bytes.extend_from_slice(&self.v_registers);
+        bytes.extend_from_slice(&self.i_register.to_be_bytes());
+        bytes.extend_from_slice(&self.stack_pointer.to_be_bytes());
+        bytes.extend_from_slice(&(self.stack.len() as u16).to_be_bytes());
+        for val in &self.stack {
+            bytes.extend_from_slice(&val.to_be_bytes());
+        }
+        for chunk in self.keys.chunks(8) {
+            bytes.push(pack_bits(chunk));
+        }
+        bytes.push(self.delay_timer);
+        bytes.push(self.sound_timer);
+        match &self.rom_info {
+            Some(info) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&(info.size as u32).to_be_bytes());
+                bytes.extend_from_slice(&info.load_address.to_be_bytes());
+                bytes.extend_from_slice(&info.sha1);
+            }
+            None => bytes.push(0),
+        }
+        encode_execution_state(&self.execution_state, &mut bytes);
+        bytes.extend_from_slice(&self.rng_seed.to_be_bytes());
+        bytes.extend_from_slice(&self.random_draws.to_be_bytes());
+        bytes
+    }
+
+    /// Encode the state as a save file: a header identifying the format and ROM, followed by the
+    /// zstd-compressed encoded state.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SAVE_FILE_HEADER_LEN + 512);
+        bytes.extend_from_slice(&SAVE_FILE_MAGIC);
+        bytes.extend_from_slice(&SAVE_FILE_VERSION.to_be_bytes());
+        match &self.rom_info {
+            Some(info) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&info.sha1);
+            }
+            None => {
+                bytes.push(0);
+                bytes.extend_from_slice(&[0; 20]);
+            }
+        }
+        // Compressing from an in-memory buffer cannot fail.
+        let body = zstd::encode_all(&self.encode()[..], 0).unwrap();
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    /// Decode a save file produced by [SaveState::to_bytes].
+    ///
+    /// Refuses to load the state if it was saved with a different ROM than `rom`.
+    pub fn from_bytes(bytes: &[u8], rom: Option<&RomInfo>) -> Result<Self, StateError> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(4)? != SAVE_FILE_MAGIC {
+            return Err(StateError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if version > SAVE_FILE_VERSION {
+            return Err(StateError::UnsupportedFileVersion(version));
+        }
+        let saved_sha1 = match reader.u8()? {
+            0 => {
+                reader.take(20)?;
+                None
+            }
+            1 => Some(reader.take(20)?),
+            _ => return Err(StateError::Corrupt("invalid ROM flag")),
+        };
+        if saved_sha1 != rom.map(|info| &info.sha1[..]) {
+            return Err(StateError::RomMismatch);
+        }
+
+        let body = zstd::decode_all(&bytes[reader.pos..]).map_err(|_| StateError::Decompression)?;
+        Self::decode(&body)
+    }
+
+    /// Decode a state previously produced by [SaveState::encode].
+    pub fn decode(bytes: &[u8]) -> Result<Self, StateError> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        let version = reader.u8()?;
+        if version == 0 || version > STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let program_counter = reader.u16()?;
+        let ram_size = if version >= 4 {
+            reader.u32()? as usize
+        } else {
+            RAM_SIZE
+        };
+        if ram_size > MAX_RAM_SIZE {
+            return Err(StateError::Corrupt("RAM too large"));
+        }
+        let ram = reader.take(ram_size)?.to_vec();
+        let mut display = Display::new();
+        let num_planes = if version >= 3 { NUM_PLANES } else { 1 };
+        for n in 0..num_planes {
+            let plane = display.plane_mut(n).unwrap();
+            for (chunk, byte) in plane.chunks_mut(8).zip(reader.take(PLANE_SIZE / 8)?) {
+                unpack_bits(*byte, chunk);
+            }
+        }
+        if version >= 3 {
+            display.select_planes(reader.u8()?);
+        }
+        let mut v_registers = [0; NUM_REGISTERS];
+        v_registers.copy_from_slice(reader.take(NUM_REGISTERS)?);
+        let i_register = reader.u16()?;
+        let stack_pointer = reader.u16()?;
+        let stack_depth = if version >= 4 {
+            reader.u16()? as usize
+        } else {
+            STACK_SIZE
+        };
+        let stack = (0..stack_depth)
+            .map(|_| reader.u16())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut keys = [false; NUM_KEYS];
+        for (chunk, byte) in keys.chunks_mut(8).zip(reader.take(NUM_KEYS / 8)?) {
+            unpack_bits(*byte, chunk);
+        }
+        let delay_timer = reader.u8()?;
+        let sound_timer = reader.u8()?;
+        let rom_info = match reader.u8()? {
+            0 => None,
+            1 => {
+                let size = reader.u32()? as usize;
+                let load_address = reader.u16()?;
+                let mut sha1 = [0; 20];
+                sha1.copy_from_slice(reader.take(20)?);
+                Some(RomInfo {
+                    size,
+                    load_address,
+                    sha1,
+                })
+            }
+            _ => return Err(StateError::Corrupt("invalid ROM info flag")),
+        };
+        let execution_state = if version >= 2 {
+            reader.execution_state()?
+        } else {
+            EmulatorState::Running
+        };
+        let (rng_seed, random_draws) = if version >= 5 {
+            (reader.u64()?, reader.u64()?)
+        } else {
+            (0, 0)
+        };
+
+        if reader.pos != bytes.len() {
+            return Err(StateError::Corrupt("trailing bytes"));
+        }
+
+        let state = Self {
+            program_counter,
+            ram,
+            display,
+            v_registers,
+            i_register,
+            stack_pointer,
+            stack,
+            keys,
+            delay_timer,
+            sound_timer,
+            rom_info,
+            execution_state,
+            rng_seed,
+            random_draws,
+        };
+        state.validate()?;
+        Ok(state)
+    }
+
+    /// Check the state can be loaded: RAM holds the fontset and fits 16-bit addresses, and the
+    /// stack pointer lies within a non-empty stack.
+    pub fn validate(&self) -> Result<(), StateError> {
+        if self.ram.len() < FONTSET_SIZE {
+            return Err(StateError::Corrupt("RAM too small"));
+        }
+        if self.ram.len() > MAX_RAM_SIZE {
+            return Err(StateError::Corrupt("RAM too large"));
+        }
+        if self.stack.is_empty() {
+            return Err(StateError::Corrupt("empty stack"));
+        }
+        if self.stack_pointer as usize > self.stack.len() {
+            return Err(StateError::Corrupt("stack pointer out of range"));
+        }
+        if let EmulatorState::WaitingForKey { x } = self.execution_state {
+            if x as usize >= NUM_REGISTERS {
+                return Err(StateError::Corrupt("key wait register out of range"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Error decoding a save state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// Data ended before the state was fully read.
+    UnexpectedEof,
+    /// State was encoded with an unsupported version.
+    UnsupportedVersion(u8),
+    /// State contents are invalid.
+    Corrupt(&'static str),
+    /// Data is not a save file.
+    BadMagic,
+    /// Save file was written with a newer, unsupported container version.
+    UnsupportedFileVersion(u16),
+    /// Save file was made with a different ROM than the one loaded.
+    RomMismatch,
+    /// Save file body could not be decompressed.
+    Decompression,
+}
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "unexpected end of save state"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported save state version: {}", v),
+            Self::Corrupt(reason) => write!(f, "corrupt save state: {}", reason),
+            Self::BadMagic => write!(f, "not a save state file"),
+            Self::UnsupportedFileVersion(v) => {
+                write!(f, "unsupported save state file version: {}", v)
+            }
+            Self::RomMismatch => write!(f, "save state was made with a different ROM"),
+            Self::Decompression => write!(f, "unable to decompress save state"),
+        }
+    }
+}
+impl Error for StateError {}
+
+/// Pack up to 8 bools into a byte, most significant bit first.
+pub(crate) fn pack_bits(bits: &[bool]) -> u8 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |byte, (i, bit)| byte | ((*bit as u8) << (7 - i)))
+}
+
+/// Unpack a byte into up to 8 bools, most significant bit first.
+pub(crate) fn unpack_bits(byte: u8, bits: &mut [bool]) {
+    for (i, bit) in bits.iter_mut().enumerate() {
+        *bit = (byte >> (7 - i)) & 1 == 1;
+    }
+}
+
+/// Append the execution state to `bytes`.
+fn encode_execution_state(state: &EmulatorState, bytes: &mut Vec<u8>) {
+    match *state {
+        EmulatorState::Running => bytes.push(0),
+        EmulatorState::WaitingForKey { x } => bytes.extend_from_slice(&[1, x]),
+        EmulatorState::Halted(HaltReason::InfiniteLoop(addr)) => {
+            bytes.extend_from_slice(&[2, 4]);
+            bytes.extend_from_slice(&addr.to_be_bytes());
+        }
+        EmulatorState::Halted(HaltReason::Error(e)) => {
+            bytes.push(2);
+            match e {
+                Chip8Error::StackOverflow => bytes.push(0),
+                Chip8Error::StackUnderflow => bytes.push(1),
+                Chip8Error::RamOutOfBounds(addr) => {
+                    bytes.push(2);
+                    bytes.extend_from_slice(&(addr as u32).to_be_bytes());
+                }
+                Chip8Error::InvalidOpcode(op) => {
+                    bytes.push(3);
+                    bytes.extend_from_slice(&op.to_be_bytes());
+                }
+                Chip8Error::InvalidKey(key) => {
+                    bytes.push(5);
+                    bytes.extend_from_slice(&(key as u32).to_be_bytes());
+                }
+                Chip8Error::InvalidRegister(x) => bytes.extend_from_slice(&[6, x]),
+            }
+        }
+        EmulatorState::Exited => bytes.push(3),
+    }
+}
+
+/// Cursor over a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        let slice = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or(StateError::UnexpectedEof)?;
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, StateError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, StateError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    fn execution_state(&mut self) -> Result<EmulatorState, StateError> {
+        let state = match self.u8()? {
+            0 => EmulatorState::Running,
+            1 => EmulatorState::WaitingForKey {
+                x: self.u8()? & 0xF,
+            },
+            2 => {
+                let reason = match self.u8()? {
+                    0 => HaltReason::Error(Chip8Error::StackOverflow),
+                    1 => HaltReason::Error(Chip8Error::StackUnderflow),
+                    2 => HaltReason::Error(Chip8Error::RamOutOfBounds(self.u32()? as usize)),
+                    3 => HaltReason::Error(Chip8Error::InvalidOpcode(self.u16()?)),
+                    4 => HaltReason::InfiniteLoop(self.u16()?),
+                    5 => HaltReason::Error(Chip8Error::InvalidKey(self.u32()? as usize)),
+                    6 => HaltReason::Error(Chip8Error::InvalidRegister(self.u8()?)),
+                    _ => return Err(StateError::Corrupt("invalid halt reason")),
+                };
+                EmulatorState::Halted(reason)
+            }
+            3 => EmulatorState::Exited,
+            _ => return Err(StateError::Corrupt("invalid execution state")),
+        };
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    #[test]
+    fn test_save_load_state() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&[0x60, 0x05, 0x70, 0x01, 0xD0, 0x05]).unwrap();
+        emu.tick().unwrap();
+        emu.keypress(3, true).unwrap();
+        emu.delay_timer = 30;
+        let state = emu.save_state();
+
+        emu.tick().unwrap();
+        emu.tick().unwrap();
+        emu.keypress(3, false).unwrap();
+        assert_ne!(emu.save_state(), state);
+
+        emu.load_state(&state).unwrap();
+        assert_eq!(emu.save_state(), state);
+        assert_eq!(emu.v(0_usize), 5);
+        assert_eq!(emu.program_counter, 0x202);
+
+        // The random number generator picks up where it was
+        let mut random = Emulator::new();
+        random.load_rom(&[0xC0, 0xFF, 0x12, 0x00]).unwrap();
+        let before = random.save_state();
+        random.tick().unwrap();
+        let drawn = random.v(0_usize);
+        random.load_state(&before).unwrap();
+        random.tick().unwrap();
+        assert_eq!(random.v(0_usize), drawn);
+
+        // Inconsistent states are refused, leaving the machine as it was
+        let bad = SaveState {
+            stack_pointer: 200,
+            ..state.clone()
+        };
+        assert_eq!(
+            emu.load_state(&bad),
+            Err(StateError::Corrupt("stack pointer out of range"))
+        );
+        let bad = SaveState {
+            ram: vec![],
+            ..state.clone()
+        };
+        assert_eq!(
+            emu.load_state(&bad),
+            Err(StateError::Corrupt("RAM too small"))
+        );
+        let bad = SaveState {
+            execution_state: EmulatorState::WaitingForKey { x: 200 },
+            ..state.clone()
+        };
+        assert_eq!(
+            emu.load_state(&bad),
+            Err(StateError::Corrupt("key wait register out of range"))
+        );
+        assert_eq!(emu.save_state(), state);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&[0xA0, 0x00, 0xD0, 0x05, 0x2F, 0x00]).unwrap();
+        for _ in 0..3 {
+            emu.tick().unwrap();
+        }
+        emu.keypress(0xA, true).unwrap();
+        let state = emu.save_state();
+
+        let bytes = state.encode();
+        assert_eq!(SaveState::decode(&bytes), Ok(state.clone()));
+
+        assert_eq!(
+            SaveState::decode(&bytes[..bytes.len() - 1]),
+            Err(StateError::UnexpectedEof)
+        );
+        let mut bad_version = bytes.clone();
+        bad_version[0] = 0xFF;
+        assert_eq!(
+            SaveState::decode(&bad_version),
+            Err(StateError::UnsupportedVersion(0xFF))
+        );
+
+        // Version 4 states lack the random number generator's position.
+        let mut v4 = bytes.clone();
+        v4[0] = 4;
+        v4.truncate(v4.len() - 16);
+        let state = SaveState {
+            rng_seed: 0,
+            random_draws: 0,
+            ..state
+        };
+        assert_eq!(SaveState::decode(&v4), Ok(state.clone()));
+
+        // Version 3 states lack the RAM size and stack depth.
+        let mut v3 = v4.clone();
+        v3[0] = 3;
+        let stack_depth =
+            1 + 2 + 4 + RAM_SIZE + NUM_PLANES * PLANE_SIZE / 8 + 1 + NUM_REGISTERS + 4;
+        v3.drain(stack_depth..stack_depth + 2);
+        v3.drain(3..7);
+        assert_eq!(SaveState::decode(&v3), Ok(state.clone()));
+
+        // Version 2 states only have the first display plane.
+        let mut v2 = v3.clone();
+        v2[0] = 2;
+        let second_plane = 1 + 2 + RAM_SIZE + PLANE_SIZE / 8;
+        v2.drain(second_plane..second_plane + PLANE_SIZE / 8 + 1);
+        assert_eq!(SaveState::decode(&v2), Ok(state.clone()));
+
+        // Version 1 states also lack the execution state.
+        let mut v1 = v2.clone();
+        v1[0] = 1;
+        v1.pop();
+        assert_eq!(SaveState::decode(&v1), Ok(state));
+
+        let mut multi = Emulator::new();
+        multi.display.select_planes(0b11);
+        multi.load_rom(&[0xD0, 0x05]).unwrap();
+        multi.tick().unwrap();
+        let planes = multi.save_state();
+        assert_eq!(SaveState::decode(&planes.encode()), Ok(planes));
+
+        emu.load_rom(&[0xF5, 0x0A]).unwrap();
+        emu.program_counter = 0x200;
+        emu.keypress(0xA, false).unwrap();
+        emu.tick().unwrap();
+        let waiting = emu.save_state();
+        assert_eq!(
+            waiting.execution_state,
+            EmulatorState::WaitingForKey { x: 5 }
+        );
+        assert_eq!(SaveState::decode(&waiting.encode()), Ok(waiting));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&[0x60, 0x2A, 0xA2, 0x00, 0xD0, 0x05]).unwrap();
+        for _ in 0..3 {
+            emu.tick().unwrap();
+        }
+        let state = emu.save_state();
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<SaveState>(&json).unwrap(), state);
+
+        let restored: Emulator =
+            serde_json::from_str(&serde_json::to_string(&emu).unwrap()).unwrap();
+        assert_eq!(restored.save_state(), state);
+
+        // Malformed input is an error rather than an emulator that panics later
+        let mut value = serde_json::to_value(&emu).unwrap();
+        value["stack_pointer"] = 200.into();
+        assert!(serde_json::from_value::<Emulator>(value).is_err());
+        let mut value = serde_json::to_value(&emu).unwrap();
+        value["ram"] = serde_json::json!([]);
+        assert!(serde_json::from_value::<Emulator>(value).is_err());
+        let mut value = serde_json::to_value(&emu).unwrap();
+        value["execution_state"] = serde_json::json!({ "WaitingForKey": { "x": 200 } });
+        let error = serde_json::from_value::<Emulator>(value).err().unwrap();
+        assert!(error.to_string().contains("key wait register out of range"));
+    }
+
+    #[test]
+    fn test_to_from_bytes() {
+        let mut emu = Emulator::new();
+        let info = emu.load_rom(&[0x60, 0x2A, 0xF0, 0x29]).unwrap();
+        emu.tick().unwrap();
+        let state = emu.save_state();
+
+        let bytes = state.to_bytes();
+        assert_eq!(&bytes[..4], &SAVE_FILE_MAGIC);
+        // Most of RAM is zeroes, so the file should be far smaller than RAM.
+        assert!(bytes.len() < RAM_SIZE / 4);
+        assert_eq!(SaveState::from_bytes(&bytes, Some(&info)), Ok(state));
+
+        // Refuse to load against a different ROM, or no ROM at all.
+        let other = RomInfo::new(&[0x00, 0xE0], 0x200);
+        assert_eq!(
+            SaveState::from_bytes(&bytes, Some(&other)),
+            Err(StateError::RomMismatch)
+        );
+        assert_eq!(
+            SaveState::from_bytes(&bytes, None),
+            Err(StateError::RomMismatch)
+        );
+
+        assert_eq!(
+            SaveState::from_bytes(b"nope", None),
+            Err(StateError::BadMagic)
+        );
+        let mut corrupt = bytes.clone();
+        corrupt.truncate(bytes.len() - 4);
+        assert_eq!(
+            SaveState::from_bytes(&corrupt, Some(&info)),
+            Err(StateError::Decompression)
+        );
+    }
+}
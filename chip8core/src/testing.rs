@@ -0,0 +1,52 @@
+//! Helpers for golden-screen tests. The snapshots are plain strings, so they can be checked in
+//! by hand or passed to snapshot-testing crates like `insta`.
+use super::{emulator::DISPLAY_WIDTH, harness::HarnessError, Emulator, TestRom};
+
+/// Run the ROM for `frames` frames with default settings and return the emulator.
+pub fn run_rom(rom: &[u8], frames: u64) -> Result<Emulator, HarnessError> {
+    TestRom::new("rom", rom).frames(frames).run()
+}
+
+/// Snapshot the display as text: a header with the display hash, then one line per row framed by
+/// `|` so editors do not strip the trailing unlit pixels.
+pub fn display_snapshot(emu: &Emulator) -> String {
+    let border = format!("+{}+", "-".repeat(DISPLAY_WIDTH));
+    let mut snapshot = format!("hash: {:016x}\n{}\n", emu.display_hash(), border);
+    for row in emu.display_to_string().lines() {
+        snapshot.push('|');
+        snapshot.push_str(row);
+        snapshot.push_str("|\n");
+    }
+    snapshot.push_str(&border);
+    snapshot.push('\n');
+    snapshot
+}
+
+/// Run the ROM for `frames` frames and snapshot the display. See [display_snapshot].
+pub fn snapshot_rom(rom: &[u8], frames: u64) -> Result<String, HarnessError> {
+    run_rom(rom, frames).map(|emu| display_snapshot(&emu))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::emulator::DISPLAY_HEIGHT;
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        // 0x200: LD V0, 0; LD F, V0; DRW V0, V0, 5; JP 0x206
+        let rom = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x06];
+        let snapshot = snapshot_rom(&rom, 1).unwrap();
+        let lines: Vec<_> = snapshot.lines().collect();
+        assert_eq!(lines.len(), DISPLAY_HEIGHT + 3);
+        assert!(lines[0].starts_with("hash: "));
+        assert!(lines[1].starts_with("+--"));
+        let pad = " ".repeat(DISPLAY_WIDTH - 4);
+        assert_eq!(lines[2], format!("|████{}|", pad));
+        assert_eq!(lines[3], format!("|█  █{}|", pad));
+        assert_eq!(snapshot, snapshot_rom(&rom, 1).unwrap());
+    }
+}
@@ -0,0 +1,177 @@
+//! Reverse stepping by restoring keyframes and re-running forward.
+use std::collections::VecDeque;
+
+use super::{state::SaveState, Chip8Error, Emulator};
+
+/// Snapshot taken at a known instruction count.
+#[derive(Debug, Clone)]
+struct Keyframe {
+    cycles: u64,
+    frames: u64,
+    state: SaveState,
+}
+
+/// Execution history which lets a debugger step backwards one instruction at a time.
+///
+/// Keyframes are taken every `interval` instructions. Stepping back restores the nearest keyframe
+/// at or before the target and re-runs forward to it, replaying timer ticks where they originally
+/// happened. Replays are exact as long as key presses are not changed in between.
+#[derive(Debug, Clone)]
+pub struct TimeTravel {
+    keyframes: VecDeque<Keyframe>,
+    /// Instruction counts at which the timers ticked, with the frame count after each tick.
+    timer_ticks: VecDeque<(u64, u64)>,
+    capacity: usize,
+    interval: u64,
+    last_frames: Option<u64>,
+}
+impl TimeTravel {
+    /// Create a history holding up to `capacity` keyframes, taken every `interval` instructions.
+    pub fn new(capacity: usize, interval: u64) -> Self {
+        Self {
+            keyframes: VecDeque::with_capacity(capacity),
+            timer_ticks: VecDeque::new(),
+            capacity: capacity.max(1),
+            interval: interval.max(1),
+            last_frames: None,
+        }
+    }
+
+    /// Record the current point in execution. Call after every instruction and timer tick, e.g.
+    /// after each [Emulator::step] or [Emulator::run_frame].
+    pub fn record(&mut self, emu: &Emulator) {
+        let (cycles, frames) = (emu.cycles(), emu.frames());
+        if let Some(last_frames) = self.last_frames {
+            for frame in last_frames + 1..=frames {
+                self.timer_ticks.push_back((cycles, frame));
+            }
+        }
+        self.last_frames = Some(frames);
+
+        let due = self
+            .keyframes
+            .back()
+            .is_none_or(|last| cycles >= last.cycles + self.interval);
+        if !due {
+            return;
+        }
+        if self.keyframes.len() == self.capacity {
+            self.keyframes.pop_front();
+            let oldest = self.keyframes.front().map_or(frames, |k| k.frames);
+            while self.timer_ticks.front().is_some_and(|(_, f)| *f <= oldest) {
+                self.timer_ticks.pop_front();
+            }
+        }
+        self.keyframes.push_back(Keyframe {
+            cycles,
+            frames,
+            state: emu.save_state(),
+        });
+    }
+
+    /// Go back one instruction. Returns `false` if it is further back than the oldest keyframe.
+    pub fn step_back(&mut self, emu: &mut Emulator) -> Result<bool, Chip8Error> {
+        match emu.cycles().checked_sub(1) {
+            Some(target) => self.seek(emu, target),
+            None => Ok(false),
+        }
+    }
+
+    /// Go back to the point where `cycles` instructions had executed, discarding the history
+    /// after it. Returns `false`, leaving the emulator untouched, if the point is in the future or
+    /// further back than the oldest keyframe.
+    pub fn seek(&mut self, emu: &mut Emulator, cycles: u64) -> Result<bool, Chip8Error> {
+        if cycles > emu.cycles() {
+            return Ok(false);
+        }
+        let Some(idx) = self.keyframes.iter().rposition(|k| k.cycles <= cycles) else {
+            return Ok(false);
+        };
+        self.keyframes.truncate(idx + 1);
+        let keyframe = &self.keyframes[idx];
+        emu.restore_state(&keyframe.state);
+        emu.set_counters(keyframe.frames, keyframe.cycles);
+
+        let mut ticks = self
+            .timer_ticks
+            .iter()
+            .filter(|(_, frame)| *frame > keyframe.frames)
+            .peekable();
+        // Ticks are bounded in case the replay gets stuck, e.g. waiting for a key.
+        let mut budget = (cycles - keyframe.cycles) * 2 + 1;
+        while emu.cycles() < cycles && budget > 0 {
+            emu.step()?;
+            budget -= 1;
+            while ticks.next_if(|(at, _)| *at <= emu.cycles()).is_some() {
+                emu.tick_timers();
+            }
+        }
+        while ticks.next_if(|(at, _)| *at <= cycles).is_some() {
+            emu.tick_timers();
+        }
+
+        let frames = emu.frames();
+        self.timer_ticks.retain(|(_, frame)| *frame <= frames);
+        self.last_frames = Some(frames);
+        Ok(true)
+    }
+
+    /// Number of keyframes currently held.
+    pub fn len(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    /// Whether there are no keyframes to go back to.
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Discard the history.
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+        self.timer_ticks.clear();
+        self.last_frames = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_step_back() {
+        let mut emu = Emulator::with_seed(1);
+        // 0x200: LD V0, 60; LD DT, V0; RND V1, 0xFF; ADD V2, V1; JP 0x204
+        emu.load_rom(&[0x60, 0x3C, 0xF0, 0x15, 0xC1, 0xFF, 0x82, 0x14, 0x12, 0x04])
+            .unwrap();
+        let mut history = TimeTravel::new(16, 5);
+        history.record(&emu);
+
+        let mut states = vec![emu.save_state()];
+        for _ in 0..10 {
+            emu.run_frame(3).unwrap();
+            history.record(&emu);
+            states.push(emu.save_state());
+        }
+        assert_eq!(emu.cycles(), 30);
+
+        // Walk back over instructions, timer ticks and random draws alike.
+        assert!(history.step_back(&mut emu).unwrap());
+        assert_eq!(emu.cycles(), 29);
+        assert!(history.seek(&mut emu, 27).unwrap());
+        assert_eq!(emu.save_state(), states[9]);
+        assert!(history.seek(&mut emu, 12).unwrap());
+        assert_eq!(emu.save_state(), states[4]);
+        assert_eq!(emu.frames(), 4);
+
+        // Running forward again reproduces the original history.
+        for state in &states[5..] {
+            emu.run_frame(3).unwrap();
+            history.record(&emu);
+            assert_eq!(&emu.save_state(), state);
+        }
+        assert!(!history.seek(&mut emu, 31).unwrap());
+    }
+}
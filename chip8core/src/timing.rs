@@ -0,0 +1,131 @@
+//! Wall-clock driven execution.
+use std::time::Duration;
+
+/// Frequency the delay and sound timers count down at.
+pub const TIMER_HZ: u32 = 60;
+
+/// Most frames [Pacer::advance] asks for at once. Time beyond that is dropped.
+pub const MAX_CATCH_UP: u64 = 4;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Converts elapsed time into instructions and timer ticks, carrying fractional remainders over
+/// to the next call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clock {
+    cycle_carry: u128,
+    timer_carry: u128,
+}
+impl Clock {
+    /// Create a clock with no time carried over.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance the clock, returning how many instructions and timer ticks are owed.
+    pub fn advance(&mut self, delta: Duration, clock_hz: u32) -> (u64, u64) {
+        let nanos = delta.as_nanos();
+
+        let cycles = nanos * clock_hz as u128 + self.cycle_carry;
+        self.cycle_carry = cycles % NANOS_PER_SEC;
+
+        let timer_ticks = nanos * TIMER_HZ as u128 + self.timer_carry;
+        self.timer_carry = timer_ticks % NANOS_PER_SEC;
+
+        (
+            (cycles / NANOS_PER_SEC) as u64,
+            (timer_ticks / NANOS_PER_SEC) as u64,
+        )
+    }
+
+    /// Discard any carried-over time.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Fixed-timestep accumulator turning elapsed wall-clock time into whole 60Hz frames, so
+/// emulation runs at the same speed whatever the display's refresh rate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pacer {
+    carry: u128,
+}
+impl Pacer {
+    /// Create a pacer with no time accumulated.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accumulate elapsed time, returning how many frames are due. After falling more than
+    /// [MAX_CATCH_UP] frames behind, e.g. while suspended, the rest are skipped instead of run all
+    /// at once.
+    pub fn advance(&mut self, delta: Duration) -> u64 {
+        self.carry += delta.as_nanos() * TIMER_HZ as u128;
+        let frames = (self.carry / NANOS_PER_SEC) as u64;
+        self.carry %= NANOS_PER_SEC;
+        frames.min(MAX_CATCH_UP)
+    }
+
+    /// Time left until the next frame is due.
+    pub fn until_next(&self) -> Duration {
+        let nanos = (NANOS_PER_SEC - self.carry).div_ceil(TIMER_HZ as u128);
+        Duration::from_nanos(nanos as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    #[test]
+    fn test_clock_carries_remainder() {
+        let mut clock = Clock::new();
+        let mut total = (0, 0);
+        for _ in 0..10 {
+            let (cycles, ticks) = clock.advance(Duration::from_millis(1), 500);
+            total.0 += cycles;
+            total.1 += ticks;
+        }
+        assert_eq!(total, (5, 0));
+        assert_eq!(clock.advance(Duration::from_millis(7), 500), (3, 1));
+    }
+
+    #[test]
+    fn test_run_for() {
+        let mut emu = Emulator::builder().clock_hz(400).build().unwrap();
+        // ADD V0, 1; JP 0x200
+        emu.load_rom(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        emu.delay_timer = 100;
+
+        emu.run_for(Duration::from_millis(500)).unwrap();
+        assert_eq!(emu.v(0_usize), 100);
+        assert_eq!(emu.delay_timer, 70);
+
+        for _ in 0..4 {
+            emu.run_for(Duration::from_micros(1250)).unwrap();
+        }
+        assert_eq!(emu.v(0_usize), 101);
+        assert_eq!(emu.delay_timer, 70);
+    }
+
+    #[test]
+    fn test_pacer() {
+        let mut pacer = Pacer::new();
+        // Exactly 60 frames a second, however the time is split up.
+        let frames: u64 = (0..144)
+            .map(|_| pacer.advance(Duration::from_secs(1) / 144))
+            .sum();
+        assert_eq!(frames, 59);
+        assert_eq!(pacer.advance(Duration::from_micros(1)), 1);
+
+        let mut pacer = Pacer::new();
+        assert_eq!(pacer.advance(Duration::from_millis(10)), 0);
+        assert_eq!(pacer.until_next(), Duration::from_nanos(6_666_667));
+
+        assert_eq!(pacer.advance(Duration::from_secs(10)), MAX_CATCH_UP);
+    }
+}
@@ -0,0 +1,286 @@
+//! Execution trace logging.
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{self, Write},
+};
+
+use super::{emulator::NUM_REGISTERS, opcodes::Instruction};
+
+/// A single executed instruction and the machine state around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// Address the instruction was fetched from.
+    pub pc: u16,
+    /// Raw opcode.
+    pub opcode: u16,
+    /// Decoded instruction.
+    pub instruction: Instruction,
+    /// V registers before execution.
+    pub previous_v_registers: [u8; NUM_REGISTERS],
+    /// V registers after execution.
+    pub v_registers: [u8; NUM_REGISTERS],
+    /// I register before execution.
+    pub previous_i_register: u16,
+    /// I register after execution.
+    pub i_register: u16,
+    /// Delay timer after execution.
+    pub delay_timer: u8,
+    /// Sound timer after execution.
+    pub sound_timer: u8,
+}
+impl TraceEntry {
+    /// Encode the entry as a single-line JSON object, e.g.
+    /// `{"pc":512,"op":24581,"mnemonic":"LD V0, 0x05","changed":{"V0":5},"dt":0,"st":0}`.
+    ///
+    /// `changed` holds the new value of every register the instruction modified.
+    pub fn to_json(&self) -> String {
+        let mut changed = Vec::new();
+        for (idx, (old, new)) in self
+            .previous_v_registers
+            .iter()
+            .zip(self.v_registers)
+            .enumerate()
+        {
+            if *old != new {
+                changed.push(format!("\"V{:X}\":{}", idx, new));
+            }
+        }
+        if self.previous_i_register != self.i_register {
+            changed.push(format!("\"I\":{}", self.i_register));
+        }
+
+        format!(
+            "{{\"pc\":{},\"op\":{},\"mnemonic\":\"{}\",\"changed\":{{{}}},\"dt\":{},\"st\":{}}}",
+            self.pc,
+            self.opcode,
+            self.instruction,
+            changed.join(","),
+            self.delay_timer,
+            self.sound_timer
+        )
+    }
+}
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04X} {:04X} {:<18}",
+            self.pc,
+            self.opcode,
+            self.instruction.to_string()
+        )?;
+        for v in self.v_registers {
+            write!(f, " {:02X}", v)?;
+        }
+        write!(f, " I={:04X}", self.i_register)
+    }
+}
+
+/// Where trace entries go.
+enum TraceSink {
+    /// Keep the most recent entries in memory.
+    Buffer {
+        entries: VecDeque<TraceEntry>,
+        capacity: usize,
+    },
+    /// Write each entry as a line of text.
+    Writer(Box<dyn Write + Send>),
+    /// Write each entry as a line of JSON.
+    JsonLines(Box<dyn Write + Send>),
+}
+
+/// Records every executed instruction. Attach to an emulator with [crate::Emulator::set_tracer].
+pub struct Tracer {
+    sink: TraceSink,
+    enabled: bool,
+    error: Option<io::Error>,
+}
+impl Tracer {
+    /// Create a tracer keeping the most recent `capacity` entries in memory.
+    pub fn buffered(capacity: usize) -> Self {
+        Self::new(TraceSink::Buffer {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        })
+    }
+
+    /// Create a tracer writing one line of text per entry to the writer.
+    pub fn to_writer<W: Write + Send + 'static>(writer: W) -> Self {
+        Self::new(TraceSink::Writer(Box::new(writer)))
+    }
+
+    /// Create a tracer writing one JSON object per line to the writer. See [TraceEntry::to_json].
+    pub fn to_json_lines<W: Write + Send + 'static>(writer: W) -> Self {
+        Self::new(TraceSink::JsonLines(Box::new(writer)))
+    }
+
+    fn new(sink: TraceSink) -> Self {
+        Self {
+            sink,
+            enabled: true,
+            error: None,
+        }
+    }
+
+    /// Whether entries are being recorded.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Start or stop recording entries.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Record an entry.
+    pub fn record(&mut self, entry: TraceEntry) {
+        if !self.enabled {
+            return;
+        }
+        match &mut self.sink {
+            TraceSink::Buffer { entries, capacity } => {
+                if *capacity == 0 {
+                    return;
+                }
+                if entries.len() == *capacity {
+                    entries.pop_front();
+                }
+                entries.push_back(entry);
+            }
+            TraceSink::Writer(writer) => {
+                let result = writeln!(writer, "{}", entry);
+                self.handle_result(result);
+            }
+            TraceSink::JsonLines(writer) => {
+                let result = writeln!(writer, "{}", entry.to_json());
+                self.handle_result(result);
+            }
+        }
+    }
+
+    /// Stop tracing on the first write error rather than failing emulation.
+    fn handle_result(&mut self, result: io::Result<()>) {
+        if let Err(e) = result {
+            self.error = Some(e);
+            self.enabled = false;
+        }
+    }
+
+    /// Iterate over buffered entries, oldest first. Empty when writing to a writer.
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> + '_ {
+        let entries = match &self.sink {
+            TraceSink::Buffer { entries, .. } => Some(entries.iter()),
+            TraceSink::Writer(_) | TraceSink::JsonLines(_) => None,
+        };
+        entries.into_iter().flatten()
+    }
+
+    /// Remove all buffered entries.
+    pub fn clear(&mut self) {
+        if let TraceSink::Buffer { entries, .. } = &mut self.sink {
+            entries.clear();
+        }
+    }
+
+    /// Flush the writer, if any.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            TraceSink::Buffer { .. } => Ok(()),
+            TraceSink::Writer(writer) | TraceSink::JsonLines(writer) => writer.flush(),
+        }
+    }
+
+    /// Take the error which stopped tracing, if any.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use pretty_assertions::assert_eq;
+
+    use crate::Emulator;
+
+    use super::*;
+
+    const ROM: [u8; 8] = [
+        0x60, 0x05, // LD V0, 5
+        0xA3, 0x00, // LD I, 0x300
+        0xF0, 0x15, // LD DT, V0
+        0x12, 0x00, // JP 0x200
+    ];
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_buffered() {
+        let mut emu = Emulator::new();
+        emu.load_rom(&ROM).unwrap();
+        emu.set_tracer(Some(Tracer::buffered(2)));
+        for _ in 0..4 {
+            emu.tick().unwrap();
+        }
+
+        let entries: Vec<_> = emu.tracer().unwrap().entries().copied().collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].pc, 0x204);
+        assert_eq!(entries[0].instruction, Instruction::LdDtVx { x: 0 });
+        assert_eq!(entries[0].i_register, 0x300);
+        assert_eq!(entries[1].opcode, 0x1200);
+        assert_eq!(entries[1].v_registers[0], 5);
+
+        emu.tracer_mut().unwrap().set_enabled(false);
+        emu.tick().unwrap();
+        assert_eq!(emu.tracer().unwrap().entries().last(), Some(&entries[1]));
+    }
+
+    #[test]
+    fn test_writer() {
+        let buf = SharedBuf::default();
+        let mut emu = Emulator::new();
+        emu.load_rom(&ROM).unwrap();
+        emu.set_tracer(Some(Tracer::to_writer(buf.clone())));
+        emu.tick().unwrap();
+
+        let text = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            text,
+            "0200 6005 LD V0, 0x05        05 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 I=0000\n"
+        );
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let buf = SharedBuf::default();
+        let mut emu = Emulator::new();
+        emu.load_rom(&ROM).unwrap();
+        emu.set_tracer(Some(Tracer::to_json_lines(buf.clone())));
+        for _ in 0..3 {
+            emu.tick().unwrap();
+        }
+
+        let text = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"pc":512,"op":24581,"mnemonic":"LD V0, 0x05","changed":{"V0":5},"dt":0,"st":0}"#,
+                r#"{"pc":514,"op":41728,"mnemonic":"LD I, 0x300","changed":{"I":768},"dt":0,"st":0}"#,
+                r#"{"pc":516,"op":61461,"mnemonic":"LD DT, V0","changed":{},"dt":5,"st":0}"#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Lockstep comparison of an [Emulator] against a reference implementation.
+use std::fmt;
+
+use super::{opcodes::decode, Chip8Error, Emulator, SaveState, StateDiff};
+
+/// A Chip-8 implementation that can be stepped alongside an [Emulator].
+pub trait Reference {
+    /// Execute one instruction.
+    fn step(&mut self) -> Result<(), Chip8Error>;
+
+    /// Decrement the timers, as at the end of a 60Hz frame.
+    fn tick_timers(&mut self);
+
+    /// Set the pressed keys, bit N being key N.
+    fn set_keys(&mut self, mask: u16);
+
+    /// Snapshot the machine state.
+    fn save_state(&self) -> SaveState;
+}
+impl Reference for Emulator {
+    fn step(&mut self) -> Result<(), Chip8Error> {
+        Emulator::step(self).map(|_| ())
+    }
+
+    fn tick_timers(&mut self) {
+        Emulator::tick_timers(self);
+    }
+
+    fn set_keys(&mut self, mask: u16) {
+        Emulator::set_keys(self, mask);
+    }
+
+    fn save_state(&self) -> SaveState {
+        Emulator::save_state(self)
+    }
+}
+
+/// The first instruction after which the two implementations disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Instructions executed before the divergent one.
+    pub cycle: u64,
+    /// Address of the divergent instruction.
+    pub pc: u16,
+    /// Opcode of the divergent instruction.
+    pub opcode: u16,
+    /// What each implementation returned from the instruction.
+    pub results: (Result<(), Chip8Error>, Result<(), Chip8Error>),
+    /// Differences between the states after the instruction, from the emulator to the reference.
+    pub diff: StateDiff,
+}
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "diverged at cycle {}, {:#05X}: {:04X}",
+            self.cycle, self.pc, self.opcode
+        )?;
+        if let Some(instruction) = decode(self.opcode) {
+            write!(f, " ({})", instruction)?;
+        }
+        writeln!(f)?;
+        if self.results.0 != self.results.1 {
+            writeln!(f, "results: {:?} vs {:?}", self.results.0, self.results.1)?;
+        }
+        write!(f, "{}", self.diff)
+    }
+}
+
+/// Runs an [Emulator] and a [Reference] in lockstep with the same inputs, comparing their states
+/// after every instruction.
+pub struct Lockstep<R> {
+    /// Emulator under test.
+    pub emulator: Emulator,
+    /// Implementation it is compared against.
+    pub reference: R,
+    cycles_per_frame: u32,
+    cycles: u64,
+}
+impl<R: Reference> Lockstep<R> {
+    /// Compare `emulator` against `reference`. Both should already have the same ROM loaded.
+    pub fn new(emulator: Emulator, reference: R, cycles_per_frame: u32) -> Self {
+        Self {
+            emulator,
+            reference,
+            cycles_per_frame,
+            cycles: 0,
+        }
+    }
+
+    /// Return the number of instructions executed so far.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Set the pressed keys on both implementations, bit N being key N.
+    pub fn set_keys(&mut self, mask: u16) {
+        self.emulator.set_keys(mask);
+        self.reference.set_keys(mask);
+    }
+
+    /// Execute one instruction on both, ticking the timers at the end of each frame. Returns the
+    /// divergence if their states no longer match.
+    pub fn step(&mut self) -> Option<Divergence> {
+        let pc = self.emulator.pc();
+        let ram = self.emulator.ram();
+        let opcode = match (ram.get(pc as usize), ram.get(pc as usize + 1)) {
+            (Some(high), Some(low)) => u16::from_be_bytes([*high, *low]),
+            _ => 0,
+        };
+
+        let results = (Reference::step(&mut self.emulator), self.reference.step());
+        self.cycles += 1;
+        if self.cycles_per_frame > 0 && self.cycles.is_multiple_of(self.cycles_per_frame as u64) {
+            Reference::tick_timers(&mut self.emulator);
+            self.reference.tick_timers();
+        }
+
+        let diff = self
+            .emulator
+            .save_state()
+            .diff(&self.reference.save_state());
+        if results.0 != results.1 || !diff.is_empty() {
+            return Some(Divergence {
+                cycle: self.cycles - 1,
+                pc,
+                opcode,
+                results,
+                diff,
+            });
+        }
+        None
+    }
+
+    /// Execute up to `cycles` instructions, stopping at the first divergence.
+    pub fn run(&mut self, cycles: u64) -> Option<Divergence> {
+        (0..cycles).find_map(|_| self.step())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Quirks;
+
+    use super::*;
+
+    #[test]
+    fn test_lockstep() {
+        // 0x200: LD V0, 0x81; LD V1, 0x02; SHR V0, V1; JP 0x206
+        let rom = [0x60, 0x81, 0x61, 0x02, 0x80, 0x16, 0x12, 0x06];
+        let new = |quirks| {
+            let mut emu = Emulator::with_seed(0);
+            emu.set_quirks(quirks);
+            emu.load_rom(&rom).unwrap();
+            emu
+        };
+
+        let mut same = Lockstep::new(new(Quirks::modern()), new(Quirks::modern()), 8);
+        assert_eq!(same.run(100), None);
+        assert_eq!(same.cycles(), 100);
+
+        // Shifting VY instead of VX is caught at the shift.
+        let mut lockstep = Lockstep::new(new(Quirks::modern()), new(Quirks::chip8()), 8);
+        let divergence = lockstep.run(100).unwrap();
+        assert_eq!(divergence.cycle, 2);
+        assert_eq!(divergence.pc, 0x204);
+        assert_eq!(divergence.opcode, 0x8016);
+        let names: Vec<_> = divergence
+            .diff
+            .registers
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["V0", "VF"]);
+        assert!(divergence
+            .to_string()
+            .starts_with("diverged at cycle 2, 0x204: 8016 (SHR V0, V1)\n"));
+    }
+}
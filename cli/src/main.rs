@@ -0,0 +1,313 @@
+//! `chip8emu`: one command line for the emulator's tools, from playing and debugging games in the
+//! terminal to assembling, disassembling and analysing ROMs.
+use std::{
+    env,
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use chip8core::{
+    console,
+    emulator::{DISPLAY_HEIGHT, DISPLAY_WIDTH, START_ADDRESS},
+    *,
+};
+use terminal::Game;
+
+/// Frames `screenshot` runs before saving the display, unless told otherwise.
+pub const DEFAULT_FRAMES: u64 = 60;
+/// Size of a Chip-8 pixel in screenshot pixels, unless told otherwise.
+pub const DEFAULT_SCALE: u32 = 8;
+/// Largest size of a Chip-8 pixel in screenshot pixels, for a 4096x2048 image.
+pub const MAX_SCALE: u32 = 64;
+/// Palette screenshots are taken in, unless told otherwise.
+pub const DEFAULT_PALETTE: &str = "green";
+
+const USAGE: &str = "\
+Usage: chip8emu run [--braille] [--cycles N] path/to/game
+       chip8emu debug [--cycles N] path/to/game
+       chip8emu disasm [--octo] path/to/game
+       chip8emu asm [-o OUT] path/to/source
+       chip8emu analyze path/to/game
+       chip8emu record -o RECORDING [--braille] [--cycles N] path/to/game
+       chip8emu replay [--screenshot PNG] path/to/game RECORDING
+       chip8emu screenshot -o PNG [--frames N] [--cycles N] [--scale N] \
+[--palette NAME] path/to/game";
+
+/// Command-line options of every subcommand, each only taking some.
+#[derive(Default)]
+struct Options {
+    /// Paths given, e.g. the ROM.
+    paths: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    braille: bool,
+    cycles: Option<u32>,
+    /// Whether to decompile to Octo source instead of listing instructions.
+    octo: bool,
+    /// File to save the display to once replayed.
+    screenshot: Option<PathBuf>,
+    frames: Option<u64>,
+    scale: Option<u32>,
+    palette: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let Some(command) = args.next() else {
+        println!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    // Options each subcommand takes, and the paths it needs
+    let (options, paths): (&[&str], usize) = match command.as_str() {
+        "run" => (&["--braille", "--cycles"], 1),
+        "debug" => (&["--cycles"], 1),
+        "disasm" => (&["--octo"], 1),
+        "asm" => (&["-o"], 1),
+        "analyze" => (&[], 1),
+        "record" => (&["-o", "--braille", "--cycles"], 1),
+        "replay" => (&["--screenshot"], 2),
+        "screenshot" => (&["-o", "--frames", "--cycles", "--scale", "--palette"], 1),
+        "help" | "--help" => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            println!("Unknown command {}\n{}", command, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let opts = match parse_args(args, options, paths) {
+        Ok(opts) => opts,
+        Err(e) => {
+            println!("{}\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match command.as_str() {
+        "run" => run(&opts),
+        "debug" => debug(&opts),
+        "disasm" => disasm(&opts),
+        "asm" => assemble(&opts),
+        "analyze" => analyze(&opts),
+        "record" => record(&opts),
+        "replay" => replay(&opts),
+        "screenshot" => screenshot(&opts),
+        _ => unreachable!("unknown commands are rejected above"),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            println!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Parse the options of a subcommand taking `options` and `paths` paths.
+fn parse_args(
+    mut args: impl Iterator<Item = String>,
+    options: &[&str],
+    paths: usize,
+) -> Result<Options, String> {
+    let mut opts = Options::default();
+    while let Some(arg) = args.next() {
+        if arg.starts_with('-') && !options.contains(&arg.as_str()) {
+            return Err(format!("Unknown option {}", arg));
+        }
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "-o" => opts.output = Some(value()?.into()),
+            "--braille" => opts.braille = true,
+            "--octo" => opts.octo = true,
+            "--screenshot" => opts.screenshot = Some(value()?.into()),
+            "--palette" => opts.palette = Some(value()?),
+            "--cycles" => {
+                opts.cycles = Some(value()?.parse().map_err(|e| format!("Bad cycles: {}", e))?)
+            }
+            "--frames" => {
+                opts.frames = Some(value()?.parse().map_err(|e| format!("Bad frames: {}", e))?)
+            }
+            "--scale" => {
+                opts.scale = Some(value()?.parse().map_err(|e| format!("Bad scale: {}", e))?)
+            }
+            _ if opts.paths.len() < paths => opts.paths.push(arg.into()),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+    if opts.paths.len() < paths {
+        return Err("Missing path".to_string());
+    }
+    Ok(opts)
+}
+
+/// Load the game at the first path, at the speed asked for.
+fn load(opts: &Options) -> Result<Game, String> {
+    let mut game = terminal::load(&opts.paths[0])?;
+    if let Some(cycles) = opts.cycles {
+        game.cycles_per_frame = cycles;
+    }
+    Ok(game)
+}
+
+/// Play a game in the terminal.
+fn run(opts: &Options) -> Result<(), String> {
+    terminal::play(load(opts)?, opts.braille, false)?;
+    Ok(())
+}
+
+/// Debug a game in the terminal.
+fn debug(opts: &Options) -> Result<(), String> {
+    terminal::debug(load(opts)?)
+}
+
+/// List a ROM's instructions, or decompile it to Octo source.
+fn disasm(opts: &Options) -> Result<(), String> {
+    let rom = terminal::read(&opts.paths[0])?;
+    if opts.octo {
+        print!("{}", decompile::decompile(&rom));
+        return Ok(());
+    }
+    let mut emu = Emulator::new();
+    emu.load_rom(&rom)
+        .map_err(|e| format!("Unable to load ROM: {}", e))?;
+    let count = rom.len().div_ceil(2) as u16;
+    for line in console::disassemble(&emu, START_ADDRESS, count) {
+        let line = console::ListingLine {
+            current: false,
+            ..line
+        };
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Assemble Chip-8 assembly, or compile Octo source, into a ROM.
+fn assemble(opts: &Options) -> Result<(), String> {
+    let source = &opts.paths[0];
+    if !source
+        .extension()
+        .is_some_and(|ext| ext == "asm" || ext == "o8")
+    {
+        return Err(format!(
+            "{} isn't a .asm or .o8 source file",
+            source.display()
+        ));
+    }
+    let rom = terminal::read(source)?;
+    let out = match &opts.output {
+        Some(out) => out.clone(),
+        None => source.with_extension("ch8"),
+    };
+    fs::write(&out, &rom).map_err(|e| format!("Unable to write {}: {}", out.display(), e))?;
+    println!("Wrote {} bytes to {}", rom.len(), out.display());
+    Ok(())
+}
+
+/// Report what a ROM runs on, which instructions depend on quirks and what looks wrong.
+fn analyze(opts: &Options) -> Result<(), String> {
+    let rom = terminal::read(&opts.paths[0])?;
+    if let Some(entry) = RomInfo::new(&rom, START_ADDRESS).identify() {
+        println!("Title: {}", entry.title);
+    }
+    let analysis = analyze::analyze(&rom, START_ADDRESS);
+    println!("Platform: {}", analysis.platform);
+    println!("Instructions reachable: {}", analysis.code.len());
+    for (quirk, addrs) in &analysis.quirks {
+        let mut addrs = addrs.clone();
+        addrs.sort_unstable();
+        let addrs: Vec<_> = addrs.iter().map(|addr| format!("{:03X}", addr)).collect();
+        println!("Quirk {:?}: {}", quirk, addrs.join(" "));
+    }
+    for problem in &analysis.problems {
+        println!("Problem: {}", problem);
+    }
+    Ok(())
+}
+
+/// Play a game in the terminal, recording the keys pressed.
+fn record(opts: &Options) -> Result<(), String> {
+    let out = opts.output.as_ref().ok_or("Missing -o RECORDING")?;
+    let recording = terminal::play(load(opts)?, opts.braille, true)?;
+    let recording = recording.expect("recording was asked for");
+    fs::write(out, recording.to_bytes())
+        .map_err(|e| format!("Unable to write {}: {}", out.display(), e))?;
+    println!("Recorded {} frames to {}", recording.frames, out.display());
+    Ok(())
+}
+
+/// Replay a recording against its game, printing the state it ends in.
+fn replay(opts: &Options) -> Result<(), String> {
+    let path = &opts.paths[1];
+    let bytes = fs::read(path).map_err(|e| format!("Unable to open {}: {}", path.display(), e))?;
+    let recording = Recording::from_bytes(&bytes)
+        .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+    // Loaded as when recording, so the quirks match
+    let mut emu = load(opts)?.emu;
+    let mut player = Player::start(&mut emu, recording).map_err(|e| e.to_string())?;
+    while player
+        .run_frame(&mut emu)
+        .map_err(|e| format!("Emulation error: {}", e))?
+    {}
+    println!("Replayed {} frames", player.frame());
+    println!("{}", console::registers(&emu));
+    if let Some(out) = &opts.screenshot {
+        save_png(&emu, out, DEFAULT_SCALE, DEFAULT_PALETTE)?;
+    }
+    Ok(())
+}
+
+/// Run a game without input for a while, then save its display.
+fn screenshot(opts: &Options) -> Result<(), String> {
+    let out = opts.output.as_ref().ok_or("Missing -o PNG")?;
+    let Game {
+        mut emu,
+        cycles_per_frame,
+        ..
+    } = load(opts)?;
+    for _ in 0..opts.frames.unwrap_or(DEFAULT_FRAMES) {
+        if emu.state() == EmulatorState::Exited {
+            break;
+        }
+        emu.run_frame(cycles_per_frame)
+            .map_err(|e| format!("Emulation error: {}", e))?;
+    }
+    let palette = opts.palette.as_deref().unwrap_or(DEFAULT_PALETTE);
+    save_png(&emu, out, opts.scale.unwrap_or(DEFAULT_SCALE), palette)
+}
+
+/// Save `emu`'s display to a PNG file at `path`, each Chip-8 pixel `scale` pixels across, in the
+/// named palette.
+fn save_png(emu: &Emulator, path: &Path, scale: u32, palette: &str) -> Result<(), String> {
+    if !(1..=MAX_SCALE).contains(&scale) {
+        return Err(format!("Scale must be from 1 to {}", MAX_SCALE));
+    }
+    let palette = PlanePalette::preset(palette).ok_or(format!("Unknown palette {}", palette))?;
+    let mut rgba = vec![0; render::RGBA_BUFFER_SIZE];
+    render::render_rgba_planes(emu.display(), &mut rgba, &palette);
+
+    let scale = scale as usize;
+    let (width, height) = (DISPLAY_WIDTH * scale, DISPLAY_HEIGHT * scale);
+    let mut scaled = Vec::with_capacity(width * height * 4);
+    for row in rgba.chunks_exact(DISPLAY_WIDTH * 4) {
+        let line: Vec<u8> = row
+            .chunks_exact(4)
+            .flat_map(|pixel| pixel.repeat(scale))
+            .collect();
+        for _ in 0..scale {
+            scaled.extend_from_slice(&line);
+        }
+    }
+
+    let error = |e: &dyn std::fmt::Display| format!("Unable to write {}: {}", path.display(), e);
+    let file = File::create(path).map_err(|e| error(&e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+    writer.write_image_data(&scaled).map_err(|e| error(&e))?;
+    writer.finish().map_err(|e| error(&e))?;
+    println!("Saved {}", path.display());
+    Ok(())
+}
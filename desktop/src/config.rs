@@ -0,0 +1,657 @@
+//! Settings read from `chip8emu/config.toml` in the user's config directory, e.g.
+//! `~/.config/chip8emu/config.toml`, and for particular ROMs from `chip8emu/games/<SHA-1>.toml`.
+//! Command-line options override them.
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chip8core::{PlanePalette, Quirks, Rotation};
+use sdl2::{controller::Button, keyboard::Keycode};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    filter::Filter, BEEP_HZ, BEEP_VOLUME, DEFAULT_PALETTE, FAST_FORWARD, GAMEPAD_MAP, KEYMAP,
+    SCALE, SLOW_MOTION,
+};
+
+/// Written by `--write-default-config`. Matches [Config::default].
+pub const DEFAULT_CONFIG: &str = r##"# chip8emu settings. Command-line options override these.
+
+[display]
+# Size of a Chip-8 pixel in screen pixels.
+scale = 15
+# How the display fills the window: "integer" scales by whole numbers so every pixel is the same
+# size, "stretch" fills as much of the window as fits.
+scaling = "integer"
+# Effect drawn over the display: "none", "scanlines", "crt" or "lcd". F9 switches between them.
+filter = "none"
+# Colours: "green", "amber", "paper", "gameboy", "octo", or "contrast" and "contrast-light" for
+# high contrast. F10 switches between them.
+palette = "green"
+# Colours overriding the palette's, as "#RRGGBB": unlit pixels, lit pixels, and in XO-CHIP games
+# pixels lit in only the second plane and in both planes.
+# background = "#000000"
+# foreground = "#00FF00"
+# plane2 = "#008800"
+# overlap = "#88FF88"
+# Whether to slow down games strobing the whole screen, for players sensitive to flashing lights.
+# F12 toggles it.
+reduce_flashing = false
+# Clockwise turn of the display in degrees, for games made for a screen on its side: 0, 90, 180 or
+# 270. Input isn't affected.
+rotation = 0
+
+[emulation]
+# Instructions per second, instead of each ROM's usual speed.
+# speed = 480
+# Quirks for ROMs the database doesn't know: "modern", "chip8" or "schip". Guessed from the ROM's
+# instructions if unset.
+# quirks = "modern"
+# Speed while Space is held, as a multiple of the normal speed. 0 runs as fast as possible.
+fast_forward = 4.0
+# Speed after pressing F2, as a multiple of the normal speed.
+slow_motion = 0.5
+
+[audio]
+# Buzzer pitch in Hz.
+frequency = 440.0
+# Buzzer volume, from 0 to 1.
+volume = 0.25
+# Whether to start with the buzzer muted. M toggles it.
+muted = false
+
+[turbo]
+# Chip-8 keys, in hex, pressed and released over and over while held, e.g. ["5"] for a game that
+# needs 5 mashed to fire.
+keys = []
+# Presses a second, at most 30.
+rate = 10
+
+[recording]
+# What PrintScreen records the focused display to: "gif", or "mp4" by piping frames to ffmpeg, which
+# must be installed.
+format = "gif"
+# Frames left out after each one recorded. GIFs play 60 frames a second too slowly in most
+# browsers, so the default records 30.
+frame_skip = 1
+# Size of a Chip-8 pixel in recorded pixels.
+scale = 4
+# Directory recordings are saved in. The current directory if unset.
+# directory = "clips"
+
+[launcher]
+# Directory whose ROMs are listed when started without one. The current directory if unset.
+# directory = "roms"
+
+# SDL key name for each Chip-8 key.
+[keys]
+1 = "1"
+2 = "2"
+3 = "3"
+C = "4"
+4 = "Q"
+5 = "W"
+6 = "E"
+D = "R"
+7 = "A"
+8 = "S"
+9 = "D"
+E = "F"
+A = "Z"
+0 = "X"
+B = "C"
+F = "V"
+
+# Chip-8 key for each gamepad button, by SDL name. "" unbinds a button.
+[gamepad]
+dpup = "2"
+dpdown = "8"
+dpleft = "4"
+dpright = "6"
+a = "5"
+b = "F"
+x = "A"
+y = "B"
+start = "1"
+back = "0"
+
+# SDL key playing each input macro, by name. The key with Ctrl held records it.
+[macros]
+# skip-intro = "H"
+
+# Settings for particular ROMs go in `games/<SHA-1>.toml` beside this file, named by the SHA-1 hash
+# of the ROM, e.g. from `sha1sum`. They may set `speed`, `quirks`, `palette`, `rotation` and a
+# `[keys]` table, overriding the settings here whenever that ROM is loaded.
+"##;
+
+/// Desktop frontend settings.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// How the display is drawn.
+    pub display: DisplayConfig,
+    /// How ROMs are run.
+    pub emulation: EmulationConfig,
+    /// How the buzzer sounds.
+    pub audio: AudioSettings,
+    /// Keys pressed over and over while held.
+    pub turbo: TurboConfig,
+    /// How gameplay is recorded.
+    pub recording: RecordingConfig,
+    /// What's listed when started without a ROM.
+    pub launcher: LauncherConfig,
+    /// SDL key names by Chip-8 key, in hex. Keys left out keep their default binding.
+    pub keys: BTreeMap<String, String>,
+    /// Chip-8 keys, in hex, by SDL gamepad button name. Buttons left out keep their default
+    /// binding, and empty keys unbind them.
+    pub gamepad: BTreeMap<String, String>,
+    /// SDL key names playing each input macro, by macro name.
+    pub macros: BTreeMap<String, String>,
+}
+
+/// How the display is drawn.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    /// Size of a Chip-8 pixel in screen pixels.
+    pub scale: u32,
+    /// How the display fills the window.
+    pub scaling: Scaling,
+    /// Effect drawn over the display.
+    pub filter: Filter,
+    /// Colours, unless overridden.
+    pub palette: PalettePreset,
+    /// Colour of unlit pixels.
+    pub background: Option<Rgb>,
+    /// Colour of lit pixels.
+    pub foreground: Option<Rgb>,
+    /// Colour of pixels lit in only the second XO-CHIP plane.
+    pub plane2: Option<Rgb>,
+    /// Colour of pixels lit in both XO-CHIP planes.
+    pub overlap: Option<Rgb>,
+    /// Whether to slow down flashes of the whole display.
+    pub reduce_flashing: bool,
+    /// How far the display is turned.
+    pub rotation: Degrees,
+}
+impl DisplayConfig {
+    /// Return the palette's colours with any overridden.
+    pub fn colors(&self) -> PlanePalette {
+        let mut palette = self.palette.colors;
+        let overrides = [self.background, self.foreground, self.plane2, self.overlap];
+        for (color, rgb) in palette.colors.iter_mut().zip(overrides) {
+            if let Some(Rgb(r, g, b)) = rgb {
+                *color = [r, g, b, 0xFF];
+            }
+        }
+        palette
+    }
+}
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            scale: SCALE,
+            scaling: Scaling::default(),
+            filter: Filter::default(),
+            palette: DEFAULT_PALETTE.parse().unwrap(),
+            background: None,
+            foreground: None,
+            plane2: None,
+            overlap: None,
+            reduce_flashing: false,
+            rotation: Degrees::default(),
+        }
+    }
+}
+
+/// How the display is scaled to fill the window. Both keep its shape.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scaling {
+    /// By the largest whole number that fits, so every pixel is the same size.
+    #[default]
+    Integer,
+    /// By as much as fits.
+    Stretch,
+}
+
+/// Keys pressed over and over while held.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TurboConfig {
+    /// Chip-8 keys, in hex.
+    pub keys: Vec<String>,
+    /// Presses a second.
+    pub rate: u32,
+}
+impl Default for TurboConfig {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            rate: 10,
+        }
+    }
+}
+
+/// How gameplay is recorded.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecordingConfig {
+    /// What recordings are saved as.
+    pub format: RecordFormat,
+    /// Frames left out after each one recorded.
+    pub frame_skip: u32,
+    /// Size of a Chip-8 pixel in recorded pixels.
+    pub scale: u32,
+    /// Directory recordings are saved in, instead of the current one.
+    pub directory: Option<PathBuf>,
+}
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            format: RecordFormat::Gif,
+            frame_skip: 1,
+            scale: 4,
+            directory: None,
+        }
+    }
+}
+
+/// What recordings are saved as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordFormat {
+    /// Animated GIF.
+    #[default]
+    Gif,
+    /// MP4 video encoded by ffmpeg.
+    Mp4,
+}
+
+/// What's listed when started without a ROM.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LauncherConfig {
+    /// Directory whose ROMs are listed, instead of the current one.
+    pub directory: Option<PathBuf>,
+}
+
+/// Settings for a particular ROM, read from its profile and overriding the others.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    /// Instructions per second.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<u32>,
+    /// Colours.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<PalettePreset>,
+    /// How far the display is turned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<Degrees>,
+    /// Quirks to run with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quirks: Option<Preset>,
+    /// SDL key names by Chip-8 key, in hex, overriding the usual bindings.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, String>,
+}
+impl GameConfig {
+    /// Read the profile of the ROM with the given lowercase hex SHA-1 hash, if it has one.
+    pub fn load(sha1: &str) -> Result<Self, ConfigError> {
+        let Some(path) = profile_path(sha1) else {
+            return Ok(Self::default());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(ConfigError::Io(path, e)),
+        };
+        toml::from_str(&text).map_err(|e| ConfigError::Parse(path, e))
+    }
+
+    /// Write the profile of the ROM with the given SHA-1 hash, returning where it was written.
+    pub fn save(&self, sha1: &str) -> Result<PathBuf, ConfigError> {
+        let path = profile_path(sha1).ok_or(ConfigError::NoConfigDir)?;
+        let text = toml::to_string(self).map_err(ConfigError::Serialize)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| ConfigError::Io(path.clone(), e))?;
+        }
+        fs::write(&path, text).map_err(|e| ConfigError::Io(path.clone(), e))?;
+        Ok(path)
+    }
+}
+
+/// How ROMs are run.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmulationConfig {
+    /// Instructions per second, instead of each ROM's usual speed.
+    pub speed: Option<u32>,
+    /// Quirks for ROMs the database doesn't know, instead of guessing.
+    pub quirks: Option<Preset>,
+    /// Speed while fast-forwarding, as a multiple of the normal speed. 0 runs as fast as possible.
+    pub fast_forward: f64,
+    /// Speed in slow motion, as a multiple of the normal speed.
+    pub slow_motion: f64,
+}
+impl Default for EmulationConfig {
+    fn default() -> Self {
+        Self {
+            speed: None,
+            quirks: None,
+            fast_forward: FAST_FORWARD,
+            slow_motion: SLOW_MOTION,
+        }
+    }
+}
+
+/// How the buzzer sounds.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioSettings {
+    /// Pitch in Hz.
+    pub frequency: f32,
+    /// Volume, from 0 to 1.
+    pub volume: f32,
+    /// Whether to start muted.
+    pub muted: bool,
+}
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            frequency: BEEP_HZ,
+            volume: BEEP_VOLUME,
+            muted: false,
+        }
+    }
+}
+
+/// Colour written as `"#RRGGBB"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Rgb(pub u8, pub u8, pub u8);
+impl TryFrom<String> for Rgb {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let hex = s.strip_prefix('#').unwrap_or(&s);
+        match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => Ok(Self((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)),
+            _ => Err(format!("invalid colour {:?}, expected \"#RRGGBB\"", s)),
+        }
+    }
+}
+
+/// Rotation written as clockwise degrees: 0, 90, 180 or 270.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "u32", into = "u32")]
+pub struct Degrees(pub Rotation);
+impl From<Degrees> for u32 {
+    fn from(degrees: Degrees) -> Self {
+        degrees.0.degrees()
+    }
+}
+impl TryFrom<u32> for Degrees {
+    type Error = String;
+
+    fn try_from(degrees: u32) -> Result<Self, Self::Error> {
+        Rotation::from_degrees(degrees).map(Self).ok_or(format!(
+            "invalid rotation {}, expected 0, 90, 180 or 270",
+            degrees
+        ))
+    }
+}
+
+/// Named colour palette from [PALETTE_PRESETS](chip8core::render::PALETTE_PRESETS).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PalettePreset {
+    /// Name the palette was given by.
+    pub name: String,
+    /// Colours it stands for.
+    pub colors: PlanePalette,
+}
+impl FromStr for PalettePreset {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let colors = PlanePalette::preset(name).ok_or(format!("unknown palette {:?}", name))?;
+        Ok(Self {
+            name: name.to_string(),
+            colors,
+        })
+    }
+}
+impl TryFrom<String> for PalettePreset {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+impl From<PalettePreset> for String {
+    fn from(palette: PalettePreset) -> Self {
+        palette.name
+    }
+}
+
+/// Quirks preset: `modern`, `chip8` or `schip`, or a table of each quirk for `custom` quirks.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "QuirksSetting", into = "QuirksSetting")]
+pub struct Preset {
+    /// Name the preset was given by.
+    pub name: String,
+    /// Quirks it stands for.
+    pub quirks: Quirks,
+}
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let quirks = match name {
+            "modern" => Quirks::modern(),
+            "chip8" => Quirks::chip8(),
+            "schip" => Quirks::schip(),
+            _ => return Err(format!("unknown quirks preset {:?}", name)),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            quirks,
+        })
+    }
+}
+impl From<Quirks> for Preset {
+    /// Name the preset with the same quirks, or `custom` if none has them.
+    fn from(quirks: Quirks) -> Self {
+        let name = ["modern", "chip8", "schip"]
+            .into_iter()
+            .find(|name| name.parse::<Preset>().unwrap().quirks == quirks)
+            .unwrap_or("custom");
+        Self {
+            name: name.to_string(),
+            quirks,
+        }
+    }
+}
+impl TryFrom<QuirksSetting> for Preset {
+    type Error = String;
+
+    fn try_from(setting: QuirksSetting) -> Result<Self, Self::Error> {
+        match setting {
+            QuirksSetting::Name(name) => name.parse(),
+            QuirksSetting::Custom(quirks) => Ok(quirks.into()),
+        }
+    }
+}
+impl From<Preset> for QuirksSetting {
+    fn from(preset: Preset) -> Self {
+        match preset.name.as_str() {
+            "custom" => Self::Custom(preset.quirks),
+            _ => Self::Name(preset.name),
+        }
+    }
+}
+
+/// Quirks as written: a preset's name or a table of each quirk.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum QuirksSetting {
+    /// Name of a preset.
+    Name(String),
+    /// Each quirk by its name.
+    Custom(Quirks),
+}
+
+impl Config {
+    /// Read the config file at `path`, or the default path if `None`. A missing file at the default
+    /// path gives the default settings.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(ConfigError::Io(path, e)),
+        };
+        toml::from_str(&text).map_err(|e| ConfigError::Parse(path, e))
+    }
+
+    /// Apply a ROM's profile to the settings shared by the whole window: its palette, rotation
+    /// and keys. Speed and quirks are applied per instance.
+    pub fn apply(&mut self, game: &GameConfig) {
+        if let Some(palette) = &game.palette {
+            // The profile's palette replaces individually overridden colours too
+            self.display.palette = palette.clone();
+            self.display.background = None;
+            self.display.foreground = None;
+            self.display.plane2 = None;
+            self.display.overlap = None;
+        }
+        if let Some(rotation) = game.rotation {
+            self.display.rotation = rotation;
+        }
+        self.keys
+            .extend(game.keys.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    /// Return the key bound to each Chip-8 key.
+    pub fn keymap(&self) -> Result<[Keycode; 16], ConfigError> {
+        let mut keymap = KEYMAP;
+        for (chip8_key, name) in &self.keys {
+            keymap[chip8_key_index(chip8_key)?] =
+                Keycode::from_name(name).ok_or(ConfigError::Key(name.clone()))?;
+        }
+        Ok(keymap)
+    }
+
+    /// Return the key playing each macro, with its name.
+    pub fn macro_keys(&self) -> Result<Vec<(Keycode, String)>, ConfigError> {
+        self.macros
+            .iter()
+            .map(|(name, key)| {
+                let key = Keycode::from_name(key).ok_or(ConfigError::Key(key.clone()))?;
+                Ok((key, name.clone()))
+            })
+            .collect()
+    }
+
+    /// Return the turbo keys, bit `n` being key `n`.
+    pub fn turbo_keys(&self) -> Result<u16, ConfigError> {
+        self.turbo
+            .keys
+            .iter()
+            .try_fold(0, |keys, key| Ok(keys | 1 << chip8_key_index(key)?))
+    }
+
+    /// Return the Chip-8 key bound to each gamepad button.
+    pub fn gamepad_map(&self) -> Result<Vec<(Button, usize)>, ConfigError> {
+        let mut map = GAMEPAD_MAP.to_vec();
+        for (name, chip8_key) in &self.gamepad {
+            let button = Button::from_string(name).ok_or(ConfigError::Button(name.clone()))?;
+            map.retain(|(b, _)| *b != button);
+            if !chip8_key.is_empty() {
+                map.push((button, chip8_key_index(chip8_key)?));
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// Parse a Chip-8 key written in hex.
+fn chip8_key_index(key: &str) -> Result<usize, ConfigError> {
+    match u8::from_str_radix(key, 16) {
+        Ok(index) if index < 16 => Ok(index as usize),
+        _ => Err(ConfigError::Key(key.to_string())),
+    }
+}
+
+/// Path settings are read from unless another is given, if there's a config directory.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("chip8emu").join("config.toml"))
+}
+
+/// Path the profile of the ROM with the given SHA-1 hash is read from, if there's a config
+/// directory.
+pub fn profile_path(sha1: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| {
+        dir.join("chip8emu")
+            .join("games")
+            .join(format!("{}.toml", sha1))
+    })
+}
+
+/// Write [DEFAULT_CONFIG] to `path`, refusing to overwrite an existing file.
+pub fn write_default(path: &Path) -> Result<(), ConfigError> {
+    if path.exists() {
+        return Err(ConfigError::Exists(path.to_path_buf()));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+    }
+    fs::write(path, DEFAULT_CONFIG).map_err(|e| ConfigError::Io(path.to_path_buf(), e))
+}
+
+/// Error reading or writing a config file.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read or written.
+    Io(PathBuf, io::Error),
+    /// The file isn't valid TOML or has invalid settings.
+    Parse(PathBuf, toml::de::Error),
+    /// A Chip-8 key or SDL key name doesn't exist.
+    Key(String),
+    /// An SDL gamepad button name in `[gamepad]` doesn't exist.
+    Button(String),
+    /// The file to write already exists.
+    Exists(PathBuf),
+    /// Settings couldn't be written as TOML.
+    Serialize(toml::ser::Error),
+    /// There's no config directory to write to.
+    NoConfigDir,
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Key(key) => write!(f, "unknown key {:?}", key),
+            Self::Button(button) => write!(f, "unknown gamepad button {:?}", button),
+            Self::Exists(path) => write!(f, "{} already exists", path.display()),
+            Self::Serialize(e) => write!(f, "{}", e),
+            Self::NoConfigDir => write!(f, "no config directory"),
+        }
+    }
+}
+impl Error for ConfigError {}
@@ -0,0 +1,452 @@
+//! Emulation on its own thread, paced by the wall clock instead of the display's refresh rate.
+use std::{
+    net::TcpStream,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chip8core::{timing::Pacer, *};
+
+/// Number of rewind snapshots to keep.
+pub const REWIND_CAPACITY: usize = 600;
+/// Frames between rewind snapshots.
+pub const REWIND_INTERVAL: u32 = 2;
+/// Instructions from the program counter on sent for the debug overlay.
+pub const NEXT_INSTRUCTIONS: usize = 4;
+
+/// Input sent to the emulation thread.
+pub enum Command {
+    /// Keys held, bit `n` being key `n`.
+    Keys(u16),
+    /// Start or stop rewinding.
+    Rewind(bool),
+    /// Move input to the next instance.
+    FocusNext,
+    /// Move input to the previous instance.
+    FocusPrevious,
+    /// Toggle sending input to every instance.
+    ToggleLinked,
+    /// Pause or resume.
+    TogglePause,
+    /// Run one frame while paused.
+    AdvanceFrame,
+    /// Run one instruction of the focused instance while paused.
+    Step,
+    /// Start or stop fast-forwarding.
+    FastForward(bool),
+    /// Toggle slow motion.
+    ToggleSlowMotion,
+    /// Reset every instance and load the given ROM into each.
+    Reload(Vec<Vec<u8>>),
+    /// Reset every instance's CPU, keeping its memory.
+    SoftReset,
+    /// Start or stop sending the focused instance's RAM with each frame.
+    WatchMemory(bool),
+    /// Change the quirks of every instance receiving input.
+    SetQuirks(Quirks),
+    /// Play key events timed by frame from now on every instance receiving input, in place of
+    /// the keys held until the last has been applied.
+    PlayMacro(Vec<ScheduledKey>),
+    /// Run a debugger command on the focused instance, printing what it shows.
+    Debug(console::Command),
+}
+
+/// Speeds emulation can be switched to, as multiples of the normal speed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Speeds {
+    /// Speed while fast-forwarding, or `None` to run as fast as possible.
+    pub fast_forward: Option<f64>,
+    /// Speed in slow motion.
+    pub slow_motion: f64,
+}
+
+/// Keys pressed and released over and over while held, for games that need a key mashed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Turbo {
+    /// Turbo keys, bit `n` being key `n`.
+    pub keys: u16,
+    /// Frames from one press to the next, half of them held.
+    pub period: u32,
+}
+impl Turbo {
+    /// Return the keys `held` as of emulated frame `frame`, releasing turbo keys for the second
+    /// half of each period.
+    pub fn apply(&self, held: u16, frame: u64) -> u16 {
+        let period = self.period.max(2) as u64;
+        match frame % period < period / 2 {
+            true => held,
+            false => held & !self.keys,
+        }
+    }
+}
+
+/// Snapshot of an emulated frame, sent to the render thread.
+pub struct Frame {
+    /// Display of every instance, with every plane.
+    pub displays: Vec<Display>,
+    /// Index of the instance receiving input.
+    pub focus: usize,
+    /// Whether every instance receives input.
+    pub linked: bool,
+    /// Label of the focused instance.
+    pub label: String,
+    /// Whether the focused instance's buzzer is sounding.
+    pub sound: bool,
+    /// Whether emulation is paused.
+    pub paused: bool,
+    /// Multiple of the normal speed emulation runs at, or `None` if as fast as possible.
+    pub rate: Option<f64>,
+    /// Frames the focused instance has run.
+    pub frames: u64,
+    /// Instructions the focused instance has executed.
+    pub cycles: u64,
+    /// CPU state of the focused instance.
+    pub cpu: Cpu,
+    /// RAM of the focused instance, if being watched.
+    pub ram: Option<Vec<u8>>,
+    /// Keys held on the focused instance, bit `n` being key `n`.
+    pub keys: u16,
+    /// Quirks the focused instance runs with.
+    pub quirks: Quirks,
+}
+
+/// Registers, timers and upcoming instructions of an instance, shown by the debug overlay.
+pub struct Cpu {
+    /// V registers, V0 first.
+    pub v: [u8; 16],
+    /// I register.
+    pub i: u16,
+    /// Program counter.
+    pub pc: u16,
+    /// Stack pointer.
+    pub sp: u16,
+    /// Delay timer.
+    pub delay: u8,
+    /// Sound timer.
+    pub sound: u8,
+    /// Address and opcode of the next instructions in memory, from the program counter on.
+    pub next: Vec<(u16, u16)>,
+}
+impl Cpu {
+    fn new(emu: &Emulator) -> Self {
+        let next = (emu.pc()..)
+            .step_by(2)
+            .take(NEXT_INSTRUCTIONS)
+            .map_while(|addr| {
+                let high = emu.read_byte(addr as usize).ok()?;
+                let low = emu.read_byte(addr as usize + 1).ok()?;
+                Some((addr, u16::from_be_bytes([high, low])))
+            })
+            .collect();
+        Self {
+            v: *emu.v_registers(),
+            i: emu.i(),
+            pc: emu.pc(),
+            sp: emu.stack_pointer(),
+            delay: emu.delay_timer(),
+            sound: emu.sound_timer(),
+            next,
+        }
+    }
+}
+
+/// Handle to the emulation thread, which runs until told to stop or every instance stops.
+pub struct Emulation {
+    commands: Sender<Command>,
+    frames: Receiver<Frame>,
+    thread: JoinHandle<Instances>,
+}
+impl Emulation {
+    /// Start running `instances` at 60 frames per second, or at `speeds` when switched to, with
+    /// `turbo` keys pulsing. Rewinding and netplay only apply to a single instance.
+    pub fn spawn(
+        instances: Instances,
+        netplay: Option<Netplay<TcpStream>>,
+        speeds: Speeds,
+        turbo: Turbo,
+    ) -> Self {
+        let (commands, command_rx) = mpsc::channel();
+        let (frame_tx, frames) = mpsc::channel();
+        let worker = Worker {
+            instances,
+            netplay,
+            rewind: RewindBuffer::new(REWIND_CAPACITY, REWIND_INTERVAL),
+            rewinding: false,
+            paused: false,
+            speeds,
+            fast_forward: false,
+            slow_motion: false,
+            watch_memory: false,
+            keys: 0,
+            turbo,
+            frames: 0,
+        };
+        let thread = thread::spawn(move || worker.run(command_rx, frame_tx));
+        Self {
+            commands,
+            frames,
+            thread,
+        }
+    }
+
+    /// Send input to the emulation thread.
+    pub fn send(&self, command: Command) {
+        // The thread only stops early when every instance has, which `frames` reports.
+        let _ = self.commands.send(command);
+    }
+
+    /// Return the frames emulated since the last call, oldest first, or `None` once the thread
+    /// has stopped.
+    pub fn frames(&self) -> Option<Vec<Frame>> {
+        let mut frames = Vec::new();
+        loop {
+            match self.frames.try_recv() {
+                Ok(frame) => frames.push(frame),
+                Err(TryRecvError::Empty) => return Some(frames),
+                Err(TryRecvError::Disconnected) => return None,
+            }
+        }
+    }
+
+    /// Stop the thread and return the instances it ran.
+    pub fn stop(self) -> Instances {
+        drop(self.commands);
+        drop(self.frames);
+        self.thread.join().expect("Emulation thread panicked")
+    }
+}
+
+/// State owned by the emulation thread.
+struct Worker {
+    instances: Instances,
+    netplay: Option<Netplay<TcpStream>>,
+    rewind: RewindBuffer,
+    rewinding: bool,
+    paused: bool,
+    speeds: Speeds,
+    fast_forward: bool,
+    slow_motion: bool,
+    watch_memory: bool,
+    keys: u16,
+    turbo: Turbo,
+    /// Frames run, timing turbo keys.
+    frames: u64,
+}
+impl Worker {
+    /// Run frames as they fall due until the render thread hangs up or every instance stops.
+    fn run(mut self, commands: Receiver<Command>, frames: Sender<Frame>) -> Instances {
+        let mut pacer = Pacer::new();
+        let mut last = Instant::now();
+        loop {
+            loop {
+                match commands.try_recv() {
+                    Ok(Command::AdvanceFrame) if self.paused => {
+                        if !self.frame() || frames.send(self.snapshot()).is_err() {
+                            return self.instances;
+                        }
+                    }
+                    Ok(command) => self.command(command),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return self.instances,
+                }
+            }
+
+            let now = Instant::now();
+            let elapsed = now - last;
+            last = now;
+            // Speed changes scale emulated time, so instructions and timers keep their ratio
+            let Some(rate) = self.rate() else {
+                // Run as many frames as fit in one, sending only the last
+                let deadline = now + Duration::from_secs(1) / timing::TIMER_HZ;
+                while Instant::now() < deadline {
+                    if !self.frame() {
+                        return self.instances;
+                    }
+                }
+                if frames.send(self.snapshot()).is_err() {
+                    return self.instances;
+                }
+                continue;
+            };
+            for _ in 0..pacer.advance(elapsed.mul_f64(rate)) {
+                // Frames keep being sent while paused, to keep the sound queue fed
+                let running = self.paused || self.frame();
+                if !running || frames.send(self.snapshot()).is_err() {
+                    return self.instances;
+                }
+            }
+            thread::sleep(pacer.until_next().div_f64(rate));
+        }
+    }
+
+    /// Multiple of the normal speed to run at, or `None` if as fast as possible.
+    fn rate(&self) -> Option<f64> {
+        // The peer runs at its own speed, so netplay can't change it
+        if self.paused || self.netplay.is_some() {
+            Some(1.0)
+        } else if self.fast_forward {
+            self.speeds.fast_forward
+        } else if self.slow_motion {
+            Some(self.speeds.slow_motion)
+        } else {
+            Some(1.0)
+        }
+    }
+
+    fn command(&mut self, command: Command) {
+        match command {
+            Command::Keys(keys) => self.keys = keys,
+            Command::Rewind(rewinding) => self.rewinding = rewinding,
+            Command::FocusNext => self.instances.focus_next(),
+            Command::FocusPrevious => self.instances.focus_previous(),
+            Command::ToggleLinked => self.instances.set_linked(!self.instances.linked()),
+            // The peer can't be paused, so netplay can't be either
+            Command::TogglePause => self.paused = !self.paused && self.netplay.is_none(),
+            Command::Step if self.paused => {
+                self.instances
+                    .set_keys(self.turbo.apply(self.keys, self.frames));
+                let focus = self.instances.focus();
+                let instance = self.instances.get_mut(focus).unwrap();
+                if instance.is_running() {
+                    if let Err(e) = instance.emu.step() {
+                        instance.error = Some(e);
+                    }
+                }
+            }
+            // Frames are advanced by the caller, and neither applies while running
+            Command::AdvanceFrame | Command::Step => {}
+            Command::FastForward(fast_forward) => self.fast_forward = fast_forward,
+            Command::ToggleSlowMotion => self.slow_motion = !self.slow_motion,
+            // Resetting one peer would desync the other
+            Command::Reload(_) | Command::SoftReset if self.netplay.is_some() => {}
+            Command::Reload(roms) => {
+                for (instance, rom) in self.instances.iter_mut().zip(roms) {
+                    let state = instance.emu.save_state();
+                    instance.emu.reset();
+                    match instance.emu.load_rom(&rom) {
+                        Ok(_) => instance.error = None,
+                        Err(e) => {
+                            println!("Unable to reload {}: {}", instance.label, e);
+                            // Saved just above, so always loads
+                            let _ = instance.emu.load_state(&state);
+                        }
+                    }
+                }
+                // Snapshots from before the reload would bring the old game back
+                self.rewind.clear();
+            }
+            Command::SoftReset => {
+                for instance in self.instances.iter_mut() {
+                    instance.emu.soft_reset();
+                    instance.error = None;
+                }
+                self.rewind.clear();
+            }
+            Command::WatchMemory(watch) => self.watch_memory = watch,
+            // Both peers must run with the same quirks
+            Command::SetQuirks(_) if self.netplay.is_some() => {}
+            Command::SetQuirks(quirks) => {
+                let (focus, linked) = (self.instances.focus(), self.instances.linked());
+                let receiving = self.instances.iter_mut().enumerate();
+                for (_, instance) in receiving.filter(|(index, _)| linked || *index == focus) {
+                    instance.emu.set_quirks(quirks);
+                }
+            }
+            // The peer only sees the keys held
+            Command::PlayMacro(_) if self.netplay.is_some() => {}
+            Command::PlayMacro(events) => {
+                let (focus, linked) = (self.instances.focus(), self.instances.linked());
+                let receiving = self.instances.iter_mut().enumerate();
+                for (_, instance) in receiving.filter(|(index, _)| linked || *index == focus) {
+                    let start = instance.emu.frames();
+                    instance.emu.set_keys(0);
+                    for event in &events {
+                        let InputTime::Frame(frame) = event.at else {
+                            continue;
+                        };
+                        let at = InputTime::Frame(start + frame);
+                        let _ = instance
+                            .emu
+                            .queue_key(at, event.key as usize, event.pressed);
+                    }
+                }
+            }
+            // Pausing or stepping one peer would desync the other
+            Command::Debug(_) if self.netplay.is_some() => {
+                println!("Debugging isn't available during netplay")
+            }
+            Command::Debug(command) => {
+                let focus = self.instances.focus();
+                let instance = self.instances.get_mut(focus).unwrap();
+                match command.run(&mut instance.emu, &mut self.paused) {
+                    Ok(text) => println!("{}", text),
+                    Err(e) => {
+                        println!("Emulation error in {}: {}", instance.label, e);
+                        instance.error = Some(e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run or rewind a frame. Returns whether any instance is still running.
+    fn frame(&mut self) -> bool {
+        let single = self.instances.len() == 1;
+        let keys = self.turbo.apply(self.keys, self.frames);
+        self.frames += 1;
+        if let Some(netplay) = &mut self.netplay {
+            let emu = &mut self.instances.get_mut(0).unwrap().emu;
+            if let Err(e) = netplay.exchange(emu, keys) {
+                println!("Netplay error: {}", e);
+                return false;
+            }
+        } else if single && self.rewinding {
+            self.rewind
+                .step_back(&mut self.instances.get_mut(0).unwrap().emu);
+            return true;
+        } else if self
+            .instances
+            .iter()
+            .all(|instance| instance.emu.queued_keys().is_empty())
+        {
+            // Keys held wait while a macro plays
+            self.instances.set_keys(keys);
+        }
+
+        let running = self.instances.run_frame();
+        for instance in self.instances.iter_mut() {
+            if let Some(stop) = instance.stop.take() {
+                println!("{} stopped on {}", instance.label, stop);
+                self.paused = true;
+            }
+        }
+        if single && self.netplay.is_none() {
+            self.rewind.record(&self.instances.get(0).unwrap().emu);
+        }
+        running
+    }
+
+    fn snapshot(&self) -> Frame {
+        let focused = self.instances.focused().unwrap();
+        Frame {
+            displays: self
+                .instances
+                .iter()
+                .map(|instance| *instance.emu.display())
+                .collect(),
+            focus: self.instances.focus(),
+            linked: self.instances.linked(),
+            label: focused.label.clone(),
+            sound: focused.emu.is_sound_playing() && !self.paused,
+            paused: self.paused,
+            rate: self.rate(),
+            frames: focused.emu.frames(),
+            cycles: focused.emu.cycles(),
+            cpu: Cpu::new(&focused.emu),
+            ram: self.watch_memory.then(|| focused.emu.ram().to_vec()),
+            keys: focused.emu.keys_bitmask(),
+            quirks: *focused.emu.quirks(),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Cosmetic effects drawn over each display, imitating old screens.
+use sdl2::{
+    pixels::Color,
+    rect::FRect,
+    render::{BlendMode, Canvas},
+    video::Window,
+};
+use serde::Deserialize;
+
+/// Darkness of scanlines, from 0 to 255.
+pub const SCANLINE_ALPHA: u8 = 96;
+/// Darkness of the gaps between LCD pixels, from 0 to 255.
+pub const LCD_GAP_ALPHA: u8 = 160;
+/// Darkness of a CRT's corners, from 0 to 255.
+pub const VIGNETTE_ALPHA: u8 = 160;
+/// Bands the CRT vignette fades over.
+pub const VIGNETTE_STEPS: u32 = 12;
+/// Share of the display's height the CRT vignette covers on each side.
+pub const VIGNETTE_SIZE: f32 = 0.2;
+
+/// Effect drawn over each display.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+    /// Plain pixels.
+    #[default]
+    None,
+    /// A dark line along the bottom of every row of pixels.
+    Scanlines,
+    /// Scanlines with the edges fading to black, like a curved tube.
+    Crt,
+    /// Gaps between pixels, like a handheld's LCD.
+    Lcd,
+}
+impl Filter {
+    /// The filter after this one, wrapping back to none.
+    pub fn next(self) -> Self {
+        match self {
+            Self::None => Self::Scanlines,
+            Self::Scanlines => Self::Crt,
+            Self::Crt => Self::Lcd,
+            Self::Lcd => Self::None,
+        }
+    }
+
+    /// Name shown when switching to the filter.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Scanlines => "scanlines",
+            Self::Crt => "CRT",
+            Self::Lcd => "LCD",
+        }
+    }
+
+    /// Draw the filter over a display drawn in `tile`, `scale` screen pixels per Chip-8 pixel.
+    /// Scales too small to show the effect are drawn plain.
+    pub fn draw(self, canvas: &mut Canvas<Window>, tile: FRect, scale: f32) {
+        if self == Self::None || scale < 2.0 {
+            return;
+        }
+        canvas.set_blend_mode(BlendMode::Blend);
+        match self {
+            Self::None => {}
+            Self::Scanlines => scanlines(canvas, tile, scale, SCANLINE_ALPHA),
+            Self::Crt => {
+                scanlines(canvas, tile, scale, SCANLINE_ALPHA / 2);
+                vignette(canvas, tile);
+            }
+            Self::Lcd => lcd_gaps(canvas, tile, scale),
+        }
+        canvas.set_blend_mode(BlendMode::None);
+    }
+}
+
+/// Darken the bottom third of every row of pixels.
+fn scanlines(canvas: &mut Canvas<Window>, tile: FRect, scale: f32, alpha: u8) {
+    let thickness = (scale / 3.0).round().max(1.0);
+    let rows = (tile.height() / scale).round() as usize;
+    let lines: Vec<_> = (1..=rows)
+        .map(|row| {
+            let bottom = tile.y() + row as f32 * scale;
+            FRect::new(tile.x(), bottom - thickness, tile.width(), thickness)
+        })
+        .collect();
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, alpha));
+    canvas.fill_frects(&lines).unwrap();
+}
+
+/// Darken thin lines between every row and column of pixels.
+fn lcd_gaps(canvas: &mut Canvas<Window>, tile: FRect, scale: f32) {
+    let thickness = (scale / 8.0).round().max(1.0);
+    let (columns, rows) = (tile.width() / scale, tile.height() / scale);
+    let columns = (1..columns.round() as usize).map(|column| {
+        let x = tile.x() + column as f32 * scale;
+        FRect::new(x, tile.y(), thickness, tile.height())
+    });
+    let rows = (1..rows.round() as usize).map(|row| {
+        let y = tile.y() + row as f32 * scale;
+        FRect::new(tile.x(), y, tile.width(), thickness)
+    });
+    let gaps: Vec<_> = columns.chain(rows).collect();
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, LCD_GAP_ALPHA));
+    canvas.fill_frects(&gaps).unwrap();
+}
+
+/// Fade the edges of the tile to black in nested bands, darkest outside.
+fn vignette(canvas: &mut Canvas<Window>, tile: FRect) {
+    let band = (tile.height() * VIGNETTE_SIZE / VIGNETTE_STEPS as f32).max(1.0);
+    for step in 0..VIGNETTE_STEPS {
+        let fade = 1.0 - step as f32 / VIGNETTE_STEPS as f32;
+        let alpha = (VIGNETTE_ALPHA as f32 * fade * fade) as u8;
+        let inset = step as f32 * band;
+        let (x, y) = (tile.x() + inset, tile.y() + inset);
+        let (width, height) = (tile.width() - 2.0 * inset, tile.height() - 2.0 * inset);
+        if width <= 2.0 * band || height <= 2.0 * band {
+            break;
+        }
+        let edges = [
+            FRect::new(x, y, width, band),
+            FRect::new(x, y + height - band, width, band),
+            FRect::new(x, y + band, band, height - 2.0 * band),
+            FRect::new(x + width - band, y + band, band, height - 2.0 * band),
+        ];
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, alpha));
+        canvas.fill_frects(&edges).unwrap();
+    }
+}
@@ -0,0 +1,52 @@
+//! Application icon: a "C8" on a little screen, drawn in the palette's colours.
+use chip8core::PlanePalette;
+use sdl2::{pixels::PixelFormatEnum, surface::Surface, video::Window};
+
+/// Icon pixels: `#` lit, `.` unlit and spaces clear.
+pub const ICON: [&str; 16] = [
+    "                ",
+    " ############## ",
+    " #............# ",
+    " #............# ",
+    " #.###...###..# ",
+    " #.#.....#.#..# ",
+    " #.#.....###..# ",
+    " #.#.....#.#..# ",
+    " #.###...###..# ",
+    " #............# ",
+    " #............# ",
+    " ############## ",
+    "      ####      ",
+    "    ########    ",
+    "                ",
+    "                ",
+];
+/// Size of an icon pixel in image pixels.
+const SCALE: usize = 4;
+
+/// Give `window` the icon in the lit and unlit colours of `palette`.
+pub fn set(window: &mut Window, palette: &PlanePalette) {
+    let size = (ICON.len() * SCALE) as u32;
+    let Ok(mut surface) = Surface::new(size, size, PixelFormatEnum::RGBA32) else {
+        return;
+    };
+    let pitch = surface.pitch() as usize;
+    surface.with_lock_mut(|pixels| {
+        for (y, row) in ICON.iter().enumerate() {
+            for (x, pixel) in row.bytes().enumerate() {
+                let rgba = match pixel {
+                    b'#' => palette.colors[1],
+                    b'.' => palette.colors[0],
+                    _ => [0; 4],
+                };
+                for dy in 0..SCALE {
+                    let start = (y * SCALE + dy) * pitch + x * SCALE * 4;
+                    for offset in (0..SCALE * 4).step_by(4) {
+                        pixels[start + offset..start + offset + 4].copy_from_slice(&rgba);
+                    }
+                }
+            }
+        }
+    });
+    window.set_icon(surface);
+}
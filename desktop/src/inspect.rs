@@ -0,0 +1,125 @@
+//! Aids for checking the display pixel by pixel, e.g. sprite alignment: grid lines between pixels
+//! and a magnifier showing the pixels under the mouse with their coordinates.
+use chip8core::{
+    emulator::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+    Rotation,
+};
+use sdl2::{
+    pixels::Color,
+    rect::{FRect, Rect},
+    render::{BlendMode, Canvas, Texture},
+    video::Window,
+};
+
+use crate::osd;
+
+/// Colour of grid lines, with alpha.
+pub const GRID_RGBA: (u8, u8, u8, u8) = (128, 128, 128, 128);
+/// Colour outlining the magnifier and the pixel under the mouse.
+pub const CURSOR_RGB: (u8, u8, u8) = (255, 64, 64);
+/// Smallest pixel size in screen pixels grid lines are drawn at, as they'd hide smaller pixels.
+pub const MIN_GRID_SCALE: f32 = 4.0;
+/// Pixels shown across and down the magnifier. Odd, so the one under the mouse is in the middle.
+pub const ZOOM_PIXELS: u32 = 9;
+/// How many times larger pixels are in the magnifier than on the display, room permitting.
+pub const ZOOM: f32 = 4.0;
+/// Gap between the mouse and the magnifier in screen pixels.
+const MOUSE_GAP: f32 = 16.0;
+
+/// Draw lines between the pixels of `columns` by `rows` pixels drawn in `area`, `scale` screen
+/// pixels each. Nothing is drawn at scales too small to leave the pixels visible.
+pub fn draw_grid(
+    canvas: &mut Canvas<Window>,
+    area: FRect,
+    columns: usize,
+    rows: usize,
+    scale: f32,
+) {
+    if scale < MIN_GRID_SCALE {
+        return;
+    }
+    let verticals = (1..columns).map(|column| {
+        let x = area.x() + column as f32 * scale;
+        FRect::new(x, area.y(), 1.0, area.height())
+    });
+    let horizontals = (1..rows).map(|row| {
+        let y = area.y() + row as f32 * scale;
+        FRect::new(area.x(), y, area.width(), 1.0)
+    });
+    let lines: Vec<_> = verticals.chain(horizontals).collect();
+    canvas.set_blend_mode(BlendMode::Blend);
+    let (r, g, b, a) = GRID_RGBA;
+    canvas.set_draw_color(Color::RGBA(r, g, b, a));
+    canvas.fill_frects(&lines).unwrap();
+    canvas.set_blend_mode(BlendMode::None);
+}
+
+/// Draw the pixels around `mouse` magnified next to it, labelled with the column and row of the
+/// one under it, if the mouse is over the display drawn from `texture` in `tile` turned by
+/// `rotation`. `pixel` is the size of a font pixel. Returns whether the mouse was over the display.
+pub fn draw_magnifier(
+    canvas: &mut Canvas<Window>,
+    texture: &Texture,
+    tile: FRect,
+    scale: f32,
+    rotation: Rotation,
+    mouse: (f32, f32),
+    pixel: u32,
+) -> bool {
+    let column = ((mouse.0 - tile.x()) / scale).floor();
+    let row = ((mouse.1 - tile.y()) / scale).floor();
+    let (width, height) = rotation.size(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+    let (width, height) = (width as f32, height as f32);
+    if column < 0.0 || row < 0.0 || column >= width || row >= height {
+        return false;
+    }
+
+    // The view stops at the display's edges rather than showing past them
+    let half = (ZOOM_PIXELS / 2) as f32;
+    let zoom_pixels = ZOOM_PIXELS as f32;
+    let left = (column - half).clamp(0.0, width - zoom_pixels);
+    let top = (row - half).clamp(0.0, height - zoom_pixels);
+    let source = Rect::new(left as i32, top as i32, ZOOM_PIXELS, ZOOM_PIXELS);
+
+    // Kept beside the mouse and at most half the window's height, flipping to the other side
+    // of the mouse near the window's edges
+    let (canvas_width, canvas_height) = canvas.output_size().unwrap();
+    let zoomed = (scale * ZOOM).min(canvas_height as f32 / 2.0 / zoom_pixels);
+    let size = zoomed * zoom_pixels;
+    let label_height = (osd::LINE_HEIGHT * pixel) as f32;
+    let mut x = mouse.0 + MOUSE_GAP;
+    if x + size > canvas_width as f32 {
+        x = mouse.0 - MOUSE_GAP - size;
+    }
+    let mut y = mouse.1 + MOUSE_GAP;
+    if y + size + label_height > canvas_height as f32 {
+        y = mouse.1 - MOUSE_GAP - size - label_height;
+    }
+    let view = FRect::new(x, y, size, size);
+    canvas.copy_f(texture, source, view).unwrap();
+    draw_grid(
+        canvas,
+        view,
+        ZOOM_PIXELS as usize,
+        ZOOM_PIXELS as usize,
+        zoomed,
+    );
+
+    let cursor = FRect::new(
+        x + (column - left) * zoomed,
+        y + (row - top) * zoomed,
+        zoomed,
+        zoomed,
+    );
+    canvas.set_draw_color(Color::RGB(CURSOR_RGB.0, CURSOR_RGB.1, CURSOR_RGB.2));
+    canvas.draw_frect(view).unwrap();
+    canvas.draw_frect(cursor).unwrap();
+
+    // Coordinates are the game's, whichever way the display is turned
+    let (column, row) =
+        rotation.unrotate(column as usize, row as usize, DISPLAY_WIDTH, DISPLAY_HEIGHT);
+    let label = format!("{},{}", column, row);
+    let left = (x + pixel as f32) as i32;
+    osd::draw_text(canvas, &label, left, (y + size) as i32, pixel);
+    true
+}
@@ -0,0 +1,58 @@
+//! Overlay of the hex keypad, showing which keys are held and the key bound to each.
+use sdl2::{
+    keyboard::Keycode,
+    pixels::Color,
+    rect::Rect,
+    render::{BlendMode, Canvas},
+    video::Window,
+};
+
+use crate::osd::{self, BACKDROP_RGBA, CHAR_WIDTH, LINE_HEIGHT};
+
+/// Chip-8 keys in the order they're laid out on the keypad, row by row.
+pub const LAYOUT: [[usize; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+/// Colour behind held keys.
+pub const HELD_RGB: (u8, u8, u8) = (0, 128, 0);
+
+/// Characters of a bound key's name shown, e.g. "Keypa" of "Keypad 7".
+const NAME_CHARS: usize = 5;
+
+/// Draw the keypad in the bottom-right corner, highlighting the keys held in `keys`, bit `n`
+/// being key `n`. `pixel` is the size of a font pixel in screen pixels.
+pub fn draw(canvas: &mut Canvas<Window>, keys: u16, keymap: &[Keycode; 16], pixel: u32) {
+    let cell_width = (NAME_CHARS as u32 * CHAR_WIDTH + 2) * pixel;
+    let cell_height = 2 * LINE_HEIGHT * pixel;
+    let (width, height) = canvas.output_size().unwrap();
+    let left = width as i32 - (4 * cell_width + pixel) as i32;
+    let top = height as i32 - (4 * cell_height + pixel) as i32;
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    let (r, g, b, a) = BACKDROP_RGBA;
+    canvas.set_draw_color(Color::RGBA(r, g, b, a));
+    canvas
+        .fill_rect(Rect::new(left, top, 4 * cell_width, 4 * cell_height))
+        .unwrap();
+    canvas.set_blend_mode(BlendMode::None);
+
+    for (row, chip8_keys) in LAYOUT.iter().enumerate() {
+        for (col, &key) in chip8_keys.iter().enumerate() {
+            let x = left + (col as u32 * cell_width) as i32;
+            let y = top + (row as u32 * cell_height) as i32;
+            if keys & (1 << key) != 0 {
+                canvas.set_draw_color(Color::RGB(HELD_RGB.0, HELD_RGB.1, HELD_RGB.2));
+                canvas
+                    .fill_rect(Rect::new(x, y, cell_width - pixel, cell_height - pixel))
+                    .unwrap();
+            }
+            let name: String = keymap[key].name().chars().take(NAME_CHARS).collect();
+            let x = x + pixel as i32;
+            osd::draw_glyphs(canvas, &format!("{:X}", key), x, y, pixel);
+            osd::draw_glyphs(canvas, &name, x, y + (LINE_HEIGHT * pixel) as i32, pixel);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Screen listing recently opened ROMs and the ROMs in a directory to pick one from, shown when
+//! started without a ROM.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use sdl2::{
+    event::{Event, WindowEvent},
+    keyboard::Keycode,
+    pixels::Color,
+    rect::Rect,
+    render::Canvas,
+    video::Window,
+    EventPump, VideoSubsystem,
+};
+
+use chip8core::{
+    romfile::{ARCHIVE_EXTENSIONS, ROM_EXTENSIONS},
+    PlanePalette,
+};
+
+use crate::{
+    icon,
+    osd::{self, CHAR_WIDTH, LINE_HEIGHT},
+    DEFAULT_PALETTE, SOURCE_EXTENSIONS,
+};
+
+/// Entries shown at once.
+pub const ROWS: usize = 24;
+/// Characters shown across.
+pub const COLUMNS: usize = 48;
+/// Size of a font pixel in screen pixels.
+pub const PIXEL: u32 = 3;
+/// Highlight behind the selected entry.
+pub const SELECTED_RGB: (u8, u8, u8) = (0, 96, 0);
+
+/// File or directory in the list.
+#[derive(Clone)]
+struct Entry {
+    path: PathBuf,
+    /// Name shown, ending in `/` for directories.
+    name: String,
+    dir: bool,
+}
+
+/// Recent ROMs followed by a directory's ROMs and subdirectories, with one selected.
+pub struct Launcher {
+    dir: PathBuf,
+    /// Recently opened ROMs, listed first whichever directory is open.
+    recent: Vec<Entry>,
+    entries: Vec<Entry>,
+    selected: usize,
+    /// Entry shown at the top.
+    first_row: usize,
+    /// Why the last directory couldn't be opened, if it couldn't.
+    error: Option<String>,
+}
+impl Launcher {
+    /// List the `recent` ROMs, newest first, then the ROMs in `dir`.
+    pub fn new(dir: &Path, recent: &[PathBuf]) -> io::Result<Self> {
+        let recent = recent
+            .iter()
+            .map(|rom| Entry {
+                path: rom.clone(),
+                name: rom
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                dir: false,
+            })
+            .collect();
+        let mut launcher = Self {
+            dir: PathBuf::new(),
+            recent,
+            entries: Vec::new(),
+            selected: 0,
+            first_row: 0,
+            error: None,
+        };
+        launcher.open(dir)?;
+        Ok(launcher)
+    }
+
+    /// List `dir` instead, keeping the current list if it can't be read.
+    fn open(&mut self, dir: &Path) -> io::Result<()> {
+        let dir = dir.canonicalize()?;
+        let mut entries = self.recent.clone();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let dir = path.is_dir();
+            let rom = path.extension().is_some_and(|ext| {
+                let ext = ext.to_string_lossy().to_lowercase();
+                let ext = ext.as_str();
+                ROM_EXTENSIONS.contains(&ext)
+                    || ARCHIVE_EXTENSIONS.contains(&ext)
+                    || SOURCE_EXTENSIONS.contains(&ext)
+            });
+            if dir || rom {
+                let mut name = path.file_name().unwrap().to_string_lossy().into_owned();
+                if dir {
+                    name.push('/');
+                }
+                entries.push(Entry { path, name, dir });
+            }
+        }
+        // Directories first, then ROMs, each by name regardless of case
+        let listed = &mut entries[self.recent.len()..];
+        listed.sort_by_cached_key(|entry| (!entry.dir, entry.name.to_lowercase()));
+        if let Some(parent) = dir.parent() {
+            entries.insert(
+                self.recent.len(),
+                Entry {
+                    path: parent.to_path_buf(),
+                    name: "../".to_string(),
+                    dir: true,
+                },
+            );
+        }
+        self.dir = dir;
+        self.entries = entries;
+        self.selected = 0;
+        self.first_row = 0;
+        Ok(())
+    }
+
+    /// Handle a key press. Returns the ROM chosen, if one was.
+    pub fn key(&mut self, key: Keycode) -> Option<PathBuf> {
+        let last = self.entries.len().saturating_sub(1);
+        match key {
+            Keycode::Up => self.selected = self.selected.saturating_sub(1),
+            Keycode::Down => self.selected = (self.selected + 1).min(last),
+            Keycode::PageUp => self.selected = self.selected.saturating_sub(ROWS),
+            Keycode::PageDown => self.selected = (self.selected + ROWS).min(last),
+            Keycode::Home => self.selected = 0,
+            Keycode::End => self.selected = last,
+            Keycode::Backspace => {
+                if let Some(parent) = self.dir.parent().map(Path::to_path_buf) {
+                    self.enter(&parent);
+                }
+            }
+            Keycode::Return | Keycode::KpEnter => {
+                let entry = self.entries.get(self.selected)?;
+                if !entry.dir {
+                    return Some(entry.path.clone());
+                }
+                let dir = entry.path.clone();
+                self.enter(&dir);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Open a directory, showing why if it can't be.
+    fn enter(&mut self, dir: &Path) {
+        self.error = self
+            .open(dir)
+            .err()
+            .map(|e| format!("Unable to open {}: {}", dir.display(), e));
+    }
+
+    /// Draw the directory, the entries in view and a line of help or the last error.
+    pub fn draw(&mut self, canvas: &mut Canvas<Window>) {
+        // Keep the selection in view
+        if self.selected < self.first_row {
+            self.first_row = self.selected;
+        } else if self.selected >= self.first_row + ROWS {
+            self.first_row = self.selected + 1 - ROWS;
+        }
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.clear();
+
+        let line_height = (LINE_HEIGHT * PIXEL) as i32;
+        let left = PIXEL as i32;
+        osd::draw_glyphs(canvas, &fit(&self.dir.to_string_lossy()), left, 0, PIXEL);
+        if self.entries.is_empty() {
+            osd::draw_glyphs(canvas, "No ROMs here", left, line_height, PIXEL);
+        }
+        let visible = self.entries.iter().enumerate().skip(self.first_row);
+        for (row, (index, entry)) in visible.take(ROWS).enumerate() {
+            let top = (row as i32 + 1) * line_height;
+            if index == self.selected {
+                let (r, g, b) = SELECTED_RGB;
+                canvas.set_draw_color(Color::RGB(r, g, b));
+                let width = (COLUMNS as u32 * CHAR_WIDTH + 1) * PIXEL;
+                canvas
+                    .fill_rect(Rect::new(0, top, width, LINE_HEIGHT * PIXEL))
+                    .unwrap();
+            }
+            osd::draw_glyphs(canvas, &fit(&entry.name), left, top, PIXEL);
+            // A rule sets the recent ROMs apart from the directory
+            if index + 1 == self.recent.len() {
+                canvas.set_draw_color(Color::RGB(128, 128, 128));
+                let bottom = top + line_height - PIXEL as i32;
+                let width = (COLUMNS as u32 * CHAR_WIDTH + 1) * PIXEL;
+                canvas
+                    .fill_rect(Rect::new(0, bottom, width, PIXEL))
+                    .unwrap();
+            }
+        }
+        let footer = match &self.error {
+            Some(error) => fit(error),
+            None => "Enter: open  Backspace: up  Esc: quit".to_string(),
+        };
+        let top = (ROWS as i32 + 1) * line_height;
+        osd::draw_glyphs(canvas, &footer, left, top, PIXEL);
+        canvas.present();
+    }
+}
+
+/// Shorten text to [COLUMNS] characters, keeping its end, which tells paths apart.
+fn fit(text: &str) -> String {
+    let len = text.chars().count();
+    if len <= COLUMNS {
+        return text.to_string();
+    }
+    let end: String = text.chars().skip(len - (COLUMNS - 3)).collect();
+    format!("...{}", end)
+}
+
+/// Show the `recent` ROMs and the ROMs in `dir` in a window of their own until one is chosen,
+/// returning it, or `None` if the window is closed first.
+pub fn choose(
+    video: &VideoSubsystem,
+    events: &mut EventPump,
+    dir: &Path,
+    recent: &[PathBuf],
+) -> Result<Option<PathBuf>, String> {
+    let mut launcher = Launcher::new(dir, recent)
+        .map_err(|e| format!("Unable to open {}: {}", dir.display(), e))?;
+    let width = (COLUMNS as u32 * CHAR_WIDTH + 1) * PIXEL;
+    let height = (ROWS as u32 + 2) * LINE_HEIGHT * PIXEL;
+    let mut window = video
+        .window("chip8emu", width, height)
+        .position_centered()
+        .build()
+        .map_err(|e| e.to_string())?;
+    icon::set(&mut window, &PlanePalette::preset(DEFAULT_PALETTE).unwrap());
+    let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    loop {
+        launcher.draw(&mut canvas);
+        match events.wait_event() {
+            Event::Quit { .. }
+            | Event::Window {
+                win_event: WindowEvent::Close,
+                ..
+            }
+            | Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => return Ok(None),
+            Event::KeyDown {
+                keycode: Some(key), ..
+            } => {
+                if let Some(rom) = launcher.key(key) {
+                    return Ok(Some(rom));
+                }
+            }
+            _ => {}
+        }
+    }
+}
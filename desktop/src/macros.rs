@@ -0,0 +1,143 @@
+//! Input macros: key presses recorded by emulated frame and played back through the emulator's
+//! key queue. Each is kept in `chip8emu/macros/<name>.txt` in the user's data directory, e.g.
+//! `~/.local/share/chip8emu/macros/<name>.txt`, one event per line: the frame from the start,
+//! the Chip-8 key in hex, and `down` or `up`.
+use std::{error::Error, fmt, fs, io, path::PathBuf};
+
+use chip8core::{InputTime, ScheduledKey};
+
+/// Key presses being recorded into a macro.
+pub struct MacroRecorder {
+    /// Macro being recorded.
+    name: String,
+    /// Frame count of the instance when recording started.
+    start: u64,
+    /// Keys held as of the last frame.
+    keys: u16,
+    events: Vec<ScheduledKey>,
+}
+impl MacroRecorder {
+    /// Start recording the macro called `name` from an instance that has run `frames` frames with
+    /// `keys` held.
+    pub fn new(name: &str, frames: u64, keys: u16) -> Self {
+        let mut recorder = Self {
+            name: name.to_string(),
+            start: frames,
+            keys: 0,
+            events: Vec::new(),
+        };
+        // Keys already held are pressed at the start
+        recorder.keys_changed(0, keys);
+        recorder
+    }
+
+    /// Macro being recorded.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Take the keys held during the frame that brought the instance to `frames` frames.
+    pub fn push(&mut self, frames: u64, keys: u16) {
+        let frame = frames.saturating_sub(self.start + 1);
+        self.keys_changed(frame, keys);
+    }
+
+    /// Record presses and releases since the last keys held.
+    fn keys_changed(&mut self, frame: u64, keys: u16) {
+        let changed = keys ^ self.keys;
+        for key in (0..16).filter(|key| changed & 1 << key != 0) {
+            self.events.push(ScheduledKey {
+                at: InputTime::Frame(frame),
+                key,
+                pressed: keys & 1 << key != 0,
+            });
+        }
+        self.keys = keys;
+    }
+
+    /// Stop recording, releasing any keys still held, and return the events.
+    pub fn finish(mut self, frames: u64) -> Vec<ScheduledKey> {
+        self.push(frames + 1, 0);
+        self.events
+    }
+}
+
+/// Read the macro called `name`.
+pub fn load(name: &str) -> Result<Vec<ScheduledKey>, MacroError> {
+    let path = path(name).ok_or(MacroError::NoDataDir)?;
+    let text = fs::read_to_string(&path).map_err(|e| MacroError::Io(path.clone(), e))?;
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_event(line).ok_or_else(|| MacroError::Parse(line.to_string())))
+        .collect()
+}
+
+/// Write the macro called `name`, replacing any of that name.
+pub fn save(name: &str, events: &[ScheduledKey]) -> Result<PathBuf, MacroError> {
+    let path = path(name).ok_or(MacroError::NoDataDir)?;
+    let io_error = |e| MacroError::Io(path.clone(), e);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let text: String = events
+        .iter()
+        .map(|event| {
+            let InputTime::Frame(frame) = event.at else {
+                unreachable!("macros are timed by frame")
+            };
+            let state = if event.pressed { "down" } else { "up" };
+            format!("{} {:X} {}\n", frame, event.key, state)
+        })
+        .collect();
+    fs::write(&path, text).map_err(io_error)?;
+    Ok(path)
+}
+
+/// Parse a line of a macro file.
+fn parse_event(line: &str) -> Option<ScheduledKey> {
+    let mut fields = line.split_whitespace();
+    let frame = fields.next()?.parse().ok()?;
+    let key = u8::from_str_radix(fields.next()?, 16)
+        .ok()
+        .filter(|key| *key < 16)?;
+    let pressed = match fields.next()? {
+        "down" => true,
+        "up" => false,
+        _ => return None,
+    };
+    fields.next().is_none().then_some(ScheduledKey {
+        at: InputTime::Frame(frame),
+        key,
+        pressed,
+    })
+}
+
+/// Path the macro called `name` is kept at, if there's a data directory.
+pub fn path(name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| {
+        dir.join("chip8emu")
+            .join("macros")
+            .join(format!("{}.txt", name))
+    })
+}
+
+/// Error reading or writing a macro.
+#[derive(Debug)]
+pub enum MacroError {
+    /// There's no data directory to keep macros in.
+    NoDataDir,
+    /// The file couldn't be read or written.
+    Io(PathBuf, io::Error),
+    /// A line isn't a frame, a Chip-8 key and `down` or `up`.
+    Parse(String),
+}
+impl fmt::Display for MacroError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoDataDir => write!(f, "no data directory"),
+            Self::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Parse(line) => write!(f, "bad macro event {:?}", line),
+        }
+    }
+}
+impl Error for MacroError {}
@@ -1,20 +1,100 @@
-use std::{env, fs::File, io::Read};
+use std::{
+    env, fs,
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::mpsc::Receiver,
+};
 
 use chip8core::*;
+use config::{Config, GameConfig, Preset, RecordingConfig, Scaling};
+use emulation::{Command, Cpu, Emulation, Frame, Speeds, Turbo};
+use filter::Filter;
+use macros::MacroRecorder;
+use memview::MemoryViewer;
+use osd::Osd;
+use recent::Recent;
+use record::Recorder;
 use sdl2::{
-    event::Event, keyboard::Keycode, pixels::Color, rect::Rect, render::Canvas, video::Window,
+    audio::{AudioQueue, AudioSpecDesired},
+    controller::{Button, GameController},
+    event::{Event, WindowEvent},
+    keyboard::{Keycode, Mod},
+    pixels::{Color, PixelFormatEnum},
+    rect::{FRect, Rect},
+    render::{Canvas, Texture},
+    video::{FullscreenType, Window},
+    EventPump, GameControllerSubsystem, VideoSubsystem,
 };
+use spriteview::SpriteViewer;
+use stats::Stats;
+use watch::Watcher;
+
+mod config;
+mod emulation;
+mod filter;
+mod icon;
+mod inspect;
+mod keypad;
+mod launcher;
+mod macros;
+mod memview;
+mod osd;
+mod recent;
+mod record;
+mod repl;
+mod spriteview;
+mod stats;
+mod watch;
+
+/// Palette used unless another is configured.
+pub const DEFAULT_PALETTE: &str = "green";
+/// Switches to the next palette.
+pub const PALETTE_KEY: Keycode = Keycode::F10;
+
+/// Default buzzer pitch in Hz.
+pub const BEEP_HZ: f32 = 440.0;
+/// Default buzzer volume, from 0 to 1.
+pub const BEEP_VOLUME: f32 = 0.25;
+/// Toggles the buzzer.
+pub const MUTE_KEY: Keycode = Keycode::M;
 
-/// Background colour.
-pub const BG_RGB: (u8, u8, u8) = (0, 0, 0);
-/// Foreground colour.
-pub const FG_RGB: (u8, u8, u8) = (0, 255, 0);
+/// Starts and stops recording the focused display.
+pub const RECORD_KEY: Keycode = Keycode::PrintScreen;
 
-/// Emulator speed.
-pub const TICKS_PER_FRAME: usize = 8;
+/// Reloads the ROM from disk and resets, or resets keeping the ROM in memory with Shift.
+pub const RESET_KEY: Keycode = Keycode::F5;
 
-/// Multiplier for screen size.
+/// Pauses and resumes.
+pub const PAUSE_KEY: Keycode = Keycode::P;
+/// Runs one frame while paused, or one instruction with Shift.
+pub const ADVANCE_KEY: Keycode = Keycode::Period;
+/// Colour of the pause indicator.
+pub const PAUSED_RGB: (u8, u8, u8) = (255, 255, 255);
+
+/// Emulator speed, unless the ROM is known or `--speed` is given.
+pub const TICKS_PER_FRAME: u32 = 8;
+/// Default speed while fast-forwarding, as a multiple of the normal speed.
+pub const FAST_FORWARD: f64 = 4.0;
+/// Default speed in slow motion, as a multiple of the normal speed.
+pub const SLOW_MOTION: f64 = 0.5;
+/// Hold to fast-forward.
+pub const FAST_FORWARD_KEY: Keycode = Keycode::Space;
+/// Toggles slow motion.
+pub const SLOW_MOTION_KEY: Keycode = Keycode::F2;
+
+/// Default multiplier for screen size.
 pub const SCALE: u32 = 15;
+/// Colour of the bars around the display when the window doesn't fit it exactly.
+pub const LETTERBOX_RGB: (u8, u8, u8) = (0, 0, 0);
+/// Toggles fullscreen.
+pub const FULLSCREEN_KEY: Keycode = Keycode::F11;
+/// Switches to the next display filter.
+pub const FILTER_KEY: Keycode = Keycode::F9;
+/// Toggles flash reduction.
+pub const FLASH_KEY: Keycode = Keycode::F12;
+
+/// Hold to rewind.
+pub const REWIND_KEY: Keycode = Keycode::Backspace;
 
 // Key bindings.
 pub const KEY_1: Keycode = Keycode::Num1;
@@ -37,113 +117,1576 @@ pub const KEY_0: Keycode = Keycode::X;
 pub const KEY_B: Keycode = Keycode::C;
 pub const KEY_F: Keycode = Keycode::V;
 
-const WINDOW_WIDTH: u32 = (emulator::DISPLAY_WIDTH as u32) * SCALE;
-const WINDOW_HEIGHT: u32 = (emulator::DISPLAY_HEIGHT as u32) * SCALE;
+/// Default Chip-8 key bound to each gamepad button.
+pub const GAMEPAD_MAP: [(Button, usize); 10] = [
+    (Button::DPadUp, 0x2),
+    (Button::DPadDown, 0x8),
+    (Button::DPadLeft, 0x4),
+    (Button::DPadRight, 0x6),
+    (Button::A, 0x5),
+    (Button::B, 0xF),
+    (Button::X, 0xA),
+    (Button::Y, 0xB),
+    (Button::Start, 0x1),
+    (Button::Back, 0x0),
+];
+
+/// Default key bound to each Chip-8 key.
+pub const KEYMAP: [Keycode; 16] = [
+    KEY_0, KEY_1, KEY_2, KEY_3, KEY_4, KEY_5, KEY_6, KEY_7, KEY_8, KEY_9, KEY_A, KEY_B, KEY_C,
+    KEY_D, KEY_E, KEY_F,
+];
+
+/// Pixels between tiles when running several instances.
+pub const TILE_GAP: u32 = 4;
+/// Outline colour of the instance receiving input.
+pub const FOCUS_RGB: (u8, u8, u8) = (255, 255, 0);
+/// Moves input to the next instance, or the previous one with Shift.
+pub const FOCUS_KEY: Keycode = Keycode::Tab;
+/// Toggles sending input to every instance.
+pub const LINK_KEY: Keycode = Keycode::F1;
+/// Toggles the rendering and emulation rates overlay.
+pub const STATS_KEY: Keycode = Keycode::F3;
+/// Toggles the registers and upcoming instructions overlay.
+pub const DEBUG_KEY: Keycode = Keycode::F4;
+/// Opens or closes the memory viewer.
+pub const MEMORY_KEY: Keycode = Keycode::F6;
+/// Opens or closes the sprite viewer.
+pub const SPRITE_KEY: Keycode = Keycode::F7;
+/// Toggles the keypad overlay.
+pub const KEYPAD_KEY: Keycode = Keycode::F8;
+/// Toggles grid lines between pixels.
+pub const GRID_KEY: Keycode = Keycode::G;
+/// Toggles the magnifier following the mouse.
+pub const ZOOM_KEY: Keycode = Keycode::I;
+/// Extensions of source files compiled or assembled when read, lowercase: Octo and assembly.
+pub const SOURCE_EXTENSIONS: [&str; 2] = ["o8", "asm"];
+
+/// Opens or closes the menu of recent ROMs.
+pub const RECENT_KEY: Keycode = Keycode::O;
+/// Opens or closes the menu of quirks.
+pub const QUIRKS_KEY: Keycode = Keycode::K;
+/// Saves the quirks to the game's profile while the menu of quirks is open.
+pub const SAVE_QUIRKS_KEY: Keycode = Keycode::S;
+/// Quirks listed in the menu of quirks, by the name profiles give them.
+const QUIRK_NAMES: [&str; 5] = [
+    "shift_vy",
+    "load_store_increment_i",
+    "jump_vx",
+    "vf_reset",
+    "clip_sprites",
+];
+
+const USAGE: &str = "Usage: cargo run [--config PATH] [--host ADDR | --join ADDR] \
+                     [--quirks PRESET]... [--linked] [--speed HZ] [--beep HZ] [--volume V] \
+                     [--start-paused] [--dump-state PATH] [--watch] [--debug] \
+                     [path/to/game...]
+       cargo run [--config PATH] --write-default-config";
+
+/// How to connect to the other player, if playing over the network.
+enum Peer {
+    Host(String),
+    Join(String),
+}
+
+/// Command-line options. Unset ones fall back to the config file.
+struct Options {
+    roms: Vec<String>,
+    /// Config file to read instead of the default one.
+    config: Option<PathBuf>,
+    /// Write the default config file instead of running anything.
+    write_default_config: bool,
+    /// Quirk presets to run every ROM with, instead of guessing.
+    quirks: Vec<Preset>,
+    /// Whether input goes to every instance at once.
+    linked: bool,
+    /// Instructions per second, instead of the ROM's usual speed.
+    speed: Option<u32>,
+    /// Buzzer pitch in Hz.
+    beep: Option<f32>,
+    /// Buzzer volume, from 0 to 1.
+    volume: Option<f32>,
+    /// Whether emulation starts paused.
+    start_paused: bool,
+    /// File to write the machine state to on exit, as JSON if it ends in `.json`.
+    dump_state: Option<PathBuf>,
+    /// Whether ROMs are reloaded when their files change.
+    watch: bool,
+    /// Whether debugger commands are read from standard input.
+    debug: bool,
+    peer: Option<Peer>,
+}
 
 fn main() {
-    let args: Vec<_> = env::args().collect();
-    if args.len() != 2 {
-        println!("Usage: cargo run path/to/game");
+    let mut opts = match parse_args(env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(e) => {
+            println!("{}\n{}", e, USAGE);
+            return;
+        }
+    };
+
+    if opts.write_default_config {
+        let Some(path) = opts.config.or_else(config::default_path) else {
+            println!("No config directory to write to");
+            return;
+        };
+        match config::write_default(&path) {
+            Ok(()) => println!("Wrote {}", path.display()),
+            Err(e) => println!("Unable to write config: {}", e),
+        }
         return;
     }
+    let config = match Config::load(opts.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("Unable to load config: {}", e);
+            return;
+        }
+    };
+    let (fast_forward, slow_motion) = (config.emulation.fast_forward, config.emulation.slow_motion);
+    if fast_forward < 0.0 || slow_motion <= 0.0 {
+        println!("Fast-forward and slow motion speeds must be positive");
+        return;
+    }
+    let speeds = Speeds {
+        fast_forward: (fast_forward > 0.0).then_some(fast_forward),
+        slow_motion,
+    };
 
-    // SDL setup
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
-    let window = video_subsystem
-        .window("chip8emu", WINDOW_WIDTH, WINDOW_HEIGHT)
-        .position_centered()
-        .opengl()
-        .build()
-        .unwrap();
-    let mut canvas = window.into_canvas().present_vsync().build().unwrap();
-    canvas.clear();
-    canvas.present();
-
-    let mut event_pump = sdl_context.event_pump().unwrap();
 
+    // Without a ROM to run, let the player pick one
+    let mut recent = Recent::load();
+    let mut roms: Vec<PathBuf> = opts.roms.iter().map(PathBuf::from).collect();
+    if roms.is_empty() {
+        let dir = config.launcher.directory.clone();
+        let dir = dir.unwrap_or_else(|| PathBuf::from("."));
+        let mut events = sdl_context.event_pump().unwrap();
+        match launcher::choose(&video_subsystem, &mut events, &dir, recent.roms()) {
+            Ok(Some(rom)) => roms.push(rom),
+            Ok(None) => return,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        }
+    }
+
+    // Read for the whole run, as one session's frontend hands it to the next
+    let mut debug_input = opts.debug.then(repl::read_stdin);
+    if debug_input.is_some() {
+        println!("Debugger ready, type help for commands");
+    }
+
+    // Runs until quit, or until a recent ROM is picked to run instead
+    loop {
+        // One instance per ROM, or per ROM and quirk preset
+        let mut instances = Instances::new();
+        let mut paths = Vec::new();
+        let mut hashes = Vec::new();
+        let mut session = None;
+        for rom in &roms {
+            let buffer = match read_rom(rom) {
+                Ok(buffer) => buffer,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            // The ROM's profile, if it has one, overrides the config file
+            let sha1 = RomInfo::new(&buffer, emulator::START_ADDRESS).sha1_hex();
+            let game = match GameConfig::load(&sha1) {
+                Ok(game) => game,
+                Err(e) => {
+                    println!("Unable to load profile: {}", e);
+                    return;
+                }
+            };
+            let speed = opts.speed.or(game.speed).or(config.emulation.speed);
+            let presets: Vec<_> = match opts.quirks.is_empty() {
+                true => vec![None],
+                false => opts.quirks.iter().map(Some).collect(),
+            };
+            for preset in presets {
+                let preset = preset.or(game.quirks.as_ref());
+                match load(
+                    &rom_name(rom),
+                    &buffer,
+                    preset,
+                    config.emulation.quirks.as_ref(),
+                ) {
+                    Ok(mut instance) => {
+                        if let Some(speed) = speed {
+                            instance.cycles_per_frame = (speed / timing::TIMER_HZ).max(1);
+                        }
+                        instances.push(instance);
+                        paths.push(rom.clone());
+                        hashes.push(sha1.clone());
+                    }
+                    Err(e) => {
+                        println!("Unable to load {}: {}", rom.display(), e);
+                        return;
+                    }
+                }
+            }
+            // Side-by-side displays share the first ROM's palette, rotation and keys, so their
+            // tiles line up and play alike
+            session.get_or_insert_with(|| {
+                let mut session = config.clone();
+                session.apply(&game);
+                session
+            });
+        }
+        let session = session.unwrap_or_else(|| config.clone());
+        let bindings = session.keymap().and_then(|keys| {
+            let turbo = session.turbo_keys()?;
+            Ok((keys, session.gamepad_map()?, turbo, session.macro_keys()?))
+        });
+        let (keymap, gamepad_map, turbo_keys, macro_keys) = match bindings {
+            Ok(bindings) => bindings,
+            Err(e) => {
+                println!("Bad key binding: {}", e);
+                return;
+            }
+        };
+        for rom in &roms {
+            recent.add(rom);
+        }
+        if let Err(e) = recent.save() {
+            println!("Unable to save recent ROMs: {}", e);
+        }
+        instances.set_linked(opts.linked);
+        if opts.peer.is_some() && instances.len() > 1 {
+            println!("Netplay runs a single instance\n{}", USAGE);
+            return;
+        }
+
+        let rotation = session.display.rotation.0;
+
+        // SDL setup
+        let (columns, rows) = multi::grid(instances.len());
+        let tiles = columns.max(rows) as u32;
+        let scale = (config.display.scale / tiles).max(1) as f32;
+        let window = video_subsystem
+            .window(
+                "chip8emu",
+                (tile_width(scale, rotation) * columns as f32 - TILE_GAP as f32) as u32,
+                (tile_height(scale, rotation) * rows as f32 - TILE_GAP as f32) as u32,
+            )
+            .position_centered()
+            .resizable()
+            .allow_highdpi()
+            .opengl()
+            .build()
+            .unwrap();
+        let mut canvas = window.into_canvas().present_vsync().build().unwrap();
+        icon::set(canvas.window_mut(), &session.display.colors());
+        canvas.clear();
+        canvas.present();
+        // Displays are drawn to textures and scaled up by the GPU, keeping pixels sharp
+        sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "nearest");
+        let texture_creator = canvas.texture_creator();
+        // Flashes are limited per second of the screen's refresh rate, which paces drawing
+        let refresh_rate = match canvas.window().display_mode() {
+            Ok(mode) if mode.refresh_rate > 0 => mode.refresh_rate as u32,
+            _ => timing::TIMER_HZ,
+        };
+        let (texture_width, texture_height) = display_size(rotation);
+        let textures = (0..instances.len())
+            .map(|_| {
+                texture_creator.create_texture_streaming(
+                    PixelFormatEnum::RGBA32,
+                    texture_width as u32,
+                    texture_height as u32,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let event_pump = sdl_context.event_pump().unwrap();
+        // Gamepads are opened as SDL reports them connected
+        let controllers = sdl_context.game_controller().unwrap();
+
+        let audio_subsystem = sdl_context.audio().unwrap();
+        let desired_spec = AudioSpecDesired {
+            freq: Some(44100),
+            channels: Some(1),
+            samples: None,
+        };
+        let audio_queue = audio_subsystem
+            .open_queue::<f32, _>(None, &desired_spec)
+            .unwrap();
+        audio_queue.resume();
+
+        let mut netplay = None;
+        if let Some(peer) = opts.peer.take() {
+            match connect(peer, instances.get_mut(0).unwrap()) {
+                Ok(session) => netplay = Some(session),
+                Err(e) => {
+                    println!("Unable to start netplay: {}", e);
+                    return;
+                }
+            }
+        }
+
+        // Source files are always watched, to rebuild them as they're saved
+        let watch = opts.watch || paths.iter().any(|path| is_source(path));
+        let watcher = match watch {
+            true => match Watcher::new(&paths) {
+                Ok(watcher) => Some(watcher),
+                Err(e) => {
+                    println!("Unable to watch ROMs: {}", e);
+                    return;
+                }
+            },
+            false => None,
+        };
+
+        let audio_config = AudioConfig {
+            frequency: opts.beep.unwrap_or(config.audio.frequency),
+            volume: opts.volume.unwrap_or(config.audio.volume).clamp(0.0, 1.0),
+            sample_rate: audio_queue.spec().freq as u32,
+            ..AudioConfig::default()
+        };
+        let mut frontend = Frontend {
+            canvas,
+            event_pump,
+            audio: audio_queue,
+            wave: SquareWave::new(audio_config),
+            keymap,
+            controllers,
+            gamepads: Vec::new(),
+            gamepad_map,
+            grid: (columns, rows),
+            scaling: config.display.scaling,
+            filter: config.display.filter,
+            scale,
+            origin: (0.0, 0.0),
+            textures,
+            palette: session.display.colors(),
+            palette_name: session.display.palette.name.clone(),
+            rgba: vec![0; render::RGBA_BUFFER_SIZE],
+            rotated: vec![0; render::RGBA_BUFFER_SIZE],
+            rotation,
+            refresh_rate,
+            limiters: config
+                .display
+                .reduce_flashing
+                .then(|| vec![FlashLimiter::new(refresh_rate); instances.len()]),
+            muted: config.audio.muted,
+            paths,
+            hashes,
+            watcher,
+            build_error: None,
+            debug_input: debug_input.take(),
+            osd: Osd::new(),
+            stats: None,
+            debug: false,
+            keypad: false,
+            pixel_grid: false,
+            magnifier: false,
+            mouse: None,
+            recent: recent.roms().to_vec(),
+            menu: None,
+            quirk_menu: None,
+            recording: config.recording.clone(),
+            recorder: None,
+            macros: macro_keys,
+            macro_recorder: None,
+            video: video_subsystem.clone(),
+            memory: None,
+            sprites: None,
+        };
+
+        frontend.fit();
+        let turbo = Turbo {
+            keys: turbo_keys,
+            period: timing::TIMER_HZ / session.turbo.rate.max(1),
+        };
+        let emulation = Emulation::spawn(instances, netplay, speeds, turbo);
+        if opts.start_paused {
+            emulation.send(Command::TogglePause);
+        }
+        let next = frontend.run(&emulation);
+        frontend.stop_recording();
+        debug_input = frontend.debug_input.take();
+        let instances = emulation.stop();
+        for instance in instances.iter() {
+            if let Some(e) = instance.error {
+                println!("Emulation error in {}: {}", instance.label, e);
+            }
+        }
+        match next {
+            Some(rom) => roms = vec![rom],
+            None => {
+                if let Some(path) = &opts.dump_state {
+                    dump_state(path, &instances);
+                }
+                return;
+            }
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut opts = Options {
+        roms: Vec::new(),
+        config: None,
+        write_default_config: false,
+        quirks: Vec::new(),
+        linked: false,
+        speed: None,
+        beep: None,
+        volume: None,
+        start_paused: false,
+        dump_state: None,
+        watch: false,
+        debug: false,
+        peer: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--config" => opts.config = Some(value()?.into()),
+            "--write-default-config" => opts.write_default_config = true,
+            "--host" => opts.peer = Some(Peer::Host(value()?)),
+            "--join" => opts.peer = Some(Peer::Join(value()?)),
+            "--quirks" => opts.quirks.push(value()?.parse()?),
+            "--linked" => opts.linked = true,
+            "--start-paused" => opts.start_paused = true,
+            "--dump-state" => opts.dump_state = Some(value()?.into()),
+            "--watch" => opts.watch = true,
+            "--debug" => opts.debug = true,
+            "--speed" => {
+                opts.speed = Some(value()?.parse().map_err(|e| format!("Bad speed: {}", e))?)
+            }
+            "--beep" => {
+                opts.beep = Some(value()?.parse().map_err(|e| format!("Bad pitch: {}", e))?)
+            }
+            "--volume" => {
+                opts.volume = Some(value()?.parse().map_err(|e| format!("Bad volume: {}", e))?)
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => opts.roms.push(arg),
+        }
+    }
+    Ok(opts)
+}
+
+/// Read a ROM, unpacking archives, compiling Octo source files and assembling assembly files. A ROM in a `.zip` archive can
+/// be picked as `archive.zip#entry`.
+fn read_rom(path: &Path) -> Result<Vec<u8>, String> {
+    let (file, entry) = romfile::split_entry(path);
+    let rom = romfile::read(&file, entry.as_deref())
+        .map_err(|e| format!("Unable to open {}: {}", path.display(), e))?;
+    if rom.name.to_lowercase().ends_with(".o8") {
+        let source = String::from_utf8_lossy(&rom.data);
+        return octo::compile(&source)
+            .map_err(|e| format!("Unable to compile {}: {}", path.display(), e));
+    }
+    if rom.name.to_lowercase().ends_with(".asm") {
+        let source = String::from_utf8_lossy(&rom.data);
+        return asm::assemble(&source)
+            .map_err(|e| format!("Unable to assemble {}:{}", path.display(), e));
+    }
+    Ok(rom.data)
+}
+
+/// Name of the ROM at `path` to show before it's identified: its file name, or its entry's in an
+/// archive, without extensions.
+fn rom_name(path: &Path) -> String {
+    let (file, entry) = romfile::split_entry(path);
+    let name = match entry {
+        Some(entry) => PathBuf::from(entry),
+        None => file,
+    };
+    let name = name.file_name().unwrap_or_default().to_string_lossy();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+    match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Load a ROM into a new instance, with the given quirks or ones to suit the ROM. `fallback` is
+/// used instead of guessing for ROMs the database doesn't know. `name` labels ROMs it doesn't
+/// know.
+fn load(
+    name: &str,
+    buffer: &[u8],
+    preset: Option<&Preset>,
+    fallback: Option<&Preset>,
+) -> Result<Instance, LoadError> {
     let mut chip8 = Emulator::new();
+    // Pick quirks to suit the instruction set the ROM uses
+    chip8.set_quirks(match fallback {
+        Some(fallback) => fallback.quirks,
+        None => analyze::analyze(buffer, emulator::START_ADDRESS)
+            .platform
+            .quirks(),
+    });
+    let rom_info = chip8.load_rom(buffer)?;
+    // Known games override the guessed settings
+    let mut cycles_per_frame = TICKS_PER_FRAME;
+    let mut label = format!("chip8emu - {}", name);
+    if let Some(entry) = rom_info.identify() {
+        chip8.set_quirks(entry.quirks);
+        cycles_per_frame = entry.cycles_per_frame;
+        label = format!("chip8emu - {}", entry.title);
+    }
+    if let Some(preset) = preset {
+        chip8.set_quirks(preset.quirks);
+        label = format!("{} ({})", label, preset.name);
+    }
+    Ok(Instance::new(chip8, label, cycles_per_frame))
+}
+
+/// Write every instance's machine state to `path`, as JSON if it ends in `.json` or as a save
+/// file otherwise. With several instances, each file is numbered, e.g. `state-2.json`.
+fn dump_state(path: &Path, instances: &Instances) {
+    for (index, instance) in instances.iter().enumerate() {
+        let path = match instances.len() {
+            1 => path.to_path_buf(),
+            _ => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let name = match path.extension() {
+                    Some(ext) => format!("{}-{}.{}", stem, index + 1, ext.to_string_lossy()),
+                    None => format!("{}-{}", stem, index + 1),
+                };
+                path.with_file_name(name)
+            }
+        };
+        let state = instance.emu.save_state();
+        let bytes = match path.extension().is_some_and(|ext| ext == "json") {
+            true => serde_json::to_vec_pretty(&state).unwrap(),
+            false => state.to_bytes(),
+        };
+        match fs::write(&path, bytes) {
+            Ok(()) => println!("Wrote state to {}", path.display()),
+            Err(e) => println!("Unable to write {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Wait for or connect to the other player and start a netplay session.
+fn connect(peer: Peer, instance: &mut Instance) -> Result<Netplay<TcpStream>, NetplayError> {
+    let stream = match &peer {
+        Peer::Host(addr) => {
+            let listener = TcpListener::bind(addr)?;
+            println!("Waiting for a player on {}", listener.local_addr()?);
+            listener.accept()?.0
+        }
+        Peer::Join(addr) => TcpStream::connect(addr)?,
+    };
+    stream.set_nodelay(true)?;
+    match peer {
+        Peer::Host(_) => Netplay::host(
+            stream,
+            &mut instance.emu,
+            instance.cycles_per_frame,
+            netplay::DEFAULT_DELAY,
+        ),
+        Peer::Join(_) => Netplay::join(stream, &mut instance.emu, instance.cycles_per_frame),
+    }
+}
+
+/// Lines of the debug overlay: registers, timers, then the next instructions.
+fn cpu_lines(cpu: &Cpu) -> Vec<String> {
+    let mut lines = vec![format!(
+        "PC {:03X}  I {:03X}  SP {:X}  DT {:02X}  ST {:02X}",
+        cpu.pc, cpu.i, cpu.sp, cpu.delay, cpu.sound
+    )];
+    for (row, values) in cpu.v.chunks(4).enumerate() {
+        let registers: Vec<_> = values
+            .iter()
+            .enumerate()
+            .map(|(col, v)| format!("V{:X} {:02X}", row * 4 + col, v))
+            .collect();
+        lines.push(registers.join("  "));
+    }
+    for (addr, op) in &cpu.next {
+        let text = opcodes::decode(*op).map_or_else(|| "???".to_string(), |i| i.to_string());
+        let marker = if *addr == cpu.pc { '>' } else { ' ' };
+        lines.push(format!("{} {:03X}  {:04X}  {}", marker, addr, op, text));
+    }
+    lines
+}
+
+/// The quirk listed at `index` in [QUIRK_NAMES].
+fn quirk_flag(quirks: &mut Quirks, index: usize) -> &mut bool {
+    match index {
+        0 => &mut quirks.shift_vy,
+        1 => &mut quirks.load_store_increment_i,
+        2 => &mut quirks.jump_vx,
+        3 => &mut quirks.vf_reset,
+        _ => &mut quirks.clip_sprites,
+    }
+}
+
+/// Whether the ROM at `path` is source code, compiled from Octo or assembled when read.
+fn is_source(path: &Path) -> bool {
+    let (file, entry) = romfile::split_entry(path);
+    let name = entry.map_or(file, PathBuf::from);
+    let ext = name
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    ext.is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Width and height of the display in Chip-8 pixels once rotated.
+fn display_size(rotation: Rotation) -> (usize, usize) {
+    rotation.size(emulator::DISPLAY_WIDTH, emulator::DISPLAY_HEIGHT)
+}
+
+/// Width of a tile, including the gap after it.
+fn tile_width(scale: f32, rotation: Rotation) -> f32 {
+    display_size(rotation).0 as f32 * scale + TILE_GAP as f32
+}
+
+/// Height of a tile, including the gap after it.
+fn tile_height(scale: f32, rotation: Rotation) -> f32 {
+    display_size(rotation).1 as f32 * scale + TILE_GAP as f32
+}
+
+/// Window, keyboard and speaker, with the settings they're used with.
+struct Frontend<'a> {
+    canvas: Canvas<Window>,
+    event_pump: EventPump,
+    audio: AudioQueue<f32>,
+    wave: SquareWave,
+    /// Key bound to each Chip-8 key.
+    keymap: [Keycode; 16],
+    controllers: GameControllerSubsystem,
+    /// Connected gamepads, kept open to receive their events.
+    gamepads: Vec<GameController>,
+    /// Chip-8 key bound to each gamepad button.
+    gamepad_map: Vec<(Button, usize)>,
+    /// Columns and rows of tiles.
+    grid: (usize, usize),
+    /// How the tiles are fitted to the window.
+    scaling: Scaling,
+    /// Effect drawn over each display.
+    filter: Filter,
+    /// Size of a Chip-8 pixel in screen pixels, fitted to the window.
+    scale: f32,
+    /// Top-left corner of the first tile, centring the tiles in the window.
+    origin: (f32, f32),
+    /// Each instance's display, scaled up when drawn.
+    textures: Vec<Texture<'a>>,
+    /// Colours of the displays.
+    palette: PlanePalette,
+    /// Preset the palette was chosen from.
+    palette_name: String,
+    /// Display pixels being rendered.
+    rgba: Vec<u8>,
+    /// Display pixels being uploaded to a texture, once rotated.
+    rotated: Vec<u8>,
+    /// How far the displays are turned.
+    rotation: Rotation,
+    /// Times per second the screen is redrawn.
+    refresh_rate: u32,
+    /// Guard against strobing for each display, if flashes are reduced.
+    limiters: Option<Vec<FlashLimiter>>,
+    muted: bool,
+    /// ROM each instance was loaded from.
+    paths: Vec<PathBuf>,
+    /// SHA-1 hash of each instance's ROM, naming its profile.
+    hashes: Vec<String>,
+    /// Watches the ROMs to reload them when they change, if asked to.
+    watcher: Option<Watcher>,
+    /// Why the ROMs last failed to reload, shown until they reload, e.g. an assembly error.
+    build_error: Option<String>,
+    /// Debugger commands typed on standard input, if reading them.
+    debug_input: Option<Receiver<String>>,
+    /// Messages confirming hotkeys.
+    osd: Osd,
+    /// Rates shown in the corner, if turned on.
+    stats: Option<Stats>,
+    /// Whether the focused instance's CPU state is shown in the corner.
+    debug: bool,
+    /// Whether the keypad is shown in the corner.
+    keypad: bool,
+    /// Whether lines are drawn between pixels.
+    pixel_grid: bool,
+    /// Whether the pixels under the mouse are shown magnified.
+    magnifier: bool,
+    /// Position of the mouse over the main window in drawable pixels, if it's there.
+    mouse: Option<(f32, f32)>,
+    video: VideoSubsystem,
+    /// Hex dump window, if open.
+    memory: Option<MemoryViewer>,
+    /// Sprite window, if open.
+    sprites: Option<SpriteViewer>,
+    /// Recently opened ROMs, newest first.
+    recent: Vec<PathBuf>,
+    /// Entry selected in the menu of recent ROMs, if open.
+    menu: Option<usize>,
+    /// Quirk selected in the menu of quirks, if open.
+    quirk_menu: Option<usize>,
+    /// How gameplay is recorded.
+    recording: RecordingConfig,
+    /// Recording in progress and the instance being recorded, if recording.
+    recorder: Option<(usize, Recorder)>,
+    /// Key playing each input macro, with its name.
+    macros: Vec<(Keycode, String)>,
+    /// Macro being recorded from the focused instance's keys, if one is.
+    macro_recorder: Option<MacroRecorder>,
+}
+impl Frontend<'_> {
+    /// Send input to the emulation thread and draw the frames it sends back, until the window is
+    /// closed, every instance stops or a recent ROM is picked, which is returned. Drawing is paced
+    /// by vsync and emulation by its own thread, so games run at the same speed whatever the
+    /// refresh rate.
+    fn run(&mut self, emulation: &Emulation) -> Option<PathBuf> {
+        let mut samples = vec![0.0; self.wave.samples_per_frame()];
+        // Keys held on the keyboard and gamepads
+        let (mut keyboard, mut gamepad) = (0, 0);
+        let mut title = String::new();
+        let mut last = None;
+        loop {
+            let events: Vec<_> = self.event_pump.poll_iter().collect();
+            for evt in events {
+                let command = match evt {
+                    // The menu takes every key while open
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        window_id,
+                        ..
+                    } if self.menu.is_some() && window_id == self.canvas.window().id() => {
+                        if let Some(rom) = self.menu_key(key) {
+                            return Some(rom);
+                        }
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        window_id,
+                        ..
+                    } if self.quirk_menu.is_some() && window_id == self.canvas.window().id() => {
+                        match self.quirk_menu_key(key, last.as_ref()) {
+                            Some(command) => command,
+                            None => continue,
+                        }
+                    }
+                    Event::Quit { .. }
+                    | Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } => return None,
+                    Event::KeyDown {
+                        keycode: Some(RECENT_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.menu = Some(0);
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(QUIRKS_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.quirk_menu = Some(0);
+                        continue;
+                    }
+                    Event::Window {
+                        win_event: WindowEvent::SizeChanged(..),
+                        ..
+                    } => {
+                        self.fit();
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(PALETTE_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.next_palette();
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(FLASH_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.limiters = match self.limiters {
+                            Some(_) => None,
+                            None => Some(vec![
+                                FlashLimiter::new(self.refresh_rate);
+                                self.textures.len()
+                            ]),
+                        };
+                        let state = if self.limiters.is_some() { "on" } else { "off" };
+                        self.osd.show(format!("Reduce flashing: {}", state));
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(FILTER_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.filter = self.filter.next();
+                        self.osd.show(format!("Filter: {}", self.filter.name()));
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(FULLSCREEN_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        let window = self.canvas.window_mut();
+                        let fullscreen = match window.fullscreen_state() {
+                            FullscreenType::Off => FullscreenType::Desktop,
+                            _ => FullscreenType::Off,
+                        };
+                        if let Err(e) = window.set_fullscreen(fullscreen) {
+                            self.osd.show(format!("Unable to change fullscreen: {}", e));
+                        }
+                        self.fit();
+                        continue;
+                    }
+                    // With a second window open, closing the game window doesn't quit by itself
+                    Event::Window {
+                        win_event: WindowEvent::Close,
+                        window_id,
+                        ..
+                    } => {
+                        if self.memory_window(window_id) {
+                            self.memory = None;
+                        } else if self.sprite_window(window_id) {
+                            self.sprites = None;
+                        } else {
+                            return None;
+                        }
+                        self.watch_memory()
+                    }
+                    Event::MouseWheel { window_id, y, .. } if self.memory_window(window_id) => {
+                        self.memory.as_mut().unwrap().scroll(-y as isize);
+                        continue;
+                    }
+                    Event::MouseWheel { window_id, y, .. } if self.sprite_window(window_id) => {
+                        self.sprites.as_mut().unwrap().scroll(-y as isize);
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        window_id,
+                        ..
+                    } if self.memory_window(window_id)
+                        && self.memory.as_mut().unwrap().key(key) =>
+                    {
+                        continue
+                    }
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        window_id,
+                        ..
+                    } if self.sprite_window(window_id)
+                        && self.sprites.as_mut().unwrap().key(key) =>
+                    {
+                        continue
+                    }
+                    Event::KeyDown {
+                        keycode: Some(MEMORY_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        if self.memory.take().is_none() {
+                            match MemoryViewer::open(&self.video) {
+                                Ok(viewer) => self.memory = Some(viewer),
+                                Err(e) => {
+                                    self.osd
+                                        .show(format!("Unable to open memory viewer: {}", e));
+                                    continue;
+                                }
+                            }
+                        }
+                        self.watch_memory()
+                    }
+                    Event::KeyDown {
+                        keycode: Some(SPRITE_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        if self.sprites.take().is_none() {
+                            let [bg, fg, ..] =
+                                self.palette.colors.map(|[r, g, b, _]| Color::RGB(r, g, b));
+                            match SpriteViewer::open(&self.video, fg, bg) {
+                                Ok(viewer) => self.sprites = Some(viewer),
+                                Err(e) => {
+                                    self.osd
+                                        .show(format!("Unable to open sprite viewer: {}", e));
+                                    continue;
+                                }
+                            }
+                        }
+                        self.watch_memory()
+                    }
+                    Event::KeyDown {
+                        keycode: Some(REWIND_KEY),
+                        ..
+                    } => Command::Rewind(true),
+                    Event::KeyUp {
+                        keycode: Some(REWIND_KEY),
+                        ..
+                    } => Command::Rewind(false),
+                    Event::KeyDown {
+                        keycode: Some(FOCUS_KEY),
+                        keymod,
+                        ..
+                    } => {
+                        if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                            Command::FocusPrevious
+                        } else {
+                            Command::FocusNext
+                        }
+                    }
+                    Event::KeyDown {
+                        keycode: Some(LINK_KEY),
+                        repeat: false,
+                        ..
+                    } => Command::ToggleLinked,
+                    Event::KeyDown {
+                        keycode: Some(FAST_FORWARD_KEY),
+                        ..
+                    } => Command::FastForward(true),
+                    Event::KeyUp {
+                        keycode: Some(FAST_FORWARD_KEY),
+                        ..
+                    } => Command::FastForward(false),
+                    Event::KeyDown {
+                        keycode: Some(SLOW_MOTION_KEY),
+                        repeat: false,
+                        ..
+                    } => Command::ToggleSlowMotion,
+                    Event::KeyDown {
+                        keycode: Some(PAUSE_KEY),
+                        repeat: false,
+                        ..
+                    } => Command::TogglePause,
+                    Event::KeyDown {
+                        keycode: Some(ADVANCE_KEY),
+                        keymod,
+                        ..
+                    } => {
+                        if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                            Command::Step
+                        } else {
+                            Command::AdvanceFrame
+                        }
+                    }
+                    Event::KeyDown {
+                        keycode: Some(RESET_KEY),
+                        keymod,
+                        repeat: false,
+                        ..
+                    } => {
+                        if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                            self.osd.show("Soft reset");
+                            Command::SoftReset
+                        } else {
+                            match self.reload("ROM reloaded") {
+                                Some(command) => command,
+                                None => continue,
+                            }
+                        }
+                    }
+                    Event::KeyDown {
+                        keycode: Some(STATS_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.stats = match self.stats {
+                            Some(_) => None,
+                            None => Some(Stats::new()),
+                        };
+                        continue;
+                    }
+                    Event::MouseMotion {
+                        window_id, x, y, ..
+                    } if window_id == self.canvas.window().id() => {
+                        // Mouse positions are in window points, fewer than pixels on HiDPI screens
+                        let (width, _) = self.canvas.window().size();
+                        let (pixels, _) = self.canvas.output_size().unwrap();
+                        let ratio = pixels as f32 / width.max(1) as f32;
+                        self.mouse = Some((x as f32 * ratio, y as f32 * ratio));
+                        continue;
+                    }
+                    Event::Window {
+                        win_event: WindowEvent::Leave,
+                        window_id,
+                        ..
+                    } if window_id == self.canvas.window().id() => {
+                        self.mouse = None;
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(GRID_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.pixel_grid = !self.pixel_grid;
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(ZOOM_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.magnifier = !self.magnifier;
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(KEYPAD_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.keypad = !self.keypad;
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(DEBUG_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.debug = !self.debug;
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(RECORD_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        match self.recorder {
+                            Some(_) => self.stop_recording(),
+                            None => {
+                                self.start_recording(last.as_ref().map_or(0, |f: &Frame| f.focus))
+                            }
+                        }
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(MUTE_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        self.muted = !self.muted;
+                        self.osd
+                            .show(if self.muted { "Sound off" } else { "Sound on" });
+                        continue;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        keymod,
+                        repeat: false,
+                        ..
+                    } if self.macro_name(key).is_some() => {
+                        let name = self.macro_name(key).unwrap().to_string();
+                        if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+                            self.toggle_macro_recording(&name, last.as_ref());
+                            continue;
+                        }
+                        match macros::load(&name) {
+                            Ok(events) => {
+                                self.osd.show(format!("Playing macro {}", name));
+                                Command::PlayMacro(events)
+                            }
+                            Err(e) => {
+                                self.osd.show(format!("Unable to play macro: {}", e));
+                                continue;
+                            }
+                        }
+                    }
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } => match self.key_to_button(key) {
+                        Some(k) => {
+                            keyboard |= 1 << k;
+                            Command::Keys(keyboard | gamepad)
+                        }
+                        None => continue,
+                    },
+                    Event::KeyUp {
+                        keycode: Some(key), ..
+                    } => match self.key_to_button(key) {
+                        Some(k) => {
+                            keyboard &= !(1 << k);
+                            Command::Keys(keyboard | gamepad)
+                        }
+                        None => continue,
+                    },
+                    Event::ControllerButtonDown { button, .. } => match self.gamepad_button(button)
+                    {
+                        Some(k) => {
+                            gamepad |= 1 << k;
+                            Command::Keys(keyboard | gamepad)
+                        }
+                        None => continue,
+                    },
+                    Event::ControllerButtonUp { button, .. } => match self.gamepad_button(button) {
+                        Some(k) => {
+                            gamepad &= !(1 << k);
+                            Command::Keys(keyboard | gamepad)
+                        }
+                        None => continue,
+                    },
+                    Event::ControllerDeviceAdded { which, .. } => {
+                        self.open_gamepad(which);
+                        continue;
+                    }
+                    Event::ControllerDeviceRemoved { which, .. } => {
+                        self.gamepads.retain(|pad| pad.instance_id() != which);
+                        // Release whatever the gamepad was holding
+                        gamepad = 0;
+                        Command::Keys(keyboard)
+                    }
+                    _ => continue,
+                };
+                emulation.send(command);
+            }
+
+            for line in self.debug_input.iter().flat_map(Receiver::try_iter) {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match line.parse() {
+                    Ok(command) => emulation.send(Command::Debug(command)),
+                    Err(e) => println!("Bad command: {}", e),
+                }
+            }
+
+            if self.watcher.as_mut().is_some_and(Watcher::changed) {
+                if let Some(command) = self.reload("ROM changed, reloaded") {
+                    emulation.send(command);
+                }
+            }
+
+            let frames = emulation.frames()?;
+            for frame in frames {
+                // Queue a frame of sound per emulated frame, dropping it if the queue is backed up
+                let queued = self.audio.size() as usize / std::mem::size_of::<f32>();
+                if queued < samples.len() * 2 {
+                    self.wave.fill(frame.sound && !self.muted, &mut samples);
+                    self.audio.queue_audio(&samples).unwrap();
+                }
+                self.announce(last.as_ref(), &frame);
+                if let Some(stats) = &mut self.stats {
+                    stats.emulated(frame.frames, frame.cycles);
+                }
+                self.record(&frame);
+                if let Some(recorder) = &mut self.macro_recorder {
+                    recorder.push(frame.frames, frame.keys);
+                }
+                last = Some(frame);
+            }
+
+            if let Some(frame) = &last {
+                let link = if frame.linked { ", linked" } else { "" };
+                let mut label = match frame.displays.len() {
+                    1 => frame.label.clone(),
+                    n => format!("{} [{}/{}{}]", frame.label, frame.focus + 1, n, link),
+                };
+                if frame.paused {
+                    label.push_str(" (paused)");
+                }
+                match frame.rate {
+                    Some(rate) if rate != 1.0 => label.push_str(&format!(" ({}x)", rate)),
+                    Some(_) => {}
+                    None => label.push_str(" (uncapped)"),
+                }
+                if self.muted {
+                    label.push_str(" (muted)");
+                }
+                if self.recorder.is_some() {
+                    label.push_str(" (recording)");
+                }
+                if label != title {
+                    self.canvas.window_mut().set_title(&label).unwrap();
+                    title = label;
+                }
+                self.draw_frame(frame);
+                if let (Some(viewer), Some(ram)) = (&mut self.memory, &frame.ram) {
+                    viewer.draw(ram, frame.cpu.pc, frame.cpu.i);
+                }
+                if let (Some(viewer), Some(ram)) = (&mut self.sprites, &frame.ram) {
+                    viewer.draw(ram, frame.cpu.i);
+                }
+            }
+        }
+    }
+
+    /// Fit the tiles to the window's drawable area, which is larger than its size on HiDPI
+    /// screens, centred and keeping their shape.
+    fn fit(&mut self) {
+        let (width, height) = self.canvas.output_size().unwrap();
+        let (width, height) = (width as f32, height as f32);
+        let (columns, rows) = (self.grid.0 as f32, self.grid.1 as f32);
+        // Gaps between tiles don't scale
+        let gap = TILE_GAP as f32;
+        let (display_width, display_height) = display_size(self.rotation);
+        let pixels_x = (width - (columns - 1.0) * gap) / (columns * display_width as f32);
+        let pixels_y = (height - (rows - 1.0) * gap) / (rows * display_height as f32);
+        let scale = pixels_x.min(pixels_y);
+        self.scale = match self.scaling {
+            Scaling::Integer => scale.floor().max(1.0),
+            Scaling::Stretch => scale.max(1.0),
+        };
+        let used_x = tile_width(self.scale, self.rotation) * columns - gap;
+        let used_y = tile_height(self.scale, self.rotation) * rows - gap;
+        self.origin = (
+            ((width - used_x) / 2.0).floor(),
+            ((height - used_y) / 2.0).floor(),
+        );
+    }
+
+    /// Draw every instance's display tiled, outlining those receiving input.
+    fn draw_frame(&mut self, frame: &Frame) {
+        // Clear canvas
+        self.canvas.set_draw_color(Color::RGB(
+            LETTERBOX_RGB.0,
+            LETTERBOX_RGB.1,
+            LETTERBOX_RGB.2,
+        ));
+        self.canvas.clear();
+
+        let (columns, _) = self.grid;
+        let width = tile_width(self.scale, self.rotation) - TILE_GAP as f32;
+        let height = tile_height(self.scale, self.rotation) - TILE_GAP as f32;
+        let (display_width, display_height) = display_size(self.rotation);
+        let mut tiles = Vec::with_capacity(frame.displays.len());
+        for (index, display) in frame.displays.iter().enumerate() {
+            let (column, row) = (index % columns, index / columns);
+            let x = self.origin.0 + column as f32 * tile_width(self.scale, self.rotation);
+            let y = self.origin.1 + row as f32 * tile_height(self.scale, self.rotation);
+            let tile = FRect::new(x, y, width, height);
+            render::render_rgba_planes(display, &mut self.rgba, &self.palette);
+            let (rgba, rotated) = (&self.rgba, &mut self.rotated);
+            render::rotate_rgba(rgba, emulator::DISPLAY_WIDTH, rotated, self.rotation);
+            if let Some(limiters) = &mut self.limiters {
+                limiters[index].apply(&mut self.rotated);
+            }
+            let texture = &mut self.textures[index];
+            texture
+                .update(None, &self.rotated, display_width * 4)
+                .unwrap();
+            self.canvas.copy_f(texture, None, tile).unwrap();
+            self.filter.draw(&mut self.canvas, tile, self.scale);
+            if self.pixel_grid {
+                let (columns, rows) = (display_width, display_height);
+                inspect::draw_grid(&mut self.canvas, tile, columns, rows, self.scale);
+            }
+            tiles.push(tile);
+            let focused = frame.linked || index == frame.focus;
+            if frame.displays.len() > 1 && focused {
+                self.canvas
+                    .set_draw_color(Color::RGB(FOCUS_RGB.0, FOCUS_RGB.1, FOCUS_RGB.2));
+                self.canvas.draw_frect(tile).unwrap();
+            }
+        }
+        if frame.paused {
+            self.draw_pause_indicator();
+        }
+        // Overlays are stacked in the top-left corner
+        let mut lines = Vec::new();
+        if let Some(stats) = &mut self.stats {
+            stats.drawn();
+            lines.extend_from_slice(stats.lines());
+        }
+        if self.debug {
+            lines.extend(cpu_lines(&frame.cpu));
+        }
+        if let Some(selected) = self.menu {
+            lines.push("Recent ROMs".to_string());
+            if self.recent.is_empty() {
+                lines.push("  None yet".to_string());
+            }
+            for (index, rom) in self.recent.iter().enumerate() {
+                let marker = if index == selected { '>' } else { ' ' };
+                let name = rom.file_name().unwrap_or_default().to_string_lossy();
+                lines.push(format!("{} {}", marker, name));
+            }
+        }
+        if let Some(selected) = self.quirk_menu {
+            lines.push("Quirks (Enter: toggle, S: save)".to_string());
+            let mut quirks = frame.quirks;
+            for (index, name) in QUIRK_NAMES.iter().enumerate() {
+                let marker = if index == selected { '>' } else { ' ' };
+                let check = if *quirk_flag(&mut quirks, index) {
+                    'x'
+                } else {
+                    ' '
+                };
+                lines.push(format!("{} [{}] {}", marker, check, name));
+            }
+        }
+        let pixel = (self.scale as u32 / 5).max(2);
+        if let Some(error) = &self.build_error {
+            // Wrapped to the window, as errors name the file and can be long
+            let (width, _) = self.canvas.output_size().unwrap();
+            let columns = (width / (osd::CHAR_WIDTH * pixel)).saturating_sub(2).max(1) as usize;
+            let chars: Vec<char> = error.chars().collect();
+            lines.extend(chars.chunks(columns).map(String::from_iter));
+        }
+        for (line, text) in lines.iter().enumerate() {
+            let top = (line as u32 * osd::LINE_HEIGHT + 1) * pixel;
+            osd::draw_text(&mut self.canvas, text, pixel as i32, top as i32, pixel);
+        }
+        if self.keypad {
+            keypad::draw(&mut self.canvas, frame.keys, &self.keymap, pixel);
+        }
+        if let Some(mouse) = self.mouse.filter(|_| self.magnifier) {
+            for (texture, tile) in self.textures.iter().zip(tiles) {
+                let canvas = &mut self.canvas;
+                let (scale, rotation) = (self.scale, self.rotation);
+                if inspect::draw_magnifier(canvas, texture, tile, scale, rotation, mouse, pixel) {
+                    break;
+                }
+            }
+        }
+        self.osd.draw(&mut self.canvas, pixel);
+        self.canvas.present();
+    }
+
+    /// Name of the macro `key` plays, if it plays one.
+    fn macro_name(&self, key: Keycode) -> Option<&str> {
+        self.macros
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Start recording the macro called `name` from the focused instance, as of the `last` frame,
+    /// or stop and save the macro being recorded.
+    fn toggle_macro_recording(&mut self, name: &str, last: Option<&Frame>) {
+        let (frames, keys) = last.map_or((0, 0), |frame| (frame.frames, frame.keys));
+        let Some(recorder) = self.macro_recorder.take() else {
+            self.macro_recorder = Some(MacroRecorder::new(name, frames, keys));
+            self.osd.show(format!("Recording macro {}", name));
+            return;
+        };
+        let name = recorder.name().to_string();
+        let events = recorder.finish(frames);
+        self.osd.show(match macros::save(&name, &events) {
+            Ok(_) => format!("Saved macro {}", name),
+            Err(e) => format!("Unable to save macro: {}", e),
+        });
+    }
+
+    /// Start recording the display of the instance at `index`.
+    fn start_recording(&mut self, index: usize) {
+        let (width, height) = display_size(self.rotation);
+        let rom = &self.paths[index];
+        match Recorder::start(&self.recording, rom, width, height) {
+            Ok(recorder) => {
+                self.osd
+                    .show(format!("Recording {}", recorder.path().display()));
+                self.recorder = Some((index, recorder));
+            }
+            Err(e) => self.osd.show(format!("Unable to record: {}", e)),
+        }
+    }
+
+    /// Finish the recording in progress, if there is one.
+    fn stop_recording(&mut self) {
+        let Some((_, recorder)) = self.recorder.take() else {
+            return;
+        };
+        let message = match recorder.finish() {
+            Ok(path) => format!("Saved {}", path.display()),
+            Err(e) => format!("Unable to save recording: {}", e),
+        };
+        // Printed too, in case the window is closing
+        println!("{}", message);
+        self.osd.show(message);
+    }
+
+    /// Add an emulated frame to the recording in progress, if there is one. Paused frames are left
+    /// out.
+    fn record(&mut self, frame: &Frame) {
+        let Some((index, recorder)) = &mut self.recorder else {
+            return;
+        };
+        if frame.paused {
+            return;
+        }
+        render::render_rgba_planes(&frame.displays[*index], &mut self.rgba, &self.palette);
+        let (rgba, rotated) = (&self.rgba, &mut self.rotated);
+        render::rotate_rgba(rgba, emulator::DISPLAY_WIDTH, rotated, self.rotation);
+        if let Err(e) = recorder.push(&self.rotated) {
+            self.recorder = None;
+            self.osd.show(format!("Recording stopped: {}", e));
+        }
+    }
+
+    /// Show messages for whatever hotkeys changed since the previous frame.
+    fn announce(&mut self, previous: Option<&Frame>, frame: &Frame) {
+        let Some(previous) = previous else {
+            return;
+        };
+        if frame.paused != previous.paused {
+            self.osd
+                .show(if frame.paused { "Paused" } else { "Resumed" });
+        }
+        // Pausing runs at normal speed without changing the speed chosen
+        if frame.rate != previous.rate && !frame.paused && !previous.paused {
+            self.osd.show(match frame.rate {
+                Some(rate) => format!("Speed {}%", (rate * 100.0).round()),
+                None => "Speed uncapped".to_string(),
+            });
+        }
+        if frame.linked != previous.linked {
+            self.osd.show(if frame.linked {
+                "Input to every instance"
+            } else {
+                "Input to one instance"
+            });
+        }
+        if frame.focus != previous.focus {
+            self.osd.show(format!(
+                "Input to {}/{}: {}",
+                frame.focus + 1,
+                frame.displays.len(),
+                frame.label
+            ));
+        }
+    }
+
+    /// Draw two bars in the top-right corner of the window.
+    fn draw_pause_indicator(&mut self) {
+        let size = (self.scale as u32).max(2) * 4;
+        let (width, _) = self.canvas.output_size().unwrap();
+        let right = width as i32 - size as i32 / 2;
+        let top = size as i32 / 2;
+        let bar = size / 3;
+        self.canvas
+            .set_draw_color(Color::RGB(PAUSED_RGB.0, PAUSED_RGB.1, PAUSED_RGB.2));
+        for left in [right - size as i32, right - bar as i32] {
+            self.canvas
+                .fill_rect(Rect::new(left, top, bar, size))
+                .unwrap();
+        }
+    }
 
-    let mut rom = File::open(&args[1]).expect("Unable to open file");
-    let mut buffer = Vec::new();
-    rom.read_to_end(&mut buffer).unwrap();
-    chip8.load(&buffer);
-
-    'game_loop: loop {
-        for evt in event_pump.poll_iter() {
-            match evt {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => {
-                    break 'game_loop;
-                }
-                Event::KeyDown {
-                    keycode: Some(key), ..
-                } => {
-                    if let Some(k) = key_to_button(key) {
-                        chip8.keypress(k, true);
-                    }
-                }
-                Event::KeyUp {
-                    keycode: Some(key), ..
-                } => {
-                    if let Some(k) = key_to_button(key) {
-                        chip8.keypress(k, false);
-                    }
-                }
-                _ => (),
-            }
-        }
-        for _ in 0..TICKS_PER_FRAME {
-            chip8.tick();
-        }
-        chip8.tick_timers();
-        draw_screen(&chip8, &mut canvas);
-    }
-}
-
-fn draw_screen(emu: &Emulator, canvas: &mut Canvas<Window>) {
-    // Clear canvas
-    canvas.set_draw_color(Color::RGB(BG_RGB.0, BG_RGB.1, BG_RGB.2));
-    canvas.clear();
-
-    let screen_buf = emu.get_display();
-    // Set to foreground colour, iterate thru pixels, check if should draw
-    canvas.set_draw_color(Color::RGB(FG_RGB.0, FG_RGB.1, FG_RGB.2));
-    for (i, pixel) in screen_buf.iter().enumerate() {
-        if *pixel {
-            // Convert index to 2D [x,y] position
-            let x = (i % emulator::DISPLAY_WIDTH) as u32;
-            let y = (i / emulator::DISPLAY_WIDTH) as u32;
-
-            // Draw scaled-up rectangle @ [x,y]
-            let rect = Rect::new((x * SCALE) as i32, (y * SCALE) as i32, SCALE, SCALE);
-            canvas.fill_rect(rect).unwrap();
-        }
-    }
-    canvas.present();
-}
-
-fn key_to_button(key: Keycode) -> Option<usize> {
-    match key {
-        KEY_1 => Some(0x1),
-        KEY_2 => Some(0x2),
-        KEY_3 => Some(0x3),
-        KEY_C => Some(0xC),
-        KEY_4 => Some(0x4),
-        KEY_5 => Some(0x5),
-        KEY_6 => Some(0x6),
-        KEY_D => Some(0xD),
-        KEY_7 => Some(0x7),
-        KEY_8 => Some(0x8),
-        KEY_9 => Some(0x9),
-        KEY_E => Some(0xE),
-        KEY_A => Some(0xA),
-        KEY_0 => Some(0x0),
-        KEY_B => Some(0xB),
-        KEY_F => Some(0xF),
-        _ => None,
+    /// Handle a key pressed with the menu of recent ROMs open. Returns the ROM picked, if one was.
+    fn menu_key(&mut self, key: Keycode) -> Option<PathBuf> {
+        let selected = self.menu.as_mut()?;
+        match key {
+            Keycode::Up => *selected = selected.saturating_sub(1),
+            Keycode::Down => *selected = (*selected + 1).min(self.recent.len().saturating_sub(1)),
+            Keycode::Return | Keycode::KpEnter => return self.recent.get(*selected).cloned(),
+            Keycode::Escape | RECENT_KEY => self.menu = None,
+            _ => {}
+        }
+        None
+    }
+
+    /// Handle a key pressed with the menu of quirks open, as of the `last` frame. Returns the
+    /// command changing the focused instance's quirks, if one was toggled.
+    fn quirk_menu_key(&mut self, key: Keycode, last: Option<&Frame>) -> Option<Command> {
+        let selected = self.quirk_menu.as_mut()?;
+        let frame = last?;
+        match key {
+            Keycode::Up => *selected = selected.saturating_sub(1),
+            Keycode::Down => *selected = (*selected + 1).min(QUIRK_NAMES.len() - 1),
+            Keycode::Return | Keycode::KpEnter => {
+                let mut quirks = frame.quirks;
+                let flag = quirk_flag(&mut quirks, *selected);
+                *flag = !*flag;
+                let state = if *flag { "on" } else { "off" };
+                self.osd
+                    .show(format!("{}: {}", QUIRK_NAMES[*selected], state));
+                return Some(Command::SetQuirks(quirks));
+            }
+            SAVE_QUIRKS_KEY => {
+                let sha1 = &self.hashes[frame.focus];
+                let saved = GameConfig::load(sha1).and_then(|mut game| {
+                    game.quirks = Some(frame.quirks.into());
+                    game.save(sha1)
+                });
+                self.osd.show(match saved {
+                    Ok(path) => format!("Saved quirks to {}", path.display()),
+                    Err(e) => format!("Unable to save quirks: {}", e),
+                });
+            }
+            Keycode::Escape | QUIRKS_KEY => self.quirk_menu = None,
+            _ => {}
+        }
+        None
+    }
+
+    /// Switch to the preset palette after the current one, dropping any configured overrides.
+    fn next_palette(&mut self) {
+        let presets = render::PALETTE_PRESETS;
+        let index = presets.iter().position(|name| *name == self.palette_name);
+        let name = presets[index.map_or(0, |index| (index + 1) % presets.len())];
+        self.palette = PlanePalette::preset(name).unwrap();
+        self.palette_name = name.to_string();
+        icon::set(self.canvas.window_mut(), &self.palette);
+        self.osd.show(format!("Palette: {}", name));
+    }
+
+    /// Whether `window_id` is the memory viewer's window.
+    fn memory_window(&self, window_id: u32) -> bool {
+        self.memory
+            .as_ref()
+            .is_some_and(|viewer| viewer.window_id() == window_id)
+    }
+
+    /// Whether `window_id` is the sprite viewer's window.
+    fn sprite_window(&self, window_id: u32) -> bool {
+        self.sprites
+            .as_ref()
+            .is_some_and(|viewer| viewer.window_id() == window_id)
+    }
+
+    /// Ask for RAM with each frame while a viewer needs it.
+    fn watch_memory(&self) -> Command {
+        Command::WatchMemory(self.memory.is_some() || self.sprites.is_some())
+    }
+
+    /// Read every instance's ROM from disk again.
+    fn read_roms(&self) -> Result<Vec<Vec<u8>>, String> {
+        self.paths.iter().map(|path| read_rom(path)).collect()
+    }
+
+    /// Read the ROMs again, returning the command reloading them and showing `message`, or
+    /// showing why they couldn't be read.
+    fn reload(&mut self, message: &str) -> Option<Command> {
+        match self.read_roms() {
+            Ok(roms) => {
+                self.osd.show(message);
+                self.build_error = None;
+                Some(Command::Reload(roms))
+            }
+            Err(e) => {
+                println!("{}", e);
+                self.build_error = Some(e);
+                None
+            }
+        }
+    }
+
+    fn key_to_button(&self, key: Keycode) -> Option<usize> {
+        self.keymap.iter().position(|k| *k == key)
+    }
+
+    fn gamepad_button(&self, button: Button) -> Option<usize> {
+        self.gamepad_map
+            .iter()
+            .find(|(b, _)| *b == button)
+            .map(|(_, k)| *k)
+    }
+
+    /// Open a newly connected gamepad by its joystick index.
+    fn open_gamepad(&mut self, index: u32) {
+        match self.controllers.open(index) {
+            Ok(pad) => {
+                println!("Connected {}", pad.name());
+                self.gamepads.push(pad);
+            }
+            Err(e) => println!("Unable to open gamepad: {}", e),
+        }
     }
 }
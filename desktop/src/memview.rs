@@ -0,0 +1,112 @@
+//! Window showing a hex dump of the focused instance's RAM, with the bytes at the program counter
+//! and I highlighted.
+use sdl2::{
+    keyboard::Keycode, pixels::Color, rect::Rect, render::Canvas, video::Window, VideoSubsystem,
+};
+
+use crate::osd::{self, CHAR_WIDTH, LINE_HEIGHT};
+
+/// Bytes shown per row.
+pub const BYTES_PER_ROW: usize = 16;
+/// Rows shown at once.
+pub const ROWS: usize = 32;
+/// Size of a font pixel in screen pixels.
+pub const PIXEL: u32 = 2;
+/// Highlight behind the instruction at the program counter.
+pub const PC_RGB: (u8, u8, u8) = (128, 96, 0);
+/// Highlight behind the byte I points to.
+pub const I_RGB: (u8, u8, u8) = (0, 64, 160);
+
+/// Characters in a row: address, gap, then each byte and the space after it.
+const ROW_CHARS: usize = 4 + 2 + BYTES_PER_ROW * 3 - 1;
+
+/// Scrollable hex dump in its own window.
+pub struct MemoryViewer {
+    canvas: Canvas<Window>,
+    /// Row shown at the top.
+    first_row: usize,
+}
+impl MemoryViewer {
+    /// Open the window, scrolled to the start of RAM.
+    pub fn open(video: &VideoSubsystem) -> Result<Self, String> {
+        let width = (ROW_CHARS as u32 * CHAR_WIDTH + 1) * PIXEL;
+        let height = (ROWS as u32 + 1) * LINE_HEIGHT * PIXEL;
+        let window = video
+            .window("chip8emu - memory", width, height)
+            .build()
+            .map_err(|e| e.to_string())?;
+        // Not synced to vblank, so presenting it doesn't hold up the game window
+        let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        Ok(Self {
+            canvas,
+            first_row: 0,
+        })
+    }
+
+    /// SDL ID of the window, to tell which window events are for.
+    pub fn window_id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    /// Scroll by `rows`, up if negative. Scrolling stops at the end of RAM when drawn.
+    pub fn scroll(&mut self, rows: isize) {
+        self.first_row = self.first_row.saturating_add_signed(rows);
+    }
+
+    /// Handle a key pressed in the window. Returns whether the key does anything here.
+    pub fn key(&mut self, key: Keycode) -> bool {
+        match key {
+            Keycode::Up => self.scroll(-1),
+            Keycode::Down => self.scroll(1),
+            Keycode::PageUp => self.scroll(-(ROWS as isize)),
+            Keycode::PageDown => self.scroll(ROWS as isize),
+            Keycode::Home => self.first_row = 0,
+            Keycode::End => self.first_row = usize::MAX,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Draw the rows in view of `ram`, highlighting the instruction at `pc` and the byte at `i`.
+    pub fn draw(&mut self, ram: &[u8], pc: u16, i: u16) {
+        let last_row = ram.len().div_ceil(BYTES_PER_ROW).saturating_sub(ROWS);
+        self.first_row = self.first_row.min(last_row);
+        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
+        self.canvas.clear();
+
+        let header = format!("PC {:04X}  I {:04X}", pc, i);
+        self.highlight(0, 0, 2, PC_RGB);
+        self.highlight(0, 9, 1, I_RGB);
+        osd::draw_glyphs(&mut self.canvas, &header, PIXEL as i32, 0, PIXEL);
+
+        let start = self.first_row * BYTES_PER_ROW;
+        for (line, row) in ram[start..].chunks(BYTES_PER_ROW).take(ROWS).enumerate() {
+            let addr = start + line * BYTES_PER_ROW;
+            for (col, _) in row.iter().enumerate() {
+                let column = 6 + col * 3;
+                if (pc as usize..pc as usize + 2).contains(&(addr + col)) {
+                    self.highlight(line + 1, column, 2, PC_RGB);
+                }
+                if i as usize == addr + col {
+                    self.highlight(line + 1, column, 2, I_RGB);
+                }
+            }
+            let bytes: Vec<_> = row.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let text = format!("{:04X}  {}", addr, bytes.join(" "));
+            let top = ((line as u32 + 1) * LINE_HEIGHT * PIXEL) as i32;
+            osd::draw_glyphs(&mut self.canvas, &text, PIXEL as i32, top, PIXEL);
+        }
+        self.canvas.present();
+    }
+
+    /// Fill behind `chars` characters from `column` on `line`.
+    fn highlight(&mut self, line: usize, column: usize, chars: u32, rgb: (u8, u8, u8)) {
+        let x = (column as u32 * CHAR_WIDTH * PIXEL) as i32;
+        let y = (line as u32 * LINE_HEIGHT * PIXEL) as i32;
+        let width = (chars * CHAR_WIDTH + 1) * PIXEL;
+        self.canvas.set_draw_color(Color::RGB(rgb.0, rgb.1, rgb.2));
+        self.canvas
+            .fill_rect(Rect::new(x, y, width, LINE_HEIGHT * PIXEL))
+            .unwrap();
+    }
+}
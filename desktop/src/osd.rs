@@ -0,0 +1,168 @@
+//! On-screen display of short messages, e.g. to confirm hotkeys, drawn over the game with a
+//! built-in bitmap font.
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{BlendMode, Canvas},
+    video::Window,
+};
+
+/// How long a message stays on screen.
+pub const MESSAGE_DURATION: Duration = Duration::from_secs(2);
+/// Most messages shown at once. Older ones are dropped first.
+pub const MAX_MESSAGES: usize = 4;
+/// Colour of message text.
+pub const TEXT_RGB: (u8, u8, u8) = (255, 255, 255);
+/// Colour behind message text, with alpha.
+pub const BACKDROP_RGBA: (u8, u8, u8, u8) = (0, 0, 0, 192);
+
+/// Width of a glyph in font pixels.
+const GLYPH_WIDTH: u32 = 3;
+/// Height of a glyph in font pixels.
+const GLYPH_HEIGHT: u32 = 5;
+/// Height of a line of text, including its backdrop, in font pixels.
+pub const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+/// Width of a character, including the gap after it, in font pixels.
+pub const CHAR_WIDTH: u32 = GLYPH_WIDTH + 1;
+
+/// Messages shown in the bottom-left corner for a while after they're posted.
+#[derive(Debug, Default)]
+pub struct Osd {
+    /// Messages with the time they were posted, oldest first.
+    messages: VecDeque<(String, Instant)>,
+}
+impl Osd {
+    /// Create a display with no messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show a message for [MESSAGE_DURATION].
+    pub fn show(&mut self, message: impl Into<String>) {
+        if self.messages.len() == MAX_MESSAGES {
+            self.messages.pop_front();
+        }
+        self.messages.push_back((message.into(), Instant::now()));
+    }
+
+    /// Draw the messages still due to be shown, newest at the bottom. `pixel` is the size of a
+    /// font pixel in screen pixels.
+    pub fn draw(&mut self, canvas: &mut Canvas<Window>, pixel: u32) {
+        self.messages
+            .retain(|(_, posted)| posted.elapsed() < MESSAGE_DURATION);
+        let (_, height) = canvas.output_size().unwrap();
+        let line_height = LINE_HEIGHT * pixel;
+        let mut top = height as i32 - (line_height * self.messages.len() as u32 + pixel) as i32;
+        for (message, _) in &self.messages {
+            draw_text(canvas, message, pixel as i32, top, pixel);
+            top += line_height as i32;
+        }
+    }
+}
+
+/// Draw a line of text on a backdrop with its top-left corner at `left` and `top`.
+pub fn draw_text(canvas: &mut Canvas<Window>, text: &str, left: i32, top: i32, pixel: u32) {
+    let width = CHAR_WIDTH * pixel * text.chars().count() as u32 + pixel;
+    let height = LINE_HEIGHT * pixel;
+    canvas.set_blend_mode(BlendMode::Blend);
+    let (r, g, b, a) = BACKDROP_RGBA;
+    canvas.set_draw_color(Color::RGBA(r, g, b, a));
+    canvas
+        .fill_rect(Rect::new(left - pixel as i32, top, width, height))
+        .unwrap();
+    canvas.set_blend_mode(BlendMode::None);
+    draw_glyphs(canvas, text, left, top, pixel);
+}
+
+/// Draw a line of text without a backdrop, with its top-left corner at `left` and `top`.
+pub fn draw_glyphs(canvas: &mut Canvas<Window>, text: &str, left: i32, top: i32, pixel: u32) {
+    let advance = CHAR_WIDTH * pixel;
+    canvas.set_draw_color(Color::RGB(TEXT_RGB.0, TEXT_RGB.1, TEXT_RGB.2));
+    let mut x = left;
+    for c in text.chars() {
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                    let px = x + (col * pixel) as i32;
+                    let py = top + ((row as u32 + 1) * pixel) as i32;
+                    canvas.fill_rect(Rect::new(px, py, pixel, pixel)).unwrap();
+                }
+            }
+        }
+        x += advance as i32;
+    }
+}
+
+/// Rows of a character's 3x5 glyph, top first, with the leftmost pixel in bit 2. Lowercase letters
+/// other than `x` are drawn as capitals, and characters without a glyph as `?`.
+fn glyph(c: char) -> [u8; 5] {
+    // Kept small so hex like 0x2A0 stays readable
+    if c == 'x' {
+        return [0b000, 0b000, 0b101, 0b010, 0b101];
+    }
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '[' => [0b011, 0b010, 0b010, 0b010, 0b011],
+        ']' => [0b110, 0b010, 0b010, 0b010, 0b110],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
@@ -0,0 +1,77 @@
+//! ROMs opened recently, kept between runs in `chip8emu/recent.txt` in the user's data directory,
+//! e.g. `~/.local/share/chip8emu/recent.txt`, one path per line.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chip8core::romfile;
+
+/// Most ROMs remembered. Older ones are forgotten first.
+pub const MAX_RECENT: usize = 10;
+
+/// Recently opened ROMs, newest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recent {
+    /// File the list is kept in, if there's a data directory.
+    path: Option<PathBuf>,
+    roms: Vec<PathBuf>,
+}
+impl Recent {
+    /// Read the list from the default path, leaving out ROMs that no longer exist. A missing or
+    /// unreadable list is empty.
+    pub fn load() -> Self {
+        let path = default_path();
+        let roms = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| {
+                text.lines()
+                    .map(PathBuf::from)
+                    .filter(|rom| romfile::split_entry(rom).0.is_file())
+                    .take(MAX_RECENT)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { path, roms }
+    }
+
+    /// ROMs in the list, newest first.
+    pub fn roms(&self) -> &[PathBuf] {
+        &self.roms
+    }
+
+    /// Move `rom` to the top of the list, dropping the oldest past [MAX_RECENT].
+    pub fn add(&mut self, rom: &Path) {
+        // Absolute, so the list works from any directory
+        let (file, entry) = romfile::split_entry(rom);
+        let mut rom = file.canonicalize().unwrap_or(file);
+        if let Some(entry) = entry {
+            rom = PathBuf::from(format!("{}#{}", rom.display(), entry));
+        }
+        self.roms.retain(|recent| *recent != rom);
+        self.roms.insert(0, rom);
+        self.roms.truncate(MAX_RECENT);
+    }
+
+    /// Write the list back to where it was read from.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let lines: Vec<_> = self
+            .roms
+            .iter()
+            .map(|rom| rom.to_string_lossy().into_owned())
+            .collect();
+        fs::write(path, lines.join("\n") + "\n")
+    }
+}
+
+/// Path the list is kept at, if there's a data directory.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("chip8emu").join("recent.txt"))
+}
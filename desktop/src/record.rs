@@ -0,0 +1,234 @@
+//! Recording a display's frames to an animated GIF, or to an MP4 video by piping them to ffmpeg.
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
+
+use chip8core::timing::TIMER_HZ;
+use gif::{Encoder, EncodingError, Repeat};
+
+use crate::config::{RecordFormat, RecordingConfig};
+
+/// Where recorded frames go.
+enum Output {
+    Gif(Encoder<BufWriter<File>>),
+    /// ffmpeg, reading raw RGBA frames from its standard input.
+    Ffmpeg(Child),
+}
+
+/// Recording in progress, taking every emulated frame and keeping those not skipped.
+pub struct Recorder {
+    output: Output,
+    /// File being written.
+    path: PathBuf,
+    /// Size of the frames given in Chip-8 pixels.
+    width: usize,
+    height: usize,
+    /// Size of a Chip-8 pixel in recorded pixels.
+    scale: usize,
+    /// Frames left out after each one recorded.
+    skip: u32,
+    /// Frames given so far.
+    given: u64,
+    /// Frames recorded so far.
+    recorded: u64,
+    /// Colour indices of a GIF frame being encoded.
+    indices: Vec<u8>,
+}
+impl Recorder {
+    /// Start recording frames of `width` by `height` pixels to a file in the configured directory
+    /// named after `rom` and the time.
+    pub fn start(
+        settings: &RecordingConfig,
+        rom: &Path,
+        width: usize,
+        height: usize,
+    ) -> Result<Self, RecordError> {
+        let extension = match settings.format {
+            RecordFormat::Gif => "gif",
+            RecordFormat::Mp4 => "mp4",
+        };
+        let name = format!(
+            "{}-{}.{}",
+            rom.file_stem().unwrap_or_default().to_string_lossy(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            extension
+        );
+        let path = match &settings.directory {
+            Some(dir) => dir.join(name),
+            None => PathBuf::from(name),
+        };
+        let scale = settings.scale.max(1) as usize;
+        let (scaled_width, scaled_height) = (width * scale, height * scale);
+        let output = match settings.format {
+            RecordFormat::Gif => {
+                let file = BufWriter::new(File::create(&path)?);
+                let (w, h) = (scaled_width as u16, scaled_height as u16);
+                // Each frame brings its own palette, which may change while recording
+                let mut encoder = Encoder::new(file, w, h, &[])?;
+                encoder.set_repeat(Repeat::Infinite)?;
+                Output::Gif(encoder)
+            }
+            RecordFormat::Mp4 => {
+                let rate = TIMER_HZ as f64 / (settings.frame_skip + 1) as f64;
+                let child = Command::new("ffmpeg")
+                    .args(["-loglevel", "error", "-y", "-f", "rawvideo"])
+                    .args(["-pixel_format", "rgba", "-video_size"])
+                    .arg(format!("{}x{}", width, height))
+                    .arg("-framerate")
+                    .arg(rate.to_string())
+                    .args(["-i", "-", "-vf"])
+                    .arg(format!(
+                        "scale={}:{}:flags=neighbor",
+                        scaled_width, scaled_height
+                    ))
+                    .args(["-pix_fmt", "yuv420p"])
+                    .arg(&path)
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .map_err(RecordError::Ffmpeg)?;
+                Output::Ffmpeg(child)
+            }
+        };
+        Ok(Self {
+            output,
+            path,
+            width,
+            height,
+            scale,
+            skip: settings.frame_skip,
+            given: 0,
+            recorded: 0,
+            indices: Vec::new(),
+        })
+    }
+
+    /// File being written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Take the next emulated frame as RGBA pixels, recording it unless it's skipped.
+    pub fn push(&mut self, rgba: &[u8]) -> Result<(), RecordError> {
+        let given = self.given;
+        self.given += 1;
+        if !given.is_multiple_of(self.skip as u64 + 1) {
+            return Ok(());
+        }
+        match &mut self.output {
+            Output::Gif(encoder) => {
+                let (palette, indices) = index_colors(rgba, &mut self.indices);
+                let scaled = scale_indices(indices, self.width, self.height, self.scale);
+                let (w, h) = (self.width * self.scale, self.height * self.scale);
+                let mut frame =
+                    gif::Frame::from_palette_pixels(w as u16, h as u16, scaled, palette, None);
+                // GIF delays are in hundredths of a second, so they alternate to keep time
+                let step = (self.skip as u64 + 1) * 100;
+                let hz = TIMER_HZ as u64;
+                let delay = (self.recorded + 1) * step / hz - self.recorded * step / hz;
+                frame.delay = delay as u16;
+                encoder.write_frame(&frame)?;
+            }
+            Output::Ffmpeg(child) => {
+                let stdin = child.stdin.as_mut().unwrap();
+                stdin.write_all(rgba).map_err(RecordError::Ffmpeg)?;
+            }
+        }
+        self.recorded += 1;
+        Ok(())
+    }
+
+    /// Stop recording, finishing the file. Returns the path it was written to.
+    pub fn finish(self) -> Result<PathBuf, RecordError> {
+        match self.output {
+            Output::Gif(encoder) => {
+                encoder.into_inner()?.flush()?;
+            }
+            Output::Ffmpeg(mut child) => {
+                // Closing its input tells ffmpeg the video is over
+                drop(child.stdin.take());
+                let status = child.wait().map_err(RecordError::Ffmpeg)?;
+                if !status.success() {
+                    return Err(RecordError::Ffmpeg(io::Error::other(format!(
+                        "ffmpeg exited with {}",
+                        status
+                    ))));
+                }
+            }
+        }
+        Ok(self.path)
+    }
+}
+
+/// Turn RGBA pixels into a palette of at most 256 RGB colours and an index into it for each
+/// pixel, in `indices`. Colours past the 256th are drawn with the last.
+fn index_colors<'a>(rgba: &[u8], indices: &'a mut Vec<u8>) -> (Vec<u8>, &'a [u8]) {
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    indices.clear();
+    for pixel in rgba.chunks_exact(4) {
+        let rgb = [pixel[0], pixel[1], pixel[2]];
+        let index = match palette.iter().position(|color| *color == rgb) {
+            Some(index) => index,
+            None if palette.len() < 256 => {
+                palette.push(rgb);
+                palette.len() - 1
+            }
+            None => palette.len() - 1,
+        };
+        indices.push(index as u8);
+    }
+    (palette.concat(), indices)
+}
+
+/// Scale pixels of a `width` by `height` image up `scale` times.
+fn scale_indices(pixels: &[u8], width: usize, height: usize, scale: usize) -> Vec<u8> {
+    let mut scaled = Vec::with_capacity(pixels.len() * scale * scale);
+    for row in pixels.chunks_exact(width).take(height) {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|&pixel| std::iter::repeat_n(pixel, scale))
+            .collect();
+        for _ in 0..scale {
+            scaled.extend_from_slice(&line);
+        }
+    }
+    scaled
+}
+
+/// Error recording gameplay.
+#[derive(Debug)]
+pub enum RecordError {
+    /// The file couldn't be written.
+    Io(io::Error),
+    /// The GIF couldn't be encoded.
+    Gif(EncodingError),
+    /// ffmpeg couldn't be run or failed.
+    Ffmpeg(io::Error),
+}
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Gif(e) => write!(f, "{}", e),
+            Self::Ffmpeg(e) => write!(f, "ffmpeg: {}", e),
+        }
+    }
+}
+impl Error for RecordError {}
+impl From<io::Error> for RecordError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<EncodingError> for RecordError {
+    fn from(e: EncodingError) -> Self {
+        Self::Gif(e)
+    }
+}
@@ -0,0 +1,23 @@
+//! Debugger console commands read from standard input with `--debug`, run on the focused
+//! instance while the game keeps rendering.
+use std::{
+    io::{self, BufRead},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+/// Read lines from standard input on a thread of their own, until it closes.
+pub fn read_stdin() -> Receiver<String> {
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    lines
+}
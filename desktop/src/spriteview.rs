@@ -0,0 +1,167 @@
+//! Window drawing RAM as a grid of 8xN sprites, for finding a game's graphics.
+use sdl2::{
+    keyboard::Keycode, pixels::Color, rect::Rect, render::Canvas, video::Window, VideoSubsystem,
+};
+
+use crate::osd::{self, CHAR_WIDTH, LINE_HEIGHT};
+
+/// Sprites per row.
+pub const COLUMNS: usize = 8;
+/// Rows of sprites shown at once.
+pub const ROWS: usize = 6;
+/// Tallest sprite height, as drawn by DXYN.
+pub const MAX_HEIGHT: usize = 15;
+/// Size of a sprite pixel in screen pixels.
+pub const SPRITE_PIXEL: u32 = 4;
+/// Size of a font pixel in screen pixels.
+pub const FONT_PIXEL: u32 = 2;
+/// Gap around each sprite in screen pixels.
+pub const GAP: u32 = 8;
+/// Colour of the gaps between sprites.
+pub const GRID_RGB: (u8, u8, u8) = (48, 48, 48);
+
+/// Characters in the address column.
+const LABEL_CHARS: u32 = 5;
+
+/// Sprites from a chosen address in their own window.
+pub struct SpriteViewer {
+    canvas: Canvas<Window>,
+    /// Address of the first sprite shown.
+    addr: usize,
+    /// Rows per sprite.
+    height: usize,
+    /// I when last drawn, to jump to.
+    i: u16,
+    fg: Color,
+    bg: Color,
+}
+impl SpriteViewer {
+    /// Open the window showing 5-row sprites from the start of RAM, where the font is.
+    pub fn open(video: &VideoSubsystem, fg: Color, bg: Color) -> Result<Self, String> {
+        let (width, height) = (Self::cell_width(), Self::cell_height());
+        let label = LABEL_CHARS * CHAR_WIDTH * FONT_PIXEL;
+        let header = LINE_HEIGHT * FONT_PIXEL;
+        let window = video
+            .window(
+                "chip8emu - sprites",
+                label + width * COLUMNS as u32,
+                header + height * ROWS as u32,
+            )
+            .build()
+            .map_err(|e| e.to_string())?;
+        // Not synced to vblank, so presenting it doesn't hold up the game window
+        let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        Ok(Self {
+            canvas,
+            addr: 0,
+            height: 5,
+            i: 0,
+            fg,
+            bg,
+        })
+    }
+
+    /// SDL ID of the window, to tell which window events are for.
+    pub fn window_id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    /// Move by `rows` rows of sprites, up if negative.
+    pub fn scroll(&mut self, rows: isize) {
+        let bytes = rows.saturating_mul((COLUMNS * self.height) as isize);
+        self.addr = self.addr.saturating_add_signed(bytes);
+    }
+
+    /// Handle a key pressed in the window. Returns whether the key does anything here.
+    pub fn key(&mut self, key: Keycode) -> bool {
+        match key {
+            Keycode::Left => self.addr = self.addr.saturating_sub(1),
+            Keycode::Right => self.addr += 1,
+            Keycode::Up => self.scroll(-1),
+            Keycode::Down => self.scroll(1),
+            Keycode::PageUp => self.scroll(-(ROWS as isize)),
+            Keycode::PageDown => self.scroll(ROWS as isize),
+            Keycode::Minus => self.height = (self.height - 1).max(1),
+            Keycode::Equals => self.height = (self.height + 1).min(MAX_HEIGHT),
+            Keycode::I => self.addr = self.i as usize,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Draw the sprites in view of `ram`, remembering `i` to jump to.
+    pub fn draw(&mut self, ram: &[u8], i: u16) {
+        self.i = i;
+        self.addr = self.addr.min(ram.len().saturating_sub(1));
+        self.canvas
+            .set_draw_color(Color::RGB(GRID_RGB.0, GRID_RGB.1, GRID_RGB.2));
+        self.canvas.clear();
+
+        let header = format!("0x{:03X}  8x{}  I 0x{:03X}", self.addr, self.height, i);
+        osd::draw_glyphs(&mut self.canvas, &header, FONT_PIXEL as i32, 0, FONT_PIXEL);
+
+        let left = LABEL_CHARS * CHAR_WIDTH * FONT_PIXEL;
+        let header = LINE_HEIGHT * FONT_PIXEL;
+        for row in 0..ROWS {
+            let row_addr = self.addr + row * COLUMNS * self.height;
+            if row_addr >= ram.len() {
+                break;
+            }
+            let top = header + row as u32 * Self::cell_height();
+            let label = format!("{:03X}", row_addr);
+            let label_top = (top + GAP / 2) as i32;
+            osd::draw_glyphs(
+                &mut self.canvas,
+                &label,
+                FONT_PIXEL as i32,
+                label_top,
+                FONT_PIXEL,
+            );
+            for column in 0..COLUMNS {
+                let start = row_addr + column * self.height;
+                let Some(sprite) = ram.get(start..(start + self.height).min(ram.len())) else {
+                    break;
+                };
+                let x = left + column as u32 * Self::cell_width() + GAP / 2;
+                self.draw_sprite(sprite, x as i32, (top + GAP / 2) as i32);
+            }
+        }
+        self.canvas.present();
+    }
+
+    /// Draw a sprite's rows, most significant bit leftmost, over the background colour.
+    fn draw_sprite(&mut self, sprite: &[u8], left: i32, top: i32) {
+        let size = 8 * SPRITE_PIXEL;
+        self.canvas.set_draw_color(self.bg);
+        self.canvas
+            .fill_rect(Rect::new(
+                left,
+                top,
+                size,
+                self.height as u32 * SPRITE_PIXEL,
+            ))
+            .unwrap();
+        self.canvas.set_draw_color(self.fg);
+        for (y, byte) in sprite.iter().enumerate() {
+            for x in 0..8 {
+                if byte & (0x80 >> x) != 0 {
+                    let px = left + (x * SPRITE_PIXEL) as i32;
+                    let py = top + (y as u32 * SPRITE_PIXEL) as i32;
+                    self.canvas
+                        .fill_rect(Rect::new(px, py, SPRITE_PIXEL, SPRITE_PIXEL))
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Width of a sprite and the gap around it.
+    fn cell_width() -> u32 {
+        8 * SPRITE_PIXEL + GAP
+    }
+
+    /// Height of the tallest sprite and the gap around it.
+    fn cell_height() -> u32 {
+        MAX_HEIGHT as u32 * SPRITE_PIXEL + GAP
+    }
+}
@@ -0,0 +1,69 @@
+//! Rendering and emulation rates measured over each second, for checking frame pacing.
+use std::time::{Duration, Instant};
+
+use chip8core::timing::TIMER_HZ;
+
+/// Rates measured over the last whole second.
+#[derive(Debug)]
+pub struct Stats {
+    /// Start of the second being measured.
+    since: Instant,
+    /// Frames drawn this second.
+    drawn: u32,
+    /// Frames and instructions emulated this second.
+    frames: u64,
+    cycles: u64,
+    /// Frame and instruction counts of the last emulated frame.
+    last: Option<(u64, u64)>,
+    /// Rates of the last whole second, ready to draw.
+    lines: Vec<String>,
+}
+impl Stats {
+    /// Start measuring from now.
+    pub fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            drawn: 0,
+            frames: 0,
+            cycles: 0,
+            last: None,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Count an emulated frame from the emulator's frame and instruction counts after it. Counts
+    /// going backwards, e.g. after a reset or while rewinding, count as nothing run.
+    pub fn emulated(&mut self, frames: u64, cycles: u64) {
+        if let Some((last_frames, last_cycles)) = self.last {
+            self.frames += frames.saturating_sub(last_frames);
+            self.cycles += cycles.saturating_sub(last_cycles);
+        }
+        self.last = Some((frames, cycles));
+    }
+
+    /// Count a drawn frame, updating the rates once a second has passed.
+    pub fn drawn(&mut self) {
+        self.drawn += 1;
+        let elapsed = self.since.elapsed();
+        if elapsed < Duration::from_secs(1) {
+            return;
+        }
+        let secs = elapsed.as_secs_f64();
+        let frames = self.frames as f64 / secs;
+        self.lines = vec![
+            format!("FPS {:.0}", self.drawn as f64 / secs),
+            format!("Frames {:.0}/s", frames),
+            format!("Instructions {:.0}/s", self.cycles as f64 / secs),
+            format!("Speed {:.0}%", frames * 100.0 / TIMER_HZ as f64),
+        ];
+        self.since = Instant::now();
+        self.drawn = 0;
+        self.frames = 0;
+        self.cycles = 0;
+    }
+
+    /// Rates of the last whole second, one per line.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
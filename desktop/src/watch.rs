@@ -0,0 +1,67 @@
+//! Watching ROM files for changes on disk, to reload them as soon as they're rebuilt.
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use chip8core::romfile;
+
+/// Time a file must go unchanged before it's reported, so one that's still being written isn't
+/// read half-finished.
+pub const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Watches files for changes.
+pub struct Watcher {
+    /// Kept to keep watching.
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// Files watched, canonicalised.
+    files: Vec<PathBuf>,
+    /// When a watched file last changed, if it hasn't been reported yet.
+    changed: Option<Instant>,
+}
+impl Watcher {
+    /// Watch the files of `roms`, which may name entries in archives.
+    pub fn new(roms: &[PathBuf]) -> notify::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        let mut files = Vec::new();
+        for rom in roms {
+            let (file, _) = romfile::split_entry(rom);
+            let file = file.canonicalize()?;
+            // Editors often save by replacing the file, which ends a watch on the file itself
+            let dir = file.parent().unwrap_or(Path::new("/"));
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            files.push(file);
+        }
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            files,
+            changed: None,
+        })
+    }
+
+    /// Whether a watched file has changed and settled since this last returned `true`.
+    pub fn changed(&mut self) -> bool {
+        while let Ok(event) = self.events.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            let written = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+            if written && event.paths.iter().any(|path| self.files.contains(path)) {
+                self.changed = Some(Instant::now());
+            }
+        }
+        match self.changed {
+            Some(at) if at.elapsed() >= SETTLE_TIME => {
+                self.changed = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
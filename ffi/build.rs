@@ -0,0 +1,40 @@
+use std::{env, fs, path::Path};
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=include/chip8.h");
+
+    // Generated into OUT_DIR, leaving the source tree alone, and compared with the checked-in
+    // header so it doesn't go stale
+    let config = match cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("cargo:warning=Unable to read cbindgen.toml: {}", e);
+            return;
+        }
+    };
+    let bindings = match cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+    {
+        Ok(bindings) => bindings,
+        Err(e) => {
+            println!("cargo:warning=Unable to generate the C header: {}", e);
+            return;
+        }
+    };
+    let generated = Path::new(&out_dir).join("chip8.h");
+    bindings.write_to_file(&generated);
+
+    let checked_in = Path::new(&crate_dir).join("include/chip8.h");
+    if fs::read(&generated).ok() != fs::read(&checked_in).ok() {
+        println!(
+            "cargo:warning=include/chip8.h is out of date; copy the generated header from {}",
+            generated.display()
+        );
+    }
+}
@@ -0,0 +1,149 @@
+/* Generated by cbindgen from ffi/src/lib.rs. Do not edit. */
+
+#ifndef CHIP8_H
+#define CHIP8_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Width of the display in pixels.
+#define CHIP8_DISPLAY_WIDTH 64
+
+// Height of the display in pixels.
+#define CHIP8_DISPLAY_HEIGHT 32
+
+// Length in bytes of the RGBA framebuffer filled by [chip8_get_framebuffer].
+#define CHIP8_FRAMEBUFFER_SIZE ((64 * 32) * 4)
+
+// Number of keys on the keypad.
+#define CHIP8_NUM_KEYS 16
+
+// Result of a call into the library.
+typedef enum Chip8Status {
+  // The call succeeded.
+  CHIP8_STATUS_OK = 0,
+  // A required pointer was null.
+  CHIP8_STATUS_NULL_POINTER = 1,
+  // The ROM is empty or does not fit in memory.
+  CHIP8_STATUS_INVALID_ROM = 2,
+  // The emulator hit an error, such as an unknown opcode or a stack overflow.
+  CHIP8_STATUS_EMULATION_ERROR = 3,
+  // The key index is not below [CHIP8_NUM_KEYS].
+  CHIP8_STATUS_INVALID_KEY = 4,
+  // The output buffer is too small.
+  CHIP8_STATUS_BUFFER_TOO_SMALL = 5,
+} Chip8Status;
+
+// Opaque handle to an emulator, created with [chip8_new] and destroyed with [chip8_free].
+typedef struct Chip8 Chip8;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create an emulator with a random seed. Free it with [chip8_free].
+struct Chip8 *chip8_new(void);
+
+// Create an emulator whose RNG is seeded with `seed`, for reproducible runs. Free it with
+// [chip8_free].
+struct Chip8 *chip8_new_with_seed(uint64_t seed);
+
+// Destroy an emulator created with [chip8_new]. Does nothing if `chip8` is null.
+//
+// # Safety
+//
+// `chip8` must be null or a pointer returned by [chip8_new] that has not been freed yet.
+void chip8_free(struct Chip8 *chip8);
+
+// Reset the machine and load the last ROM again.
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new].
+enum Chip8Status chip8_reset(struct Chip8 *chip8);
+
+// Load `len` bytes of ROM from `data` at the start address.
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new], and `data` must be null or
+// point to at least `len` readable bytes.
+enum Chip8Status chip8_load_rom(struct Chip8 *chip8, const uint8_t *data, size_t len);
+
+// Execute a single instruction, without ticking the timers.
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new].
+enum Chip8Status chip8_tick(struct Chip8 *chip8);
+
+// Tick the delay and sound timers once. Call at 60Hz when driving the emulator with
+// [chip8_tick].
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new].
+enum Chip8Status chip8_tick_timers(struct Chip8 *chip8);
+
+// Run one 60Hz frame: execute `cycles` instructions, then tick the timers once.
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new].
+enum Chip8Status chip8_run_frame(struct Chip8 *chip8, uint32_t cycles);
+
+// Set the colours used by [chip8_get_framebuffer], as `0xRRGGBBAA`.
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new].
+enum Chip8Status chip8_set_palette(struct Chip8 *chip8, uint32_t background, uint32_t foreground);
+
+// Render the display into `out` as row-major RGBA bytes. `len` must be at least
+// [CHIP8_FRAMEBUFFER_SIZE].
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new], and `out` must be null or
+// point to at least `len` writable bytes.
+enum Chip8Status chip8_get_framebuffer(const struct Chip8 *chip8, uint8_t *out, size_t len);
+
+// Press or release a key from 0 to 15. A press completes a pending FX0A.
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new].
+enum Chip8Status chip8_keypress(struct Chip8 *chip8, uint8_t key, bool pressed);
+
+// Whether the sound timer is running and a tone should be playing. Returns `false` if `chip8` is
+// null.
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new].
+bool chip8_is_sound_playing(const struct Chip8 *chip8);
+
+// Return the program counter, or 0 if `chip8` is null.
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new].
+uint16_t chip8_get_pc(const struct Chip8 *chip8);
+
+// Copy RAM into `out`, up to `len` bytes. Returns the number of bytes copied, or 0 if either
+// pointer is null.
+//
+// # Safety
+//
+// `chip8` must be null or a live pointer returned by [chip8_new], and `out` must be null or
+// point to at least `len` writable bytes.
+size_t chip8_read_ram(const struct Chip8 *chip8, uint8_t *out, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHIP8_H */
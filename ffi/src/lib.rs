@@ -0,0 +1,283 @@
+//! C API for embedding the Chip-8 core in other engines. The header is generated into
+//! `include/chip8.h` by cbindgen when the crate is built.
+//!
+//! Functions taking a `Chip8` pointer return [Chip8Status::NullPointer], or zero for those
+//! returning values, if it is null.
+#![warn(missing_docs)]
+use std::{ptr, slice};
+
+use chip8core::{
+    emulator::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+    render::RGBA_BUFFER_SIZE,
+    Emulator, Palette,
+};
+
+/// Width of the display in pixels.
+pub const CHIP8_DISPLAY_WIDTH: usize = 64;
+/// Height of the display in pixels.
+pub const CHIP8_DISPLAY_HEIGHT: usize = 32;
+/// Length in bytes of the RGBA framebuffer filled by [chip8_get_framebuffer].
+pub const CHIP8_FRAMEBUFFER_SIZE: usize = 64 * 32 * 4;
+/// Number of keys on the keypad.
+pub const CHIP8_NUM_KEYS: u8 = 16;
+
+// cbindgen needs literal values, so make sure they stay in step with the core.
+const _: () = assert!(CHIP8_DISPLAY_WIDTH == DISPLAY_WIDTH);
+const _: () = assert!(CHIP8_DISPLAY_HEIGHT == DISPLAY_HEIGHT);
+const _: () = assert!(CHIP8_FRAMEBUFFER_SIZE == RGBA_BUFFER_SIZE);
+
+/// Result of a call into the library.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chip8Status {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// The ROM is empty or does not fit in memory.
+    InvalidRom = 2,
+    /// The emulator hit an error, such as an unknown opcode or a stack overflow.
+    EmulationError = 3,
+    /// The key index is not below [CHIP8_NUM_KEYS].
+    InvalidKey = 4,
+    /// The output buffer is too small.
+    BufferTooSmall = 5,
+}
+
+/// Opaque handle to an emulator, created with [chip8_new] and destroyed with [chip8_free].
+pub struct Chip8 {
+    emu: Emulator,
+    palette: Palette,
+    /// Last ROM loaded, reloaded on reset.
+    rom: Vec<u8>,
+}
+
+/// Convert a `0xRRGGBBAA` colour to RGBA bytes.
+fn rgba(color: u32) -> [u8; 4] {
+    color.to_be_bytes()
+}
+
+/// Create an emulator with a random seed. Free it with [chip8_free].
+#[no_mangle]
+pub extern "C" fn chip8_new() -> *mut Chip8 {
+    wrap(Emulator::new())
+}
+
+/// Create an emulator whose RNG is seeded with `seed`, for reproducible runs. Free it with
+/// [chip8_free].
+#[no_mangle]
+pub extern "C" fn chip8_new_with_seed(seed: u64) -> *mut Chip8 {
+    wrap(Emulator::with_seed(seed))
+}
+
+/// Move an emulator onto the heap behind a handle.
+fn wrap(emu: Emulator) -> *mut Chip8 {
+    Box::into_raw(Box::new(Chip8 {
+        emu,
+        palette: Palette::default(),
+        rom: Vec::new(),
+    }))
+}
+
+/// Destroy an emulator created with [chip8_new]. Does nothing if `chip8` is null.
+///
+/// # Safety
+///
+/// `chip8` must be null or a pointer returned by [chip8_new] that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_free(chip8: *mut Chip8) {
+    if !chip8.is_null() {
+        drop(Box::from_raw(chip8));
+    }
+}
+
+/// Reset the machine and load the last ROM again.
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new].
+#[no_mangle]
+pub unsafe extern "C" fn chip8_reset(chip8: *mut Chip8) -> Chip8Status {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Status::NullPointer;
+    };
+    chip8.emu.reset();
+    if chip8.rom.is_empty() {
+        return Chip8Status::Ok;
+    }
+    match chip8.emu.load_rom(&chip8.rom) {
+        Ok(_) => Chip8Status::Ok,
+        Err(_) => Chip8Status::InvalidRom,
+    }
+}
+
+/// Load `len` bytes of ROM from `data` at the start address.
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new], and `data` must be null or
+/// point to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_rom(
+    chip8: *mut Chip8,
+    data: *const u8,
+    len: usize,
+) -> Chip8Status {
+    let (Some(chip8), false) = (chip8.as_mut(), data.is_null()) else {
+        return Chip8Status::NullPointer;
+    };
+    let rom = slice::from_raw_parts(data, len);
+    match chip8.emu.load_rom(rom) {
+        Ok(_) => {
+            chip8.rom = rom.to_vec();
+            Chip8Status::Ok
+        }
+        Err(_) => Chip8Status::InvalidRom,
+    }
+}
+
+/// Execute a single instruction, without ticking the timers.
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new].
+#[no_mangle]
+pub unsafe extern "C" fn chip8_tick(chip8: *mut Chip8) -> Chip8Status {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Status::NullPointer;
+    };
+    match chip8.emu.tick() {
+        Ok(_) => Chip8Status::Ok,
+        Err(_) => Chip8Status::EmulationError,
+    }
+}
+
+/// Tick the delay and sound timers once. Call at 60Hz when driving the emulator with
+/// [chip8_tick].
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new].
+#[no_mangle]
+pub unsafe extern "C" fn chip8_tick_timers(chip8: *mut Chip8) -> Chip8Status {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Status::NullPointer;
+    };
+    chip8.emu.tick_timers();
+    Chip8Status::Ok
+}
+
+/// Run one 60Hz frame: execute `cycles` instructions, then tick the timers once.
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new].
+#[no_mangle]
+pub unsafe extern "C" fn chip8_run_frame(chip8: *mut Chip8, cycles: u32) -> Chip8Status {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Status::NullPointer;
+    };
+    match chip8.emu.run_frame(cycles) {
+        Ok(_) => Chip8Status::Ok,
+        Err(_) => Chip8Status::EmulationError,
+    }
+}
+
+/// Set the colours used by [chip8_get_framebuffer], as `0xRRGGBBAA`.
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new].
+#[no_mangle]
+pub unsafe extern "C" fn chip8_set_palette(
+    chip8: *mut Chip8,
+    background: u32,
+    foreground: u32,
+) -> Chip8Status {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Status::NullPointer;
+    };
+    chip8.palette = Palette::new(rgba(background), rgba(foreground));
+    Chip8Status::Ok
+}
+
+/// Render the display into `out` as row-major RGBA bytes. `len` must be at least
+/// [CHIP8_FRAMEBUFFER_SIZE].
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new], and `out` must be null or
+/// point to at least `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_get_framebuffer(
+    chip8: *const Chip8,
+    out: *mut u8,
+    len: usize,
+) -> Chip8Status {
+    let (Some(chip8), false) = (chip8.as_ref(), out.is_null()) else {
+        return Chip8Status::NullPointer;
+    };
+    if len < CHIP8_FRAMEBUFFER_SIZE {
+        return Chip8Status::BufferTooSmall;
+    }
+    let out = slice::from_raw_parts_mut(out, CHIP8_FRAMEBUFFER_SIZE);
+    chip8.emu.render_rgba(out, &chip8.palette);
+    Chip8Status::Ok
+}
+
+/// Press or release a key from 0 to 15. A press completes a pending FX0A.
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new].
+#[no_mangle]
+pub unsafe extern "C" fn chip8_keypress(chip8: *mut Chip8, key: u8, pressed: bool) -> Chip8Status {
+    let Some(chip8) = chip8.as_mut() else {
+        return Chip8Status::NullPointer;
+    };
+    match chip8.emu.keypress(key as usize, pressed) {
+        Ok(()) => Chip8Status::Ok,
+        Err(_) => Chip8Status::InvalidKey,
+    }
+}
+
+/// Whether the sound timer is running and a tone should be playing. Returns `false` if `chip8` is
+/// null.
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new].
+#[no_mangle]
+pub unsafe extern "C" fn chip8_is_sound_playing(chip8: *const Chip8) -> bool {
+    chip8
+        .as_ref()
+        .is_some_and(|chip8| chip8.emu.is_sound_playing())
+}
+
+/// Return the program counter, or 0 if `chip8` is null.
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new].
+#[no_mangle]
+pub unsafe extern "C" fn chip8_get_pc(chip8: *const Chip8) -> u16 {
+    chip8.as_ref().map_or(0, |chip8| chip8.emu.pc())
+}
+
+/// Copy RAM into `out`, up to `len` bytes. Returns the number of bytes copied, or 0 if either
+/// pointer is null.
+///
+/// # Safety
+///
+/// `chip8` must be null or a live pointer returned by [chip8_new], and `out` must be null or
+/// point to at least `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_read_ram(chip8: *const Chip8, out: *mut u8, len: usize) -> usize {
+    let (Some(chip8), false) = (chip8.as_ref(), out.is_null()) else {
+        return 0;
+    };
+    let ram = chip8.emu.ram();
+    let len = len.min(ram.len());
+    ptr::copy_nonoverlapping(ram.as_ptr(), out, len);
+    len
+}
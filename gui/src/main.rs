@@ -0,0 +1,279 @@
+use std::{env, fs, path::Path};
+
+use chip8core::*;
+use eframe::egui::{self, Color32, Key, TextureHandle, TextureOptions};
+
+mod panels;
+
+/// Instructions run by "Step over" before giving up on a subroutine returning.
+pub const STEP_OVER_BUDGET: usize = 100_000;
+
+// Key bindings, in the same layout as the SDL frontend.
+pub const KEYMAP: [(Key, usize); 16] = [
+    (Key::Num1, 0x1),
+    (Key::Num2, 0x2),
+    (Key::Num3, 0x3),
+    (Key::Num4, 0xC),
+    (Key::Q, 0x4),
+    (Key::W, 0x5),
+    (Key::E, 0x6),
+    (Key::R, 0xD),
+    (Key::A, 0x7),
+    (Key::S, 0x8),
+    (Key::D, 0x9),
+    (Key::F, 0xE),
+    (Key::Z, 0xA),
+    (Key::X, 0x0),
+    (Key::C, 0xB),
+    (Key::V, 0xF),
+];
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    if args.len() != 2 {
+        println!("Usage: cargo run -p gui path/to/game");
+        return;
+    }
+
+    let path = Path::new(&args[1]);
+    let mut buffer = fs::read(path).expect("Unable to open file");
+    // Compile Octo source files before loading them
+    if path.extension().is_some_and(|ext| ext == "o8") {
+        let source = String::from_utf8_lossy(&buffer);
+        buffer = match octo::compile(&source) {
+            Ok(rom) => rom,
+            Err(e) => {
+                println!("Unable to compile {}: {}", path.display(), e);
+                return;
+            }
+        };
+    }
+
+    let mut chip8 = Emulator::new();
+    // Pick quirks to suit the instruction set the ROM uses
+    chip8.set_quirks(
+        analyze::analyze(&buffer, emulator::START_ADDRESS)
+            .platform
+            .quirks(),
+    );
+    let rom_info = match chip8.load_rom(&buffer) {
+        Ok(info) => info,
+        Err(e) => {
+            println!("Unable to load ROM: {}", e);
+            return;
+        }
+    };
+    // Known games override the guessed settings
+    let mut cycles_per_frame = host::DEFAULT_CYCLES_PER_FRAME;
+    let mut title = "chip8emu".to_string();
+    if let Some(entry) = rom_info.identify() {
+        chip8.set_quirks(entry.quirks);
+        cycles_per_frame = entry.cycles_per_frame;
+        title = format!("chip8emu - {}", entry.title);
+    }
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([1280.0, 800.0]),
+        ..Default::default()
+    };
+    let app = App::new(chip8, buffer, cycles_per_frame);
+    if let Err(e) = eframe::run_native(&title, options, Box::new(|_| Ok(Box::new(app)))) {
+        println!("Unable to open window: {}", e);
+    }
+}
+
+/// Which debugger panels are open.
+struct OpenPanels {
+    registers: bool,
+    disassembly: bool,
+    memory: bool,
+    stack: bool,
+    breakpoints: bool,
+}
+
+/// Game view with debugger panels.
+pub struct App {
+    emu: Emulator,
+    /// ROM reloaded on reset.
+    rom: Vec<u8>,
+    cycles_per_frame: u32,
+    running: bool,
+    /// Why execution last stopped, shown in the toolbar.
+    status: String,
+    palette: Palette,
+    pixels: Vec<u8>,
+    texture: Option<TextureHandle>,
+    open: OpenPanels,
+    /// Address typed into the breakpoints panel.
+    breakpoint_input: String,
+}
+impl App {
+    fn new(emu: Emulator, rom: Vec<u8>, cycles_per_frame: u32) -> Self {
+        Self {
+            emu,
+            rom,
+            cycles_per_frame,
+            running: true,
+            status: String::new(),
+            palette: Palette::new([0x00, 0x00, 0x00, 0xFF], [0x00, 0xFF, 0x00, 0xFF]),
+            pixels: vec![0; render::RGBA_BUFFER_SIZE],
+            texture: None,
+            open: OpenPanels {
+                registers: true,
+                disassembly: true,
+                memory: false,
+                stack: true,
+                breakpoints: true,
+            },
+            breakpoint_input: String::new(),
+        }
+    }
+
+    /// Pause with a message explaining why.
+    fn stop(&mut self, status: String) {
+        self.running = false;
+        self.status = status;
+    }
+
+    /// Apply the keyboard state to the keypad, unless a text field has focus.
+    fn update_keys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        for (key, button) in KEYMAP {
+            let down = ctx.input(|i| i.key_down(key));
+            if down != self.emu.is_key_pressed(button) {
+                self.emu.keypress(button, down).unwrap();
+            }
+        }
+    }
+
+    /// Run a frame if running, pausing on breakpoints and errors.
+    fn run(&mut self) {
+        if !self.running {
+            return;
+        }
+        match self.emu.run_frame(self.cycles_per_frame) {
+            Ok(output) => {
+                if let Some(stop) = output.stop() {
+                    self.stop(format!("Stopped: {:?}", stop));
+                }
+            }
+            Err(e) => self.stop(format!("Emulation error: {}", e)),
+        }
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = if self.running { "Pause" } else { "Run" };
+            if ui.button(label).clicked() {
+                if !self.running {
+                    // Step off a breakpoint so it doesn't stop execution straight away.
+                    if let Err(e) = self.emu.step() {
+                        self.stop(format!("Emulation error: {}", e));
+                        return;
+                    }
+                    self.status.clear();
+                }
+                self.running = !self.running;
+            }
+            ui.add_enabled_ui(!self.running, |ui| {
+                if ui.button("Step").clicked() {
+                    if let Err(e) = self.emu.step() {
+                        self.status = format!("Emulation error: {}", e);
+                    }
+                }
+                if ui.button("Step over").clicked() {
+                    match self.emu.step_over(STEP_OVER_BUDGET) {
+                        Ok(result) => self.status = format!("{:?}", result.outcome),
+                        Err(e) => self.status = format!("Emulation error: {}", e),
+                    }
+                }
+                if ui.button("Frame").clicked() {
+                    self.running = true;
+                    self.run();
+                    self.running = false;
+                }
+            });
+            if ui.button("Reset").clicked() {
+                self.emu.reset();
+                self.status.clear();
+                if let Err(e) = self.emu.load_rom(&self.rom) {
+                    self.stop(format!("Unable to load ROM: {}", e));
+                }
+            }
+            ui.add(egui::Slider::new(&mut self.cycles_per_frame, 1..=100).text("cycles/frame"));
+
+            ui.separator();
+            ui.toggle_value(&mut self.open.registers, "Registers");
+            ui.toggle_value(&mut self.open.disassembly, "Disassembly");
+            ui.toggle_value(&mut self.open.memory, "Memory");
+            ui.toggle_value(&mut self.open.stack, "Stack");
+            ui.toggle_value(&mut self.open.breakpoints, "Breakpoints");
+
+            ui.separator();
+            ui.label(&self.status);
+        });
+    }
+
+    /// Draw the display, scaled to fit the space left over by the panels.
+    fn game_view(&mut self, ui: &mut egui::Ui) {
+        self.emu.render_rgba(&mut self.pixels, &self.palette);
+        let size = [emulator::DISPLAY_WIDTH, emulator::DISPLAY_HEIGHT];
+        let image = egui::ColorImage::from_rgba_unmultiplied(size, &self.pixels);
+        let texture = match &mut self.texture {
+            Some(texture) => {
+                texture.set(image, TextureOptions::NEAREST);
+                texture
+            }
+            None => self.texture.insert(ui.ctx().load_texture(
+                "display",
+                image,
+                TextureOptions::NEAREST,
+            )),
+        };
+
+        let available = ui.available_size();
+        let scale = (available.x / size[0] as f32)
+            .min(available.y / size[1] as f32)
+            .max(1.0);
+        let display_size = egui::vec2(size[0] as f32, size[1] as f32) * scale;
+        ui.centered_and_justified(|ui| {
+            ui.add(egui::Image::new((texture.id(), display_size)).fit_to_exact_size(display_size));
+        });
+    }
+}
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.update_keys(ctx);
+        self.run();
+
+        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| self.toolbar(ui));
+        egui::CentralPanel::default()
+            .frame(egui::Frame::new().fill(Color32::BLACK))
+            .show(ctx, |ui| self.game_view(ui));
+
+        egui::Window::new("Registers")
+            .open(&mut self.open.registers)
+            .show(ctx, |ui| panels::registers(ui, &self.emu));
+        egui::Window::new("Disassembly")
+            .open(&mut self.open.disassembly)
+            .show(ctx, |ui| panels::disassembly(ui, &mut self.emu));
+        egui::Window::new("Memory")
+            .open(&mut self.open.memory)
+            .show(ctx, |ui| panels::memory(ui, &self.emu));
+        egui::Window::new("Stack")
+            .open(&mut self.open.stack)
+            .show(ctx, |ui| panels::stack(ui, &self.emu));
+        egui::Window::new("Breakpoints")
+            .open(&mut self.open.breakpoints)
+            .show(ctx, |ui| {
+                panels::breakpoints(ui, &mut self.emu, &mut self.breakpoint_input)
+            });
+
+        // Frame pacing comes from vsync.
+        if self.running {
+            ctx.request_repaint();
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Debugger panels shown alongside the game view.
+use chip8core::{opcodes::decode, Emulator};
+use eframe::egui::{self, Color32, RichText};
+
+/// Instructions shown before the program counter in the disassembly.
+pub const DISASSEMBLY_BEFORE: u16 = 8;
+/// Instructions shown in the disassembly.
+pub const DISASSEMBLY_LINES: u16 = 32;
+/// Bytes per row of the memory view.
+pub const MEMORY_ROW: usize = 16;
+
+/// Colour of the line the program counter is on.
+const CURRENT: Color32 = Color32::YELLOW;
+
+/// V registers, I, PC, the stack pointer and timers.
+pub fn registers(ui: &mut egui::Ui, emu: &Emulator) {
+    egui::Grid::new("registers").striped(true).show(ui, |ui| {
+        for (x, v) in emu.v_registers().iter().enumerate() {
+            ui.monospace(format!("V{:X}", x));
+            ui.monospace(format!("{:02X}", v));
+            if x % 4 == 3 {
+                ui.end_row();
+            }
+        }
+        ui.monospace("I");
+        ui.monospace(format!("{:03X}", emu.i()));
+        ui.monospace("PC");
+        ui.monospace(format!("{:03X}", emu.pc()));
+        ui.monospace("SP");
+        ui.monospace(format!("{:X}", emu.stack_pointer()));
+        ui.end_row();
+        ui.monospace("DT");
+        ui.monospace(format!("{:02X}", emu.delay_timer()));
+        ui.monospace("ST");
+        ui.monospace(format!("{:02X}", emu.sound_timer()));
+        ui.end_row();
+    });
+    ui.monospace(format!("State: {:?}", emu.state()));
+}
+
+/// Instructions around the program counter. Clicking the margin toggles a breakpoint.
+pub fn disassembly(ui: &mut egui::Ui, emu: &mut Emulator) {
+    let pc = emu.pc();
+    let start = pc.saturating_sub(DISASSEMBLY_BEFORE * 2);
+    let breakpoints: Vec<_> = emu.debugger().breakpoints().collect();
+    for addr in (start..).step_by(2).take(DISASSEMBLY_LINES as usize) {
+        let (Ok(high), Ok(low)) = (
+            emu.read_byte(addr as usize),
+            emu.read_byte(addr as usize + 1),
+        ) else {
+            break;
+        };
+        let op = u16::from_be_bytes([high, low]);
+        let text = decode(op).map_or_else(|| "???".to_string(), |i| i.to_string());
+        let has_breakpoint = breakpoints.contains(&addr);
+
+        ui.horizontal(|ui| {
+            let marker = if has_breakpoint { "●" } else { "○" };
+            let marker = RichText::new(marker).color(Color32::RED).monospace();
+            if ui.add(egui::Button::new(marker).frame(false)).clicked() {
+                if has_breakpoint {
+                    emu.debugger_mut().remove_breakpoint(addr);
+                } else {
+                    emu.debugger_mut().add_breakpoint(addr);
+                }
+            }
+            let line = RichText::new(format!("{:03X}  {:04X}  {}", addr, op, text)).monospace();
+            ui.label(if addr == pc {
+                line.color(CURRENT)
+            } else {
+                line
+            });
+        });
+    }
+}
+
+/// Hex dump of RAM.
+pub fn memory(ui: &mut egui::Ui, emu: &Emulator) {
+    let ram = emu.ram();
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let rows = ram.len().div_ceil(MEMORY_ROW);
+    egui::ScrollArea::vertical().show_rows(ui, row_height, rows, |ui, rows| {
+        for row in rows {
+            let addr = row * MEMORY_ROW;
+            let bytes = &ram[addr..(addr + MEMORY_ROW).min(ram.len())];
+            let hex: Vec<_> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let ascii: String = bytes
+                .iter()
+                .map(|byte| match byte {
+                    0x20..=0x7E => *byte as char,
+                    _ => '.',
+                })
+                .collect();
+            let line = RichText::new(format!("{:03X}  {}  {}", addr, hex.join(" "), ascii));
+            let current = (addr..addr + MEMORY_ROW).contains(&(emu.pc() as usize));
+            ui.label(if current {
+                line.monospace().color(CURRENT)
+            } else {
+                line.monospace()
+            });
+        }
+    });
+}
+
+/// Subroutine calls on the stack, innermost first.
+pub fn stack(ui: &mut egui::Ui, emu: &Emulator) {
+    let frames = emu.call_stack();
+    if frames.is_empty() {
+        ui.label("Empty");
+    }
+    for frame in frames.iter().rev() {
+        let entry = frame
+            .entry
+            .map_or_else(|| "???".to_string(), |entry| format!("{:03X}", entry));
+        ui.monospace(format!("{}  called from {:03X}", entry, frame.call_site));
+    }
+}
+
+/// Breakpoint list, with a field to add breakpoints by hex address.
+pub fn breakpoints(ui: &mut egui::Ui, emu: &mut Emulator, input: &mut String) {
+    ui.horizontal(|ui| {
+        let field = ui.add(
+            egui::TextEdit::singleline(input)
+                .desired_width(60.0)
+                .hint_text("200"),
+        );
+        let submitted = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui.button("Add").clicked() || submitted {
+            let text = input.trim().trim_start_matches("0x");
+            if let Ok(addr) = u16::from_str_radix(text, 16) {
+                emu.debugger_mut().add_breakpoint(addr);
+                input.clear();
+            }
+        }
+    });
+
+    let breakpoints: Vec<_> = emu.debugger().breakpoints().collect();
+    for addr in breakpoints {
+        ui.horizontal(|ui| {
+            ui.monospace(format!("{:03X}", addr));
+            if ui.small_button("Remove").clicked() {
+                emu.debugger_mut().remove_breakpoint(addr);
+            }
+        });
+    }
+}
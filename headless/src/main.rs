@@ -0,0 +1,228 @@
+use std::{env, fs, path::Path, process::ExitCode};
+
+use chip8core::*;
+use server::Server;
+
+mod server;
+
+/// Frames to run when `--frames` is not given.
+pub const DEFAULT_FRAMES: u64 = 600;
+
+const USAGE: &str = "Usage: headless [--frames N] [--cycles N] [--dump-display PATH] \
+                     [--dump-ram PATH] [--script PATH] [--serve ADDR] path/to/game";
+
+/// Command-line options.
+struct Options {
+    rom: String,
+    /// Frames to run, or `None` to run until interrupted.
+    frames: Option<u64>,
+    cycles: u32,
+    dump_display: Option<String>,
+    dump_ram: Option<String>,
+    script: Option<String>,
+    serve: Option<String>,
+}
+
+/// Quits after a fixed number of frames, or when the script fails. Runs in real time if serving
+/// the display.
+struct FrameLimit {
+    frames: Option<u64>,
+    script: Option<Script>,
+    server: Option<Server>,
+    error: Option<ScriptError>,
+}
+impl FrameLimit {
+    /// Call the script for breakpoints hit during the last frame, then for the next frame.
+    fn run_script(&mut self, emu: &mut Emulator) -> Result<(), ScriptError> {
+        let Some(script) = self.script.as_mut() else {
+            return Ok(());
+        };
+        let stops: Vec<_> = emu
+            .drain_events()
+            .filter_map(|event| match event {
+                Event::Breakpoint(stop) => Some(stop),
+                _ => None,
+            })
+            .collect();
+        for stop in stops {
+            script.on_stop(emu, stop)?;
+        }
+        script.on_frame(emu)
+    }
+}
+impl InputSource for FrameLimit {
+    fn poll(&mut self, emu: &mut Emulator) -> Control {
+        if let Some(frames) = &mut self.frames {
+            if *frames == 0 {
+                return Control::Quit;
+            }
+            *frames -= 1;
+        }
+        if let Err(e) = self.run_script(emu) {
+            self.error = Some(e);
+            return Control::Quit;
+        }
+        match self.server.as_mut().map(|server| server.update(emu)) {
+            Some(false) => Control::Pause,
+            _ => Control::Run,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let opts = match parse_args(env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(e) => {
+            println!("{}\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let path = Path::new(&opts.rom);
+    let mut buffer = match fs::read(path) {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            println!("Unable to open {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    // Compile Octo source files before loading them
+    if path.extension().is_some_and(|ext| ext == "o8") {
+        let source = String::from_utf8_lossy(&buffer);
+        buffer = match octo::compile(&source) {
+            Ok(rom) => rom,
+            Err(e) => {
+                println!("Unable to compile {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
+        };
+    }
+
+    let mut chip8 = Emulator::new();
+    if let Err(e) = chip8.load_rom(&buffer) {
+        println!("Unable to load ROM: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let script = match &opts.script {
+        Some(path) => match fs::read_to_string(path).map(|source| Script::new(&source)) {
+            Ok(Ok(script)) => Some(script),
+            Ok(Err(e)) => {
+                println!("Unable to load {}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+            Err(e) => {
+                println!("Unable to open {}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    let server = match &opts.serve {
+        Some(addr) => match Server::bind(addr) {
+            Ok(server) => {
+                if let Ok(addr) = server.local_addr() {
+                    println!("Serving on http://{}", addr);
+                }
+                Some(server)
+            }
+            Err(e) => {
+                println!("Unable to listen on {}: {}", addr, e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    let input = FrameLimit {
+        frames: opts.frames,
+        script,
+        server,
+        error: None,
+    };
+    let mut runner = Runner::new((), input, ());
+    runner.set_cycles_per_frame(opts.cycles);
+    if let Err(e) = runner.run(&mut chip8) {
+        println!("Emulation error: {}", e);
+        return ExitCode::FAILURE;
+    }
+    if let Some(e) = &runner.input.error {
+        println!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    print_registers(&chip8);
+    if let Some(out) = &opts.dump_display {
+        if let Err(e) = fs::write(out, chip8.display_to_string()) {
+            println!("Unable to write {}: {}", out, e);
+            return ExitCode::FAILURE;
+        }
+    }
+    if let Some(out) = &opts.dump_ram {
+        if let Err(e) = fs::write(out, chip8.ram()) {
+            println!("Unable to write {}: {}", out, e);
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut rom = None;
+    let mut frames = None;
+    let mut cycles = host::DEFAULT_CYCLES_PER_FRAME;
+    let mut dump_display = None;
+    let mut dump_ram = None;
+    let mut script = None;
+    let mut serve = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--frames" => {
+                frames = Some(value()?.parse().map_err(|e| format!("Bad frames: {}", e))?)
+            }
+            "--cycles" => cycles = value()?.parse().map_err(|e| format!("Bad cycles: {}", e))?,
+            "--dump-display" => dump_display = Some(value()?),
+            "--dump-ram" => dump_ram = Some(value()?),
+            "--script" => script = Some(value()?),
+            "--serve" => serve = Some(value()?),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ if rom.is_none() => rom = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+
+    Ok(Options {
+        rom: rom.ok_or("Missing ROM path")?,
+        // Serving runs until interrupted unless told otherwise
+        frames: frames.or(match serve {
+            Some(_) => None,
+            None => Some(DEFAULT_FRAMES),
+        }),
+        cycles,
+        dump_display,
+        dump_ram,
+        script,
+        serve,
+    })
+}
+
+fn print_registers(emu: &Emulator) {
+    println!("State: {:?}", emu.state());
+    println!("PC: {:#05X}  I: {:#05X}", emu.pc(), emu.i());
+    for (x, v) in emu.v_registers().iter().enumerate() {
+        print!(
+            "V{:X}: {:02X}{}",
+            x,
+            v,
+            if x % 8 == 7 { "\n" } else { "  " }
+        );
+    }
+    println!(
+        "DT: {:02X}  ST: {:02X}",
+        emu.delay_timer(),
+        emu.sound_timer()
+    );
+    println!("Stack: {:03X?}", emu.stack());
+}
@@ -0,0 +1,212 @@
+//! Remote display and control over WebSocket, with a page to play from in a browser.
+//!
+//! Each binary message sent to clients is the display packed 1 bit per pixel, sent whenever it
+//! changes. Clients send text commands:
+//! - `down K` and `up K` press and release key `K`, in hex.
+//! - `pause`, `resume` and `step` control execution.
+//! - `state` asks for the registers, sent back as a text message.
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chip8core::{render::PACKED_BUFFER_SIZE, Emulator};
+use tungstenite::{Message, WebSocket};
+
+/// Page served to browsers, which connects back over WebSocket.
+const PAGE: &str = include_str!("remote.html");
+
+/// Time between frames.
+pub const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Longest request header read before giving up on a connection.
+const MAX_REQUEST: usize = 8192;
+
+/// How long a new connection has to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Serves the display to browsers and applies their input, pacing frames to 60Hz.
+pub struct Server {
+    listener: TcpListener,
+    clients: Vec<WebSocket<TcpStream>>,
+    /// Display last sent to clients.
+    sent: Option<[u8; PACKED_BUFFER_SIZE]>,
+    paused: bool,
+    next_frame: Instant,
+}
+impl Server {
+    /// Listen for browsers on `addr`.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            clients: Vec::new(),
+            sent: None,
+            paused: false,
+            next_frame: Instant::now(),
+        })
+    }
+
+    /// Address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Send the display if it changed, accept connections and apply commands, then wait for the
+    /// next frame. Returns whether to run the next frame.
+    pub fn update(&mut self, emu: &mut Emulator) -> bool {
+        self.accept();
+        self.send_display(emu);
+        self.read_commands(emu);
+
+        thread::sleep(self.next_frame.saturating_duration_since(Instant::now()));
+        // Don't try to catch up after falling far behind
+        self.next_frame = (self.next_frame + FRAME_TIME).max(Instant::now());
+        !self.paused
+    }
+
+    /// Accept waiting connections, serving the page to plain requests.
+    fn accept(&mut self) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            match upgrade(stream) {
+                Ok(Some(ws)) => {
+                    self.clients.push(ws);
+                    // Make sure the new client gets the display.
+                    self.sent = None;
+                }
+                Ok(None) => {}
+                Err(e) => println!("Connection failed: {}", e),
+            }
+        }
+    }
+
+    fn send_display(&mut self, emu: &Emulator) {
+        let mut packed = [0; PACKED_BUFFER_SIZE];
+        emu.render_packed(&mut packed);
+        if self.sent == Some(packed) {
+            return;
+        }
+        self.sent = Some(packed);
+        self.broadcast(Message::Binary(packed.to_vec().into()));
+    }
+
+    /// Send a message to every client, dropping those that have gone.
+    fn broadcast(&mut self, message: Message) {
+        self.clients
+            .retain_mut(|ws| match ws.send(message.clone()) {
+                Ok(()) => true,
+                Err(tungstenite::Error::Io(e)) => e.kind() == ErrorKind::WouldBlock,
+                Err(_) => false,
+            });
+    }
+
+    fn read_commands(&mut self, emu: &mut Emulator) {
+        let mut commands = Vec::new();
+        self.clients.retain_mut(|ws| loop {
+            match ws.read() {
+                Ok(Message::Text(text)) => commands.push(text.to_string()),
+                Ok(Message::Close(_)) => return false,
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(_) => return false,
+            }
+        });
+        for command in commands {
+            self.command(emu, &command);
+        }
+    }
+
+    fn command(&mut self, emu: &mut Emulator, command: &str) {
+        let mut parts = command.split_whitespace();
+        let key = |key: Option<&str>| key.and_then(|key| usize::from_str_radix(key, 16).ok());
+        match parts.next() {
+            Some("down") => {
+                if let Some(key) = key(parts.next()) {
+                    let _ = emu.press(key);
+                }
+            }
+            Some("up") => {
+                if let Some(key) = key(parts.next()) {
+                    let _ = emu.release(key);
+                }
+            }
+            Some("pause") => self.paused = true,
+            Some("resume") => self.paused = false,
+            Some("step") => {
+                if let Err(e) = emu.step() {
+                    println!("Emulation error: {}", e);
+                }
+            }
+            Some("state") => {
+                let state = state(emu, self.paused);
+                self.broadcast(Message::Text(state.into()))
+            }
+            _ => println!("Unknown command {:?}", command),
+        }
+    }
+}
+
+/// Registers as a line of text.
+fn state(emu: &Emulator, paused: bool) -> String {
+    let v: Vec<_> = emu
+        .v_registers()
+        .iter()
+        .map(|v| format!("{:02X}", v))
+        .collect();
+    format!(
+        "pc={:03X} i={:03X} v={} dt={:02X} st={:02X} paused={}",
+        emu.pc(),
+        emu.i(),
+        v.join(","),
+        emu.delay_timer(),
+        emu.sound_timer(),
+        paused,
+    )
+}
+
+/// Complete a WebSocket handshake, or answer a plain HTTP request with the page.
+fn upgrade(mut stream: TcpStream) -> Result<Option<WebSocket<TcpStream>>, String> {
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(REQUEST_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+
+    // Peek so the handshake can read the request again.
+    let mut buffer = vec![0; MAX_REQUEST];
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    let request = loop {
+        let len = stream.peek(&mut buffer).map_err(|e| e.to_string())?;
+        if len == 0 || Instant::now() > deadline {
+            return Err("no request received".to_string());
+        }
+        let request = String::from_utf8_lossy(&buffer[..len]).to_ascii_lowercase();
+        if request.contains("\r\n\r\n") || len == MAX_REQUEST {
+            break request;
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+
+    if !request.contains("upgrade: websocket") {
+        // Drain the request before answering so the connection closes cleanly.
+        let _ = stream.read(&mut buffer);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            PAGE.len(),
+            PAGE
+        );
+        stream
+            .write_all(response.as_bytes())
+            .map_err(|e| e.to_string())?;
+        return Ok(None);
+    }
+
+    let ws = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    ws.get_ref()
+        .set_nonblocking(true)
+        .map_err(|e| e.to_string())?;
+    Ok(Some(ws))
+}
@@ -0,0 +1,356 @@
+//! Debugging a game in the terminal: the display drawn in Braille beside panes for the
+//! disassembly, registers and memory, with a command line setting breakpoints and stepping.
+use std::{io, time::Instant};
+
+use chip8core::{console, *};
+use ratatui::{
+    crossterm::{
+        event::{
+            self, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+            PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+        },
+        execute,
+        terminal::supports_keyboard_enhancement,
+    },
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+use crate::{key_to_button, Game, Screen, FG_COLOR, FRAME_TIME, KEY_HOLD_FRAMES};
+
+/// Lines of command output kept.
+pub const LOG_LENGTH: usize = 200;
+/// Height of the command pane, including its border.
+pub const COMMAND_HEIGHT: u16 = 8;
+/// Bytes PageUp and PageDown move the memory pane by.
+pub const MEMORY_PAGE: u16 = 0x40;
+
+/// Debug `game` until quit, starting paused before the first instruction.
+pub fn debug(game: Game) -> Result<(), String> {
+    let mut terminal = ratatui::init();
+    // Ask for key releases where the terminal can report them
+    let releases = supports_keyboard_enhancement().unwrap_or(false)
+        && execute!(
+            io::stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )
+        .is_ok();
+
+    let mut debugger = Debugger {
+        cycles_per_frame: game.cycles_per_frame,
+        game,
+        paused: true,
+        playing: false,
+        input: String::new(),
+        log: vec!["Paused before the first instruction, type help for commands".to_string()],
+        disasm_at: None,
+        memory_at: None,
+        releases,
+        held: [0; 16],
+        quit: false,
+    };
+    let mut next_frame = Instant::now();
+    let mut result = Ok(());
+    while !debugger.quit {
+        debugger.frame();
+        if let Err(e) = terminal.draw(|frame| debugger.draw(frame)) {
+            result = Err(e);
+            break;
+        }
+        if let Err(e) = debugger.wait(next_frame) {
+            result = Err(e);
+            break;
+        }
+        // Don't try to catch up after falling far behind, e.g. while suspended
+        next_frame = (next_frame + FRAME_TIME).max(Instant::now());
+    }
+
+    if releases {
+        let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+    }
+    ratatui::restore();
+    result.map_err(|e| format!("Terminal error: {}", e))
+}
+
+/// Game being debugged, with the state of the panes around it.
+struct Debugger {
+    game: Game,
+    cycles_per_frame: u32,
+    paused: bool,
+    /// Whether keys go to the game's keypad instead of the command line.
+    playing: bool,
+    /// Command being typed.
+    input: String,
+    /// Commands run and what they showed, oldest first.
+    log: Vec<String>,
+    /// Address the disassembly starts at, or `None` to follow the program counter.
+    disasm_at: Option<u16>,
+    /// Address the memory pane starts at, or `None` to follow I.
+    memory_at: Option<u16>,
+    /// Whether the terminal reports key releases.
+    releases: bool,
+    /// Frames left before each key is released, when the terminal doesn't report releases.
+    held: [u8; 16],
+    quit: bool,
+}
+impl Debugger {
+    /// Run a frame unless paused, pausing on breakpoints, watchpoints and errors.
+    fn frame(&mut self) {
+        let emu = &mut self.game.emu;
+        if !self.releases {
+            for (k, frames) in self.held.iter_mut().enumerate() {
+                if *frames > 0 {
+                    *frames -= 1;
+                    if *frames == 0 {
+                        emu.release(k).unwrap();
+                    }
+                }
+            }
+        }
+        if self.paused {
+            return;
+        }
+        match emu.run_frame(self.cycles_per_frame) {
+            Ok(output) => {
+                if let Some(stop) = output.stop() {
+                    self.stop(format!("Stopped on {}", stop));
+                } else if emu.state() == EmulatorState::Exited {
+                    self.stop("Program exited".to_string());
+                }
+            }
+            Err(e) => self.stop(format!("Emulation error: {}", e)),
+        }
+    }
+
+    /// Pause, showing why.
+    fn stop(&mut self, message: String) {
+        self.paused = true;
+        self.show(message);
+    }
+
+    /// Add lines to the log, dropping the oldest past [LOG_LENGTH].
+    fn show(&mut self, text: String) {
+        self.log.extend(text.lines().map(str::to_string));
+        let excess = self.log.len().saturating_sub(LOG_LENGTH);
+        self.log.drain(..excess);
+    }
+
+    /// Read key events until `deadline`.
+    fn wait(&mut self, deadline: Instant) -> io::Result<()> {
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if !event::poll(timeout)? {
+                return Ok(());
+            }
+            if let event::Event::Key(key) = event::read()? {
+                self.key(key);
+            }
+            if self.quit {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Apply a key event, to the keypad while playing or to the command line otherwise.
+    fn key(&mut self, key: KeyEvent) {
+        let pressed = key.kind != KeyEventKind::Release;
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+        match key.code {
+            KeyCode::Tab if pressed => self.playing = !self.playing,
+            KeyCode::Esc if pressed => self.playing = false,
+            KeyCode::F(5) if pressed => self.run(console::Command::Continue),
+            KeyCode::F(6) if pressed => self.run(console::Command::Break(None)),
+            KeyCode::F(10) if pressed => self.run(console::Command::Step(1)),
+            KeyCode::PageUp if pressed => {
+                let at = self.memory_start().saturating_sub(MEMORY_PAGE);
+                self.memory_at = Some(at);
+            }
+            KeyCode::PageDown if pressed => {
+                let at = self.memory_start().saturating_add(MEMORY_PAGE);
+                self.memory_at = Some(at.min(self.game.emu.ram().len() as u16 - MEMORY_PAGE));
+            }
+            KeyCode::Char(c) if self.playing => {
+                let Some(k) = key_to_button(c.to_ascii_lowercase()) else {
+                    return;
+                };
+                let emu = &mut self.game.emu;
+                match key.kind {
+                    KeyEventKind::Press | KeyEventKind::Repeat => {
+                        emu.press(k).unwrap();
+                        self.held[k] = KEY_HOLD_FRAMES;
+                    }
+                    KeyEventKind::Release => emu.release(k).unwrap(),
+                }
+            }
+            _ if self.playing || !pressed => {}
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => {
+                let line = std::mem::take(&mut self.input);
+                self.command(line.trim());
+            }
+            _ => {}
+        }
+    }
+
+    /// Run a line typed at the command line.
+    fn command(&mut self, line: &str) {
+        if line.is_empty() {
+            return;
+        }
+        self.show(format!("> {}", line));
+        if line == "quit" || line == "q" {
+            self.quit = true;
+            return;
+        }
+        match line.parse() {
+            // The panes show the disassembly and memory, so these move them instead
+            Ok(console::Command::Disasm(addr, _)) => self.disasm_at = addr,
+            Ok(console::Command::Memory(addr, _)) => self.memory_at = Some(addr),
+            Ok(command) => self.run(command),
+            Err(e) => self.show(format!("Bad command: {}", e)),
+        }
+    }
+
+    /// Run a console command on the game, showing what it shows.
+    fn run(&mut self, command: console::Command) {
+        match command.run(&mut self.game.emu, &mut self.paused) {
+            Ok(text) => self.show(text),
+            Err(e) => self.stop(format!("Emulation error: {}", e)),
+        }
+    }
+
+    /// Address the memory pane starts at.
+    fn memory_start(&self) -> u16 {
+        self.memory_at.unwrap_or(self.game.emu.i() & !0xF)
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let emu = &self.game.emu;
+        let screen = Screen {
+            display: emu.get_display(),
+            braille: true,
+        };
+        let (width, height) = screen.size();
+        let [top, middle, bottom] = Layout::vertical([
+            Constraint::Length(height + 2),
+            Constraint::Min(4),
+            Constraint::Length(COMMAND_HEIGHT),
+        ])
+        .areas(frame.area());
+        let [display, registers] =
+            Layout::horizontal([Constraint::Length(width + 2), Constraint::Min(0)]).areas(top);
+        let [disasm, memory] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(middle);
+
+        let block = Block::bordered().title(self.game.title.as_str());
+        frame.render_widget(&block, display);
+        frame.render_widget(screen, block.inner(display));
+
+        let state = match self.paused {
+            true => "Paused",
+            false => "Running",
+        };
+        frame.render_widget(
+            Paragraph::new(self.registers()).block(Block::bordered().title(state)),
+            registers,
+        );
+        frame.render_widget(
+            Paragraph::new(self.disassembly(disasm)).block(Block::bordered().title("Disassembly")),
+            disasm,
+        );
+        let rows = memory.height.saturating_sub(2);
+        let lines = console::dump(emu, self.memory_start(), rows * console::BYTES_PER_LINE);
+        let lines: Vec<_> = lines.into_iter().map(Line::from).collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title("Memory")),
+            memory,
+        );
+        self.draw_command(frame, bottom);
+    }
+
+    /// Registers, timers and breakpoints.
+    fn registers(&self) -> Vec<Line<'_>> {
+        let emu = &self.game.emu;
+        let mut lines: Vec<Line> = (0..4)
+            .map(|row| {
+                let v: Vec<_> = (row * 4..row * 4 + 4)
+                    .map(|x| format!("V{:X} {:02X}", x, emu.v(x as usize)))
+                    .collect();
+                Line::from(v.join("  "))
+            })
+            .collect();
+        lines.push(Line::from(format!(
+            "I  {:03X}  PC {:03X}  SP {:X}",
+            emu.i(),
+            emu.pc(),
+            emu.stack_pointer()
+        )));
+        lines.push(Line::from(format!(
+            "DT {:02X}   ST {:02X}",
+            emu.delay_timer(),
+            emu.sound_timer()
+        )));
+        let breakpoints: Vec<_> = emu
+            .debugger()
+            .breakpoints()
+            .map(|addr| format!("{:03X}", addr))
+            .collect();
+        if !breakpoints.is_empty() {
+            lines.push(Line::from(format!("Break {}", breakpoints.join(" "))));
+        }
+        lines
+    }
+
+    /// Instructions filling `area`, a few before the program counter unless told where to start.
+    fn disassembly(&self, area: Rect) -> Vec<Line<'_>> {
+        let emu = &self.game.emu;
+        let rows = area.height.saturating_sub(2);
+        let start = self
+            .disasm_at
+            .unwrap_or_else(|| emu.pc().saturating_sub(rows / 3 * 2));
+        console::disassemble(emu, start, rows)
+            .into_iter()
+            .map(|line| {
+                let style = match (line.current, line.breakpoint) {
+                    (true, _) => Style::default().add_modifier(Modifier::REVERSED),
+                    (false, true) => Style::default().fg(Color::Red),
+                    (false, false) => Style::default(),
+                };
+                Line::styled(line.to_string(), style)
+            })
+            .collect()
+    }
+
+    /// Command output, newest at the bottom, above the line being typed.
+    fn draw_command(&self, frame: &mut Frame, area: Rect) {
+        let (title, hint) = match self.playing {
+            true => ("Keypad", "Esc: commands"),
+            false => ("Command", "Tab: keypad"),
+        };
+        let block = Block::bordered().title(title).title_bottom(format!(
+            "{}  F5: continue  F6: pause  F10: step  Ctrl+C: quit",
+            hint
+        ));
+        let rows = block.inner(area).height.saturating_sub(1) as usize;
+        let mut lines: Vec<Line> = self.log[self.log.len().saturating_sub(rows)..]
+            .iter()
+            .map(|line| Line::from(line.as_str()))
+            .collect();
+        let prompt = match self.playing {
+            true => Line::styled("Playing", Style::default().fg(FG_COLOR)),
+            false => Line::from(format!("> {}_", self.input)),
+        };
+        lines.push(prompt);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
@@ -0,0 +1,185 @@
+//! Playing and debugging games in a terminal, e.g. over SSH where SDL isn't an option.
+pub mod debugger;
+pub mod play;
+
+use std::{fs, path::Path, time::Duration};
+
+use chip8core::{
+    emulator::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+    *,
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+pub use debugger::debug;
+pub use play::play;
+
+/// Foreground colour.
+pub const FG_COLOR: Color = Color::Green;
+
+/// Time between frames.
+pub const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Frames a key stays pressed after a key press or repeat, for terminals that don't report
+/// releases.
+pub const KEY_HOLD_FRAMES: u8 = 6;
+
+/// ROM loaded into an emulator, with the settings to run it at.
+pub struct Game {
+    /// Emulator the ROM is loaded into.
+    pub emu: Emulator,
+    /// Title to show, naming the game if it's known.
+    pub title: String,
+    /// Instructions to run per frame.
+    pub cycles_per_frame: u32,
+}
+
+/// Read the ROM at `path`, compiling it first if it's an Octo source file or assembling it if
+/// it's an assembly file.
+pub fn read(path: &Path) -> Result<Vec<u8>, String> {
+    let buffer = fs::read(path).map_err(|e| format!("Unable to open {}: {}", path.display(), e))?;
+    let source = || String::from_utf8_lossy(&buffer);
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("o8") => octo::compile(&source())
+            .map_err(|e| format!("Unable to compile {}: {}", path.display(), e)),
+        Some("asm") => asm::assemble(&source())
+            .map_err(|e| format!("Unable to assemble {}:{}", path.display(), e)),
+        _ => Ok(buffer),
+    }
+}
+
+/// Load the ROM at `path`, as [read], with quirks to suit it.
+pub fn load(path: &Path) -> Result<Game, String> {
+    let buffer = read(path)?;
+    let mut chip8 = Emulator::new();
+    // Pick quirks to suit the instruction set the ROM uses
+    chip8.set_quirks(
+        analyze::analyze(&buffer, emulator::START_ADDRESS)
+            .platform
+            .quirks(),
+    );
+    let rom_info = chip8
+        .load_rom(&buffer)
+        .map_err(|e| format!("Unable to load ROM: {}", e))?;
+    // Known games override the guessed settings
+    let mut cycles_per_frame = host::DEFAULT_CYCLES_PER_FRAME;
+    let mut title = "chip8emu".to_string();
+    if let Some(entry) = rom_info.identify() {
+        chip8.set_quirks(entry.quirks);
+        cycles_per_frame = entry.cycles_per_frame;
+        title = format!("chip8emu - {}", entry.title);
+    }
+    Ok(Game {
+        emu: chip8,
+        title,
+        cycles_per_frame,
+    })
+}
+
+/// Display pixels drawn with two pixels per character using half blocks, or eight using Braille.
+#[derive(Clone, Copy)]
+pub struct Screen<'a> {
+    /// Pixels, row by row.
+    pub display: &'a [bool],
+    /// Whether to draw with Braille.
+    pub braille: bool,
+}
+impl Screen<'_> {
+    /// Pixels per character, as `(columns, rows)`.
+    fn cell(&self) -> (usize, usize) {
+        if self.braille {
+            (2, 4)
+        } else {
+            (1, 2)
+        }
+    }
+
+    /// Size in characters.
+    pub fn size(&self) -> (u16, u16) {
+        let (columns, rows) = self.cell();
+        (
+            (DISPLAY_WIDTH / columns) as u16,
+            (DISPLAY_HEIGHT / rows) as u16,
+        )
+    }
+
+    /// Whether the pixel at `(x, y)` is on.
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        self.display[y * DISPLAY_WIDTH + x]
+    }
+
+    /// Character showing the pixels in the cell whose top-left pixel is at `(x, y)`.
+    fn symbol(&self, x: usize, y: usize) -> char {
+        if self.braille {
+            // Braille dots are numbered down the left column, then the right, then the bottom row.
+            const DOTS: [(usize, usize); 8] = [
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (1, 0),
+                (1, 1),
+                (1, 2),
+                (0, 3),
+                (1, 3),
+            ];
+            let bits = DOTS
+                .iter()
+                .enumerate()
+                .filter(|(_, (dx, dy))| self.pixel(x + dx, y + dy))
+                .fold(0, |bits, (n, _)| bits | 1 << n);
+            match bits {
+                0 => ' ',
+                _ => char::from_u32(0x2800 + bits).unwrap_or(' '),
+            }
+        } else {
+            match (self.pixel(x, y), self.pixel(x, y + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            }
+        }
+    }
+}
+impl Widget for Screen<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (columns, rows) = self.cell();
+        let (width, height) = self.size();
+        let style = Style::default().fg(FG_COLOR);
+        for row in 0..height.min(area.height) {
+            for column in 0..width.min(area.width) {
+                let symbol = self.symbol(column as usize * columns, row as usize * rows);
+                buf[(area.x + column, area.y + row)]
+                    .set_char(symbol)
+                    .set_style(style);
+            }
+        }
+    }
+}
+
+/// Chip-8 key bound to a keyboard key, laid out on 1234/QWER/ASDF/ZXCV.
+pub fn key_to_button(key: char) -> Option<usize> {
+    match key {
+        '1' => Some(0x1),
+        '2' => Some(0x2),
+        '3' => Some(0x3),
+        '4' => Some(0xC),
+        'q' => Some(0x4),
+        'w' => Some(0x5),
+        'e' => Some(0x6),
+        'r' => Some(0xD),
+        'a' => Some(0x7),
+        's' => Some(0x8),
+        'd' => Some(0x9),
+        'f' => Some(0xE),
+        'z' => Some(0xA),
+        'x' => Some(0x0),
+        'c' => Some(0xB),
+        'v' => Some(0xF),
+        _ => None,
+    }
+}
@@ -0,0 +1,201 @@
+//! Playing a game in the terminal, with the display drawn in half blocks or Braille and the
+//! buzzer ringing the terminal bell.
+use std::{
+    io::{self, Write},
+    time::Instant,
+};
+
+use chip8core::*;
+use ratatui::{
+    crossterm::{
+        event::{
+            self, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+            PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+        },
+        execute,
+        terminal::supports_keyboard_enhancement,
+    },
+    layout::Rect,
+    widgets::Block,
+    DefaultTerminal,
+};
+
+use crate::{key_to_button, Game, Screen, FRAME_TIME, KEY_HOLD_FRAMES};
+
+/// Play `game` until Esc or the program exits, drawn with Braille if `braille`. Returns the keys
+/// pressed as a recording, if `record`.
+pub fn play(game: Game, braille: bool, record: bool) -> Result<Option<Recording>, String> {
+    let Game {
+        emu: mut chip8,
+        title,
+        cycles_per_frame,
+    } = game;
+    // Start straight after loading, so replays start from the same state
+    let recorder = record.then(|| Recorder::start(&mut chip8, cycles_per_frame));
+
+    let terminal = ratatui::init();
+    // Ask for key releases where the terminal can report them
+    let releases = supports_keyboard_enhancement().unwrap_or(false)
+        && execute!(
+            io::stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )
+        .is_ok();
+
+    let display = TermDisplay {
+        terminal,
+        title,
+        braille,
+        beeping: false,
+    };
+    let input = TermInput {
+        releases,
+        held: [0; 16],
+        next_frame: Instant::now(),
+        recorder,
+        polled: false,
+        error: None,
+    };
+    let mut runner = Runner::new(display, input, ());
+    runner.set_cycles_per_frame(cycles_per_frame);
+    let result = runner.run(&mut chip8);
+
+    if releases {
+        let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+    }
+    ratatui::restore();
+    result.map_err(|e| format!("Emulation error: {}", e))?;
+    if let Some(e) = runner.input.error.take() {
+        return Err(format!("Terminal error: {}", e));
+    }
+    let mut recorder = runner.input.recorder.take();
+    // The last frame ran without another poll to count it
+    if chip8.state() == EmulatorState::Exited {
+        recorder.as_mut().map(Recorder::end_frame);
+    }
+    Ok(recorder.map(Recorder::finish))
+}
+
+/// Terminal the display is drawn to. The buzzer rings the terminal bell.
+struct TermDisplay {
+    terminal: DefaultTerminal,
+    title: String,
+    braille: bool,
+    beeping: bool,
+}
+impl DisplaySink for TermDisplay {
+    fn present(&mut self, emu: &Emulator) {
+        let screen = Screen {
+            display: emu.get_display(),
+            braille: self.braille,
+        };
+        let block = Block::bordered()
+            .title(self.title.as_str())
+            .title_bottom("Esc: quit");
+        let _ = self.terminal.draw(|frame| {
+            let (width, height) = screen.size();
+            let area = Rect::new(0, 0, width + 2, height + 2).intersection(frame.area());
+            frame.render_widget(&block, area);
+            frame.render_widget(screen, block.inner(area));
+        });
+
+        let beeping = emu.is_sound_playing();
+        if beeping && !self.beeping {
+            let _ = io::stdout().write_all(b"\x07");
+            let _ = io::stdout().flush();
+        }
+        self.beeping = beeping;
+    }
+}
+
+/// Keyboard input, read while waiting for the next frame.
+struct TermInput {
+    /// Whether the terminal reports key releases.
+    releases: bool,
+    /// Frames left before each key is released, when the terminal doesn't report releases.
+    held: [u8; 16],
+    next_frame: Instant,
+    /// Records the keys pressed, if recording.
+    recorder: Option<Recorder>,
+    /// Whether a frame has been polled for, so the next poll follows a frame.
+    polled: bool,
+    error: Option<io::Error>,
+}
+impl TermInput {
+    /// Apply a key event. Returns whether it asks to quit.
+    fn key(&mut self, chip8: &mut Emulator, key: KeyEvent) -> bool {
+        let ctrl_c =
+            key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+        if key.code == KeyCode::Esc || ctrl_c {
+            return true;
+        }
+        let KeyCode::Char(c) = key.code else {
+            return false;
+        };
+        let Some(k) = key_to_button(c.to_ascii_lowercase()) else {
+            return false;
+        };
+        match key.kind {
+            KeyEventKind::Press | KeyEventKind::Repeat => {
+                self.keypress(chip8, k, true);
+                self.held[k] = KEY_HOLD_FRAMES;
+            }
+            KeyEventKind::Release => self.keypress(chip8, k, false),
+        }
+        false
+    }
+
+    /// Press or release a key, recording it if recording.
+    fn keypress(&mut self, chip8: &mut Emulator, k: usize, pressed: bool) {
+        match &mut self.recorder {
+            Some(recorder) => recorder.keypress(chip8, k, pressed).unwrap(),
+            None => chip8.keypress(k, pressed).unwrap(),
+        }
+    }
+
+    /// Read key events until the next frame is due. Returns whether one asks to quit.
+    fn wait(&mut self, chip8: &mut Emulator) -> io::Result<bool> {
+        loop {
+            let timeout = self.next_frame.saturating_duration_since(Instant::now());
+            if !event::poll(timeout)? {
+                return Ok(false);
+            }
+            if let event::Event::Key(key) = event::read()? {
+                if self.key(chip8, key) {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
+impl InputSource for TermInput {
+    fn poll(&mut self, chip8: &mut Emulator) -> Control {
+        if let Some(recorder) = self.recorder.as_mut().filter(|_| self.polled) {
+            recorder.end_frame();
+        }
+        self.polled = true;
+        if !self.releases {
+            for k in 0..self.held.len() {
+                if self.held[k] > 0 {
+                    self.held[k] -= 1;
+                    if self.held[k] == 0 {
+                        self.keypress(chip8, k, false);
+                    }
+                }
+            }
+        }
+
+        match self.wait(chip8) {
+            Ok(true) => return Control::Quit,
+            Ok(false) => {}
+            Err(e) => {
+                self.error = Some(e);
+                return Control::Quit;
+            }
+        }
+        // Don't try to catch up after falling far behind, e.g. while suspended
+        let now = Instant::now();
+        self.next_frame = (self.next_frame + FRAME_TIME).max(now);
+        Control::Run
+    }
+}
@@ -0,0 +1,94 @@
+//! WebAssembly bindings for running the Chip-8 core in a browser. Build with
+//! `wasm-pack build web --target web` and serve `web/www` alongside the generated `pkg`.
+#![warn(missing_docs)]
+use chip8core::{
+    emulator::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+    render::RGBA_BUFFER_SIZE,
+    Emulator, Palette,
+};
+use wasm_bindgen::{prelude::*, Clamped};
+
+/// Emulator exposed to JavaScript as `Chip8`.
+#[wasm_bindgen]
+pub struct Chip8 {
+    emu: Emulator,
+    palette: Palette,
+    /// Last ROM loaded, reloaded on reset.
+    rom: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Chip8 {
+    /// Create an emulator whose RNG is seeded with `seed`, e.g. from `Math.random()`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u32) -> Chip8 {
+        Chip8 {
+            emu: Emulator::with_seed(seed as u64),
+            palette: Palette::default(),
+            rom: Vec::new(),
+        }
+    }
+
+    /// Width of the display in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        DISPLAY_WIDTH
+    }
+
+    /// Height of the display in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        DISPLAY_HEIGHT
+    }
+
+    /// Load a ROM from a `Uint8Array` at the start address.
+    #[wasm_bindgen(js_name = loadRom)]
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), JsError> {
+        self.emu.load_rom(rom)?;
+        self.rom = rom.to_vec();
+        Ok(())
+    }
+
+    /// Reset the machine and load the last ROM again.
+    pub fn reset(&mut self) -> Result<(), JsError> {
+        self.emu.reset();
+        if !self.rom.is_empty() {
+            self.emu.load_rom(&self.rom)?;
+        }
+        Ok(())
+    }
+
+    /// Run one 60Hz frame: execute `cycles` instructions, then tick the timers once. Returns
+    /// whether a tone should be playing.
+    #[wasm_bindgen(js_name = runFrame)]
+    pub fn run_frame(&mut self, cycles: u32) -> Result<bool, JsError> {
+        Ok(self.emu.run_frame(cycles)?.sound_active)
+    }
+
+    /// Render the display as RGBA bytes, ready for `new ImageData(pixels, width, height)`.
+    pub fn framebuffer(&self) -> Clamped<Vec<u8>> {
+        let mut pixels = vec![0; RGBA_BUFFER_SIZE];
+        self.emu.render_rgba(&mut pixels, &self.palette);
+        Clamped(pixels)
+    }
+
+    /// Set the colours used by [Chip8::framebuffer], as `0xRRGGBBAA`.
+    #[wasm_bindgen(js_name = setPalette)]
+    pub fn set_palette(&mut self, background: u32, foreground: u32) {
+        self.palette = Palette::new(background.to_be_bytes(), foreground.to_be_bytes());
+    }
+
+    /// Press a key from 0 to 15.
+    #[wasm_bindgen(js_name = keyDown)]
+    pub fn key_down(&mut self, key: usize) -> Result<(), JsError> {
+        self.emu.press(key)?;
+        Ok(())
+    }
+
+    /// Release a key from 0 to 15.
+    #[wasm_bindgen(js_name = keyUp)]
+    pub fn key_up(&mut self, key: usize) -> Result<(), JsError> {
+        self.emu.release(key)?;
+        Ok(())
+    }
+}
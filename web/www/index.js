@@ -0,0 +1,53 @@
+// Minimal browser frontend. Build the bindings into `web/www/pkg` first:
+//   wasm-pack build web --target web --out-dir www/pkg
+import init, { Chip8 } from "./pkg/chip8web.js";
+
+const CYCLES_PER_FRAME = 8;
+
+// Same layout as the desktop frontend: the left side of a QWERTY keyboard.
+const KEYMAP = {
+  Digit1: 0x1, Digit2: 0x2, Digit3: 0x3, Digit4: 0xc,
+  KeyQ: 0x4, KeyW: 0x5, KeyE: 0x6, KeyR: 0xd,
+  KeyA: 0x7, KeyS: 0x8, KeyD: 0x9, KeyF: 0xe,
+  KeyZ: 0xa, KeyX: 0x0, KeyC: 0xb, KeyV: 0xf,
+};
+
+await init();
+
+const canvas = document.getElementById("screen");
+const context = canvas.getContext("2d");
+let chip8 = null;
+
+document.getElementById("rom").addEventListener("change", async (event) => {
+  const file = event.target.files[0];
+  if (!file) {
+    return;
+  }
+  chip8 = new Chip8((Math.random() * 2 ** 32) >>> 0);
+  chip8.loadRom(new Uint8Array(await file.arrayBuffer()));
+});
+
+for (const [type, pressed] of [["keydown", true], ["keyup", false]]) {
+  document.addEventListener(type, (event) => {
+    const key = KEYMAP[event.code];
+    if (chip8 && key !== undefined) {
+      pressed ? chip8.keyDown(key) : chip8.keyUp(key);
+      event.preventDefault();
+    }
+  });
+}
+
+function frame() {
+  if (chip8) {
+    try {
+      chip8.runFrame(CYCLES_PER_FRAME);
+      const image = new ImageData(chip8.framebuffer(), chip8.width, chip8.height);
+      context.putImageData(image, 0, 0);
+    } catch (error) {
+      console.error(error);
+      chip8 = null;
+    }
+  }
+  requestAnimationFrame(frame);
+}
+requestAnimationFrame(frame);